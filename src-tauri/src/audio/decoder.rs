@@ -0,0 +1,278 @@
+//! Decoding tracks to f32 samples
+//!
+//! rodio's `Decoder` rounds every format to 16-bit integers. That's fine while
+//! the output mixes, resamples and runs the DSP chain anyway, but exclusive
+//! output promises the file's own samples. There symphonia decodes straight to
+//! f32 instead, which holds 16- and 24-bit samples exactly.
+
+use std::time::Duration;
+
+use rodio::source::{SamplesConverter, SeekError};
+use rodio::{Decoder, Source};
+use symphonia::core::audio::{Channels, SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{self, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::stream::TrackReader;
+
+/// Consecutive undecodable packets skipped before giving up on a track
+const MAX_DECODE_ERRORS: usize = 3;
+
+/// Sample rate, channel count and bit depth a track decodes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Bits per sample of lossless codecs; `None` for lossy ones
+    pub bits_per_sample: Option<u32>,
+}
+
+/// A track decoded by rodio, or at full precision for exclusive output
+pub enum TrackDecoder {
+    Rodio(SamplesConverter<Decoder<TrackReader>, f32>),
+    Exact(ExactDecoder),
+}
+
+impl TrackDecoder {
+    /// Decode `reader`. `exact` keeps the file's full sample precision.
+    pub fn new(reader: TrackReader, exact: bool) -> Result<Self, String> {
+        if exact {
+            ExactDecoder::new(reader).map(TrackDecoder::Exact)
+        } else {
+            Decoder::new(reader)
+                .map(|decoder| TrackDecoder::Rodio(decoder.convert_samples()))
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+impl Iterator for TrackDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        match self {
+            TrackDecoder::Rodio(source) => source.next(),
+            TrackDecoder::Exact(source) => source.next(),
+        }
+    }
+}
+
+impl Source for TrackDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self {
+            TrackDecoder::Rodio(source) => source.current_frame_len(),
+            TrackDecoder::Exact(source) => source.current_frame_len(),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        match self {
+            TrackDecoder::Rodio(source) => source.channels(),
+            TrackDecoder::Exact(source) => source.channels(),
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        match self {
+            TrackDecoder::Rodio(source) => source.sample_rate(),
+            TrackDecoder::Exact(source) => source.sample_rate(),
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            TrackDecoder::Rodio(source) => source.total_duration(),
+            TrackDecoder::Exact(source) => source.total_duration(),
+        }
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        match self {
+            TrackDecoder::Rodio(source) => source.try_seek(pos),
+            TrackDecoder::Exact(source) => source.try_seek(pos),
+        }
+    }
+}
+
+/// Symphonia decoder yielding samples converted losslessly to f32
+pub struct ExactDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn codecs::Decoder>,
+    track_id: u32,
+    spec: SignalSpec,
+    bits_per_sample: Option<u32>,
+    total_duration: Option<Duration>,
+    buffer: Option<SampleBuffer<f32>>,
+    /// Next sample to hand out from `buffer`
+    offset: usize,
+}
+
+impl ExactDecoder {
+    pub fn new(reader: TrackReader) -> Result<Self, String> {
+        let stream = MediaSourceStream::new(Box::new(reader), Default::default());
+        let format_opts = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
+        let probed = symphonia::default::get_probe()
+            .format(&Hint::new(), stream, &format_opts, &MetadataOptions::default())
+            .map_err(|e| format!("Unsupported audio format: {}", e))?;
+        let track = probed
+            .format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or("No playable audio track")?;
+        let params = track.codec_params.clone();
+        let track_id = track.id;
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&params, &DecoderOptions::default())
+            .map_err(|e| format!("Unsupported codec: {}", e))?;
+        let total_duration = params.time_base.zip(params.n_frames).map(|(base, frames)| {
+            let time = base.calc_time(frames);
+            Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac)
+        });
+
+        let mut this = Self {
+            format: probed.format,
+            decoder,
+            track_id,
+            // Replaced by the real layout once the first packet is decoded
+            spec: SignalSpec::new(
+                params.sample_rate.unwrap_or(0),
+                params.channels.unwrap_or(Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+            ),
+            bits_per_sample: params.bits_per_sample,
+            total_duration,
+            buffer: None,
+            offset: 0,
+        };
+        if !this.decode_next() {
+            return Err("No audio in track".to_string());
+        }
+        Ok(this)
+    }
+
+    pub fn format(&self) -> PcmFormat {
+        PcmFormat {
+            sample_rate: self.spec.rate,
+            channels: self.spec.channels.count() as u16,
+            bits_per_sample: self.bits_per_sample,
+        }
+    }
+
+    /// Decode the next packet of the track into `buffer`. False at the end of
+    /// the track or on an error that ends it.
+    fn decode_next(&mut self) -> bool {
+        let mut errors = 0;
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                // End of stream, or a read error that ends it
+                Err(_) => return false,
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    if decoded.frames() == 0 {
+                        continue;
+                    }
+                    let spec = *decoded.spec();
+                    let needed = decoded.capacity() * spec.channels.count();
+                    let buffer = match self.buffer.take() {
+                        Some(buffer) if spec == self.spec && buffer.capacity() >= needed => buffer,
+                        _ => SampleBuffer::new(decoded.capacity() as u64, spec),
+                    };
+                    let buffer = self.buffer.insert(buffer);
+                    buffer.copy_interleaved_ref(decoded);
+                    self.spec = spec;
+                    self.offset = 0;
+                    return true;
+                }
+                Err(Error::DecodeError(e)) => {
+                    errors += 1;
+                    if errors > MAX_DECODE_ERRORS {
+                        eprintln!("[Decoder] Giving up after decode errors: {}", e);
+                        return false;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[Decoder] {}", e);
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.buffer
+            .as_ref()
+            .map_or(0, |buffer| buffer.len().saturating_sub(self.offset))
+    }
+}
+
+impl Iterator for ExactDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.remaining() == 0 && !self.decode_next() {
+            return None;
+        }
+        let sample = self.buffer.as_ref()?.samples()[self.offset];
+        self.offset += 1;
+        Some(sample)
+    }
+}
+
+impl Source for ExactDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        // The next packet may change the layout, so only the decoded rest is known
+        Some(self.remaining()).filter(|&n| n > 0)
+    }
+
+    fn channels(&self) -> u16 {
+        self.spec.channels.count() as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.spec.rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: pos.as_secs_f64().into(),
+                    track_id: Some(self.track_id),
+                },
+            )
+            .map_err(|e| SeekError::Other(Box::new(e)))?;
+        self.decoder.reset();
+        self.buffer = None;
+        self.offset = 0;
+
+        // The reader lands on a packet boundary at or before the target; drop
+        // the frames in between so playback starts exactly at `pos`
+        let skip = seeked.required_ts.saturating_sub(seeked.actual_ts) as usize
+            * self.channels() as usize;
+        for _ in 0..skip {
+            if self.next().is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,27 @@ use rodio::Source;
 use std::f32::consts::PI;
 use std::sync::{Arc, Mutex};
 
+/// Read-only presets shipped with the app (10-band gains in dB, 31 Hz to 16 kHz)
+pub const BUILTIN_PRESETS: &[(&str, [f32; 10])] = &[
+    ("Flat", [0.0; 10]),
+    (
+        "Bass Boost",
+        [6.0, 5.0, 4.0, 2.5, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    ),
+    (
+        "Vocal",
+        [-2.0, -1.5, -1.0, 0.5, 2.0, 3.0, 3.0, 2.0, 0.5, -1.0],
+    ),
+];
+
+/// Gains for a built-in preset, matched case-insensitively
+pub fn builtin_preset(name: &str) -> Option<&'static [f32; 10]> {
+    BUILTIN_PRESETS
+        .iter()
+        .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, gains)| gains)
+}
+
 #[derive(Clone, Copy, Debug)]
 struct BiquadCoeffs {
     b0: f32,
@@ -139,6 +160,8 @@ where
     update_counter: usize,
     pending_sample: Option<f32>,
     reverb: Freeverb,
+    /// Pass samples through untouched (native rate output)
+    bypass: bool,
 }
 
 impl<I> Equalizer<I>
@@ -181,6 +204,7 @@ where
             update_counter: 0,
             pending_sample: None,
             reverb: Freeverb::new(sample_rate),
+            bypass: false,
         };
 
         eq.recalculate_coeffs();
@@ -191,6 +215,12 @@ where
         eq
     }
 
+    /// Skip every stage (preamp, EQ, width, mono, balance, reverb) when `bypass` is set
+    pub fn with_bypass(mut self, bypass: bool) -> Self {
+        self.bypass = bypass;
+        self
+    }
+
     fn recalculate_coeffs(&mut self) {
         let mut _coeffs_updated = false;
         if let Ok(gains) = self.gains.try_lock() {
@@ -232,6 +262,9 @@ where
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.bypass {
+            return self.input.next();
+        }
         if let Some(sample) = self.pending_sample.take() {
             return Some(sample);
         }
@@ -242,11 +275,19 @@ where
             self.update_counter = 0;
         }
 
-        let mut left = self.input.next()?;
+        // Preamp runs ahead of the filters so EQ boosts have headroom to work with
+        let preamp_db = *self.cached_gains.get(10).unwrap_or(&0.0);
+        let preamp = if preamp_db != 0.0 {
+            10.0f32.powf(preamp_db / 20.0)
+        } else {
+            1.0
+        };
+
+        let mut left = self.input.next()? * preamp;
 
         let mut right = if self.channels == 2 {
             match self.input.next() {
-                Some(s) => s,
+                Some(s) => s * preamp,
                 None => return Some(left),
             }
         } else {
@@ -274,13 +315,6 @@ where
             return Some(left);
         }
 
-        let preamp_db = *self.cached_gains.get(10).unwrap_or(&0.0);
-        if preamp_db != 0.0 {
-            let factor = 10.0f32.powf(preamp_db / 20.0);
-            left *= factor;
-            right *= factor;
-        }
-
         let width_factor = *self.cached_gains.get(12).unwrap_or(&1.0);
 
         if (width_factor - 1.0).abs() > 0.01 {
@@ -291,6 +325,13 @@ where
             right = mid - new_side;
         }
 
+        // Mono before balance, so the summed signal can be sent to one speaker
+        if *self.cached_gains.get(15).unwrap_or(&0.0) >= 0.5 {
+            let mid = (left + right) * 0.5;
+            left = mid;
+            right = mid;
+        }
+
         let balance = *self.cached_gains.get(11).unwrap_or(&0.0);
         if balance != 0.0 {
             if balance < 0.0 {
@@ -338,3 +379,23 @@ where
         self.input.total_duration()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn mono_is_summed_before_balance() {
+        let mut gains = vec![0.0; 16];
+        gains[12] = 1.0;
+        gains[11] = 0.5; // Right-leaning balance
+        gains[15] = 1.0; // Mono
+        let input = SamplesBuffer::new(2, 44_100, vec![0.8f32, 0.0]);
+
+        let out: Vec<f32> = Equalizer::new(input, Arc::new(Mutex::new(gains))).collect();
+        assert_eq!(out.len(), 2);
+        assert!((out[0] - 0.2).abs() < 1e-3, "left = {}", out[0]);
+        assert!((out[1] - 0.4).abs() < 1e-3, "right = {}", out[1]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Short gain ramps that keep play, pause, seek and stop from clicking
+//!
+//! Every source in the processing chain ends in a `ClickFade`. The audio thread
+//! flips the shared `FadeControl` between audible and silent; each source then
+//! ramps its own gain toward that target over `duration_ms`, one step per frame.
+//! Once the gain settles at 1.0 samples pass through untouched.
+
+use rodio::Source;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fade length used until `set_click_fade_ms` is called
+pub const DEFAULT_FADE_MS: u32 = 30;
+
+/// Longest accepted fade; anything longer is audible as a fade, not a de-click
+pub const MAX_FADE_MS: u32 = 200;
+
+/// Fade target and length shared by the audio thread and all live sources
+pub struct FadeControl {
+    audible: AtomicBool,
+    duration_ms: AtomicU32,
+}
+
+impl Default for FadeControl {
+    fn default() -> Self {
+        Self {
+            audible: AtomicBool::new(true),
+            duration_ms: AtomicU32::new(DEFAULT_FADE_MS),
+        }
+    }
+}
+
+impl FadeControl {
+    pub fn set_audible(&self, audible: bool) {
+        self.audible.store(audible, Ordering::Relaxed);
+    }
+
+    pub fn set_duration_ms(&self, ms: u32) {
+        self.duration_ms.store(ms.min(MAX_FADE_MS), Ordering::Relaxed);
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms.load(Ordering::Relaxed) as u64)
+    }
+}
+
+pub struct ClickFade<I> {
+    input: I,
+    control: Arc<FadeControl>,
+    gain: f32,
+    channels: u16,
+    /// Position within the current frame; the gain only moves on frame boundaries
+    channel: u16,
+}
+
+impl<I> ClickFade<I>
+where
+    I: Source<Item = f32>,
+{
+    /// `fade_in` starts the source silent and ramps it up; without it the source
+    /// starts at full gain (used for gapless appends, which must not dip).
+    pub fn new(input: I, control: Arc<FadeControl>, fade_in: bool) -> Self {
+        let channels = input.channels().max(1);
+        Self {
+            input,
+            control,
+            gain: if fade_in { 0.0 } else { 1.0 },
+            channels,
+            channel: 0,
+        }
+    }
+
+    fn step_gain(&mut self) {
+        let target = if self.control.audible.load(Ordering::Relaxed) { 1.0 } else { 0.0 };
+        if self.gain == target {
+            return;
+        }
+        let fade_ms = self.control.duration_ms.load(Ordering::Relaxed);
+        let frames = fade_ms as f32 * self.input.sample_rate() as f32 / 1000.0;
+        if frames < 1.0 {
+            self.gain = target;
+        } else if self.gain < target {
+            self.gain = (self.gain + 1.0 / frames).min(target);
+        } else {
+            self.gain = (self.gain - 1.0 / frames).max(target);
+        }
+    }
+}
+
+impl<I> Iterator for ClickFade<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.channel == 0 {
+            self.step_gain();
+        }
+        self.channel = (self.channel + 1) % self.channels;
+
+        let sample = self.input.next()?;
+        if self.gain == 1.0 {
+            Some(sample)
+        } else {
+            Some(sample * self.gain)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for ClickFade<I>
+where
+    I: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn fades_in_over_the_configured_length_per_frame() {
+        let control = Arc::new(FadeControl::default());
+        control.set_duration_ms(4);
+        // 4ms at 1 kHz is 4 frames; both channels of a frame share one gain
+        let input = SamplesBuffer::new(2, 1000, vec![1.0f32; 12]);
+
+        let out: Vec<f32> = ClickFade::new(input, control, true).collect();
+        assert_eq!(
+            out,
+            vec![0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! File extensions treated as audio by library scans, the folder watcher,
+//! `add_files` and torrent file lists
+//!
+//! Starts out as `DEFAULT_EXTENSIONS`. `set_scan_extensions` replaces the list
+//! for the running app and saves it in `settings.json`, which applies it again
+//! at startup. Scans started afterwards use the new list.
+
+use std::path::Path;
+use std::sync::RwLock;
+
+/// ALAC is picked up through `.m4a`. WMA is left out since nothing decodes it.
+pub const DEFAULT_EXTENSIONS: &[&str] = &["mp3", "flac", "wav", "ogg", "m4a", "aac", "opus", "aiff"];
+
+/// Configured extensions; `None` while the defaults apply
+static EXTENSIONS: RwLock<Option<Vec<String>>> = RwLock::new(None);
+
+/// Lowercase, without a leading dot, duplicates and blanks dropped
+pub fn normalize(extensions: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for ext in extensions {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        if !ext.is_empty() && !out.contains(&ext) {
+            out.push(ext);
+        }
+    }
+    out
+}
+
+/// Use `extensions` from now on; `None` goes back to the defaults
+pub fn set(extensions: Option<Vec<String>>) {
+    *EXTENSIONS.write().unwrap() = extensions;
+}
+
+pub fn current() -> Vec<String> {
+    match &*EXTENSIONS.read().unwrap() {
+        Some(extensions) => extensions.clone(),
+        None => DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+/// `ext` is lowercase and without the dot, as `Path::extension` lowercased gives it
+pub fn is_audio_extension(ext: &str) -> bool {
+    match &*EXTENSIONS.read().unwrap() {
+        Some(extensions) => extensions.iter().any(|e| e == ext),
+        None => DEFAULT_EXTENSIONS.contains(&ext),
+    }
+}
+
+pub fn is_audio_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| is_audio_extension(&ext.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_strips_dots_case_and_duplicates() {
+        let exts = vec![".DSF".to_string(), "dsf".to_string(), " flac ".to_string(), "".to_string()];
+        assert_eq!(normalize(exts), vec!["dsf".to_string(), "flac".to_string()]);
+    }
+}
@@ -1,17 +1,25 @@
-//! Windows System Media Transport Controls integration using souvlaki
+//! System media controls integration using souvlaki
+//!
+//! - Windows: System Media Transport Controls
+//! - Linux: MPRIS over D-Bus (zbus backend)
+//! - macOS: MPNowPlayingInfoCenter / MPRemoteCommandCenter
 //!
 //! Service pattern: Spawns a dedicated thread to manage media controls.
 //! Uses 'windows' crate for message pumping on the background thread.
 
-#[cfg(target_os = "windows")]
-use souvlaki::{MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, PlatformConfig};
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use souvlaki::{
+    MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, PlatformConfig, SeekDirection,
+};
 use std::sync::mpsc::{channel, Sender};
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+use std::sync::mpsc::Receiver;
 #[cfg(target_os = "windows")]
-use std::sync::mpsc::{Receiver, TryRecvError};
-#[cfg(target_os = "windows")]
+use std::sync::mpsc::TryRecvError;
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 use std::thread;
 use tauri::AppHandle;
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 use tauri::Emitter;
 
 #[cfg(target_os = "windows")]
@@ -21,8 +29,12 @@ use windows::Win32::UI::WindowsAndMessaging::{
     DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE, WM_QUIT,
 };
 
+/// Seconds skipped by a bare "seek forward/backward" request
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+const SEEK_STEP_SECS: f64 = 5.0;
+
 #[derive(Debug)]
-#[allow(dead_code)] // Variants unused on non-Windows
+#[allow(dead_code)] // Variants unused on platforms without media controls
 pub enum MediaCmd {
     SetMetadata {
         title: String,
@@ -35,12 +47,12 @@ pub enum MediaCmd {
     Shutdown,
 }
 
-#[allow(dead_code)] // Struct unused on non-Windows
+#[allow(dead_code)] // Struct unused on platforms without media controls
 pub struct MediaControlService;
 
-#[allow(dead_code)] // Methods unused on non-Windows
+#[allow(dead_code)] // Methods unused on platforms without media controls
 impl MediaControlService {
-    #[cfg(target_os = "windows")]
+    #[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
     pub fn start(app: AppHandle, hwnd: isize) -> Sender<MediaCmd> {
         let (tx, rx) = channel::<MediaCmd>();
 
@@ -53,7 +65,7 @@ impl MediaControlService {
         tx
     }
 
-    #[cfg(not(target_os = "windows"))]
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
     pub fn start(_app: AppHandle, _hwnd: isize) -> Sender<MediaCmd> {
         let (tx, _) = channel::<MediaCmd>();
         tx
@@ -131,19 +143,7 @@ impl MediaControlService {
         // Attach event handler
         let app_clone = app.clone();
         controls
-            .attach(move |event: MediaControlEvent| {
-                let event_name = match event {
-                    MediaControlEvent::Play | MediaControlEvent::Toggle => "media:play",
-                    MediaControlEvent::Pause => "media:pause",
-                    MediaControlEvent::Next => "media:next",
-                    MediaControlEvent::Previous => "media:prev",
-                    MediaControlEvent::Stop => "media:stop",
-                    _ => return,
-                };
-
-                // Emit event to frontend
-                let _ = app_clone.emit(event_name, ());
-            })
+            .attach(move |event: MediaControlEvent| forward_event(&app_clone, event))
             .map_err(|e| format!("Failed to attach event handler: {:?}", e))?;
 
         // Loop handling commands AND pumping Windows messages
@@ -151,31 +151,11 @@ impl MediaControlService {
             // 1. Process all pending commands from channel non-blocking
             loop {
                 match rx.try_recv() {
-                    Ok(MediaCmd::SetMetadata {
-                        title,
-                        artist,
-                        album,
-                    }) => {
-                        let _ = controls.set_metadata(MediaMetadata {
-                            title: Some(&title),
-                            artist: Some(&artist),
-                            album: Some(&album),
-                            ..Default::default()
-                        });
-                    }
-                    Ok(MediaCmd::SetPlaying) => {
-                        let _ = controls.set_playback(MediaPlayback::Playing { progress: None });
-                        crate::taskbar_controls::update_play_status(true);
-                    }
-                    Ok(MediaCmd::SetPaused) => {
-                        let _ = controls.set_playback(MediaPlayback::Paused { progress: None });
-                        crate::taskbar_controls::update_play_status(false);
-                    }
-                    Ok(MediaCmd::SetStopped) => {
-                        let _ = controls.set_playback(MediaPlayback::Stopped);
-                        crate::taskbar_controls::update_play_status(false);
+                    Ok(cmd) => {
+                        if !apply_cmd(&mut controls, cmd) {
+                            return Ok(());
+                        }
                     }
-                    Ok(MediaCmd::Shutdown) => return Ok(()),
                     Err(TryRecvError::Empty) => break,
                     Err(TryRecvError::Disconnected) => return Ok(()),
                 }
@@ -199,4 +179,91 @@ impl MediaControlService {
             thread::sleep(std::time::Duration::from_millis(20));
         }
     }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn run_loop(app: AppHandle, _hwnd: isize, rx: Receiver<MediaCmd>) -> Result<(), String> {
+        let config = PlatformConfig {
+            dbus_name: "vibeon",  // Must be alphanumeric for MPRIS (no underscores/hyphens)
+            display_name: "VIBE-ON!",
+            hwnd: None,
+        };
+
+        let mut controls = MediaControls::new(config)
+            .map_err(|e| format!("Failed to create media controls: {:?}", e))?;
+
+        let app_clone = app.clone();
+        controls
+            .attach(move |event: MediaControlEvent| forward_event(&app_clone, event))
+            .map_err(|e| format!("Failed to attach event handler: {:?}", e))?;
+
+        // Remote commands are delivered by the D-Bus thread (Linux) or the app's main
+        // run loop (macOS), so this thread only has to forward state updates
+        while let Ok(cmd) = rx.recv() {
+            if !apply_cmd(&mut controls, cmd) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Emit a media key / remote control event to the frontend
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+fn forward_event(app: &AppHandle, event: MediaControlEvent) {
+    let seek_secs = |direction: SeekDirection, secs: f64| match direction {
+        SeekDirection::Forward => secs,
+        SeekDirection::Backward => -secs,
+    };
+
+    let _ = match event {
+        MediaControlEvent::Play | MediaControlEvent::Toggle => app.emit("media:play", ()),
+        MediaControlEvent::Pause => app.emit("media:pause", ()),
+        MediaControlEvent::Next => app.emit("media:next", ()),
+        MediaControlEvent::Previous => app.emit("media:prev", ()),
+        MediaControlEvent::Stop => app.emit("media:stop", ()),
+        MediaControlEvent::SetPosition(position) => app.emit("media:seek", position.0.as_secs_f64()),
+        MediaControlEvent::Seek(direction) => {
+            app.emit("media:seek_by", seek_secs(direction, SEEK_STEP_SECS))
+        }
+        MediaControlEvent::SeekBy(direction, amount) => {
+            app.emit("media:seek_by", seek_secs(direction, amount.as_secs_f64()))
+        }
+        _ => return,
+    };
+}
+
+/// Apply a command to the platform controls; `false` means shut down
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
+fn apply_cmd(controls: &mut MediaControls, cmd: MediaCmd) -> bool {
+    match cmd {
+        MediaCmd::SetMetadata {
+            title,
+            artist,
+            album,
+        } => {
+            let _ = controls.set_metadata(MediaMetadata {
+                title: Some(&title),
+                artist: Some(&artist),
+                album: Some(&album),
+                ..Default::default()
+            });
+        }
+        MediaCmd::SetPlaying => {
+            let _ = controls.set_playback(MediaPlayback::Playing { progress: None });
+            #[cfg(target_os = "windows")]
+            crate::taskbar_controls::update_play_status(true);
+        }
+        MediaCmd::SetPaused => {
+            let _ = controls.set_playback(MediaPlayback::Paused { progress: None });
+            #[cfg(target_os = "windows")]
+            crate::taskbar_controls::update_play_status(false);
+        }
+        MediaCmd::SetStopped => {
+            let _ = controls.set_playback(MediaPlayback::Stopped);
+            #[cfg(target_os = "windows")]
+            crate::taskbar_controls::update_play_status(false);
+        }
+        MediaCmd::Shutdown => return false,
+    }
+    true
 }
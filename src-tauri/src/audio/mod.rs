@@ -1,11 +1,21 @@
+pub mod decoder;
 pub mod equalizer;
+pub mod fade;
+pub mod formats;
 pub mod media_controls;
+pub mod output;
 pub mod player;
+pub mod replaygain;
 pub mod reverb;
+pub mod silence;
 pub mod state;
+pub mod stream;
+pub mod waveform;
+#[cfg(windows)]
+pub mod wasapi;
 
 pub use media_controls::MediaCmd;
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 pub use media_controls::MediaControlService;
 pub use player::AudioPlayer;
 pub use state::{PlayerState, TrackInfo};
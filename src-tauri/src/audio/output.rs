@@ -0,0 +1,140 @@
+//! Output device streams
+//!
+//! rodio's own `OutputStream` only prints cpal stream errors, so a device that
+//! goes away can't be told apart from silence. This opens the cpal stream
+//! itself, fed from a rodio mixer like rodio does, and records a lost device
+//! from the stream's error callback.
+//!
+//! On Windows the same mixer can instead feed a WASAPI exclusive stream, see
+//! `wasapi`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, StreamError};
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::Sink;
+
+use super::decoder::PcmFormat;
+
+/// An open output device. Dropping it closes the device, and sinks created
+/// from its handle go silent.
+pub struct OutputStream {
+    _stream: Stream,
+    lost: Arc<AtomicBool>,
+}
+
+/// Only held, so the device stays open until the `OutputStream` is dropped
+#[allow(dead_code)]
+enum Stream {
+    Shared(cpal::Stream),
+    #[cfg(windows)]
+    Exclusive(super::wasapi::ExclusiveStream),
+}
+
+/// Creates sinks that play on an `OutputStream`
+pub struct OutputStreamHandle {
+    mixer: Arc<DynamicMixerController<f32>>,
+}
+
+impl OutputStream {
+    /// Open the default device in its default format
+    pub fn try_default() -> Result<(Self, OutputStreamHandle), String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No audio output device")?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| format!("Cannot query the default output format: {}", e))?;
+        Self::try_from_device_config(&device, config)
+    }
+
+    pub fn try_from_device_config(
+        device: &cpal::Device,
+        config: cpal::SupportedStreamConfig,
+    ) -> Result<(Self, OutputStreamHandle), String> {
+        let (mixer, source) = dynamic_mixer::mixer::<f32>(config.channels(), config.sample_rate().0);
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream_config = config.config();
+        let stream = match config.sample_format() {
+            SampleFormat::I16 => build::<i16>(device, &stream_config, source, lost.clone()),
+            SampleFormat::U16 => build::<u16>(device, &stream_config, source, lost.clone()),
+            SampleFormat::I32 => build::<i32>(device, &stream_config, source, lost.clone()),
+            SampleFormat::F32 => build::<f32>(device, &stream_config, source, lost.clone()),
+            format => return Err(format!("Unsupported output sample format {}", format)),
+        }
+        .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        Ok((
+            Self {
+                _stream: Stream::Shared(stream),
+                lost,
+            },
+            OutputStreamHandle { mixer },
+        ))
+    }
+
+    /// Open the default device in exclusive mode at exactly `format`. Fails
+    /// if the device doesn't accept the format as-is, and off Windows.
+    #[cfg(windows)]
+    pub fn try_exclusive(format: PcmFormat) -> Result<(Self, OutputStreamHandle), String> {
+        let (mixer, source) = dynamic_mixer::mixer::<f32>(format.channels, format.sample_rate);
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = super::wasapi::ExclusiveStream::open(format, source, lost.clone())?;
+        Ok((
+            Self {
+                _stream: Stream::Exclusive(stream),
+                lost,
+            },
+            OutputStreamHandle { mixer },
+        ))
+    }
+
+    #[cfg(not(windows))]
+    pub fn try_exclusive(_format: PcmFormat) -> Result<(Self, OutputStreamHandle), String> {
+        Err("Exclusive output is only available on Windows".to_string())
+    }
+
+    /// True once cpal reported the device as gone (unplugged, disabled, driver
+    /// reset). Other stream errors are only logged.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+}
+
+impl OutputStreamHandle {
+    /// New sink playing on this output
+    pub fn new_sink(&self) -> Sink {
+        let (sink, queue) = Sink::new_idle();
+        self.mixer.add(queue);
+        sink
+    }
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut source: DynamicMixer<f32>,
+    lost: Arc<AtomicBool>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    device.build_output_stream::<T, _, _>(
+        config,
+        move |data: &mut [T], _| {
+            for sample in data.iter_mut() {
+                *sample = T::from_sample(source.next().unwrap_or(0.0));
+            }
+        },
+        move |err| {
+            eprintln!("[Audio] Output stream error: {}", err);
+            if matches!(err, StreamError::DeviceNotAvailable) {
+                lost.store(true, Ordering::Relaxed);
+            }
+        },
+        None,
+    )
+}
@@ -1,19 +1,41 @@
-use std::fs::File;
-use std::io::BufReader;
 use std::path::Path;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use lofty::prelude::*;
 use lofty::probe::Probe;
-use rodio::{Decoder, OutputStream, Sink, Source};
+use rodio::{Sink, Source};
 
+use super::decoder::{ExactDecoder, PcmFormat, TrackDecoder};
 use super::equalizer::Equalizer;
-use super::state::{PlayerState, PlayerStatus, TrackInfo};
+use super::fade::{ClickFade, FadeControl};
+use super::output::{OutputStream, OutputStreamHandle};
+use super::replaygain::{self, NormalizationMode};
+use super::silence;
+use super::stream::{self, LiveTitle, TrackReader};
+use super::state::{LoopRegion, PlayerState, PlayerStatus, SilenceTrim, TrackInfo, VolumeCurve};
 use std::sync::Mutex;
 
+/// Decoder -> ReplayGain -> Equalizer -> click fade, as appended to a sink
+type ProcessedSource = ClickFade<Equalizer<rodio::source::Amplify<TrackDecoder>>>;
+
+/// Slots in the shared gains vector read by the `Equalizer`
+const PREAMP_SLOT: usize = 10;
+const BALANCE_SLOT: usize = 11;
+const MONO_SLOT: usize = 15;
+
+/// Accepted pre-amp range in dB: enough cut to tame stacked EQ boosts, and a
+/// little boost for quiet masters
+pub const PREAMP_RANGE_DB: (f32, f32) = (-24.0, 12.0);
+
+/// How far ahead of the end the next track is appended for gapless playback
+const GAPLESS_PRELOAD_SECS: f64 = 3.0;
+
+/// Delay between attempts to reopen output while no device is available
+const OUTPUT_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Commands sent to the audio thread
 pub enum AudioCommand {
     Play(TrackInfo),
@@ -30,6 +52,17 @@ pub enum AudioCommand {
     SetEqAll(Vec<f32>), // All band gains at once
     SetSpeed(f32),
     SetReverb(f32, f32), // mix (0-1), decay (0-1)
+    SetCrossfade(f32),   // overlap window in seconds (0 = hard cut)
+    SetNextTrack(Option<TrackInfo>), // Track to fade into when the current one ends
+    SetNormalization(NormalizationMode, f32), // mode, default gain (dB) for untagged tracks
+    SetGapless(bool),
+    SetAbLoop(LoopRegion),
+    ClearAbLoop,
+    SetExclusiveOutput(bool, Sender<Result<(), String>>),
+    SetSkipSilence(bool),
+    SetClickFade(u32), // milliseconds, 0 disables
+    SetVolumeCurve(VolumeCurve),
+    StopAfterTrack(Option<Sender<()>>), // Notified once paused at the end; None withdraws
 }
 
 /// Thread-safe handle to the audio player
@@ -37,6 +70,11 @@ pub struct AudioPlayer {
     command_tx: Sender<AudioCommand>,
     _thread: JoinHandle<()>,
     eq_gains: Arc<Mutex<Vec<f32>>>,
+    /// Set by the audio thread when it lost its output device or couldn't
+    /// open it for a track in exclusive mode, until taken
+    device_error: Arc<Mutex<Option<String>>>,
+    /// Set by the audio thread when a radio stream announced a new title, until taken
+    live_update: Arc<Mutex<Option<TrackInfo>>>,
 }
 
 impl AudioPlayer {
@@ -50,14 +88,26 @@ impl AudioPlayer {
         // 10: Preamp (0dB)
         // 11: Balance (0.0)
         // 12: Stereo Width (1.0 default)
-        let mut initial_gains = vec![0.0; 15];
+        // 13-14: Reverb mix / decay
+        // 15: Mono downmix (0 off, 1 on)
+        let mut initial_gains = vec![0.0; 16];
         initial_gains[12] = 1.0;
 
         let eq_gains = Arc::new(Mutex::new(initial_gains));
         let eq_gains_clone = eq_gains.clone();
+        let device_error = Arc::new(Mutex::new(None));
+        let device_error_clone = device_error.clone();
+        let live_update = Arc::new(Mutex::new(None));
+        let live_update_clone = live_update.clone();
 
         let thread = thread::spawn(move || {
-            AudioThread::run(command_rx, init_tx, eq_gains_clone);
+            AudioThread::run(
+                command_rx,
+                init_tx,
+                eq_gains_clone,
+                device_error_clone,
+                live_update_clone,
+            );
         });
 
         // Wait for initialization to complete
@@ -66,6 +116,8 @@ impl AudioPlayer {
                 command_tx,
                 _thread: thread,
                 eq_gains,
+                device_error,
+                live_update,
             }),
             Ok(Err(e)) => Err(format!("Audio initialization failed: {}", e)),
             Err(_) => Err("Audio thread panicked during initialization".to_string()),
@@ -142,12 +194,15 @@ impl AudioPlayer {
             .map_err(|e| format!("Failed to send mute command: {}", e))
     }
     pub fn get_status(&self) -> PlayerStatus {
+        self.request_status().recv().unwrap_or_default()
+    }
+
+    /// Ask for the status without waiting for it. The audio thread answers
+    /// after the commands sent before, e.g. once a stream is open.
+    pub fn request_status(&self) -> Receiver<PlayerStatus> {
         let (tx, rx) = channel();
-        if self.command_tx.send(AudioCommand::GetStatus(tx)).is_ok() {
-            rx.recv().unwrap_or_default()
-        } else {
-            PlayerStatus::default()
-        }
+        let _ = self.command_tx.send(AudioCommand::GetStatus(tx));
+        rx
     }
 
     pub fn set_eq(&self, band: usize, gain: f32) -> Result<(), String> {
@@ -192,6 +247,146 @@ impl AudioPlayer {
             .map_err(|e| format!("Failed to send reverb command: {}", e))
     }
 
+    /// Master gain ahead of the EQ, clamped to `PREAMP_RANGE_DB`
+    pub fn set_preamp(&self, db: f32) -> Result<(), String> {
+        if !db.is_finite() {
+            return Err("Invalid preamp gain".to_string());
+        }
+        self.set_eq(PREAMP_SLOT, db.clamp(PREAMP_RANGE_DB.0, PREAMP_RANGE_DB.1))
+    }
+
+    /// Left/right balance: -1.0 is full left, 1.0 full right
+    pub fn set_balance(&self, balance: f32) -> Result<(), String> {
+        if !balance.is_finite() {
+            return Err("Invalid balance".to_string());
+        }
+        self.set_eq(BALANCE_SLOT, balance.clamp(-1.0, 1.0))
+    }
+
+    /// Sum both channels into mono (applied before balance, so it can be panned)
+    pub fn set_mono(&self, enabled: bool) -> Result<(), String> {
+        self.set_eq(MONO_SLOT, if enabled { 1.0 } else { 0.0 })
+    }
+
+    /// Set the crossfade window. 0 disables crossfading (hard cut between tracks).
+    pub fn set_crossfade(&self, duration_secs: f32) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetCrossfade(duration_secs))
+            .map_err(|e| format!("Failed to send crossfade command: {}", e))
+    }
+
+    /// Select ReplayGain normalization: "off", "track" or "album".
+    /// Applied when the next track starts.
+    pub fn set_normalization_mode(&self, mode: NormalizationMode, default_gain_db: f32) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetNormalization(mode, default_gain_db))
+            .map_err(|e| format!("Failed to send normalization command: {}", e))
+    }
+
+    /// Enable gapless playback: the next track is appended to the current sink
+    /// shortly before the end, if it shares the current sample rate.
+    pub fn set_gapless(&self, enabled: bool) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetGapless(enabled))
+            .map_err(|e| format!("Failed to send gapless command: {}", e))
+    }
+
+    /// Repeat the section between `start_secs` and `end_secs` of the current track
+    pub fn set_ab_loop(&self, start_secs: f64, end_secs: f64) -> Result<(), String> {
+        if !start_secs.is_finite() || !end_secs.is_finite() || start_secs < 0.0 {
+            return Err("Invalid A-B loop bounds".to_string());
+        }
+        if start_secs >= end_secs {
+            return Err(format!(
+                "A-B loop start ({:.2}s) must be before end ({:.2}s)",
+                start_secs, end_secs
+            ));
+        }
+        self.command_tx
+            .send(AudioCommand::SetAbLoop(LoopRegion {
+                start_secs,
+                end_secs,
+            }))
+            .map_err(|e| format!("Failed to send A-B loop command: {}", e))
+    }
+
+    pub fn clear_ab_loop(&self) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::ClearAbLoop)
+            .map_err(|e| format!("Failed to send clear A-B loop command: {}", e))
+    }
+
+    /// Exclusive output (Windows): hold the device in WASAPI exclusive mode at
+    /// each track's sample rate and bit depth, with ReplayGain, the DSP chain,
+    /// speed and volume bypassed. Fails if the device can't play the current
+    /// track's format, or off Windows.
+    pub fn set_exclusive_output(&self, enabled: bool) -> Result<(), String> {
+        let (tx, rx) = channel();
+        self.command_tx
+            .send(AudioCommand::SetExclusiveOutput(enabled, tx))
+            .map_err(|e| format!("Failed to send exclusive output command: {}", e))?;
+        rx.recv()
+            .map_err(|_| "Audio thread did not respond".to_string())?
+    }
+
+    /// Skip leading and trailing silence longer than a second
+    pub fn set_skip_silence(&self, enabled: bool) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetSkipSilence(enabled))
+            .map_err(|e| format!("Failed to send skip silence command: {}", e))
+    }
+
+    /// Length of the de-click fades on play, pause, resume, seek and stop. 0 disables them.
+    pub fn set_click_fade_ms(&self, ms: u32) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetClickFade(ms))
+            .map_err(|e| format!("Failed to send click fade command: {}", e))
+    }
+
+    /// Curve the volume slider maps through
+    pub fn set_volume_curve(&self, curve: VolumeCurve) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetVolumeCurve(curve))
+            .map_err(|e| format!("Failed to send volume curve command: {}", e))
+    }
+
+    /// Pause at the end of the current track instead of moving on to the next
+    /// one. The receiver gets a message once playback paused there. Replaces an
+    /// earlier request.
+    pub fn stop_after_track(&self) -> Result<Receiver<()>, String> {
+        let (tx, rx) = channel();
+        self.command_tx
+            .send(AudioCommand::StopAfterTrack(Some(tx)))
+            .map_err(|e| format!("Failed to send stop after track command: {}", e))?;
+        Ok(rx)
+    }
+
+    /// Withdraw `stop_after_track`
+    pub fn cancel_stop_after_track(&self) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::StopAfterTrack(None))
+            .map_err(|e| format!("Failed to send stop after track command: {}", e))
+    }
+
+    /// The error that made the audio thread pause after losing its output
+    /// device, if one happened since the last call
+    pub fn take_device_error(&self) -> Option<String> {
+        self.device_error.lock().ok().and_then(|mut error| error.take())
+    }
+
+    /// The current track with its new title, if the radio stream playing
+    /// announced one since the last call
+    pub fn take_live_update(&self) -> Option<TrackInfo> {
+        self.live_update.lock().ok().and_then(|mut track| track.take())
+    }
+
+    /// Tell the audio thread which track follows the current one, so it can be
+    /// faded in before the current track ends. `None` means there is no next track.
+    pub fn set_next_track(&self, track: Option<TrackInfo>) -> Result<(), String> {
+        self.command_tx
+            .send(AudioCommand::SetNextTrack(track))
+            .map_err(|e| format!("Failed to send next track command: {}", e))
+    }
 }
 
 impl Drop for AudioPlayer {
@@ -203,15 +398,83 @@ impl Drop for AudioPlayer {
 /// The actual audio thread that owns the non-Send types
 struct AudioThread {
     sink: Option<Sink>,
-    _stream: OutputStream,
+    /// `None` only while no device could be opened
+    output: Option<OutputStream>,
     state: PlayerState,
     current_track: Option<TrackInfo>,
     current_path: Option<String>, // Store path for seek reload
     volume: f32,
+    volume_curve: VolumeCurve,
     muted: bool,
     play_start_time: Option<Instant>,
     accumulated_time: f64,
     eq_gains: Arc<Mutex<Vec<f32>>>,
+    crossfade_secs: f32,
+    next_track: Option<TrackInfo>,
+    /// Outgoing sink while a crossfade is in progress
+    fading_sink: Option<Sink>,
+    fade_start: Option<Instant>,
+    normalization: NormalizationMode,
+    default_gain_db: f32,
+    /// ReplayGain factor of the current track, reused when seeking reloads the file
+    current_gain: f32,
+    current_sample_rate: u32,
+    gapless: bool,
+    /// Next track already appended to the sink, waiting for the current one to drain
+    gapless_pending: Option<(TrackInfo, f32, u32)>,
+    /// `(current, next)` paths whose sample rates don't match; that next track
+    /// starts as a normal track change instead
+    gapless_rejected: Option<(String, String)>,
+    ab_loop: Option<LoopRegion>,
+    exclusive_output: bool,
+    /// Format the device is held at in exclusive mode; `None` for the shared mix format
+    output_format: Option<PcmFormat>,
+    source_format: SourceFormat,
+    skip_silence: bool,
+    silence_trim: Option<SilenceTrim>,
+    /// Result of the detection running for the current track
+    silence_rx: Option<Receiver<Option<SilenceTrim>>>,
+    fade: Arc<FadeControl>,
+    /// Set while the output device is gone; holds the last reopen attempt
+    output_lost: Option<Instant>,
+    device_error: Arc<Mutex<Option<String>>>,
+    /// False for remote streams whose server doesn't accept range requests
+    seekable: bool,
+    /// Title slot of the radio stream playing, if any
+    live_title: Option<LiveTitle>,
+    live_update: Arc<Mutex<Option<TrackInfo>>>,
+    /// Set while playback should pause at the end of the current track; notified then
+    stop_after_track: Option<Sender<()>>,
+}
+
+/// Sample rate, bit depth and channel count of the file being played
+#[derive(Debug, Clone, Copy, Default)]
+struct SourceFormat {
+    sample_rate: Option<u32>,
+    bit_depth: Option<u8>,
+    channels: Option<u16>,
+}
+
+impl SourceFormat {
+    fn probe(path: &Path) -> Self {
+        match Probe::open(path).and_then(|p| p.read()) {
+            Ok(tagged_file) => {
+                let properties = tagged_file.properties();
+                Self {
+                    sample_rate: properties.sample_rate(),
+                    bit_depth: properties.bit_depth(),
+                    channels: properties.channels().map(u16::from),
+                }
+            }
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// Format a file decodes to at full precision
+fn exclusive_format(path: &Path) -> Option<PcmFormat> {
+    let reader = TrackReader::open(&path.to_string_lossy()).ok()?;
+    ExactDecoder::new(reader).ok().map(|decoder| decoder.format())
 }
 
 impl AudioThread {
@@ -219,6 +482,8 @@ impl AudioThread {
         command_rx: Receiver<AudioCommand>,
         init_tx: std::sync::mpsc::SyncSender<Result<(), String>>,
         eq_gains: Arc<Mutex<Vec<f32>>>,
+        device_error: Arc<Mutex<Option<String>>>,
+        live_update: Arc<Mutex<Option<TrackInfo>>>,
     ) {
         // Initialize audio output on this thread
         let (stream, stream_handle) = match OutputStream::try_default() {
@@ -231,8 +496,9 @@ impl AudioThread {
             }
         };
 
-        // Store stream_handle for creating sinks
-        let stream_handle = Arc::new(stream_handle);
+        // Store stream_handle for creating sinks. Replaced when exclusive
+        // output reopens the device at another format.
+        let mut stream_handle = Arc::new(stream_handle);
 
         // Signal success
         if let Err(e) = init_tx.send(Ok(())) {
@@ -242,22 +508,58 @@ impl AudioThread {
 
         let mut audio = AudioThread {
             sink: None,
-            _stream: stream,
+            output: Some(stream),
             state: PlayerState::Stopped,
             current_track: None,
             current_path: None,
             volume: 1.0,
+            volume_curve: VolumeCurve::default(),
             muted: false,
             play_start_time: None,
             accumulated_time: 0.0,
             eq_gains,
+            crossfade_secs: 0.0,
+            next_track: None,
+            fading_sink: None,
+            fade_start: None,
+            normalization: NormalizationMode::Off,
+            default_gain_db: 0.0,
+            current_gain: 1.0,
+            current_sample_rate: 0,
+            gapless: false,
+            gapless_pending: None,
+            gapless_rejected: None,
+            ab_loop: None,
+            exclusive_output: false,
+            output_format: None,
+            source_format: SourceFormat::default(),
+            skip_silence: false,
+            silence_trim: None,
+            silence_rx: None,
+            fade: Arc::new(FadeControl::default()),
+            output_lost: None,
+            device_error,
+            seekable: true,
+            live_title: None,
+            live_update,
+            stop_after_track: None,
         };
 
         loop {
-            // Use timeout to allow polling for track completion
-            match command_rx.recv_timeout(std::time::Duration::from_millis(100)) {
+            // Use timeout to allow polling for track completion.
+            // Poll faster while crossfading so the volume ramp stays smooth,
+            // and while an A-B loop is active so the jump back lands close to B.
+            let poll_ms = if audio.fading_sink.is_some() || audio.ab_loop.is_some() {
+                20
+            } else {
+                100
+            };
+            match command_rx.recv_timeout(std::time::Duration::from_millis(poll_ms)) {
                 Ok(AudioCommand::Play(track)) => {
-                    audio.handle_play(track, &stream_handle);
+                    match audio.match_output_format(Path::new(&track.path), &mut stream_handle) {
+                        Ok(()) => audio.handle_play(track, &stream_handle),
+                        Err(e) => audio.report_device_error(e),
+                    }
                 }
                 Ok(AudioCommand::Load(track)) => {
                     audio.handle_load(track);
@@ -317,7 +619,65 @@ impl AudioThread {
                     }
                     println!("[AudioThread] Reverb set: mix={}, decay={}", mix, decay);
                 }
+                Ok(AudioCommand::SetCrossfade(secs)) => {
+                    audio.crossfade_secs = secs.clamp(0.0, 12.0);
+                    println!("[AudioThread] Crossfade set: {}s", audio.crossfade_secs);
+                }
+                Ok(AudioCommand::SetNextTrack(track)) => {
+                    audio.next_track = track;
+                }
+                Ok(AudioCommand::SetNormalization(mode, default_gain_db)) => {
+                    audio.normalization = mode;
+                    audio.default_gain_db = default_gain_db;
+                    println!(
+                        "[AudioThread] Normalization set: {:?} (default {} dB)",
+                        mode, default_gain_db
+                    );
+                }
+                Ok(AudioCommand::SetGapless(enabled)) => {
+                    audio.gapless = enabled;
+                    println!("[AudioThread] Gapless playback: {}", enabled);
+                }
+                Ok(AudioCommand::SetAbLoop(region)) => {
+                    println!(
+                        "[AudioThread] A-B loop set: {:.2}s -> {:.2}s",
+                        region.start_secs, region.end_secs
+                    );
+                    audio.ab_loop = Some(region);
+                }
+                Ok(AudioCommand::ClearAbLoop) => {
+                    audio.ab_loop = None;
+                }
+                Ok(AudioCommand::SetExclusiveOutput(enabled, reply)) => {
+                    let result = audio.handle_set_exclusive_output(enabled, &mut stream_handle);
+                    let _ = reply.send(result);
+                }
+                Ok(AudioCommand::SetSkipSilence(enabled)) => {
+                    audio.handle_set_skip_silence(enabled);
+                }
+                Ok(AudioCommand::SetClickFade(ms)) => {
+                    audio.fade.set_duration_ms(ms);
+                }
+                Ok(AudioCommand::SetVolumeCurve(curve)) => {
+                    audio.handle_set_volume_curve(curve);
+                }
+                Ok(AudioCommand::StopAfterTrack(done)) => {
+                    audio.stop_after_track = done;
+                }
                 Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    if audio.output.as_ref().is_none_or(OutputStream::is_lost)
+                        || audio.output_lost.is_some()
+                    {
+                        if let Some(handle) = audio.recover_output() {
+                            stream_handle = handle;
+                        }
+                    }
+                    audio.update_silence_trim(&stream_handle);
+                    audio.update_ab_loop(&stream_handle);
+                    audio.update_crossfade(&stream_handle);
+                    audio.update_gapless();
+                    audio.update_live_title();
+
                     // Check if track finished
                     if audio.state == PlayerState::Playing {
                         if let Some(ref sink) = audio.sink {
@@ -329,7 +689,11 @@ impl AudioThread {
 
                             if elapsed > 500 && sink.empty() {
                                 println!("[Audio] Track finished naturally");
-                                audio.handle_stop();
+                                if audio.stop_after_track.is_some() {
+                                    audio.pause_at_track_end();
+                                } else {
+                                    audio.handle_stop();
+                                }
                             }
                         }
                     }
@@ -341,65 +705,681 @@ impl AudioThread {
         }
     }
 
-    fn handle_play(&mut self, track: TrackInfo, stream_handle: &Arc<rodio::OutputStreamHandle>) {
+    /// Handle a lost output device: pause, report the error, and reopen the
+    /// default device with the track loaded there, paused. Retried every
+    /// `OUTPUT_RETRY_INTERVAL` while no device can be opened. Returns the new
+    /// stream handle once output is back.
+    fn recover_output(&mut self) -> Option<Arc<OutputStreamHandle>> {
+        match self.output_lost {
+            Some(at) if at.elapsed() < OUTPUT_RETRY_INTERVAL => return None,
+            Some(_) => {}
+            None => self.pause_for_lost_output(),
+        }
+        self.output_lost = Some(Instant::now());
+
+        // Release the old stream first, in case it still holds the device
+        self.output = None;
+
+        // Exclusive output stays at the track's own format. If the new device
+        // can't take it, exclusive mode is turned off and that is reported.
+        let exclusive = self
+            .current_path
+            .as_deref()
+            .filter(|_| self.exclusive_output)
+            .and_then(|path| exclusive_format(Path::new(path)))
+            .map(|format| (format, OutputStream::try_exclusive(format)));
+        let (stream, handle, format) = match exclusive {
+            Some((format, Ok((stream, handle)))) => (stream, handle, Some(format)),
+            exclusive => {
+                let (stream, handle) = match OutputStream::try_default() {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        eprintln!("[Audio] Failed to open audio device: {}; retrying", e);
+                        return None;
+                    }
+                };
+                if let Some((_, Err(e))) = exclusive {
+                    self.exclusive_output = false;
+                    self.report_device_error(format!("Exclusive output turned off: {}", e));
+                }
+                (stream, handle, None)
+            }
+        };
+
+        self.output = Some(stream);
+        self.output_format = format;
+        self.output_lost = None;
+        let handle = Arc::new(handle);
+        println!("[Audio] Output reopened on the default device");
+
+        // Rebuild the sink on the new stream at the saved position, still paused
+        if let Some(track) = self.current_track.clone() {
+            let position = self.accumulated_time;
+            let ab_loop = self.ab_loop;
+            self.handle_load(track);
+            self.ab_loop = ab_loop;
+            self.handle_seek(position, Some(&handle));
+        }
+        Some(handle)
+    }
+
+    /// Pause where playback stopped and report the error
+    fn pause_for_lost_output(&mut self) {
+        let position = self.get_status().position_secs;
+        let message = "Audio output device was disconnected".to_string();
+        eprintln!("[Audio] {} at {:.1}s; pausing", message, position);
+
+        // Nothing consumes the old sink any more, so there is nothing to fade
+        self.fade.set_audible(false);
+        self.finish_crossfade();
+        if let Some((next, _, _)) = self.gapless_pending.take() {
+            self.next_track = Some(next);
+        }
+        self.sink = None;
+        self.play_start_time = None;
+        self.accumulated_time = position;
+        if self.current_track.is_some() {
+            self.state = PlayerState::Paused;
+        }
+        if let Ok(mut error) = self.device_error.lock() {
+            *error = Some(message);
+        }
+    }
+
+    /// Hand an error to the frontend without pausing anything
+    fn report_device_error(&mut self, message: String) {
+        eprintln!("[Audio] {}", message);
+        if let Ok(mut error) = self.device_error.lock() {
+            *error = Some(message);
+        }
+    }
+
+    /// In exclusive mode, reopen the device when `path` decodes to a different
+    /// format than the current stream, replacing `stream_handle`. Errors if the
+    /// device can't play the format; the track must not be played then.
+    fn match_output_format(
+        &mut self,
+        path: &Path,
+        stream_handle: &mut Arc<OutputStreamHandle>,
+    ) -> Result<(), String> {
+        if !self.exclusive_output {
+            return Ok(());
+        }
+        let format = exclusive_format(path)
+            .ok_or_else(|| format!("Cannot decode {} for exclusive output", path.display()))?;
+        if self.output_format == Some(format) {
+            return Ok(());
+        }
+        self.reopen_output(Some(format), stream_handle)
+    }
+
+    /// Switch exclusive output on or off, restarting the current track on the
+    /// new stream at the same position. If the device can't play the current
+    /// track exclusively, it carries on in shared mode and the error is returned.
+    fn handle_set_exclusive_output(
+        &mut self,
+        enabled: bool,
+        stream_handle: &mut Arc<OutputStreamHandle>,
+    ) -> Result<(), String> {
+        if enabled == self.exclusive_output {
+            return Ok(());
+        }
+        if enabled && !cfg!(windows) {
+            return Err("Exclusive output is only available on Windows".to_string());
+        }
+
+        let resume = self.current_track.clone().map(|track| {
+            let status = self.get_status();
+            (track, status.position_secs, status.state)
+        });
+        let format = match resume.as_ref() {
+            Some((track, _, _)) if enabled => Some(
+                exclusive_format(Path::new(&track.path))
+                    .ok_or("Cannot decode the current track for exclusive output")?,
+            ),
+            // Nothing playing yet: the device is reopened when a track starts
+            None if enabled => {
+                self.exclusive_output = true;
+                return Ok(());
+            }
+            _ => None,
+        };
+
+        let result = self.reopen_output(format, stream_handle);
+        if result.is_ok() {
+            self.exclusive_output = enabled;
+            println!("[Audio] Exclusive output: {}", enabled);
+        }
+        if let Some((track, position, state)) = resume.filter(|_| self.output.is_some()) {
+            self.handle_play(track, stream_handle);
+            if position > 0.0 {
+                self.handle_seek(position, Some(stream_handle));
+            }
+            if state != PlayerState::Playing {
+                self.handle_pause();
+            }
+        }
+        result
+    }
+
+    /// Stop playback and reopen the default device: exclusively at `format`,
+    /// or shared for `None`. A device refusing `format` is reopened shared, and
+    /// the error returned.
+    fn reopen_output(
+        &mut self,
+        format: Option<PcmFormat>,
+        stream_handle: &mut Arc<OutputStreamHandle>,
+    ) -> Result<(), String> {
+        // Sinks on the old stream go silent once it is dropped, and the device
+        // has to be released before it can be opened in another mode
+        self.handle_stop();
+        self.output = None;
+        self.output_format = None;
+
+        let exclusive = format.map(|format| OutputStream::try_exclusive(format).map(|o| (o, format)));
+        let result = match exclusive {
+            Some(Ok(((stream, handle), format))) => {
+                println!(
+                    "[Audio] Output opened exclusively at {} Hz, {} channels",
+                    format.sample_rate, format.channels
+                );
+                self.output = Some(stream);
+                self.output_format = Some(format);
+                *stream_handle = Arc::new(handle);
+                return Ok(());
+            }
+            Some(Err(e)) => Err(e),
+            None => Ok(()),
+        };
+        match OutputStream::try_default() {
+            Ok((stream, handle)) => {
+                self.output = Some(stream);
+                *stream_handle = Arc::new(handle);
+                result
+            }
+            // Left to `recover_output`, which retries until a device opens
+            Err(e) => result.and(Err(format!("Failed to open audio device: {}", e))),
+        }
+    }
+
+    fn handle_play(&mut self, track: TrackInfo, stream_handle: &Arc<OutputStreamHandle>) {
         println!("[AudioThread] Handling play for track: '{}'", track.title);
         // Stop current playback
         let path_str = track.path.clone();
         self.handle_stop();
 
         let path = Path::new(&path_str);
-
-        // Open and decode the file
-        let file = match File::open(path) {
-            Ok(f) => f,
+        let reader = match TrackReader::open(&path_str) {
+            Ok(reader) => reader,
             Err(e) => {
-                eprintln!("[AudioThread] Failed to open file: {}", e);
+                eprintln!("[AudioThread] {}", e);
                 return;
             }
         };
-        // Increase buffer size to prevent underruns (static/breaking)
-        let reader = BufReader::with_capacity(512 * 1024, file);
-        let source = match Decoder::new(reader) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Failed to decode audio: {}", e);
-                return;
-            }
+        let seekable = reader.stream_info().is_none_or(|info| info.seekable);
+        let live_title = reader.live_title();
+
+        // Extract metadata if needed (or combine). Streams have no tags to read
+        // up front; their response headers stand in.
+        let extracted = match reader.stream_info() {
+            Some(info) => info.track_info(&path_str),
+            None => self.extract_metadata(path),
         };
-
-        // Extract metadata if needed (or combine)
-        let mut track_info = self.extract_metadata(path);
+        let mut track_info = extracted.clone();
 
         // If passed track has metadata, use it instead of extraction (which might be less complete/DB-cached)
         if track.title != "Unknown" && !track.title.is_empty() {
             track_info = track;
+            // DB/queue entries don't carry ReplayGain tags; take them from the file
+            if track_info.replaygain_track_gain.is_none() && track_info.replaygain_album_gain.is_none() {
+                track_info.replaygain_track_gain = extracted.replaygain_track_gain;
+                track_info.replaygain_album_gain = extracted.replaygain_album_gain;
+            }
         }
 
-        // Create new sink and play
-        let sink = match Sink::try_new(stream_handle) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Failed to create audio sink: {}", e);
-                return;
+        let gain = self.gain_for(&track_info);
+        self.fade.set_audible(true);
+        let Some(source) = self.decode_source(reader, gain, true) else {
+            return;
+        };
+        if track_info.duration_secs <= 0.0 {
+            if let Some(duration) = source.total_duration() {
+                track_info.duration_secs = duration.as_secs_f64();
             }
+        }
+        let Some(sink) = self.sink_for(source, stream_handle) else {
+            return;
         };
 
-        sink.set_volume(self.volume);
-
-        // Wrap source in processing chain:
-        // Decoder -> f32 -> Equalizer -> Sink
-        let source_f32 = source.convert_samples::<f32>();
-        let equalizer = Equalizer::new(source_f32, self.eq_gains.clone());
-        sink.append(equalizer);
+        sink.set_volume(self.effective_volume());
+        self.current_gain = gain;
 
         self.sink = Some(sink);
+        self.seekable = seekable;
+        self.live_title = live_title;
         self.state = PlayerState::Playing;
         self.current_track = Some(track_info);
+        self.source_format = SourceFormat::probe(path);
+        self.detect_silence(path);
         self.current_path = Some(path.to_string_lossy().to_string());
         self.play_start_time = Some(Instant::now());
         self.accumulated_time = 0.0;
     }
 
+    /// Open and decode a file into a new sink with the standard processing chain:
+    /// Decoder -> f32 -> Equalizer -> Sink
+    fn create_sink(
+        &mut self,
+        path: &Path,
+        gain: f32,
+        stream_handle: &Arc<OutputStreamHandle>,
+    ) -> Option<Sink> {
+        let source = self.create_source(path, gain, true)?;
+        self.sink_for(source, stream_handle)
+    }
+
+    /// New sink playing `source`
+    fn sink_for(
+        &mut self,
+        source: ProcessedSource,
+        stream_handle: &Arc<OutputStreamHandle>,
+    ) -> Option<Sink> {
+        let sink = stream_handle.new_sink();
+
+        self.current_sample_rate = source.sample_rate();
+        sink.append(source);
+        Some(sink)
+    }
+
+    /// Open and decode a file into the processing chain, ready to append to a sink.
+    /// `fade_in` starts it with a click fade rather than at full level.
+    fn create_source(&self, path: &Path, gain: f32, fade_in: bool) -> Option<ProcessedSource> {
+        let reader = match TrackReader::open(&path.to_string_lossy()) {
+            Ok(reader) => reader,
+            Err(e) => {
+                eprintln!("[AudioThread] {}", e);
+                return None;
+            }
+        };
+        self.decode_source(reader, gain, fade_in)
+    }
+
+    /// Decode an opened file or stream into the processing chain
+    fn decode_source(&self, reader: TrackReader, gain: f32, fade_in: bool) -> Option<ProcessedSource> {
+        let source = match TrackDecoder::new(reader, self.exclusive_output) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to decode audio: {}", e);
+                return None;
+            }
+        };
+
+        let equalizer = Equalizer::new(source.amplify(gain), self.eq_gains.clone())
+            .with_bypass(self.exclusive_output);
+        Some(ClickFade::new(equalizer, self.fade.clone(), fade_in))
+    }
+
+    /// Fill in ReplayGain tags from the file for tracks that came from the DB/queue
+    fn fill_replaygain(&self, track: &mut TrackInfo) {
+        if self.normalization != NormalizationMode::Off
+            && track.replaygain_track_gain.is_none()
+            && track.replaygain_album_gain.is_none()
+        {
+            let extracted = self.extract_metadata(Path::new(&track.path));
+            track.replaygain_track_gain = extracted.replaygain_track_gain;
+            track.replaygain_album_gain = extracted.replaygain_album_gain;
+        }
+    }
+
+    /// ReplayGain amplitude factor for a track under the current normalization mode
+    fn gain_for(&self, track: &TrackInfo) -> f32 {
+        if self.exclusive_output {
+            return 1.0;
+        }
+        replaygain::gain_factor(
+            self.normalization,
+            track.replaygain_track_gain,
+            track.replaygain_album_gain,
+            self.default_gain_db,
+        )
+    }
+
+    /// Volume the active sink should have, honouring mute and the volume curve.
+    /// Exclusive output plays at full scale; only mute applies there.
+    fn effective_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else if self.exclusive_output {
+            1.0
+        } else {
+            self.volume_curve.gain(self.volume)
+        }
+    }
+
+    /// Start a crossfade into `next_track` when the current track enters the fade
+    /// window, and ramp both sinks while a fade is running.
+    /// Falls back to the normal hard cut when crossfade is 0 or no next track is set.
+    fn update_crossfade(&mut self, stream_handle: &Arc<OutputStreamHandle>) {
+        if self.fading_sink.is_none() {
+            // Exclusive output plays tracks one at a time so each gets its own stream
+            if self.state != PlayerState::Playing
+                || self.crossfade_secs <= 0.0
+                || self.ab_loop.is_some()
+                || self.exclusive_output
+                || self.stop_after_track.is_some()
+            {
+                return;
+            }
+            let Some(ref track) = self.current_track else { return };
+            let fade = self.crossfade_secs as f64;
+            // Skip very short tracks: fading would cover most of the song
+            if track.duration_secs <= fade * 2.0 {
+                return;
+            }
+            let remaining = self.effective_end(track) - self.get_status().position_secs;
+            if remaining > fade {
+                return;
+            }
+            let Some(mut next) = self.next_track.take() else { return };
+
+            let next_path = next.path.clone();
+            self.fill_replaygain(&mut next);
+            let gain = self.gain_for(&next);
+            let Some(next_sink) = self.create_sink(Path::new(&next_path), gain, stream_handle) else {
+                return;
+            };
+            self.current_gain = gain;
+            next_sink.set_volume(0.0);
+
+            println!("[Audio] Crossfading into '{}' over {:.1}s", next.title, fade);
+            self.fading_sink = self.sink.replace(next_sink);
+            self.fade_start = Some(Instant::now());
+            self.source_format = SourceFormat::probe(Path::new(&next_path));
+            self.detect_silence(Path::new(&next_path));
+            self.seekable = !stream::is_remote(&next_path);
+            self.live_title = None;
+            self.current_track = Some(next);
+            self.current_path = Some(next_path);
+            self.play_start_time = Some(Instant::now());
+            self.accumulated_time = 0.0;
+            return;
+        }
+
+        let progress = self
+            .fade_start
+            .map(|t| t.elapsed().as_secs_f32() / self.crossfade_secs.max(0.01))
+            .unwrap_or(1.0)
+            .min(1.0);
+
+        if progress >= 1.0 {
+            self.finish_crossfade();
+            return;
+        }
+
+        let volume = self.effective_volume();
+        if let Some(ref old) = self.fading_sink {
+            old.set_volume(volume * (1.0 - progress));
+        }
+        if let Some(ref sink) = self.sink {
+            sink.set_volume(volume * progress);
+        }
+    }
+
+    /// Drop the outgoing sink and restore the incoming one to full volume
+    fn finish_crossfade(&mut self) {
+        if let Some(old) = self.fading_sink.take() {
+            old.stop();
+        }
+        self.fade_start = None;
+        if let Some(ref sink) = self.sink {
+            sink.set_volume(self.effective_volume());
+        }
+    }
+
+    /// Jump back to A once the playhead passes B. Paused playback keeps the region.
+    fn update_ab_loop(&mut self, stream_handle: &Arc<OutputStreamHandle>) {
+        let Some(region) = self.ab_loop else { return };
+        if self.state != PlayerState::Playing {
+            return;
+        }
+        if self.get_status().position_secs >= region.end_secs {
+            self.handle_seek(region.start_secs, Some(stream_handle));
+        }
+    }
+
+    /// Gapless playback: append the next track to the running sink a few seconds
+    /// before the end, then switch `current_track` once the previous source drains.
+    /// Only used when crossfade is off and both tracks share a sample rate (and,
+    /// in exclusive mode, the whole output format).
+    fn update_gapless(&mut self) {
+        if self.gapless_pending.is_some() {
+            let drained = self.sink.as_ref().map(|s| s.len() <= 1).unwrap_or(true);
+            if drained {
+                if let Some((next, gain, sample_rate)) = self.gapless_pending.take() {
+                    println!("[Audio] Gapless transition to '{}'", next.title);
+                    self.source_format = SourceFormat::probe(Path::new(&next.path));
+                    self.detect_silence(Path::new(&next.path));
+                    self.seekable = !stream::is_remote(&next.path);
+                    self.live_title = None;
+                    self.current_path = Some(next.path.clone());
+                    self.current_track = Some(next);
+                    self.current_gain = gain;
+                    self.current_sample_rate = sample_rate;
+                    self.accumulated_time = 0.0;
+                    self.play_start_time = if self.state == PlayerState::Playing {
+                        Some(Instant::now())
+                    } else {
+                        None
+                    };
+                    // Appended before playback was asked to stop after the track
+                    // that just ended; pause at the start of this one instead
+                    if let Some(done) = self.stop_after_track.take() {
+                        self.handle_pause();
+                        let _ = done.send(());
+                    }
+                }
+            }
+            return;
+        }
+
+        if !self.gapless
+            || self.crossfade_secs > 0.0
+            || self.ab_loop.is_some()
+            || self.state != PlayerState::Playing
+            || self.stop_after_track.is_some()
+        {
+            return;
+        }
+        let Some(ref track) = self.current_track else { return };
+        // Unknown length (live streams): nothing to preload against
+        if track.duration_secs <= 0.0 {
+            return;
+        }
+        let remaining = self.effective_end(track) - self.get_status().position_secs;
+        if remaining > GAPLESS_PRELOAD_SECS {
+            return;
+        }
+        let Some(next_path) = self.next_track.as_ref().map(|t| t.path.clone()) else { return };
+        let pair = (track.path.clone(), next_path);
+        if self.gapless_rejected.as_ref() == Some(&pair) {
+            return;
+        }
+        let Some(mut next) = self.next_track.take() else { return };
+
+        self.fill_replaygain(&mut next);
+        let gain = self.gain_for(&next);
+        // Appended at full level: a fade here would put a dip in the gapless join
+        let Some(source) = self.create_source(Path::new(&next.path), gain, false) else {
+            return;
+        };
+        let sample_rate = source.sample_rate();
+        // The exclusive stream is held at one bit depth and channel count too
+        let format_changes = self.exclusive_output
+            && exclusive_format(Path::new(&next.path)) != self.output_format;
+        if sample_rate != self.current_sample_rate || format_changes {
+            println!(
+                "[Audio] Gapless skipped: format of '{}' differs from the current track",
+                next.title
+            );
+            // Still the next track, just not a gapless one
+            self.gapless_rejected = Some(pair);
+            self.next_track = Some(next);
+            return;
+        }
+
+        if let Some(ref sink) = self.sink {
+            sink.append(source);
+            self.gapless_pending = Some((next, gain, sample_rate));
+        }
+    }
+
+    /// Start detecting the silence trim of a newly current track, if enabled.
+    /// Any result still pending for the previous track is discarded.
+    fn detect_silence(&mut self, path: &Path) {
+        self.silence_trim = None;
+        self.silence_rx = None;
+        if !self.skip_silence {
+            return;
+        }
+        let (tx, rx) = channel();
+        let path = path.to_path_buf();
+        thread::spawn(move || {
+            let _ = tx.send(silence::detect(&path));
+        });
+        self.silence_rx = Some(rx);
+    }
+
+    fn handle_set_skip_silence(&mut self, enabled: bool) {
+        if enabled == self.skip_silence {
+            return;
+        }
+        self.skip_silence = enabled;
+        println!("[Audio] Skip silence: {}", enabled);
+        match self.current_path.clone() {
+            Some(path) if enabled && self.current_track.is_some() => {
+                self.detect_silence(Path::new(&path))
+            }
+            _ => {
+                self.silence_trim = None;
+                self.silence_rx = None;
+            }
+        }
+    }
+
+    /// Where playback of `track` should end: the trimmed end when skip-silence
+    /// found trailing silence, otherwise the full duration
+    fn effective_end(&self, track: &TrackInfo) -> f64 {
+        self.silence_trim
+            .map(|trim| trim.end_secs.min(track.duration_secs))
+            .unwrap_or(track.duration_secs)
+    }
+
+    /// Apply detected trim points: jump past leading silence once detection
+    /// finishes, and end the track when the playhead reaches trailing silence.
+    fn update_silence_trim(&mut self, stream_handle: &Arc<OutputStreamHandle>) {
+        if let Some(ref rx) = self.silence_rx {
+            match rx.try_recv() {
+                Ok(trim) => {
+                    self.silence_rx = None;
+                    self.silence_trim = trim;
+                    if let Some(trim) = trim {
+                        println!(
+                            "[Audio] Audible range {:.2}s - {:.2}s",
+                            trim.start_secs, trim.end_secs
+                        );
+                        // A loaded-but-unplayed track has no sink yet; it is
+                        // detected again when it starts playing
+                        let position = self.get_status().position_secs;
+                        if self.sink.is_some() && position < trim.start_secs {
+                            self.handle_seek(trim.start_secs, Some(stream_handle));
+                        }
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.silence_rx = None,
+            }
+        }
+
+        if self.state != PlayerState::Playing || self.fading_sink.is_some() {
+            return;
+        }
+        let Some(ref track) = self.current_track else { return };
+        let Some(trim) = self.silence_trim else { return };
+        if trim.end_secs >= track.duration_secs
+            || self.get_status().position_secs < trim.end_secs
+        {
+            return;
+        }
+
+        // The trim belongs to the track being left; clearing it also stops this
+        // from firing again before a gapless transition lands
+        self.silence_trim = None;
+        if self.gapless_pending.is_some() {
+            println!("[Audio] Skipping trailing silence into gapless track");
+            if let Some(ref sink) = self.sink {
+                sink.skip_one();
+            }
+        } else if self.stop_after_track.is_some() {
+            println!("[Audio] Track finished (trailing silence skipped)");
+            self.pause_at_track_end();
+        } else {
+            println!("[Audio] Track finished (trailing silence skipped)");
+            self.finish_at_trim_end();
+        }
+    }
+
+    /// End the current track for `stop_after_track`: paused at its end with the
+    /// track kept, so resuming moves on to whatever comes next
+    fn pause_at_track_end(&mut self) {
+        self.fade_out();
+        if let Some(ref sink) = self.sink {
+            sink.pause();
+        }
+        self.state = PlayerState::Paused;
+        self.play_start_time = None;
+        self.accumulated_time = self
+            .current_track
+            .as_ref()
+            .map_or(0.0, |track| track.duration_secs);
+        if let Some(done) = self.stop_after_track.take() {
+            let _ = done.send(());
+        }
+    }
+
+    /// End the current track at its trimmed end. Unlike `handle_stop` the track
+    /// stays current and reports its full duration as the position, which is
+    /// what autoplay waits for, until the next one loads.
+    fn finish_at_trim_end(&mut self) {
+        self.fade_out();
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+        self.state = PlayerState::Stopped;
+        self.play_start_time = None;
+        self.accumulated_time = self
+            .current_track
+            .as_ref()
+            .map_or(0.0, |track| track.duration_secs);
+    }
+
+    /// Show the latest title announced by a radio stream as the current track
+    fn update_live_title(&mut self) {
+        let Some(title) = self
+            .live_title
+            .as_ref()
+            .and_then(|slot| slot.lock().ok().and_then(|mut title| title.take()))
+        else {
+            return;
+        };
+        let Some(ref mut track) = self.current_track else { return };
+        let (artist, song) = stream::split_stream_title(&title);
+        track.title = song.to_string();
+        track.artist = artist.unwrap_or("Unknown Artist").to_string();
+        if let Ok(mut update) = self.live_update.lock() {
+            *update = Some(track.clone());
+        }
+    }
+
     fn handle_load(&mut self, track: TrackInfo) {
         println!("[AudioThread] Handling load for track: '{}'", track.title);
         // Stop current playback
@@ -407,8 +1387,15 @@ impl AudioThread {
         self.handle_stop();
 
         let path_obj = Path::new(&path_str);
-        // Extract metadata if needed
-        let mut track_info = self.extract_metadata(path_obj);
+        // Extract metadata if needed. A stream isn't opened until it plays, so
+        // whether it can seek is not known yet.
+        let remote = stream::is_remote(&path_str);
+        let mut track_info = if remote {
+            stream::StreamInfo::default().track_info(&path_str)
+        } else {
+            self.extract_metadata(path_obj)
+        };
+        self.seekable = !remote;
 
         if track.title != "Unknown" && !track.title.is_empty() {
             track_info = track;
@@ -416,6 +1403,8 @@ impl AudioThread {
 
         self.state = PlayerState::Paused; // Load starts in paused state
         self.current_track = Some(track_info);
+        self.source_format = SourceFormat::probe(path_obj);
+        self.detect_silence(path_obj);
         self.current_path = Some(path_str);
         self.play_start_time = None;
         self.accumulated_time = 0.0;
@@ -446,6 +1435,12 @@ impl AudioThread {
                     album_romaji: None,
                     album_en: None,
                     playlist_track_id: None,
+                    replaygain_track_gain: None,
+                    replaygain_album_gain: None,
+                    genre: None,
+                    year: None,
+                    album_artist: None,
+                    is_compilation: false,
                 };
             }
         };
@@ -453,6 +1448,12 @@ impl AudioThread {
         let properties = tagged_file.properties();
         let duration_secs = properties.duration().as_secs_f64();
 
+        let (replaygain_track_gain, replaygain_album_gain) = tagged_file
+            .primary_tag()
+            .or_else(|| tagged_file.first_tag())
+            .map(replaygain::read_replaygain)
+            .unwrap_or((None, None));
+
         let (title, artist, album) = if let Some(tag) = tagged_file.primary_tag() {
             (
                 tag.title().map(|s| s.to_string()).unwrap_or_else(|| {
@@ -496,10 +1497,30 @@ impl AudioThread {
             album_romaji: None,
             album_en: None,
             playlist_track_id: None,
+            replaygain_track_gain,
+            replaygain_album_gain,
+            genre: None,
+            year: None,
+            album_artist: None,
+            is_compilation: false,
+        }
+    }
+
+    /// Ramp playing output down and wait for the fade to finish. Sources stay
+    /// silent until `fade.set_audible(true)`.
+    fn fade_out(&self) {
+        self.fade.set_audible(false);
+        let audible = self.state == PlayerState::Playing
+            && self.sink.as_ref().is_some_and(|sink| !sink.empty());
+        let duration = self.fade.duration();
+        if audible && !duration.is_zero() {
+            thread::sleep(duration);
         }
     }
 
     fn handle_pause(&mut self) {
+        self.finish_crossfade();
+        self.fade_out();
         if let Some(ref sink) = self.sink {
             sink.pause();
 
@@ -515,17 +1536,27 @@ impl AudioThread {
     fn handle_resume(&mut self) {
         if let Some(ref sink) = self.sink {
             sink.play();
+            self.fade.set_audible(true);
             self.play_start_time = Some(Instant::now());
             self.state = PlayerState::Playing;
         }
     }
 
     fn handle_stop(&mut self) {
+        self.fade_out();
+        self.finish_crossfade();
+        self.gapless_pending = None;
+        // The loop region belongs to the track that was playing
+        self.ab_loop = None;
         if let Some(sink) = self.sink.take() {
             sink.stop();
         }
         self.state = PlayerState::Stopped;
         self.current_track = None;
+        self.source_format = SourceFormat::default();
+        self.silence_trim = None;
+        self.silence_rx = None;
+        self.live_title = None;
         self.play_start_time = None;
         self.accumulated_time = 0.0;
     }
@@ -534,7 +1565,18 @@ impl AudioThread {
         self.volume = value.clamp(0.0, 1.0);
         if !self.muted {
             if let Some(ref sink) = self.sink {
-                sink.set_volume(self.volume);
+                sink.set_volume(self.effective_volume());
+            }
+        }
+    }
+
+    fn handle_set_volume_curve(&mut self, curve: VolumeCurve) {
+        self.volume_curve = curve;
+        println!("[AudioThread] Volume curve: {:?}", curve);
+        // A running crossfade picks the new curve up on its next step
+        if self.fading_sink.is_none() {
+            if let Some(ref sink) = self.sink {
+                sink.set_volume(self.effective_volume());
             }
         }
     }
@@ -542,15 +1584,15 @@ impl AudioThread {
     fn handle_set_mute(&mut self, mute: bool) {
         self.muted = mute;
         if let Some(ref sink) = self.sink {
-            if mute {
-                sink.set_volume(0.0);
-            } else {
-                sink.set_volume(self.volume);
-            }
+            sink.set_volume(self.effective_volume());
         }
     }
 
     fn handle_set_speed(&mut self, value: f32) {
+        // Changing speed resamples, which exclusive output promises not to do
+        if self.exclusive_output {
+            return;
+        }
         if let Some(ref sink) = self.sink {
             sink.set_speed(value);
         }
@@ -559,15 +1601,33 @@ impl AudioThread {
     fn handle_seek(
         &mut self,
         seconds: f64,
-        stream_handle: Option<&Arc<rodio::OutputStreamHandle>>,
+        stream_handle: Option<&Arc<OutputStreamHandle>>,
     ) {
         println!("[Audio] Seeking to {} seconds", seconds);
+        if !self.seekable {
+            println!("[Audio] Seek ignored: stream does not support seeking");
+            return;
+        }
+        self.finish_crossfade();
+        self.fade_out();
+        // A gapless-appended next track is queued in the sink behind the current
+        // one, and a native seek would leave it there. Rebuild the sink instead,
+        // which drops it, and hand the track back for the next gapless preload.
+        let rebuild = self.gapless_pending.is_some()
+            && self.current_path.is_some()
+            && stream_handle.is_some();
+        if rebuild {
+            if let Some((next, _, _)) = self.gapless_pending.take() {
+                self.next_track = Some(next);
+            }
+        }
 
         // First try native seek
-        if let Some(ref mut sink) = self.sink {
+        if let Some(sink) = self.sink.as_ref().filter(|_| !rebuild) {
             match sink.try_seek(std::time::Duration::from_secs_f64(seconds)) {
                 Ok(_) => {
                     println!("[Audio] Native seek successful");
+                    self.fade.set_audible(self.state == PlayerState::Playing);
                     self.accumulated_time = seconds;
                     if self.state == PlayerState::Playing {
                         self.play_start_time = Some(Instant::now());
@@ -592,16 +1652,15 @@ impl AudioThread {
             }
 
             // Reload and skip
-            let file = match File::open(&path) {
-                Ok(f) => f,
+            let reader = match TrackReader::open(&path) {
+                Ok(reader) => reader,
                 Err(e) => {
                     eprintln!("[Audio] Seek reload failed: {}", e);
                     return;
                 }
             };
 
-            let reader = BufReader::with_capacity(512 * 1024, file);
-            let source = match Decoder::new(reader) {
+            let source = match TrackDecoder::new(reader, self.exclusive_output) {
                 Ok(s) => s,
                 Err(e) => {
                     eprintln!("[Audio] Seek decode failed: {}", e);
@@ -612,20 +1671,16 @@ impl AudioThread {
             // Skip to the target position using skip_duration
             let skipped_source = source.skip_duration(std::time::Duration::from_secs_f64(seconds));
 
-            let sink = match Sink::try_new(stream_handle) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("[Audio] Seek sink creation failed: {}", e);
-                    return;
-                }
-            };
+            let sink = stream_handle.new_sink();
 
-            sink.set_volume(self.volume);
+            sink.set_volume(self.effective_volume());
 
             // Wrap source in processing chain (same as handle_play)
-            let source_f32 = skipped_source.convert_samples::<f32>();
-            let equalizer = Equalizer::new(source_f32, self.eq_gains.clone());
-            sink.append(equalizer);
+            let amplified = skipped_source.amplify(self.current_gain);
+            let equalizer = Equalizer::new(amplified, self.eq_gains.clone())
+                .with_bypass(self.exclusive_output);
+            sink.append(ClickFade::new(equalizer, self.fade.clone(), true));
+            self.fade.set_audible(was_playing);
 
             if !was_playing {
                 sink.pause();
@@ -649,10 +1704,23 @@ impl AudioThread {
             println!("[Audio] Seek via reload successful");
         } else {
             println!("[Audio] Seek failed: no path or stream handle");
+            self.fade.set_audible(self.state == PlayerState::Playing);
         }
     }
 
     fn get_status(&self) -> PlayerStatus {
+        let (preamp_db, balance, mono) = self
+            .eq_gains
+            .lock()
+            .map(|gains| {
+                (
+                    gains.get(PREAMP_SLOT).copied().unwrap_or(0.0),
+                    gains.get(BALANCE_SLOT).copied().unwrap_or(0.0),
+                    gains.get(MONO_SLOT).is_some_and(|&m| m >= 0.5),
+                )
+            })
+            .unwrap_or((0.0, 0.0, false));
+
         let mut position_secs = {
             let current = self
                 .play_start_time
@@ -661,9 +1729,9 @@ impl AudioThread {
             self.accumulated_time + current
         };
 
-        // Cap position to duration to prevent exceeding
+        // Cap position to duration to prevent exceeding; streams of unknown length have none
         if let Some(ref track) = self.current_track {
-            if position_secs > track.duration_secs {
+            if track.duration_secs > 0.0 && position_secs > track.duration_secs {
                 position_secs = track.duration_secs;
             }
         }
@@ -673,6 +1741,23 @@ impl AudioThread {
             track: self.current_track.clone(),
             position_secs,
             volume: self.volume,
+            ab_loop: self.ab_loop,
+            exclusive_output: self.exclusive_output,
+            sample_rate: self
+                .source_format
+                .sample_rate
+                .or((self.current_sample_rate > 0).then_some(self.current_sample_rate)),
+            bit_depth: self.source_format.bit_depth,
+            channels: self.source_format.channels,
+            preamp_db,
+            balance,
+            mono,
+            skip_silence: self.skip_silence,
+            silence_trim: self.silence_trim,
+            seekable: self.seekable,
+            is_live: self.current_path.as_deref().is_some_and(stream::is_remote)
+                && self.current_track.as_ref().is_some_and(|t| t.duration_secs <= 0.0),
+            volume_curve: self.volume_curve,
         }
     }
 }
@@ -0,0 +1,89 @@
+use lofty::tag::{ItemKey, Tag};
+use serde::{Deserialize, Serialize};
+
+/// Which ReplayGain value (if any) is applied when a track starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+impl NormalizationMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "track" => Ok(Self::Track),
+            "album" => Ok(Self::Album),
+            other => Err(format!(
+                "Invalid normalization mode '{}' (expected off, track or album)",
+                other
+            )),
+        }
+    }
+}
+
+/// Read `REPLAYGAIN_TRACK_GAIN` / `REPLAYGAIN_ALBUM_GAIN` from a tag, in dB
+pub fn read_replaygain(tag: &Tag) -> (Option<f32>, Option<f32>) {
+    (
+        tag.get_string(&ItemKey::ReplayGainTrackGain)
+            .and_then(parse_gain_db),
+        tag.get_string(&ItemKey::ReplayGainAlbumGain)
+            .and_then(parse_gain_db),
+    )
+}
+
+/// Parse values like "-6.54 dB", "+1.20dB" or "3.1"
+pub fn parse_gain_db(value: &str) -> Option<f32> {
+    let trimmed = value.trim();
+    let number = trimmed
+        .strip_suffix("dB")
+        .or_else(|| trimmed.strip_suffix("db"))
+        .or_else(|| trimmed.strip_suffix("DB"))
+        .unwrap_or(trimmed)
+        .trim();
+    number.parse::<f32>().ok().filter(|g| g.is_finite())
+}
+
+/// Linear amplitude factor for a track, given the normalization mode.
+/// Album mode falls back to the track gain, and missing tags use `default_gain_db`.
+pub fn gain_factor(
+    mode: NormalizationMode,
+    track_gain: Option<f32>,
+    album_gain: Option<f32>,
+    default_gain_db: f32,
+) -> f32 {
+    let gain_db = match mode {
+        NormalizationMode::Off => return 1.0,
+        NormalizationMode::Track => track_gain.unwrap_or(default_gain_db),
+        NormalizationMode::Album => album_gain.or(track_gain).unwrap_or(default_gain_db),
+    };
+    10.0f32.powf(gain_db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_common_gain_formats() {
+        assert_eq!(parse_gain_db("-6.54 dB"), Some(-6.54));
+        assert_eq!(parse_gain_db("+1.5dB"), Some(1.5));
+        assert_eq!(parse_gain_db(" 3 "), Some(3.0));
+        assert_eq!(parse_gain_db("loud"), None);
+    }
+
+    #[test]
+    fn album_mode_falls_back_to_track_then_default() {
+        let unity = gain_factor(NormalizationMode::Album, None, None, 0.0);
+        assert!((unity - 1.0).abs() < f32::EPSILON);
+
+        let from_track = gain_factor(NormalizationMode::Album, Some(-20.0), None, 0.0);
+        assert!((from_track - 0.1).abs() < 1e-4);
+
+        let off = gain_factor(NormalizationMode::Off, Some(-20.0), Some(-20.0), 0.0);
+        assert!((off - 1.0).abs() < f32::EPSILON);
+    }
+}
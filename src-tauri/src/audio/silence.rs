@@ -0,0 +1,96 @@
+//! Leading/trailing silence detection for skip-silence playback
+//!
+//! A track is decoded once on a worker thread; the first and last samples louder
+//! than `SILENCE_THRESHOLD_DBFS` mark the audible part. Silence shorter than
+//! `MIN_SILENCE_SECS` is left alone so fade-ins and short gaps between live
+//! tracks stay intact.
+
+use rodio::Source;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::state::SilenceTrim;
+
+/// Samples quieter than this are treated as silence. -50 dBFS sits below the
+/// noise floor of typical masters and tape hiss, but well above dithering noise.
+const SILENCE_THRESHOLD_DBFS: f32 = -50.0;
+
+/// Shortest run of leading or trailing silence that gets trimmed
+const MIN_SILENCE_SECS: f64 = 1.0;
+
+/// Decode `path` and find where its audible part starts and ends.
+/// Blocking: decodes the whole file, call from a worker thread.
+/// Returns `None` if the file can't be decoded or is silent throughout.
+pub fn detect(path: &Path) -> Option<SilenceTrim> {
+    let file = File::open(path).ok()?;
+    let decoder = rodio::Decoder::new(BufReader::new(file)).ok()?;
+    let channels = decoder.channels().max(1);
+    let sample_rate = decoder.sample_rate().max(1);
+    find_trim(decoder, channels, sample_rate)
+}
+
+fn find_trim(
+    samples: impl Iterator<Item = i16>,
+    channels: u16,
+    sample_rate: u32,
+) -> Option<SilenceTrim> {
+    let threshold = (32768.0 * 10f32.powf(SILENCE_THRESHOLD_DBFS / 20.0)) as i32;
+
+    let mut first_loud: Option<usize> = None;
+    let mut last_loud = 0;
+    let mut total = 0;
+    for (i, sample) in samples.enumerate() {
+        if (sample as i32).abs() > threshold {
+            first_loud.get_or_insert(i);
+            last_loud = i;
+        }
+        total = i + 1;
+    }
+
+    let channels = channels as usize;
+    let rate = sample_rate as f64;
+    let frame_secs = |sample: usize| (sample / channels) as f64 / rate;
+
+    let total_secs = frame_secs(total);
+    let audible_start = frame_secs(first_loud?);
+    let audible_end = frame_secs(last_loud) + 1.0 / rate;
+
+    Some(SilenceTrim {
+        start_secs: if audible_start >= MIN_SILENCE_SECS { audible_start } else { 0.0 },
+        end_secs: if total_secs - audible_end >= MIN_SILENCE_SECS {
+            audible_end
+        } else {
+            total_secs
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trims_only_silence_longer_than_the_minimum() {
+        let rate = 1000;
+        // 2s silence, 1s tone, 0.5s silence, 1s tone, 3s silence
+        let mut samples = vec![0i16; 2000];
+        samples.extend([10_000; 1000]);
+        samples.extend([0; 500]);
+        samples.extend([-10_000; 1000]);
+        samples.extend([20; 3000]);
+
+        let trim = find_trim(samples.into_iter(), 1, rate).unwrap();
+        assert_eq!(trim.start_secs, 2.0);
+        assert!((trim.end_secs - 4.5).abs() < 1e-9);
+
+        // Half a second of lead-in is kept
+        let mut samples = vec![0i16; 500];
+        samples.extend([10_000; 1000]);
+        let trim = find_trim(samples.into_iter(), 1, rate).unwrap();
+        assert_eq!(trim.start_secs, 0.0);
+        assert_eq!(trim.end_secs, 1.5);
+
+        assert_eq!(find_trim(vec![0i16; 5000].into_iter(), 1, rate), None);
+    }
+}
@@ -30,6 +30,14 @@ pub struct TrackInfo {
     pub album_romaji: Option<String>,
     pub album_en: Option<String>,
     pub playlist_track_id: Option<i64>,
+    pub replaygain_track_gain: Option<f32>,
+    pub replaygain_album_gain: Option<f32>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub album_artist: Option<String>,
+    /// Part of a various-artists compilation (`TCMP` / `COMPILATION` tag)
+    #[serde(default)]
+    pub is_compilation: bool,
 }
 
 impl Default for TrackInfo {
@@ -51,10 +59,30 @@ impl Default for TrackInfo {
             album_romaji: None,
             album_en: None,
             playlist_track_id: None,
+            replaygain_track_gain: None,
+            replaygain_album_gain: None,
+            genre: None,
+            year: None,
+            album_artist: None,
+            is_compilation: false,
         }
     }
 }
 
+/// A-B repeat region within the current track
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoopRegion {
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+/// Audible part of a track found by skip-silence, in seconds from the start of the file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SilenceTrim {
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
 /// Complete player status for frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerStatus {
@@ -62,6 +90,45 @@ pub struct PlayerStatus {
     pub track: Option<TrackInfo>,
     pub position_secs: f64,
     pub volume: f32,
+    pub ab_loop: Option<LoopRegion>,
+    /// Device held in exclusive mode at the track's format, DSP and volume bypassed
+    #[serde(default)]
+    pub exclusive_output: bool,
+    /// Native sample rate of the current file (Hz)
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    /// Bits per sample of the current file; `None` for lossy formats
+    #[serde(default)]
+    pub bit_depth: Option<u8>,
+    #[serde(default)]
+    pub channels: Option<u16>,
+    /// Master gain ahead of the EQ (dB)
+    #[serde(default)]
+    pub preamp_db: f32,
+    /// -1.0 (left) to 1.0 (right)
+    #[serde(default)]
+    pub balance: f32,
+    /// Channels summed to mono
+    #[serde(default)]
+    pub mono: bool,
+    #[serde(default)]
+    pub skip_silence: bool,
+    /// Trim points of the current track; `None` until detection finishes
+    #[serde(default)]
+    pub silence_trim: Option<SilenceTrim>,
+    /// False for remote streams whose server doesn't accept range requests
+    #[serde(default = "default_seekable")]
+    pub seekable: bool,
+    /// Live stream without a fixed length, such as internet radio
+    #[serde(default)]
+    pub is_live: bool,
+    /// How `volume` maps to output gain
+    #[serde(default)]
+    pub volume_curve: VolumeCurve,
+}
+
+fn default_seekable() -> bool {
+    true
 }
 
 impl Default for PlayerStatus {
@@ -71,6 +138,78 @@ impl Default for PlayerStatus {
             track: None,
             position_secs: 0.0,
             volume: 1.0,
+            ab_loop: None,
+            exclusive_output: false,
+            sample_rate: None,
+            bit_depth: None,
+            channels: None,
+            preamp_db: 0.0,
+            balance: 0.0,
+            mono: false,
+            skip_silence: false,
+            silence_trim: None,
+            seekable: true,
+            is_live: false,
+            volume_curve: VolumeCurve::default(),
+        }
+    }
+}
+
+/// How the 0..1 volume slider maps to sink gain. Hearing is roughly
+/// logarithmic, so a linear slider does little in its lower half and jumps
+/// near the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeCurve {
+    /// gain = v, for automation that expects raw amplitude
+    Linear,
+    /// gain = 10^(3(v - 1)): a 60 dB range with equal dB per slider step,
+    /// and 0 still silent
+    #[default]
+    Log,
+    /// gain = v³, a softer approximation of the log curve
+    Cubic,
+}
+
+impl VolumeCurve {
+    pub fn parse(curve: &str) -> Result<Self, String> {
+        match curve.to_lowercase().as_str() {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            "cubic" => Ok(Self::Cubic),
+            other => Err(format!(
+                "Invalid volume curve '{}' (expected linear, log or cubic)",
+                other
+            )),
+        }
+    }
+
+    /// Sink gain for a slider value in 0..1
+    pub fn gain(self, volume: f32) -> f32 {
+        let v = volume.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => v,
+            Self::Log if v == 0.0 => 0.0,
+            Self::Log => 10f32.powf(3.0 * (v - 1.0)),
+            Self::Cubic => v * v * v,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_curves_span_silence_to_unity() {
+        for curve in [VolumeCurve::Linear, VolumeCurve::Log, VolumeCurve::Cubic] {
+            assert_eq!(curve.gain(0.0), 0.0);
+            assert!((curve.gain(1.0) - 1.0).abs() < 1e-6);
         }
+        // Halfway on the log curve is 30 dB down
+        assert!((VolumeCurve::Log.gain(0.5) - 0.031_622_8).abs() < 1e-5);
+        assert_eq!(VolumeCurve::Cubic.gain(0.5), 0.125);
+        assert_eq!(VolumeCurve::parse("LOG"), Ok(VolumeCurve::Log));
+        assert!(VolumeCurve::parse("exp").is_err());
     }
 }
@@ -0,0 +1,586 @@
+//! Playback of remote audio over http(s)
+//!
+//! `HttpReader` gives the decoder a `Read + Seek` view of a URL. A download
+//! thread reads ahead into a bounded buffer so network jitter doesn't reach the
+//! output callback. Seeking reopens the request at the new offset when the
+//! server accepts byte ranges; otherwise the stream can only move forward, apart
+//! from its first bytes, which are kept so the decoder can rewind while probing
+//! the format.
+//!
+//! Internet radio interleaves ICY metadata with the audio when asked to. It is
+//! stripped out before the decoder sees it, and each new `StreamTitle` is left
+//! in a slot the audio thread picks up to update "now playing".
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header;
+use reqwest::StatusCode;
+use symphonia::core::io::MediaSource;
+
+use super::state::TrackInfo;
+
+/// Read buffer for local files, large enough to prevent underruns (static/breaking)
+const FILE_BUFFER_BYTES: usize = 512 * 1024;
+
+/// Downloaded but unread bytes held per stream before the download waits
+const READ_AHEAD_BYTES: usize = 4 * 1024 * 1024;
+
+/// Start of the stream kept for rewinds while the decoder probes the format
+const PROBE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// How long opening waits for response headers, and a read for data, before
+/// the stream is given up on
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(20);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// True for paths that are http(s) URLs rather than local files
+pub fn is_remote(path: &str) -> bool {
+    let scheme = path.get(..8).unwrap_or(path).to_ascii_lowercase();
+    scheme.starts_with("http://") || scheme.starts_with("https://")
+}
+
+/// What the server said about a stream in its response headers
+#[derive(Debug, Clone, Default)]
+pub struct StreamInfo {
+    /// `icy-name`: station or stream name
+    pub name: Option<String>,
+    /// `icy-genre`
+    pub genre: Option<String>,
+    pub length: Option<u64>,
+    /// The server accepts byte range requests for this resource
+    pub seekable: bool,
+}
+
+impl StreamInfo {
+    /// Track metadata for a stream: header values where present, the file name
+    /// in the URL otherwise. The station name doubles as the album so it stays
+    /// visible once live titles replace the title.
+    pub fn track_info(&self, url: &str) -> TrackInfo {
+        let mut track = TrackInfo {
+            path: url.to_string(),
+            title: self.name.clone().unwrap_or_else(|| title_from_url(url)),
+            genre: self.genre.clone(),
+            ..TrackInfo::default()
+        };
+        if let Some(ref name) = self.name {
+            track.album = name.clone();
+        }
+        track
+    }
+}
+
+/// Latest `StreamTitle` of a radio stream, until taken by the audio thread
+pub type LiveTitle = Arc<Mutex<Option<String>>>;
+
+/// Split an ICY title in the usual "Artist - Title" form
+pub fn split_stream_title(title: &str) -> (Option<&str>, &str) {
+    match title.split_once(" - ") {
+        Some((artist, song)) if !artist.trim().is_empty() && !song.trim().is_empty() => {
+            (Some(artist.trim()), song.trim())
+        }
+        _ => (None, title.trim()),
+    }
+}
+
+/// Title for a URL without metadata: its decoded file name without extension,
+/// or the host for URLs without a path
+pub fn title_from_url(url: &str) -> String {
+    let without_query = url.split(['?', '#']).next().unwrap_or(url);
+    let rest = without_query
+        .split_once("://")
+        .map_or(without_query, |(_, rest)| rest)
+        .trim_end_matches('/');
+    let Some((_, file_name)) = rest.rsplit_once('/') else {
+        return rest.to_string();
+    };
+    let file_name = urlencoding::decode(file_name)
+        .map(|name| name.into_owned())
+        .unwrap_or_else(|_| file_name.to_string());
+    match file_name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+        _ => file_name,
+    }
+}
+
+/// A local file or remote stream, as handed to the decoder
+pub enum TrackReader {
+    File(BufReader<File>),
+    Http(HttpReader),
+}
+
+impl TrackReader {
+    /// Open `path`, which may be an http(s) URL
+    pub fn open(path: &str) -> Result<Self, String> {
+        if is_remote(path) {
+            return HttpReader::open(path).map(TrackReader::Http);
+        }
+        let file = File::open(Path::new(path)).map_err(|e| format!("Failed to open file: {}", e))?;
+        Ok(TrackReader::File(BufReader::with_capacity(FILE_BUFFER_BYTES, file)))
+    }
+
+    /// Response headers of a remote stream; `None` for local files
+    pub fn stream_info(&self) -> Option<&StreamInfo> {
+        match self {
+            TrackReader::File(_) => None,
+            TrackReader::Http(reader) => Some(&reader.info),
+        }
+    }
+
+    /// Where new titles of a radio stream show up; `None` for local files
+    pub fn live_title(&self) -> Option<LiveTitle> {
+        match self {
+            TrackReader::File(_) => None,
+            TrackReader::Http(reader) => Some(reader.live_title.clone()),
+        }
+    }
+}
+
+impl Read for TrackReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            TrackReader::File(reader) => reader.read(buf),
+            TrackReader::Http(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for TrackReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            TrackReader::File(reader) => reader.seek(pos),
+            TrackReader::Http(reader) => reader.seek(pos),
+        }
+    }
+}
+
+impl MediaSource for TrackReader {
+    fn is_seekable(&self) -> bool {
+        self.stream_info().is_none_or(|info| info.seekable)
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        match self {
+            TrackReader::File(reader) => reader.get_ref().metadata().ok().map(|m| m.len()),
+            TrackReader::Http(reader) => reader.info.length,
+        }
+    }
+}
+
+/// Downloaded bytes shared between the download thread and the reader
+#[derive(Default)]
+struct Buffer {
+    data: VecDeque<u8>,
+    /// Stream offset of `data[0]`
+    offset: u64,
+    /// The download reached the end of the body or failed
+    finished: bool,
+    error: Option<String>,
+    /// Offset the reader wants the download to restart from
+    restart: Option<u64>,
+    /// The reader was dropped
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    buffer: Mutex<Buffer>,
+    changed: Condvar,
+}
+
+pub struct HttpReader {
+    shared: Arc<Shared>,
+    info: StreamInfo,
+    live_title: LiveTitle,
+    pos: u64,
+    /// The first `PROBE_BUFFER_BYTES` of the stream, as far as read
+    head: Vec<u8>,
+}
+
+fn request(client: &Client, url: &str, from: Option<u64>) -> reqwest::Result<Response> {
+    let mut request = client.get(url).header("Icy-MetaData", "1");
+    if let Some(offset) = from {
+        request = request.header(header::RANGE, format!("bytes={}-", offset));
+    }
+    request.send()?.error_for_status()
+}
+
+/// `request` that gives up after `NETWORK_TIMEOUT` without response headers.
+/// The client has no overall timeout, so a server that accepts the connection
+/// and never answers would otherwise block the caller (the audio thread) for good.
+fn request_headers(client: &Client, url: &str) -> Result<Response, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (client, url) = (client.clone(), url.to_string());
+    // Left to finish or fail on its own if we stop waiting
+    thread::spawn(move || {
+        let _ = tx.send(request(&client, &url, None));
+    });
+    match rx.recv_timeout(NETWORK_TIMEOUT) {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("no response within {}s", NETWORK_TIMEOUT.as_secs())),
+    }
+}
+
+impl HttpReader {
+    pub fn open(url: &str) -> Result<Self, String> {
+        // No overall timeout: it would cut off long tracks and radio mid-stream
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(None::<Duration>)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let response =
+            request_headers(&client, url).map_err(|e| format!("Failed to open {}: {}", url, e))?;
+
+        let headers = response.headers();
+        let text = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let length = response.content_length();
+        let info = StreamInfo {
+            name: text("icy-name"),
+            genre: text("icy-genre"),
+            length,
+            seekable: length.is_some()
+                && text(header::ACCEPT_RANGES.as_str())
+                    .is_some_and(|ranges| ranges.eq_ignore_ascii_case("bytes")),
+        };
+        println!(
+            "[Stream] Opened {} ({} bytes, seekable: {})",
+            url,
+            length.map_or_else(|| "unknown".to_string(), |l| l.to_string()),
+            info.seekable
+        );
+
+        let shared = Arc::new(Shared::default());
+        let live_title = LiveTitle::default();
+        let download_shared = shared.clone();
+        let download_title = live_title.clone();
+        let url = url.to_string();
+        thread::spawn(move || download(client, url, response, download_shared, download_title));
+
+        Ok(Self {
+            shared,
+            info,
+            live_title,
+            pos: 0,
+            head: Vec::new(),
+        })
+    }
+}
+
+/// Response body with ICY metadata removed when the server interleaves it
+fn audio_body(response: Response, live_title: &LiveTitle) -> Box<dyn Read + Send> {
+    let metaint = response
+        .headers()
+        .get("icy-metaint")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|&metaint| metaint > 0);
+    match metaint {
+        Some(metaint) => Box::new(IcyStrip::new(response, metaint, live_title.clone())),
+        None => Box::new(response),
+    }
+}
+
+/// Download thread: append the body to the shared buffer, waiting while it is
+/// full, and start over at another offset when the reader asks for it
+fn download(
+    client: Client,
+    url: String,
+    response: Response,
+    shared: Arc<Shared>,
+    live_title: LiveTitle,
+) {
+    let mut body = audio_body(response, &live_title);
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        {
+            let mut buffer = shared.buffer.lock().unwrap();
+            while !buffer.closed
+                && buffer.restart.is_none()
+                && (buffer.finished || buffer.data.len() >= READ_AHEAD_BYTES)
+            {
+                buffer = shared.changed.wait(buffer).unwrap();
+            }
+            if buffer.closed {
+                return;
+            }
+            if let Some(offset) = buffer.restart {
+                drop(buffer);
+                let reopened = match request(&client, &url, Some(offset)) {
+                    Ok(r) if r.status() == StatusCode::PARTIAL_CONTENT => Ok(r),
+                    Ok(_) => Err("server ignored the range request".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+
+                let mut buffer = shared.buffer.lock().unwrap();
+                buffer.data.clear();
+                buffer.offset = offset;
+                buffer.restart = None;
+                match reopened {
+                    Ok(r) => {
+                        body = audio_body(r, &live_title);
+                        buffer.finished = false;
+                        buffer.error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("[Stream] Seek in {} failed: {}", url, e);
+                        buffer.finished = true;
+                        buffer.error = Some(e);
+                    }
+                }
+                shared.changed.notify_all();
+                continue;
+            }
+        }
+
+        let read = body.read(&mut chunk);
+
+        let mut buffer = shared.buffer.lock().unwrap();
+        // Bytes from before a requested restart belong to the old offset
+        if buffer.restart.is_some() {
+            continue;
+        }
+        match read {
+            Ok(0) => buffer.finished = true,
+            Ok(n) => buffer.data.extend(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                eprintln!("[Stream] Download of {} failed: {}", url, e);
+                buffer.finished = true;
+                buffer.error = Some(e.to_string());
+            }
+        }
+        shared.changed.notify_all();
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(kept) = self.head.get(self.pos as usize..).filter(|kept| !kept.is_empty()) {
+            let n = buf.len().min(kept.len());
+            buf[..n].copy_from_slice(&kept[..n]);
+            self.pos += n as u64;
+            return Ok(n);
+        }
+
+        let shared = self.shared.clone();
+        let mut buffer = shared.buffer.lock().unwrap();
+        loop {
+            if buffer.restart.is_none() {
+                let downloaded_to = buffer.offset + buffer.data.len() as u64;
+                // Behind the buffer is only reachable for seekable streams, see
+                // `seek`; far ahead of it a new request beats downloading the gap
+                if self.pos < buffer.offset
+                    || (self.info.seekable && self.pos > downloaded_to + READ_AHEAD_BYTES as u64)
+                {
+                    buffer.restart = Some(self.pos);
+                    shared.changed.notify_all();
+                } else {
+                    // Drop bytes before the read position; this is also how
+                    // streams without range support skip ahead
+                    let behind = ((self.pos - buffer.offset) as usize).min(buffer.data.len());
+                    buffer.data.drain(..behind);
+                    buffer.offset += behind as u64;
+
+                    if self.pos == buffer.offset && !buffer.data.is_empty() {
+                        let n = buf.len().min(buffer.data.len());
+                        for (dst, src) in buf.iter_mut().zip(buffer.data.drain(..n)) {
+                            *dst = src;
+                        }
+                        buffer.offset += n as u64;
+                        shared.changed.notify_all();
+                        drop(buffer);
+
+                        if self.head.len() as u64 == self.pos && self.head.len() < PROBE_BUFFER_BYTES {
+                            let keep = n.min(PROBE_BUFFER_BYTES - self.head.len());
+                            self.head.extend_from_slice(&buf[..keep]);
+                        }
+                        self.pos += n as u64;
+                        return Ok(n);
+                    }
+                    if buffer.finished {
+                        return match buffer.error {
+                            Some(ref e) => Err(io::Error::other(e.clone())),
+                            None => Ok(0),
+                        };
+                    }
+                }
+            }
+
+            let (next, wait) = shared.changed.wait_timeout(buffer, NETWORK_TIMEOUT).unwrap();
+            buffer = next;
+            if wait.timed_out() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "stream stopped sending data"));
+            }
+        }
+    }
+}
+
+impl Seek for HttpReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.info.length.and_then(|len| len.checked_add_signed(delta)),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        // Without range support only the kept head and the bytes ahead are reachable
+        if !self.info.seekable && target >= self.head.len() as u64 {
+            let downloaded_from = self.shared.buffer.lock().unwrap().offset;
+            if target < downloaded_from {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "stream does not support seeking",
+                ));
+            }
+        }
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+/// Reader over an ICY stream that passes the audio through and takes out the
+/// metadata block sent after every `metaint` bytes of it
+struct IcyStrip<R> {
+    inner: R,
+    metaint: usize,
+    /// Audio bytes left before the next metadata block
+    until_meta: usize,
+    live_title: LiveTitle,
+    last_title: Option<String>,
+}
+
+impl<R: Read> IcyStrip<R> {
+    fn new(inner: R, metaint: usize, live_title: LiveTitle) -> Self {
+        Self {
+            inner,
+            metaint,
+            until_meta: metaint,
+            live_title,
+            last_title: None,
+        }
+    }
+
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut len = [0u8; 1];
+        self.inner.read_exact(&mut len)?;
+        let mut meta = vec![0u8; len[0] as usize * 16];
+        self.inner.read_exact(&mut meta)?;
+        self.until_meta = self.metaint;
+
+        // Most blocks are empty or repeat the title; only report changes
+        if let Some(title) = parse_stream_title(&meta) {
+            if self.last_title.as_ref() != Some(&title) {
+                println!("[Stream] Now playing: {}", title);
+                if let Ok(mut slot) = self.live_title.lock() {
+                    *slot = Some(title.clone());
+                }
+                self.last_title = Some(title);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyStrip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.until_meta == 0 {
+            match self.read_metadata() {
+                Ok(()) => {}
+                // The stream ended right at a metadata boundary
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                Err(e) => return Err(e),
+            }
+        }
+        let len = buf.len().min(self.until_meta);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.until_meta -= n;
+        Ok(n)
+    }
+}
+
+/// `StreamTitle` from an ICY metadata block such as
+/// `StreamTitle='Artist - Title';StreamUrl='';` padded with NULs
+fn parse_stream_title(meta: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(meta);
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles may contain quotes themselves; the field ends at the quote before ';'
+    let end = rest
+        .find("';")
+        .unwrap_or_else(|| rest.trim_end_matches('\0').trim_end_matches('\'').len());
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+impl Drop for HttpReader {
+    fn drop(&mut self) {
+        if let Ok(mut buffer) = self.shared.buffer.lock() {
+            buffer.closed = true;
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_titles_use_the_decoded_file_name() {
+        assert_eq!(
+            title_from_url("https://nas.local/Music/01%20Intro.flac?token=x#t=5"),
+            "01 Intro"
+        );
+        assert_eq!(title_from_url("http://radio.example.com:8000/"), "radio.example.com:8000");
+        assert_eq!(title_from_url("http://radio.example.com/live"), "live");
+        assert!(is_remote("HTTPS://nas.local/a.mp3"));
+        assert!(!is_remote("C:/Music/a.mp3"));
+    }
+
+    #[test]
+    fn icy_metadata_is_stripped_and_titles_reported() {
+        fn block(text: &str) -> Vec<u8> {
+            let mut meta = text.as_bytes().to_vec();
+            meta.resize(text.len().div_ceil(16) * 16, 0);
+            let mut block = vec![(meta.len() / 16) as u8];
+            block.extend(meta);
+            block
+        }
+        // 4 audio bytes between metadata blocks; the second block is empty
+        let mut stream = b"abcd".to_vec();
+        stream.extend(block("StreamTitle='Band - It's On';StreamUrl='';"));
+        stream.extend(b"efgh");
+        stream.push(0);
+        stream.extend(b"ij");
+
+        let live_title = LiveTitle::default();
+        let mut reader = IcyStrip::new(io::Cursor::new(stream), 4, live_title.clone());
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+
+        assert_eq!(audio, b"abcdefghij");
+        let title = live_title.lock().unwrap().take().unwrap();
+        assert_eq!(title, "Band - It's On");
+        assert_eq!(split_stream_title(&title), (Some("Band"), "It's On"));
+        assert_eq!(split_stream_title("Station ID"), (None, "Station ID"));
+    }
+}
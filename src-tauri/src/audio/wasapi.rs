@@ -0,0 +1,335 @@
+//! WASAPI exclusive-mode output (Windows only)
+//!
+//! cpal only opens devices in shared mode, where Windows mixes every app at the
+//! engine's format and resamples to it. In exclusive mode the device plays our
+//! samples as they are, at the track's own rate and bit depth, but only formats
+//! the device accepts as-is can be opened: there is no conversion on the way.
+//!
+//! The stream runs on its own thread, which owns the COM objects, and is fed
+//! from the same rodio mixer as the shared output.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use rodio::dynamic_mixer::DynamicMixer;
+use windows::core::w;
+use windows::Win32::Foundation::{CloseHandle, HANDLE, S_OK, WAIT_OBJECT_0};
+use windows::Win32::Media::Audio::{
+    eConsole, eRender, IAudioClient, IAudioRenderClient, IMMDeviceEnumerator, MMDeviceEnumerator,
+    AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED, AUDCLNT_SHAREMODE_EXCLUSIVE,
+    AUDCLNT_STREAMFLAGS_EVENTCALLBACK, WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+    WAVEFORMATEXTENSIBLE_0,
+};
+use windows::Win32::Media::KernelStreaming::{KSDATAFORMAT_SUBTYPE_PCM, WAVE_FORMAT_EXTENSIBLE};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_ALL, COINIT_MULTITHREADED,
+};
+use windows::Win32::System::Threading::{
+    AvRevertMmThreadCharacteristics, AvSetMmThreadCharacteristicsW, CreateEventW,
+    WaitForSingleObject,
+};
+
+use super::decoder::PcmFormat;
+
+/// How long the device may go without asking for data before it counts as lost
+const EVENT_TIMEOUT_MS: u32 = 2000;
+
+/// Integer sample layouts exclusive mode is tried with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    Int16,
+    /// 24 significant bits, packed in 3 bytes
+    Int24,
+    /// 24 significant bits, MSB-aligned in 4 bytes
+    Int24In32,
+    Int32,
+}
+
+impl Layout {
+    fn container_bits(self) -> u16 {
+        match self {
+            Layout::Int16 => 16,
+            Layout::Int24 => 24,
+            Layout::Int24In32 | Layout::Int32 => 32,
+        }
+    }
+
+    fn valid_bits(self) -> u16 {
+        match self {
+            Layout::Int16 => 16,
+            Layout::Int24 | Layout::Int24In32 => 24,
+            Layout::Int32 => 32,
+        }
+    }
+
+    /// Layouts that carry every bit of a source with `bits` bits per sample,
+    /// best first. Lossy sources have no bit depth of their own.
+    fn candidates(bits: Option<u32>) -> &'static [Layout] {
+        match bits {
+            Some(0..=16) => &[Layout::Int16],
+            Some(17..=24) => &[Layout::Int24In32, Layout::Int24],
+            Some(_) => &[Layout::Int32],
+            None => &[Layout::Int24In32, Layout::Int24, Layout::Int16],
+        }
+    }
+
+    /// Write one f32 sample, as decoded from a source of this depth, back as
+    /// the integer it came from
+    fn write(self, sample: f32, out: &mut [u8]) {
+        match self {
+            Layout::Int16 => {
+                let value = (sample * 32_768.0).round() as i16;
+                out.copy_from_slice(&value.to_le_bytes());
+            }
+            Layout::Int24 => {
+                let value = ((sample * 2_147_483_648.0).round() as i32) >> 8;
+                out.copy_from_slice(&value.to_le_bytes()[..3]);
+            }
+            Layout::Int24In32 | Layout::Int32 => {
+                let value = (sample * 2_147_483_648.0).round() as i32;
+                out.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Default speaker positions for a channel count, as `dwChannelMask` bits
+fn channel_mask(channels: u16) -> u32 {
+    match channels {
+        1 => 0x4,   // front center
+        2 => 0x3,   // front left, front right
+        4 => 0x33,  // front and back pairs
+        6 => 0x3f,  // 5.1
+        8 => 0x63f, // 7.1 with side pair
+        _ => 0,
+    }
+}
+
+fn wave_format(format: PcmFormat, layout: Layout) -> WAVEFORMATEXTENSIBLE {
+    let block_align = format.channels * layout.container_bits() / 8;
+    WAVEFORMATEXTENSIBLE {
+        Format: WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_EXTENSIBLE as u16,
+            nChannels: format.channels,
+            nSamplesPerSec: format.sample_rate,
+            nAvgBytesPerSec: format.sample_rate * block_align as u32,
+            nBlockAlign: block_align,
+            wBitsPerSample: layout.container_bits(),
+            cbSize: (std::mem::size_of::<WAVEFORMATEXTENSIBLE>()
+                - std::mem::size_of::<WAVEFORMATEX>()) as u16,
+        },
+        Samples: WAVEFORMATEXTENSIBLE_0 {
+            wValidBitsPerSample: layout.valid_bits(),
+        },
+        dwChannelMask: channel_mask(format.channels),
+        SubFormat: KSDATAFORMAT_SUBTYPE_PCM,
+    }
+}
+
+/// The default device held in exclusive mode. Dropping it stops the stream and
+/// hands the device back to the system.
+pub struct ExclusiveStream {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExclusiveStream {
+    /// Open the default device exclusively at `format`, playing `source`.
+    /// Fails if the device can't take the format without conversion.
+    pub fn open(
+        format: PcmFormat,
+        source: DynamicMixer<f32>,
+        lost: Arc<AtomicBool>,
+    ) -> Result<Self, String> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = sync_channel(1);
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                // SAFETY: COM is initialized for this thread only, and every
+                // COM object is created and released on it
+                unsafe {
+                    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+                    match Renderer::open(format) {
+                        Ok(renderer) => {
+                            let _ = ready_tx.send(Ok(()));
+                            renderer.run(source, &stop, &lost);
+                        }
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(e));
+                        }
+                    }
+                    CoUninitialize();
+                }
+            })
+        };
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => Ok(Self {
+                stop,
+                thread: Some(thread),
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err("Exclusive output thread failed to start".to_string()),
+        }
+    }
+}
+
+impl Drop for ExclusiveStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// An initialized exclusive-mode client, owned by the stream thread
+struct Renderer {
+    client: IAudioClient,
+    render: IAudioRenderClient,
+    event: HANDLE,
+    layout: Layout,
+    channels: usize,
+    buffer_frames: u32,
+}
+
+impl Renderer {
+    unsafe fn open(format: PcmFormat) -> Result<Self, String> {
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+            .map_err(|e| format!("Cannot list audio devices: {}", e))?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eConsole)
+            .map_err(|e| format!("No audio output device: {}", e))?;
+        let activate = || -> Result<IAudioClient, String> {
+            device
+                .Activate(CLSCTX_ALL, None)
+                .map_err(|e| format!("Cannot open the output device: {}", e))
+        };
+
+        let mut client = activate()?;
+        let (layout, wave) = Layout::candidates(format.bits_per_sample)
+            .iter()
+            .map(|&layout| (layout, wave_format(format, layout)))
+            .find(|(_, wave)| {
+                client.IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, &wave.Format, None) == S_OK
+            })
+            .ok_or_else(|| {
+                let depth = format
+                    .bits_per_sample
+                    .map(|bits| format!("{}-bit", bits))
+                    .unwrap_or_else(|| "any bit depth".to_string());
+                format!(
+                    "Output device does not support {} Hz, {}, {} channels in exclusive mode",
+                    format.sample_rate, depth, format.channels
+                )
+            })?;
+
+        let mut period = 0i64;
+        client
+            .GetDevicePeriod(Some(&mut period), None)
+            .map_err(|e| format!("Cannot query the device period: {}", e))?;
+        let init = |client: &IAudioClient, period: i64| {
+            client.Initialize(
+                AUDCLNT_SHAREMODE_EXCLUSIVE,
+                AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+                period,
+                period,
+                &wave.Format,
+                None,
+            )
+        };
+        if let Err(e) = init(&client, period) {
+            if e.code() != AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED {
+                return Err(format!("Device refused exclusive mode: {}", e));
+            }
+            // Round the period to what the device can buffer and start over on
+            // a fresh client, as the failed one can't be initialized again
+            let frames = client
+                .GetBufferSize()
+                .map_err(|e| format!("Cannot query the device buffer: {}", e))?;
+            period = (10_000_000.0 * frames as f64 / format.sample_rate as f64).round() as i64;
+            client = activate()?;
+            init(&client, period).map_err(|e| format!("Device refused exclusive mode: {}", e))?;
+        }
+
+        let event = CreateEventW(None, false, false, None)
+            .map_err(|e| format!("Cannot create the output event: {}", e))?;
+        let setup = || -> windows::core::Result<(IAudioRenderClient, u32)> {
+            client.SetEventHandle(event)?;
+            Ok((client.GetService()?, client.GetBufferSize()?))
+        };
+        match setup() {
+            Ok((render, buffer_frames)) => {
+                println!(
+                    "[Audio] Exclusive output at {} Hz, {}-bit ({:?}), {} channels",
+                    format.sample_rate,
+                    layout.valid_bits(),
+                    layout,
+                    format.channels
+                );
+                Ok(Self {
+                    client,
+                    render,
+                    event,
+                    layout,
+                    channels: format.channels as usize,
+                    buffer_frames,
+                })
+            }
+            Err(e) => {
+                let _ = CloseHandle(event);
+                Err(format!("Cannot start exclusive output: {}", e))
+            }
+        }
+    }
+
+    /// Fill one device period from `source`
+    unsafe fn fill(&self, source: &mut DynamicMixer<f32>) -> windows::core::Result<()> {
+        let frames = self.buffer_frames as usize;
+        let sample_bytes = self.layout.container_bits() as usize / 8;
+        let data = self.render.GetBuffer(self.buffer_frames)?;
+        let out = std::slice::from_raw_parts_mut(data, frames * self.channels * sample_bytes);
+        for chunk in out.chunks_exact_mut(sample_bytes) {
+            self.layout.write(source.next().unwrap_or(0.0), chunk);
+        }
+        self.render.ReleaseBuffer(self.buffer_frames, 0)
+    }
+
+    unsafe fn run(self, mut source: DynamicMixer<f32>, stop: &AtomicBool, lost: &AtomicBool) {
+        let mut task_index = 0;
+        let task = AvSetMmThreadCharacteristicsW(w!("Pro Audio"), &mut task_index).ok();
+
+        // Queue one period before starting so the first callback isn't a glitch
+        let started = self.fill(&mut source).and_then(|_| self.client.Start());
+        if let Err(e) = started {
+            eprintln!("[Audio] Exclusive output failed to start: {}", e);
+            lost.store(true, Ordering::Relaxed);
+        } else {
+            while !stop.load(Ordering::Relaxed) {
+                if WaitForSingleObject(self.event, EVENT_TIMEOUT_MS) != WAIT_OBJECT_0 {
+                    eprintln!("[Audio] Exclusive output stopped asking for data");
+                    lost.store(true, Ordering::Relaxed);
+                    break;
+                }
+                // Any failure here means the device went away or was reset
+                if let Err(e) = self.fill(&mut source) {
+                    eprintln!("[Audio] Output stream error: {}", e);
+                    lost.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+            let _ = self.client.Stop();
+        }
+
+        if let Some(task) = task {
+            let _ = AvRevertMmThreadCharacteristics(task);
+        }
+        let _ = CloseHandle(self.event);
+    }
+}
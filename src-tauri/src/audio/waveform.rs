@@ -0,0 +1,133 @@
+//! Precomputed waveform peaks for scrubbable seek bars
+//!
+//! A track is decoded once and reduced to `PEAK_COUNT` min/max pairs in [-1, 1].
+//! The result is cached as JSON under `<app cache>/waveform/`, keyed by the
+//! source path and its modification time, so edits to the file invalidate it.
+
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Number of min/max pairs in a waveform
+const PEAK_COUNT: usize = 1000;
+
+/// Frames folded into one intermediate bucket while decoding. Keeps memory flat
+/// for long files whose total length is not known up front.
+const FRAMES_PER_BUCKET: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Waveform {
+    pub duration_secs: f64,
+    /// `[min, max]` sample values per slice of the track
+    pub peaks: Vec<[f32; 2]>,
+}
+
+/// MD5 rather than `DefaultHasher`, whose output may change between Rust
+/// releases and would orphan the cache
+fn cache_file_name(source: &Path, modified_secs: u64) -> String {
+    let key = format!("{}\n{}", source.to_string_lossy(), modified_secs);
+    format!("{:x}.json", md5::compute(key))
+}
+
+/// Return the cached waveform of `source`, decoding it first if needed.
+/// Blocking: decoding a full track takes a while, call from a worker thread.
+pub fn waveform_cached(cache_dir: &Path, source: &Path) -> Result<Waveform, String> {
+    let modified_secs = std::fs::metadata(source)
+        .map_err(|e| format!("Cannot read {}: {}", source.display(), e))?
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let dir = cache_dir.join("waveform");
+    let cached = dir.join(cache_file_name(source, modified_secs));
+    if let Some(waveform) = std::fs::read(&cached)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+    {
+        return Ok(waveform);
+    }
+
+    let waveform = compute_waveform(source)?;
+
+    // Write to a temp file first so a concurrent reader never sees partial JSON
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let temp = dir.join(format!("{}.part", uuid::Uuid::new_v4().simple()));
+    let json = serde_json::to_vec(&waveform).map_err(|e| e.to_string())?;
+    if std::fs::write(&temp, json).and_then(|_| std::fs::rename(&temp, &cached)).is_err() {
+        let _ = std::fs::remove_file(&temp);
+        eprintln!("[Waveform] Failed to cache waveform for {}", source.display());
+    }
+    Ok(waveform)
+}
+
+fn compute_waveform(source: &Path) -> Result<Waveform, String> {
+    let file = File::open(source).map_err(|e| format!("Cannot open {}: {}", source.display(), e))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Cannot decode {}: {}", source.display(), e))?;
+
+    let channels = decoder.channels().max(1) as usize;
+    let sample_rate = decoder.sample_rate().max(1) as f64;
+    let samples_per_bucket = FRAMES_PER_BUCKET * channels;
+
+    let mut buckets = Vec::new();
+    let mut current = [0.0f32; 2];
+    let mut in_bucket = 0;
+    let mut total_samples = 0usize;
+    for sample in decoder {
+        let value = sample as f32 / 32768.0;
+        current[0] = current[0].min(value);
+        current[1] = current[1].max(value);
+        in_bucket += 1;
+        total_samples += 1;
+        if in_bucket == samples_per_bucket {
+            buckets.push(current);
+            current = [0.0; 2];
+            in_bucket = 0;
+        }
+    }
+    if in_bucket > 0 {
+        buckets.push(current);
+    }
+
+    Ok(Waveform {
+        duration_secs: (total_samples / channels) as f64 / sample_rate,
+        peaks: downsample(&buckets, PEAK_COUNT),
+    })
+}
+
+/// Merge consecutive buckets into at most `count` min/max pairs
+fn downsample(buckets: &[[f32; 2]], count: usize) -> Vec<[f32; 2]> {
+    if buckets.len() <= count {
+        return buckets.to_vec();
+    }
+    (0..count)
+        .map(|i| {
+            let start = i * buckets.len() / count;
+            let end = ((i + 1) * buckets.len() / count).max(start + 1);
+            buckets[start..end].iter().fold([0.0f32, 0.0f32], |acc, b| {
+                [acc[0].min(b[0]), acc[1].max(b[1])]
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_keeps_extremes_of_each_slice() {
+        let buckets: Vec<[f32; 2]> = (0..10)
+            .map(|i| [-(i as f32) / 10.0, i as f32 / 10.0])
+            .collect();
+
+        let peaks = downsample(&buckets, 2);
+        assert_eq!(peaks, vec![[-0.4, 0.4], [-0.9, 0.9]]);
+        assert_eq!(downsample(&buckets, 20).len(), 10);
+    }
+}
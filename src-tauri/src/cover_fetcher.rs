@@ -1,4 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a failed lookup is remembered before the providers are asked again
+const NEGATIVE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,36 +21,191 @@ struct ItunesResult {
     artist_name: Option<String>,
 }
 
-/// Try to fetch album artwork from iTunes
+/// A source of album artwork URLs. Providers are tried in order until one matches.
+trait CoverProvider {
+    fn name(&self) -> &'static str;
+    fn search(&self, artist: &str, album: &str) -> Option<String>;
+}
+
+/// iTunes Search API
+struct Itunes;
+
+impl CoverProvider for Itunes {
+    fn name(&self) -> &'static str {
+        "iTunes"
+    }
+
+    fn search(&self, artist: &str, album: &str) -> Option<String> {
+        // Try artist + album with matching
+        let full_term = format!("{} {}", artist, album);
+        if let Some(url) = search_itunes_with_match(&full_term, artist, album) {
+            return Some(url);
+        }
+
+        // Try just artist with album matching
+        if let Some(url) = search_itunes_with_match(artist, artist, album) {
+            return Some(url);
+        }
+
+        // Try ASCII-only version
+        let ascii_term = strip_non_ascii(&full_term);
+        let ascii_album = strip_non_ascii(album);
+        if !ascii_term.trim().is_empty() {
+            if let Some(url) = search_itunes_with_match(&ascii_term, artist, &ascii_album) {
+                return Some(url);
+            }
+        }
+
+        None
+    }
+}
+
+/// MusicBrainz release search, with artwork from the Cover Art Archive
+struct CoverArtArchive;
+
+#[derive(Debug, Deserialize)]
+struct MusicBrainzSearch {
+    #[serde(default)]
+    releases: Vec<MusicBrainzRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MusicBrainzRelease {
+    id: String,
+    #[serde(default)]
+    score: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoverArtArchiveListing {
+    #[serde(default)]
+    images: Vec<CoverArtArchiveImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoverArtArchiveImage {
+    #[serde(default)]
+    front: bool,
+    image: String,
+    #[serde(default)]
+    thumbnails: HashMap<String, String>,
+}
+
+/// Releases scoring below this are too loose a match to trust
+const MIN_MUSICBRAINZ_SCORE: u32 = 90;
+/// Releases checked for artwork before giving up
+const MAX_MUSICBRAINZ_RELEASES: usize = 3;
+
+impl CoverArtArchive {
+    fn front_cover(client: &reqwest::blocking::Client, release_id: &str) -> Option<String> {
+        let listing = client
+            .get(format!("https://coverartarchive.org/release/{}", release_id))
+            .send()
+            .ok()
+            .filter(|r| r.status().is_success())?
+            .json::<CoverArtArchiveListing>()
+            .ok()?;
+
+        let front = listing.images.iter().find(|img| img.front)?;
+        Some(
+            front
+                .thumbnails
+                .get("500")
+                .or_else(|| front.thumbnails.get("large"))
+                .unwrap_or(&front.image)
+                .replace("http://", "https://"),
+        )
+    }
+}
+
+impl CoverProvider for CoverArtArchive {
+    fn name(&self) -> &'static str {
+        "Cover Art Archive"
+    }
+
+    fn search(&self, artist: &str, album: &str) -> Option<String> {
+        // MusicBrainz rejects requests without a descriptive User-Agent
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .user_agent(crate::USER_AGENT)
+            .build()
+            .ok()?;
+
+        let query = format!(
+            "release:\"{}\" AND artist:\"{}\"",
+            lucene_escape(album),
+            lucene_escape(artist)
+        );
+        println!("[Cover] Trying MusicBrainz: {}", query);
+
+        let search = client
+            .get("https://musicbrainz.org/ws/2/release/")
+            .query(&[("query", query.as_str()), ("fmt", "json"), ("limit", "5")])
+            .send()
+            .ok()
+            .filter(|r| r.status().is_success())?
+            .json::<MusicBrainzSearch>()
+            .ok()?;
+
+        search
+            .releases
+            .iter()
+            .filter(|r| r.score >= MIN_MUSICBRAINZ_SCORE)
+            .take(MAX_MUSICBRAINZ_RELEASES)
+            .find_map(|release| Self::front_cover(&client, &release.id))
+    }
+}
+
+/// Escape Lucene query syntax inside a quoted MusicBrainz search term
+fn lucene_escape(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Artist/album pairs that no provider had artwork for, and when we last asked
+fn negative_cache() -> &'static Mutex<HashMap<(String, String), Instant>> {
+    static MISSES: OnceLock<Mutex<HashMap<(String, String), Instant>>> = OnceLock::new();
+    MISSES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_key(artist: &str, album: &str) -> (String, String) {
+    (artist.trim().to_lowercase(), album.trim().to_lowercase())
+}
+
+fn recently_missed(artist: &str, album: &str) -> bool {
+    let mut misses = negative_cache().lock().unwrap();
+    misses.retain(|_, at| at.elapsed() < NEGATIVE_CACHE_TTL);
+    misses.contains_key(&cache_key(artist, album))
+}
+
+fn record_miss(artist: &str, album: &str) {
+    let mut misses = negative_cache().lock().unwrap();
+    misses.retain(|_, at| at.elapsed() < NEGATIVE_CACHE_TTL);
+    misses.insert(cache_key(artist, album), Instant::now());
+}
+
+/// Try to fetch album artwork, asking iTunes first and then MusicBrainz/Cover Art Archive
 pub fn search_cover(artist: &str, album: &str) -> Option<String> {
     if artist == "Unknown Artist" || album == "Unknown Album" {
         return None;
     }
 
-    // First try: exact search with album matching
-    println!("[Cover] Searching for: {} - {}", artist, album);
-
-    // Try artist + album with matching
-    let full_term = format!("{} {}", artist, album);
-    if let Some(url) = search_itunes_with_match(&full_term, artist, album) {
-        return Some(url);
+    if recently_missed(artist, album) {
+        println!("[Cover] Skipping recent miss: {} - {}", artist, album);
+        return None;
     }
 
-    // Try just artist with album matching
-    if let Some(url) = search_itunes_with_match(artist, artist, album) {
-        return Some(url);
-    }
+    println!("[Cover] Searching for: {} - {}", artist, album);
 
-    // Try ASCII-only version
-    let ascii_term = strip_non_ascii(&full_term);
-    let ascii_album = strip_non_ascii(album);
-    if !ascii_term.trim().is_empty() {
-        if let Some(url) = search_itunes_with_match(&ascii_term, artist, &ascii_album) {
+    let providers: [&dyn CoverProvider; 2] = [&Itunes, &CoverArtArchive];
+    for provider in providers {
+        if let Some(url) = provider.search(artist, album) {
+            println!("[Cover] Matched via {}", provider.name());
             return Some(url);
         }
     }
 
     println!("[Cover] No matching cover found");
+    record_miss(artist, album);
     None
 }
 
@@ -141,3 +302,33 @@ fn search_itunes_with_match(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn misses_are_remembered_case_insensitively() {
+        assert!(!recently_missed("Obscure Band", "Demo Tape"));
+        record_miss("Obscure Band", "Demo Tape");
+        assert!(recently_missed("obscure band ", "DEMO TAPE"));
+        assert!(!recently_missed("Obscure Band", "Second Demo"));
+    }
+
+    #[test]
+    fn misses_expire_after_the_ttl() {
+        let Some(long_ago) = Instant::now().checked_sub(NEGATIVE_CACHE_TTL + Duration::from_secs(1)) else {
+            return;
+        };
+        let key = cache_key("Expired Band", "Old Demo");
+        negative_cache().lock().unwrap().insert(key.clone(), long_ago);
+
+        assert!(!recently_missed("Expired Band", "Old Demo"));
+        assert!(!negative_cache().lock().unwrap().contains_key(&key));
+    }
+
+    #[test]
+    fn escapes_quotes_in_musicbrainz_terms() {
+        assert_eq!(lucene_escape(r#"Say "Hi""#), r#"Say \"Hi\""#);
+    }
+}
@@ -3,12 +3,12 @@ use serde::Serialize;
 use image::ImageReader;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Manager};
 use uuid::Uuid;
 
-use super::schema::init_db;
+use super::schema::{init_db, TRACKS_FTS_SCHEMA};
 use crate::audio::TrackInfo;
 
 pub struct DbAlbum {
@@ -17,6 +17,7 @@ pub struct DbAlbum {
     pub cover_image_path: Option<String>,
     pub main_color: Option<i64>,
     pub track_count: usize,
+    pub is_compilation: bool,
 }
 
 pub struct DbArtist {
@@ -25,6 +26,108 @@ pub struct DbArtist {
     pub track_count: usize,
 }
 
+/// Canonical form of a track path as stored in the DB: forward slashes, no `.`
+/// segments or doubled separators, and an upper-case Windows drive letter.
+/// Every path written to or compared against `tracks.path` goes through this.
+pub fn normalize_track_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    // Keep the leading `//` of UNC paths
+    let (prefix, rest) = match path.strip_prefix("//") {
+        Some(rest) => ("//", rest),
+        None => ("", path.as_str()),
+    };
+
+    let mut normalized = String::with_capacity(path.len());
+    normalized.push_str(prefix);
+    if rest.starts_with('/') {
+        normalized.push('/');
+    }
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty() && *s != ".").collect();
+    normalized.push_str(&segments.join("/"));
+
+    if normalized.as_bytes().get(1) == Some(&b':') {
+        normalized[..1].make_ascii_uppercase();
+    }
+    normalized
+}
+
+/// Normalized `folder` with a trailing separator, so it only prefixes paths
+/// inside it (`/music/` doesn't prefix `/music2/a.mp3`)
+pub fn folder_prefix(folder: &str) -> String {
+    let folder = normalize_track_path(folder);
+    if folder.ends_with('/') {
+        folder
+    } else {
+        folder + "/"
+    }
+}
+
+/// MIME type and file extension of image data, from its magic bytes. Tags
+/// often mislabel embedded art, so the bytes are what count. Anything
+/// unrecognised is treated as JPEG.
+pub fn image_type(data: &[u8]) -> (&'static str, &'static str) {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ("image/png", "png")
+    } else if data.starts_with(b"GIF8") {
+        ("image/gif", "gif")
+    } else if data.starts_with(b"BM") {
+        ("image/bmp", "bmp")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        ("image/webp", "webp")
+    } else {
+        ("image/jpeg", "jpg")
+    }
+}
+
+/// Album artist for compilations that carry no album-artist tag
+pub const VARIOUS_ARTISTS: &str = "Various Artists";
+
+/// Tracks whose durations fall in the same bucket count as the same recording
+const DUPLICATE_DURATION_BUCKET_SECS: f64 = 3.0;
+
+/// Bracketed or dashed title suffixes that mark a re-release of the same song
+const EDITION_MARKERS: &[&str] = &[
+    "remaster", "deluxe", "bonus", "explicit", "mono", "stereo", "album version", "single version",
+];
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Lowercased title without edition suffixes like "(Remastered 2011)" or "- Deluxe Edition"
+fn normalize_title(title: &str) -> String {
+    let mut title = title.trim().to_lowercase();
+    loop {
+        let trimmed = title.trim_end();
+        let cut = if let Some(open) = trimmed
+            .strip_suffix(')')
+            .and_then(|t| t.rfind('('))
+            .or_else(|| trimmed.strip_suffix(']').and_then(|t| t.rfind('[')))
+        {
+            Some(open)
+        } else {
+            trimmed.rfind(" - ")
+        };
+        match cut {
+            Some(pos) if EDITION_MARKERS.iter().any(|m| trimmed[pos..].contains(m)) => {
+                title.truncate(pos);
+            }
+            _ => break,
+        }
+    }
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lyrics fetched from the API and persisted for a track
+pub struct StoredLyrics {
+    pub synced_lyrics: Option<String>,
+    pub plain_lyrics: Option<String>,
+    pub instrumental: bool,
+}
+
 pub struct AlbumColorBackfillStats {
     pub total_albums: usize,
     pub already_colored: usize,
@@ -53,7 +156,67 @@ impl DatabaseManager {
 
         // Initialize schema
         init_db(&conn)?;
+        Self::migrate(&conn);
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            covers_dir,
+        })
+    }
+
+    /// Rewrite lower-case drive letters in every path column. A track whose
+    /// upper-case twin already exists is a duplicate the old mismatch created:
+    /// its favorite, play count and dates are merged into the twin before it is
+    /// removed, and its playlist entries and history move over with the rest.
+    fn uppercase_drive_letters(conn: &Connection) -> Result<()> {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE tracks AS t SET
+                 is_favorite = max(t.is_favorite, l.is_favorite),
+                 play_count = t.play_count + l.play_count,
+                 last_played_ms = coalesce(max(t.last_played_ms, l.last_played_ms), t.last_played_ms, l.last_played_ms),
+                 added_at = coalesce(min(t.added_at, l.added_at), t.added_at, l.added_at)
+             FROM tracks AS l
+             WHERE l.path GLOB '[a-z]:/*'
+               AND t.path = upper(substr(l.path, 1, 1)) || substr(l.path, 2)",
+            [],
+        )?;
+        for (table, column) in [
+            ("tracks", "path"),
+            ("playlist_tracks", "track_path"),
+            ("playback_events", "song_id"),
+            ("lyrics", "track_path"),
+            ("lyrics_offsets", "track_path"),
+        ] {
+            tx.execute(
+                &format!(
+                    "UPDATE OR IGNORE {table} SET {column} = upper(substr({column}, 1, 1)) || substr({column}, 2)
+                     WHERE {column} GLOB '[a-z]:/*'"
+                ),
+                [],
+            )?;
+            // What is left clashes with a row already under the upper-case path
+            tx.execute(&format!("DELETE FROM {table} WHERE {column} GLOB '[a-z]:/*'"), [])?;
+        }
+        tx.pragma_update(None, "user_version", 1)?;
+        tx.commit()
+    }
+
+    /// In-memory database with the full schema, for tests
+    #[cfg(test)]
+    pub(crate) fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        init_db(&conn)?;
+        Self::migrate(&conn);
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            covers_dir: std::env::temp_dir(),
+        })
+    }
 
+    /// Add columns introduced after the first release. Errors mean the column already exists.
+    fn migrate(conn: &Connection) {
         // Migration: Add new columns if missing
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN disc_number INTEGER", []);
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN track_number INTEGER", []);
@@ -66,6 +229,59 @@ impl DatabaseManager {
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN album_romaji TEXT", []);
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN album_en TEXT", []);
 
+        // Migration: Favorites
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0", []);
+
+        // Migration: Rows whose tags were read before a tag column existed. The
+        // next scan of their folder re-reads them once; see `get_tracks_missing_metadata`.
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN needs_tag_reread INTEGER NOT NULL DEFAULT 0", []);
+
+        // Migration: Album artist, so compilations group as one album. Existing
+        // rows use the track artist (the old grouping) until their tag is re-read.
+        if conn.execute("ALTER TABLE tracks ADD COLUMN album_artist TEXT", []).is_ok() {
+            let _ = conn.execute("UPDATE tracks SET needs_tag_reread = 1", []);
+        }
+        let _ = conn.execute("UPDATE tracks SET album_artist = artist WHERE album_artist IS NULL", []);
+
+        // Migration: Compilation flag. Untagged compilations group under "Various Artists".
+        // Existing rows have their compilation tag read on the next scan.
+        if conn.execute("ALTER TABLE tracks ADD COLUMN is_compilation INTEGER NOT NULL DEFAULT 0", []).is_ok() {
+            let _ = conn.execute("UPDATE tracks SET needs_tag_reread = 1", []);
+        }
+
+        // Migration: Genre and year, read from the tags of existing rows on their next scan
+        let added_genre = conn.execute("ALTER TABLE tracks ADD COLUMN genre TEXT", []).is_ok();
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN year INTEGER", []);
+        if added_genre {
+            let _ = conn.execute("UPDATE tracks SET needs_tag_reread = 1", []);
+        }
+        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_tracks_genre ON tracks(genre)", []);
+
+        // Migration: Play counts
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN last_played_ms INTEGER", []);
+
+        // Migration: File size, part of the fingerprint used to follow moved files
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN file_size INTEGER", []);
+
+        // Migration: Insertion time (ms epoch) for the recently-added view. Rows
+        // from before the column existed get the migration time.
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN added_at INTEGER", []);
+        let _ = conn.execute(
+            "UPDATE tracks SET added_at = ?1 WHERE added_at IS NULL",
+            params![now_ms()],
+        );
+        let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_tracks_added_at ON tracks(added_at)", []);
+
+        // Migration (schema version 1): Upper-case Windows drive letters, as
+        // `normalize_track_path` now stores them
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap_or(0);
+        if version < 1 {
+            if let Err(e) = Self::uppercase_drive_letters(conn) {
+                eprintln!("[DB] Failed to upper-case drive letters: {}", e);
+            }
+        }
+
         // Migration: Persist album primary color seed
         let _ = conn.execute("ALTER TABLE albums ADD COLUMN main_color INTEGER", []);
 
@@ -77,43 +293,78 @@ impl DatabaseManager {
         let _ = conn.execute("ALTER TABLE playlists ADD COLUMN created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP", []);
         let _ = conn.execute("ALTER TABLE playlists ADD COLUMN updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP", []);
 
-        Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
-            covers_dir,
-        })
+        // Migration: Full-text search index, backfilled from existing rows on first creation.
+        // An index from before the trigram tokenizer can't match inside CJK text; rebuild it.
+        let fts_sql: Option<String> = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'tracks_fts'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+        let fts_exists = match fts_sql {
+            Some(sql) if sql.contains("trigram") => true,
+            Some(_) => {
+                let _ = conn.execute("DROP TABLE tracks_fts", []);
+                false
+            }
+            None => false,
+        };
+        if let Err(e) = conn.execute_batch(TRACKS_FTS_SCHEMA) {
+            eprintln!("[Database] Failed to create search index: {}", e);
+        } else if !fts_exists {
+            let _ = conn.execute("INSERT INTO tracks_fts(tracks_fts) VALUES('rebuild')", []);
+        }
     }
 
     pub fn insert_track(&self, track: &TrackInfo, cover_data: Option<&[u8]>) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
-        // Generate Romaji if needed
-        let title_romaji = if crate::lyrics_transliteration::has_japanese(&track.title) {
-            Some(crate::lyrics_transliteration::to_romaji(&track.title))
-        } else {
-            Some("".to_string())
-        };
+        // Generate Romaji (or RR / pinyin) if needed
+        let title_romaji = Some(crate::lyrics_transliteration::transliterate(&track.title).unwrap_or_default());
 
-        let artist_romaji = if crate::lyrics_transliteration::has_japanese(&track.artist) {
-            Some(crate::lyrics_transliteration::to_romaji(&track.artist))
-        } else {
-            Some("".to_string())
-        };
+        let artist_romaji = Some(crate::lyrics_transliteration::transliterate(&track.artist).unwrap_or_default());
 
-        let album_romaji = if crate::lyrics_transliteration::has_japanese(&track.album) {
-            Some(crate::lyrics_transliteration::to_romaji(&track.album))
-        } else {
-            Some("".to_string())
-        };
+        let album_romaji = Some(crate::lyrics_transliteration::transliterate(&track.album).unwrap_or_default());
+
+        let normalized_path = normalize_track_path(&track.path);
 
-        let normalized_path = track.path.replace("\\", "/");
+        // Albums are keyed on the album artist. Without one, compilations share
+        // "Various Artists" and other tracks use the track artist.
+        let album_artist = track
+            .album_artist
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .unwrap_or(if track.is_compilation { VARIOUS_ARTISTS } else { &track.artist });
 
-        // Insert into tracks
+        let file_size = fs::metadata(&normalized_path).ok().map(|m| m.len() as i64);
+
+        // Upsert into tracks. Only the scanned metadata columns are updated so
+        // user-owned state (favorites, play counts) and `added_at` survive a rescan.
         conn.execute(
-            "INSERT OR REPLACE INTO tracks (
+            "INSERT INTO tracks (
                 path, title, artist, album, duration_secs, disc_number, track_number,
-                title_romaji, artist_romaji, album_romaji
-            ) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                title_romaji, artist_romaji, album_romaji, genre, year, album_artist, file_size,
+                added_at, is_compilation
+            )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+             ON CONFLICT(path) DO UPDATE SET
+                title = excluded.title,
+                artist = excluded.artist,
+                album = excluded.album,
+                duration_secs = excluded.duration_secs,
+                disc_number = excluded.disc_number,
+                track_number = excluded.track_number,
+                title_romaji = excluded.title_romaji,
+                artist_romaji = excluded.artist_romaji,
+                album_romaji = excluded.album_romaji,
+                genre = excluded.genre,
+                year = excluded.year,
+                album_artist = excluded.album_artist,
+                file_size = excluded.file_size,
+                is_compilation = excluded.is_compilation,
+                needs_tag_reread = 0",
             params![
                 normalized_path,
                 track.title,
@@ -124,7 +375,13 @@ impl DatabaseManager {
                 track.track_number,
                 title_romaji,
                 artist_romaji,
-                album_romaji
+                album_romaji,
+                track.genre,
+                track.year,
+                album_artist,
+                file_size,
+                now_ms(),
+                track.is_compilation
             ],
         )?;
 
@@ -132,7 +389,7 @@ impl DatabaseManager {
         let album_row: Option<Option<String>> = conn
             .query_row(
                 "SELECT cover_image_path FROM albums WHERE name = ?1 AND artist = ?2",
-                params![track.album, track.artist],
+                params![track.album, album_artist],
                 |row| row.get(0),
             )
             .optional()?;
@@ -142,52 +399,88 @@ impl DatabaseManager {
 
         if let Some(data) = cover_data {
             if existing_cover.is_none() {
-                let filename = format!("{}.jpg", Uuid::new_v4());
-                let path = self.covers_dir.join(&filename);
-
-                let saved = if let Ok(mut file) = fs::File::create(&path) {
-                    file.write_all(data).is_ok()
-                } else {
-                    false
-                };
-
-                if saved {
+                if let Some(filename) = self.save_cover_data(data) {
                     if album_exists {
                         conn.execute(
                             "UPDATE albums SET cover_image_path = ?1 WHERE name = ?2 AND artist = ?3",
-                            params![filename, track.album, track.artist],
+                            params![filename, track.album, album_artist],
                         )?;
                     } else {
                         conn.execute(
                             "INSERT INTO albums (name, artist, cover_image_path) VALUES (?1, ?2, ?3)",
-                            params![track.album, track.artist, filename],
+                            params![track.album, album_artist, filename],
                         )?;
                     }
                 } else if !album_exists {
                     // Create album entry even if save failed
                     conn.execute(
                         "INSERT INTO albums (name, artist, cover_image_path) VALUES (?1, ?2, ?3)",
-                        params![track.album, track.artist, Option::<String>::None],
+                        params![track.album, album_artist, Option::<String>::None],
                     )?;
                 }
             } else if !album_exists {
                 // No cover data, just insert album
                 conn.execute(
                     "INSERT INTO albums (name, artist, cover_image_path) VALUES (?1, ?2, ?3)",
-                    params![track.album, track.artist, Option::<String>::None],
+                    params![track.album, album_artist, Option::<String>::None],
                 )?;
             }
         } else if !album_exists {
             // No cover data and album doesn't exist
             conn.execute(
                 "INSERT INTO albums (name, artist, cover_image_path) VALUES (?1, ?2, ?3)",
-                params![track.album, track.artist, Option::<String>::None],
+                params![track.album, album_artist, Option::<String>::None],
             )?;
         }
 
         Ok(())
     }
 
+    /// Write cover art into the covers dir under a fresh name, with the
+    /// extension its bytes call for. Returns the filename, or `None` if it
+    /// could not be written.
+    pub fn save_cover_data(&self, data: &[u8]) -> Option<String> {
+        let (_, extension) = image_type(data);
+        let filename = format!("{}.{}", Uuid::new_v4(), extension);
+        let mut file = fs::File::create(self.covers_dir.join(&filename)).ok()?;
+        file.write_all(data).ok()?;
+        Some(filename)
+    }
+
+    /// Albums that still have no cover, paired with each of their tracks listed
+    /// in `paths`, as `(album, album artist, track path)`
+    pub fn albums_missing_cover(&self, paths: &[String]) -> Result<Vec<(String, String, String)>> {
+        let wanted: std::collections::HashSet<String> =
+            paths.iter().map(|p| normalize_track_path(p)).collect();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT a.name, a.artist, t.path FROM albums a
+             JOIN tracks t ON t.album = a.name AND t.album_artist = a.artist
+             WHERE a.cover_image_path IS NULL
+             ORDER BY a.name, a.artist, t.path",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, String>(2)?)))?
+            .collect::<Result<Vec<(String, String, String)>>>()?;
+        Ok(rows.into_iter().filter(|(_, _, path)| wanted.contains(path)).collect())
+    }
+
+    /// An album's cover filename (if it has one) and the path of its first
+    /// track, or `None` when no tracks belong to the album
+    pub fn get_album_cover(&self, album: &str, artist: &str) -> Result<Option<(Option<String>, String)>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT a.cover_image_path, t.path FROM tracks t
+             LEFT JOIN albums a ON a.name = t.album AND a.artist = t.album_artist
+             WHERE t.album = ?1 AND t.album_artist = ?2
+             ORDER BY COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number, t.path
+             LIMIT 1",
+            params![album, artist],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+    }
+
     pub fn update_album_cover(
         &self,
         album: &str,
@@ -204,6 +497,51 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Copy a user-chosen PNG/JPEG into the covers dir and make it the album's cover.
+    /// Rescans only fill in missing covers, so this overrides embedded and fetched art.
+    /// Returns the new cover filename.
+    pub fn set_album_cover_from_file(
+        &self,
+        album: &str,
+        artist: &str,
+        image_path: &Path,
+    ) -> std::result::Result<String, String> {
+        let data = fs::read(image_path).map_err(|e| format!("Failed to read image: {}", e))?;
+        let extension = match image::guess_format(&data) {
+            Ok(image::ImageFormat::Png) => "png",
+            Ok(image::ImageFormat::Jpeg) => "jpg",
+            _ => return Err("Unsupported image format: choose a PNG or JPEG file".to_string()),
+        };
+
+        let album_exists = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT 1 FROM albums WHERE name = ?1 AND artist = ?2",
+                params![album.replace("\\", "/"), artist.replace("\\", "/")],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?
+            .is_some()
+        };
+        if !album_exists {
+            return Err(format!("Album not found: {} - {}", artist, album));
+        }
+
+        let filename = format!("{}.{}", Uuid::new_v4(), extension);
+        fs::write(self.covers_dir.join(&filename), &data)
+            .map_err(|e| format!("Failed to save cover: {}", e))?;
+        self.update_album_cover(album, artist, &filename)
+            .map_err(|e| e.to_string())?;
+
+        // Keep the album accent color in step with the new art
+        if let Some(color) = self.derive_color_from_cover(&filename) {
+            let _ = self.update_album_main_color(album, artist, color);
+        }
+
+        Ok(filename)
+    }
+
     pub fn update_album_main_color(&self, album: &str, artist: &str, main_color: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let normalized_album = album.replace("\\", "/");
@@ -309,15 +647,21 @@ impl DatabaseManager {
         Some((0xFF_i64 << 24) | (red << 16) | (green << 8) | blue)
     }
 
+    /// Total number of tracks, used for pagination without loading every row
+    pub fn get_track_count(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM tracks", [], |row| row.get(0))
+    }
+
     pub fn get_tracks_paginated(&self, limit: usize, offset: usize) -> Result<Vec<TrackInfo>> {
         let conn = self.conn.lock().unwrap();
 
         let mut stmt = conn.prepare(
             "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
-             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, t.genre, t.year, t.album_artist, t.is_compilation
              FROM tracks t 
-             LEFT JOIN albums a ON t.album = a.name AND t.artist = a.artist
-             ORDER BY t.artist, t.album, t.disc_number, t.track_number, t.title
+             LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist
+             ORDER BY t.artist, t.album, COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number, t.title
              LIMIT ?1 OFFSET ?2",
         )?;
 
@@ -340,6 +684,12 @@ impl DatabaseManager {
                 album_romaji: row.get(13).unwrap_or(None),
                 album_en: row.get(14).unwrap_or(None),
                 playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: row.get(15).unwrap_or(None),
+                year: row.get(16).unwrap_or(None),
+                album_artist: row.get(17).unwrap_or(None),
+                is_compilation: row.get(18).unwrap_or(false),
             })
         })?;
 
@@ -351,29 +701,80 @@ impl DatabaseManager {
         Ok(tracks)
     }
 
-    #[allow(dead_code)]
+    /// Full-text search over title/artist/album (including romaji and English
+    /// variants). Every word must appear in some field, in any order and
+    /// anywhere within it, so CJK text and partly typed words match too.
+    /// Ranked by bm25.
     pub fn search_tracks(
         &self,
         query: &str,
         limit: usize,
         offset: usize,
     ) -> Result<Vec<TrackInfo>> {
-        let conn = self.conn.lock().unwrap();
-        let search_query = format!("%{}%", query);
+        match build_fts_query(query) {
+            Some(terms) => self.query_search(&terms, None, Some((limit, offset))),
+            None => self.get_tracks_paginated(limit, offset),
+        }
+    }
 
-        let mut stmt = conn.prepare(
+    /// Every track matching `query` within the given search index columns
+    /// (see `ALBUM_SEARCH_COLUMNS`), best matches first. A blank query matches
+    /// all tracks.
+    pub fn search_tracks_in(&self, query: &str, columns: &[&str]) -> Result<Vec<TrackInfo>> {
+        match build_fts_query(query) {
+            Some(terms) => self.query_search(&terms, Some(columns), None),
+            None => self.get_all_tracks(),
+        }
+    }
+
+    fn query_search(
+        &self,
+        terms: &SearchTerms,
+        columns: Option<&[&str]>,
+        page: Option<(usize, usize)>,
+    ) -> Result<Vec<TrackInfo>> {
+        use rusqlite::types::Value;
+
+        let columns = columns.unwrap_or(SEARCH_COLUMNS);
+        let mut sql = String::from(
             "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
-             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en
-             FROM tracks t 
-             LEFT JOIN albums a ON t.album = a.name AND t.artist = a.artist
-             WHERE t.title LIKE ?1 OR t.artist LIKE ?1 OR t.album LIKE ?1
-                OR t.title_romaji LIKE ?1 OR t.artist_romaji LIKE ?1 OR t.album_romaji LIKE ?1
-                OR t.title_en LIKE ?1 OR t.artist_en LIKE ?1 OR t.album_en LIKE ?1
-             ORDER BY t.artist, t.album, t.disc_number, t.track_number, t.title
-             LIMIT ?2 OFFSET ?3",
-        )?;
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, t.genre, t.year, t.album_artist, t.is_compilation
+             FROM tracks t",
+        );
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(ref fts) = terms.fts {
+            sql.push_str(" JOIN tracks_fts f ON f.rowid = t.id");
+            conditions.push("tracks_fts MATCH ?".to_string());
+            values.push(Value::Text(format!("{{{}}} : ({})", columns.join(" "), fts)));
+        }
+        sql.push_str(" LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist");
+        // Too short for the trigram index; scan the columns instead
+        let text = columns
+            .iter()
+            .map(|c| format!("IFNULL(t.{}, '')", c))
+            .collect::<Vec<_>>()
+            .join(" || ' ' || ");
+        for word in &terms.short {
+            conditions.push(format!("({}) LIKE ?", text));
+            values.push(Value::Text(format!("%{}%", word)));
+        }
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+        sql.push_str(" ORDER BY ");
+        if terms.fts.is_some() {
+            sql.push_str("bm25(tracks_fts, 10.0, 5.0, 3.0, 10.0, 5.0, 3.0, 10.0, 5.0, 3.0), ");
+        }
+        sql.push_str("t.artist, t.album, t.track_number IS NULL, t.track_number");
+        if let Some((limit, offset)) = page {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            values.push(Value::Integer(limit as i64));
+            values.push(Value::Integer(offset as i64));
+        }
 
-        let track_iter = stmt.query_map(params![search_query, limit, offset], |row| {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let track_iter = stmt.query_map(rusqlite::params_from_iter(values), |row| {
             let cover_filename: Option<String> = row.get(5)?;
             Ok(TrackInfo {
                 path: row.get(0)?,
@@ -392,6 +793,12 @@ impl DatabaseManager {
                 album_romaji: row.get(13).unwrap_or(None),
                 album_en: row.get(14).unwrap_or(None),
                 playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: row.get(15).unwrap_or(None),
+                year: row.get(16).unwrap_or(None),
+                album_artist: row.get(17).unwrap_or(None),
+                is_compilation: row.get(18).unwrap_or(false),
             })
         })?;
 
@@ -406,12 +813,12 @@ impl DatabaseManager {
     pub fn get_track(&self, path: &str) -> Result<Option<TrackInfo>> {
         let conn = self.conn.lock().unwrap();
 
-        let normalized_path = path.replace("\\", "/");
+        let normalized_path = normalize_track_path(path);
         let mut stmt = conn.prepare(
             "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
-             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, t.genre, t.year, t.album_artist, t.is_compilation
              FROM tracks t 
-             LEFT JOIN albums a ON t.album = a.name AND t.artist = a.artist
+             LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist
              WHERE t.path = ?1",
         )?;
 
@@ -434,6 +841,12 @@ impl DatabaseManager {
                 album_romaji: row.get(13).unwrap_or(None),
                 album_en: row.get(14).unwrap_or(None),
                 playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: row.get(15).unwrap_or(None),
+                year: row.get(16).unwrap_or(None),
+                album_artist: row.get(17).unwrap_or(None),
+                is_compilation: row.get(18).unwrap_or(false),
             })
         })?;
 
@@ -453,18 +866,20 @@ impl DatabaseManager {
 
         // Count total albums (approximate or separate query)
         // For distinct albums:
+        // Albums left behind by older artist-keyed grouping have no tracks and are skipped
         let total: usize = conn
             .query_row(
-                "SELECT COUNT(*) FROM (SELECT DISTINCT name, artist FROM albums)",
+                "SELECT COUNT(*) FROM (SELECT DISTINCT album, album_artist FROM tracks)",
                 [],
                 |row| row.get(0),
             )
             .unwrap_or(0);
 
         let mut stmt = conn.prepare(
-            "SELECT a.name, a.artist, a.cover_image_path, a.main_color, COUNT(t.path) as track_count
+            "SELECT a.name, a.artist, a.cover_image_path, a.main_color, COUNT(t.path) as track_count,
+                    MAX(t.is_compilation)
              FROM albums a
-             LEFT JOIN tracks t ON t.album = a.name AND t.artist = a.artist
+             JOIN tracks t ON t.album = a.name AND t.album_artist = a.artist
              GROUP BY a.name, a.artist
              ORDER BY a.name
              LIMIT ?1 OFFSET ?2",
@@ -478,6 +893,7 @@ impl DatabaseManager {
                 cover_image_path: cover_filename,
                 main_color: row.get(3).unwrap_or(None),
                 track_count: row.get(4)?,
+                is_compilation: row.get(5).unwrap_or(false),
             })
         })?;
 
@@ -544,7 +960,7 @@ impl DatabaseManager {
              WHERE title LIKE ?1 OR artist LIKE ?1 OR album LIKE ?1
                 OR title_romaji LIKE ?1 OR artist_romaji LIKE ?1 OR album_romaji LIKE ?1
                 OR title_en LIKE ?1 OR artist_en LIKE ?1 OR album_en LIKE ?1
-             ORDER BY artist, album, track_number",
+             ORDER BY artist, album, track_number IS NULL, track_number",
         )?;
 
         let track_iter = stmt.query_map(params![search_query], |row| {
@@ -565,6 +981,12 @@ impl DatabaseManager {
                 album_romaji: row.get(11).unwrap_or(None),
                 album_en: row.get(12).unwrap_or(None),
                 playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: None,
+                year: None,
+                album_artist: None,
+                is_compilation: false,
             })
         })?;
 
@@ -582,10 +1004,10 @@ impl DatabaseManager {
         // Join tracks with albums to get the cover image path
         let mut stmt = conn.prepare(
             "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
-             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, t.genre, t.year, t.album_artist, t.is_compilation
              FROM tracks t 
-             LEFT JOIN albums a ON t.album = a.name AND t.artist = a.artist
-             ORDER BY t.artist, t.album, t.disc_number, t.track_number, t.title",
+             LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist
+             ORDER BY t.artist, t.album, COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number, t.title",
         )?;
 
         let track_iter = stmt.query_map([], |row| {
@@ -608,6 +1030,219 @@ impl DatabaseManager {
                 album_romaji: row.get(13).unwrap_or(None),
                 album_en: row.get(14).unwrap_or(None),
                 playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: row.get(15).unwrap_or(None),
+                year: row.get(16).unwrap_or(None),
+                album_artist: row.get(17).unwrap_or(None),
+                is_compilation: row.get(18).unwrap_or(false),
+            })
+        })?;
+
+        let mut tracks = Vec::new();
+        for track in track_iter {
+            tracks.push(track?);
+        }
+
+        Ok(tracks)
+    }
+
+    /// Flip the favorite flag for a track and return the new value
+    pub fn toggle_favorite(&self, path: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(path);
+        conn.execute(
+            "UPDATE tracks SET is_favorite = 1 - is_favorite WHERE path = ?1",
+            params![normalized_path],
+        )?;
+        conn.query_row(
+            "SELECT is_favorite FROM tracks WHERE path = ?1",
+            params![normalized_path],
+            |row| row.get::<_, bool>(0),
+        )
+    }
+
+    pub fn get_favorites(&self) -> Result<Vec<TrackInfo>> {
+        self.query_track_list(
+            "WHERE t.is_favorite = 1
+             ORDER BY t.artist, t.album, COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number, t.title",
+            [],
+        )
+    }
+
+    /// Distinct genres with their track counts, grouped case-insensitively
+    pub fn get_genres(&self) -> Result<Vec<(String, usize)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT genre, COUNT(*) FROM tracks
+             WHERE genre IS NOT NULL AND genre != ''
+             GROUP BY genre COLLATE NOCASE
+             ORDER BY genre COLLATE NOCASE",
+        )?;
+
+        let genre_iter = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+
+        let mut genres = Vec::new();
+        for genre in genre_iter {
+            genres.push(genre?);
+        }
+        Ok(genres)
+    }
+
+    pub fn get_tracks_by_genre(&self, genre: &str) -> Result<Vec<TrackInfo>> {
+        self.query_track_list(
+            "WHERE t.genre = ?1 COLLATE NOCASE
+             ORDER BY t.artist, t.album, COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number, t.title",
+            params![genre],
+        )
+    }
+
+    /// If `track` is a file that was moved or renamed, point its old row at the new
+    /// path so favorites, play counts, playlists and history follow it.
+    /// A row matches when its file is gone from disk and it has the same title,
+    /// artist, duration and file size. Returns the old path when a row was moved.
+    pub fn adopt_moved_track(&self, track: &TrackInfo) -> Result<Option<String>> {
+        let new_path = normalize_track_path(&track.path);
+        let Ok(metadata) = fs::metadata(&new_path) else {
+            return Ok(None);
+        };
+
+        let old_path = {
+            let conn = self.conn.lock().unwrap();
+            let already_known: bool = conn
+                .query_row("SELECT 1 FROM tracks WHERE path = ?1", params![new_path], |_| Ok(true))
+                .optional()?
+                .unwrap_or(false);
+            if already_known {
+                return Ok(None);
+            }
+
+            // Rows scanned before sizes were recorded match on tags and duration alone
+            let mut stmt = conn.prepare(
+                "SELECT path FROM tracks
+                 WHERE title = ?1 AND artist = ?2 AND ABS(duration_secs - ?3) < 0.5
+                   AND (file_size = ?4 OR file_size IS NULL)",
+            )?;
+            let candidates = stmt
+                .query_map(
+                    params![track.title, track.artist, track.duration_secs, metadata.len() as i64],
+                    |row| row.get::<_, String>(0),
+                )?
+                .collect::<Result<Vec<String>>>()?;
+            match candidates.into_iter().find(|p| !Path::new(p).exists()) {
+                Some(path) => path,
+                None => return Ok(None),
+            }
+        };
+
+        self.relocate_track(&old_path, &new_path)?;
+        println!("[Database] Track moved: {} -> {}", old_path, new_path);
+        Ok(Some(old_path))
+    }
+
+    /// Rename a track's path everywhere it is referenced
+    fn relocate_track(&self, old_path: &str, new_path: &str) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE tracks SET path = ?2 WHERE path = ?1", params![old_path, new_path])?;
+        tx.execute(
+            "UPDATE playlist_tracks SET track_path = ?2 WHERE track_path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE playback_events SET song_id = ?2 WHERE song_id = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE lyrics SET track_path = ?2 WHERE track_path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.execute(
+            "UPDATE OR IGNORE lyrics_offsets SET track_path = ?2 WHERE track_path = ?1",
+            params![old_path, new_path],
+        )?;
+        tx.commit()
+    }
+
+    /// Count a completed play: bump `play_count` and stamp `last_played_ms`
+    pub fn increment_play_count(&self, path: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(path);
+        conn.execute(
+            "UPDATE tracks SET play_count = play_count + 1, last_played_ms = ?1 WHERE path = ?2",
+            params![now_ms(), normalized_path],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_most_played(&self, limit: usize) -> Result<Vec<TrackInfo>> {
+        self.query_track_list(
+            "WHERE t.play_count > 0
+             ORDER BY t.play_count DESC, t.last_played_ms DESC
+             LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// Tracks ordered by `last_played_ms`. The stats-based `get_recently_played`
+    /// returns playback events instead.
+    pub fn get_recently_played_tracks(&self, limit: usize) -> Result<Vec<TrackInfo>> {
+        self.query_track_list(
+            "WHERE t.last_played_ms IS NOT NULL
+             ORDER BY t.last_played_ms DESC
+             LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// Newest tracks first, by when they were first added to the library
+    pub fn get_recently_added(&self, limit: usize) -> Result<Vec<TrackInfo>> {
+        self.query_track_list(
+            "ORDER BY t.added_at DESC, t.album, COALESCE(t.disc_number, 1), t.track_number IS NULL, t.track_number
+             LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// Run the standard track + album cover SELECT with a custom WHERE/ORDER/LIMIT tail
+    fn query_track_list<P: rusqlite::Params>(&self, tail: &str, params: P) -> Result<Vec<TrackInfo>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, t.genre, t.year, t.album_artist, t.is_compilation
+             FROM tracks t
+             LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist
+             {}",
+            tail
+        ))?;
+
+        let track_iter = stmt.query_map(params, |row| {
+            Ok(TrackInfo {
+                path: row.get(0)?,
+                title: row.get(1)?,
+                artist: row.get(2)?,
+                album: row.get(3)?,
+                duration_secs: row.get(4)?,
+                cover_image: row.get(5)?,
+                album_main_color: row.get(6).unwrap_or(None),
+                disc_number: row.get(7).unwrap_or(None),
+                track_number: row.get(8).unwrap_or(None),
+                title_romaji: row.get(9).unwrap_or(None),
+                title_en: row.get(10).unwrap_or(None),
+                artist_romaji: row.get(11).unwrap_or(None),
+                artist_en: row.get(12).unwrap_or(None),
+                album_romaji: row.get(13).unwrap_or(None),
+                album_en: row.get(14).unwrap_or(None),
+                playlist_track_id: None,
+                replaygain_track_gain: None,
+                replaygain_album_gain: None,
+                genre: row.get(15).unwrap_or(None),
+                year: row.get(16).unwrap_or(None),
+                album_artist: row.get(17).unwrap_or(None),
+                is_compilation: row.get(18).unwrap_or(false),
             })
         })?;
 
@@ -639,21 +1274,77 @@ impl DatabaseManager {
 
     pub fn remove_folder(&self, path: &str) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        // Delete all tracks where path starts with the folder path
-        // We ensure path ends with separator to avoid partial matches on similar folder names
-        // But users might pass path without separator.
-        // Let's rely on LIKE 'path%' but maybe be careful.
-        // Actually, tracks.path is absolute. folder path is absolute.
-        // Ideally we should normalize separators.
-        // For simple usage: LIKE ? || '%'
-        let normalized_path = path.replace("\\", "/");
+        // Compared as a plain prefix: LIKE would treat `_` and `%` in folder
+        // names as wildcards and ignore case
         conn.execute(
-            "DELETE FROM tracks WHERE path LIKE ?1 || '%'",
-            params![normalized_path],
+            "DELETE FROM tracks WHERE substr(path, 1, length(?1)) = ?1",
+            params![folder_prefix(path)],
         )?;
         Ok(())
     }
 
+    /// Delete track rows in one transaction, then drop albums left without tracks
+    /// (and their cached covers). Returns the number of tracks removed.
+    pub fn remove_tracks(&self, paths: &[String]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut removed = 0;
+        {
+            let mut stmt = tx.prepare("DELETE FROM tracks WHERE path = ?1")?;
+            for path in paths {
+                removed += stmt.execute(params![normalize_track_path(path)])?;
+            }
+        }
+
+        let orphaned_covers: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT cover_image_path FROM albums
+                 WHERE cover_image_path IS NOT NULL
+                   AND NOT EXISTS (SELECT 1 FROM tracks t WHERE t.album = albums.name AND t.album_artist = albums.artist)",
+            )?;
+            let covers = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>>>()?;
+            covers
+        };
+        tx.execute(
+            "DELETE FROM albums
+             WHERE NOT EXISTS (SELECT 1 FROM tracks t WHERE t.album = albums.name AND t.album_artist = albums.artist)",
+            [],
+        )?;
+        tx.commit()?;
+
+        for cover in orphaned_covers {
+            let _ = fs::remove_file(self.covers_dir.join(cover));
+        }
+        Ok(removed)
+    }
+
+    /// Groups of tracks that look like the same recording: same normalized title and
+    /// artist, with durations within a few seconds. Only groups of two or more are returned.
+    pub fn find_duplicate_tracks(&self) -> Result<Vec<Vec<TrackInfo>>> {
+        use std::collections::HashMap;
+
+        let mut groups: HashMap<(String, String, i64), Vec<TrackInfo>> = HashMap::new();
+        for track in self.get_all_tracks()? {
+            let key = (
+                normalize_title(&track.title),
+                track.artist.trim().to_lowercase(),
+                (track.duration_secs / DUPLICATE_DURATION_BUCKET_SECS).round() as i64,
+            );
+            groups.entry(key).or_default().push(track);
+        }
+
+        let mut duplicates: Vec<Vec<TrackInfo>> =
+            groups.into_values().filter(|g| g.len() > 1).collect();
+        for group in &mut duplicates {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+        duplicates.sort_by(|a, b| a[0].title.to_lowercase().cmp(&b[0].title.to_lowercase()));
+        Ok(duplicates)
+    }
+
     pub fn clear_all_data(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -662,6 +1353,8 @@ impl DatabaseManager {
         // Delete all tracks, albums, and unreleased tracks
         conn.execute("DELETE FROM tracks", [])?;
         conn.execute("DELETE FROM albums", [])?;
+        conn.execute("DELETE FROM lyrics", [])?;
+        conn.execute("DELETE FROM settings", [])?;
 
         println!("[Database] Tables cleared.");
 
@@ -692,8 +1385,8 @@ impl DatabaseManager {
 
     pub fn get_tracks_missing_metadata(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
-        // Check all tracks where romaji is NULL.
-        let mut stmt = conn.prepare("SELECT path FROM tracks WHERE title_romaji IS NULL")?;
+        // Check all tracks where romaji is NULL, or whose tags predate a newer column
+        let mut stmt = conn.prepare("SELECT path FROM tracks WHERE title_romaji IS NULL OR needs_tag_reread = 1")?;
 
         let paths_iter = stmt.query_map([], |row| row.get(0))?;
 
@@ -706,10 +1399,6 @@ impl DatabaseManager {
 
     // Playlist Methods
 
-    pub fn create_playlist(&self, name: &str) -> Result<String> {
-        self.create_playlist_with_options(name, None, None, None, None, Vec::new())
-    }
-
     pub fn create_playlist_with_options(
         &self,
         name: &str,
@@ -836,6 +1525,24 @@ impl DatabaseManager {
         Ok(())
     }
 
+    /// Remove an entry by its `playlist_tracks` row id alone, resolving the owning playlist
+    pub fn remove_playlist_track(&self, playlist_track_id: i64) -> Result<()> {
+        let playlist_id: Option<String> = {
+            let conn = self.conn.lock().unwrap();
+            conn.query_row(
+                "SELECT playlist_id FROM playlist_tracks WHERE id = ?1",
+                params![playlist_track_id],
+                |row| row.get(0),
+            )
+            .optional()?
+        };
+
+        match playlist_id {
+            Some(playlist_id) => self.remove_track_from_playlist(&playlist_id, playlist_track_id),
+            None => Ok(()),
+        }
+    }
+
     // Simple reorder: just update position of one item (swap logic might be needed in frontend or complex here)
     // Actually, simple way is to delete and re-insert or update one.
     // Better: update position. But dealing with shifting other items is tricky in simple SQL without a transaction block handling it.
@@ -879,10 +1586,10 @@ impl DatabaseManager {
 
         let mut stmt = conn.prepare(
             "SELECT t.path, t.title, t.artist, t.album, t.duration_secs, a.cover_image_path, a.main_color, t.disc_number, t.track_number,
-             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, pt.id as playlist_track_id
+             t.title_romaji, t.title_en, t.artist_romaji, t.artist_en, t.album_romaji, t.album_en, pt.id as playlist_track_id, t.genre, t.year, t.album_artist, t.is_compilation
              FROM playlist_tracks pt
              LEFT JOIN tracks t ON pt.track_path = t.path
-             LEFT JOIN albums a ON t.album = a.name AND t.artist = a.artist
+             LEFT JOIN albums a ON t.album = a.name AND t.album_artist = a.artist
              WHERE pt.playlist_id = ?1
              ORDER BY pt.position"
         )?;
@@ -911,6 +1618,12 @@ impl DatabaseManager {
                     album_romaji: row.get(13).unwrap_or(None),
                     album_en: row.get(14).unwrap_or(None),
                     playlist_track_id: Some(row.get(15)?),
+                    replaygain_track_gain: None,
+                    replaygain_album_gain: None,
+                    genre: row.get(16).unwrap_or(None),
+                    year: row.get(17).unwrap_or(None),
+                    album_artist: row.get(18).unwrap_or(None),
+                    is_compilation: row.get(19).unwrap_or(false),
                 })
             } else {
                 // Return dummy or empty track for missing file?
@@ -933,6 +1646,12 @@ impl DatabaseManager {
                     album_romaji: None,
                     album_en: None,
                     playlist_track_id: Some(row.get(15)?),
+                    replaygain_track_gain: None,
+                    replaygain_album_gain: None,
+                    genre: None,
+                    year: None,
+                    album_artist: None,
+                    is_compilation: false,
                 })
             }
         })?;
@@ -951,30 +1670,210 @@ impl DatabaseManager {
     // Playback Events / Analytics
     // ========================================================================
 
-    pub fn insert_playback_event(&self, event: &crate::stats::PlaybackEvent) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+    // EQ Preset Methods
+
+    /// Save (or overwrite) a user EQ preset. Gains are stored as a JSON array.
+    pub fn save_eq_preset(&self, name: &str, gains: &[f32]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let gains_json = serde_json::to_string(gains).unwrap_or_else(|_| "[]".to_string());
         conn.execute(
-            "INSERT OR IGNORE INTO playback_events (song_id, timestamp_ms, duration_ms, start_ms, end_ms, output)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![
-                event.song_id,
-                event.timestamp,
-                event.duration_ms,
-                event.start_timestamp,
-                event.end_timestamp,
-                event.output,
-            ],
-        )
-        .map_err(|e| format!("insert playback event: {e}"))?;
+            "INSERT INTO eq_presets (name, gains) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET gains = excluded.gains, updated_at = CURRENT_TIMESTAMP",
+            params![name, gains_json],
+        )?;
         Ok(())
     }
 
-    pub fn load_playback_events(
-        &self,
-        start_ms: Option<i64>,
-        end_ms: Option<i64>,
-    ) -> Result<Vec<crate::stats::PlaybackEvent>, String> {
-        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+    pub fn get_eq_presets(&self) -> Result<Vec<(String, Vec<f32>)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT name, gains FROM eq_presets ORDER BY name")?;
+
+        let preset_iter = stmt.query_map([], |row| {
+            let name: String = row.get(0)?;
+            let gains_json: String = row.get(1)?;
+            Ok((name, serde_json::from_str(&gains_json).unwrap_or_default()))
+        })?;
+
+        let mut presets = Vec::new();
+        for preset in preset_iter {
+            presets.push(preset?);
+        }
+        Ok(presets)
+    }
+
+    pub fn delete_eq_preset(&self, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM eq_presets WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+
+    // Internet Radio Stations
+
+    /// Save a station, renaming it if its URL is already saved. Returns its id.
+    pub fn add_station(&self, name: &str, url: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO stations (name, url) VALUES (?1, ?2)
+             ON CONFLICT(url) DO UPDATE SET name = excluded.name",
+            params![name, url],
+        )?;
+        conn.query_row(
+            "SELECT id FROM stations WHERE url = ?1",
+            params![url],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_stations(&self) -> Result<Vec<DbStation>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, name, url FROM stations ORDER BY name COLLATE NOCASE")?;
+        let stations = stmt
+            .query_map([], |row| {
+                Ok(DbStation {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    url: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(stations)
+    }
+
+    pub fn remove_station(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM stations WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    pub fn get_stored_lyrics(&self, track_path: &str) -> Result<Option<StoredLyrics>> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(track_path);
+        conn.query_row(
+            "SELECT synced_lyrics, plain_lyrics, instrumental FROM lyrics WHERE track_path = ?1",
+            params![normalized_path],
+            |row| {
+                Ok(StoredLyrics {
+                    synced_lyrics: row.get(0)?,
+                    plain_lyrics: row.get(1)?,
+                    instrumental: row.get::<_, i64>(2)? != 0,
+                })
+            },
+        )
+        .optional()
+    }
+
+    pub fn save_lyrics(
+        &self,
+        track_path: &str,
+        synced_lyrics: Option<&str>,
+        plain_lyrics: Option<&str>,
+        instrumental: bool,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(track_path);
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        conn.execute(
+            "INSERT INTO lyrics (track_path, synced_lyrics, plain_lyrics, instrumental, fetched_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(track_path) DO UPDATE SET
+                synced_lyrics = excluded.synced_lyrics,
+                plain_lyrics = excluded.plain_lyrics,
+                instrumental = excluded.instrumental,
+                fetched_at_ms = excluded.fetched_at_ms",
+            params![normalized_path, synced_lyrics, plain_lyrics, instrumental as i64, now_ms],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_lyrics_cache(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM lyrics", [])
+    }
+
+    /// Manual lyrics offset for a track in milliseconds (0 when unset)
+    pub fn get_lyrics_offset(&self, track_path: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(track_path);
+        let offset = conn
+            .query_row(
+                "SELECT offset_ms FROM lyrics_offsets WHERE track_path = ?1",
+                params![normalized_path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(offset.unwrap_or(0))
+    }
+
+    pub fn set_lyrics_offset(&self, track_path: &str, offset_ms: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let normalized_path = normalize_track_path(track_path);
+        if offset_ms == 0 {
+            conn.execute("DELETE FROM lyrics_offsets WHERE track_path = ?1", params![normalized_path])?;
+        } else {
+            conn.execute(
+                "INSERT INTO lyrics_offsets (track_path, offset_ms) VALUES (?1, ?2)
+                 ON CONFLICT(track_path) DO UPDATE SET offset_ms = excluded.offset_ms",
+                params![normalized_path, offset_ms],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// A persisted app preference, `None` when it was never set
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+
+    #[cfg(test)]
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    pub fn insert_playback_event(&self, event: &crate::stats::PlaybackEvent) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO playback_events (song_id, timestamp_ms, duration_ms, start_ms, end_ms, output)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                event.song_id,
+                event.timestamp,
+                event.duration_ms,
+                event.start_timestamp,
+                event.end_timestamp,
+                event.output,
+            ],
+        )
+        .map_err(|e| format!("insert playback event: {e}"))?;
+        Ok(())
+    }
+
+    pub fn load_playback_events(
+        &self,
+        start_ms: Option<i64>,
+        end_ms: Option<i64>,
+    ) -> Result<Vec<crate::stats::PlaybackEvent>, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
         let mut stmt = conn
             .prepare(
                 "SELECT song_id, timestamp_ms, duration_ms, start_ms, end_ms, output
@@ -1052,6 +1951,163 @@ impl DatabaseManager {
         Ok(out)
     }
 
+    /// Every playback event, oldest first, with title/artist/album resolved from the library.
+    pub fn load_playback_events_for_export(&self) -> Result<Vec<crate::stats::ExportedEvent>, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT e.song_id, e.timestamp_ms, e.duration_ms, e.start_ms, e.end_ms, e.output,
+                        t.title, t.artist, t.album
+                 FROM playback_events e
+                 LEFT JOIN tracks t ON t.path = e.song_id
+                 ORDER BY e.timestamp_ms ASC",
+            )
+            .map_err(|e| format!("prepare: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(crate::stats::ExportedEvent {
+                    event: crate::stats::PlaybackEvent {
+                        song_id: row.get(0)?,
+                        timestamp: row.get(1)?,
+                        duration_ms: row.get(2)?,
+                        start_timestamp: row.get(3)?,
+                        end_timestamp: row.get(4)?,
+                        output: row.get(5)?,
+                    },
+                    title: row.get(6)?,
+                    artist: row.get(7)?,
+                    album: row.get(8)?,
+                })
+            })
+            .map_err(|e| format!("query: {e}"))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("row: {e}"))
+    }
+
+    /// Songs ranked by listening time. Weighting by `duration_ms` keeps short
+    /// skips from outranking full listens.
+    pub fn top_songs(
+        &self,
+        start_ms: Option<i64>,
+        end_ms: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<crate::stats::SongRanking>, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT e.song_id, t.title, t.artist, t.album,
+                        COUNT(*) AS play_count,
+                        SUM(e.duration_ms) AS total_listen_ms
+                 FROM playback_events e
+                 LEFT JOIN tracks t ON t.path = e.song_id
+                 WHERE e.timestamp_ms >= ?1 AND e.timestamp_ms <= ?2
+                 GROUP BY e.song_id
+                 ORDER BY total_listen_ms DESC, play_count DESC
+                 LIMIT ?3",
+            )
+            .map_err(|e| format!("prepare: {e}"))?;
+        let params = rusqlite::params![start_ms.unwrap_or(0), end_ms.unwrap_or(i64::MAX), limit as i64];
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok(crate::stats::SongRanking {
+                    song_id: row.get(0)?,
+                    title: row.get(1)?,
+                    artist: row.get(2)?,
+                    album: row.get(3)?,
+                    play_count: row.get(4)?,
+                    total_listen_ms: row.get(5)?,
+                })
+            })
+            .map_err(|e| format!("query: {e}"))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("row: {e}"))
+    }
+
+    /// Artists ranked by listening time. Plays of files no longer in the library are skipped.
+    pub fn top_artists(
+        &self,
+        start_ms: Option<i64>,
+        end_ms: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<crate::stats::ArtistRanking>, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.artist,
+                        COUNT(*) AS play_count,
+                        COUNT(DISTINCT e.song_id) AS track_count,
+                        SUM(e.duration_ms) AS total_listen_ms
+                 FROM playback_events e
+                 JOIN tracks t ON t.path = e.song_id
+                 WHERE e.timestamp_ms >= ?1 AND e.timestamp_ms <= ?2
+                 GROUP BY t.artist
+                 ORDER BY total_listen_ms DESC, play_count DESC
+                 LIMIT ?3",
+            )
+            .map_err(|e| format!("prepare: {e}"))?;
+        let params = rusqlite::params![start_ms.unwrap_or(0), end_ms.unwrap_or(i64::MAX), limit as i64];
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok(crate::stats::ArtistRanking {
+                    artist: row.get(0)?,
+                    play_count: row.get(1)?,
+                    track_count: row.get(2)?,
+                    total_listen_ms: row.get(3)?,
+                })
+            })
+            .map_err(|e| format!("query: {e}"))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("row: {e}"))
+    }
+
+    /// Albums ranked by listening time, grouped like the album list (album + album artist).
+    pub fn top_albums(
+        &self,
+        start_ms: Option<i64>,
+        end_ms: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<crate::stats::AlbumRanking>, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.album, COALESCE(t.album_artist, t.artist) AS album_artist,
+                        COUNT(*) AS play_count,
+                        SUM(e.duration_ms) AS total_listen_ms
+                 FROM playback_events e
+                 JOIN tracks t ON t.path = e.song_id
+                 WHERE e.timestamp_ms >= ?1 AND e.timestamp_ms <= ?2
+                 GROUP BY t.album, album_artist
+                 ORDER BY total_listen_ms DESC, play_count DESC
+                 LIMIT ?3",
+            )
+            .map_err(|e| format!("prepare: {e}"))?;
+        let params = rusqlite::params![start_ms.unwrap_or(0), end_ms.unwrap_or(i64::MAX), limit as i64];
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok(crate::stats::AlbumRanking {
+                    album: row.get(0)?,
+                    artist: row.get(1)?,
+                    play_count: row.get(2)?,
+                    total_listen_ms: row.get(3)?,
+                })
+            })
+            .map_err(|e| format!("query: {e}"))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| format!("row: {e}"))
+    }
+
+    /// Sum of listening time in the range.
+    pub fn total_listening_ms(&self, start_ms: Option<i64>, end_ms: Option<i64>) -> Result<i64, String> {
+        let conn = self.conn.lock().map_err(|_| "db lock poisoned".to_string())?;
+        conn.query_row(
+            "SELECT COALESCE(SUM(duration_ms), 0) FROM playback_events
+             WHERE timestamp_ms >= ?1 AND timestamp_ms <= ?2",
+            rusqlite::params![start_ms.unwrap_or(0), end_ms.unwrap_or(i64::MAX)],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("query: {e}"))
+    }
+
     /// Most recently played tracks (unique song, latest timestamp).
     pub fn get_recently_played(
         &self,
@@ -1099,6 +2155,13 @@ pub struct DbPlaylist {
     pub updated_at: String,
 }
 
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DbStation {
+    pub id: i64,
+    pub name: String,
+    pub url: String,
+}
+
 #[derive(Serialize)]
 #[allow(dead_code)]
 pub struct DbPlaylistTrack {
@@ -1108,3 +2171,651 @@ pub struct DbPlaylistTrack {
     pub position: i32,
     pub added_at: String,
 }
+
+/// Columns of the search index, in its column order
+const SEARCH_COLUMNS: &[&str] = &[
+    "title", "artist", "album",
+    "title_romaji", "artist_romaji", "album_romaji",
+    "title_en", "artist_en", "album_en",
+];
+
+/// Search index columns naming an album or its artist
+pub const ALBUM_SEARCH_COLUMNS: &[&str] = &[
+    "artist", "album", "artist_romaji", "album_romaji", "artist_en", "album_en",
+];
+
+/// Search index columns naming an artist
+pub const ARTIST_SEARCH_COLUMNS: &[&str] = &["artist", "artist_romaji", "artist_en"];
+
+/// Free text split for the trigram index
+#[derive(Debug, PartialEq)]
+struct SearchTerms {
+    /// FTS5 query ANDing the words of three or more characters
+    fts: Option<String>,
+    /// Shorter words, which the trigram index can't look up
+    short: Vec<String>,
+}
+
+/// Turn free text into search terms: each word is quoted (so punctuation and
+/// FTS operators are taken literally) and the words are ANDed together.
+/// Returns None when the query has no searchable words.
+fn build_fts_query(query: &str) -> Option<SearchTerms> {
+    let (long, short): (Vec<&str>, Vec<&str>) = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .partition(|w| w.chars().count() >= 3);
+    if long.is_empty() && short.is_empty() {
+        return None;
+    }
+
+    let fts = (!long.is_empty()).then(|| {
+        long.iter()
+            .map(|w| format!("\"{}\"", w))
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    Some(SearchTerms {
+        fts,
+        short: short.into_iter().map(str::to_string).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_track(path: &str, title: &str) -> TrackInfo {
+        TrackInfo {
+            path: path.to_string(),
+            title: title.to_string(),
+            artist: "Artist".to_string(),
+            album: "Album".to_string(),
+            duration_secs: 180.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn album_cover_override_rejects_unsupported_formats() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/a.flac", "A"), None).unwrap();
+
+        let dir = std::env::temp_dir();
+        let png = dir.join(format!("vibe-on-cover-{}.png", std::process::id()));
+        let gif = dir.join(format!("vibe-on-cover-{}.gif", std::process::id()));
+        image::RgbImage::from_pixel(2, 2, image::Rgb([200, 40, 40]))
+            .save(&png)
+            .unwrap();
+        fs::write(&gif, b"GIF89a....").unwrap();
+
+        let filename = db.set_album_cover_from_file("Album", "Artist", &png).unwrap();
+        assert!(filename.ends_with(".png"));
+        let stored: Option<String> = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT cover_image_path FROM albums WHERE name = 'Album' AND artist = 'Artist'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some(filename.as_str()));
+
+        assert!(db.set_album_cover_from_file("Album", "Artist", &gif).is_err());
+        assert!(db.set_album_cover_from_file("Missing", "Artist", &png).is_err());
+
+        let _ = fs::remove_file(&png);
+        let _ = fs::remove_file(&gif);
+        let _ = fs::remove_file(dir.join(&filename));
+    }
+
+    #[test]
+    fn stored_lyrics_round_trip_and_clear() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        assert!(db.get_stored_lyrics("/music/a.flac").unwrap().is_none());
+
+        db.save_lyrics("/music/a.flac", Some("[00:01.00]Hi"), None, false).unwrap();
+        db.save_lyrics("/music/a.flac", Some("[00:02.00]Hi"), Some("Hi"), false).unwrap();
+        let stored = db.get_stored_lyrics("/music/a.flac").unwrap().unwrap();
+        assert_eq!(stored.synced_lyrics.as_deref(), Some("[00:02.00]Hi"));
+        assert_eq!(stored.plain_lyrics.as_deref(), Some("Hi"));
+        assert!(!stored.instrumental);
+
+        assert_eq!(db.clear_lyrics_cache().unwrap(), 1);
+        assert!(db.get_stored_lyrics("/music/a.flac").unwrap().is_none());
+    }
+
+    #[test]
+    fn lyrics_offset_defaults_to_zero_and_survives_cache_clear() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        assert_eq!(db.get_lyrics_offset("/music/a.flac").unwrap(), 0);
+
+        db.set_lyrics_offset("/music/a.flac", -750).unwrap();
+        db.clear_lyrics_cache().unwrap();
+        assert_eq!(db.get_lyrics_offset("/music/a.flac").unwrap(), -750);
+
+        db.set_lyrics_offset("/music/a.flac", 0).unwrap();
+        assert_eq!(db.get_lyrics_offset("/music/a.flac").unwrap(), 0);
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        assert_eq!(db.get_setting("lastfm_scrobbled_until").unwrap(), None);
+
+        db.set_setting("lastfm_scrobbled_until", "1").unwrap();
+        db.set_setting("lastfm_scrobbled_until", "2").unwrap();
+        assert_eq!(db.get_setting("lastfm_scrobbled_until").unwrap().as_deref(), Some("2"));
+
+        db.delete_setting("lastfm_scrobbled_until").unwrap();
+        assert_eq!(db.get_setting("lastfm_scrobbled_until").unwrap(), None);
+    }
+
+    #[test]
+    fn rankings_weight_by_listening_time() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        let mut skipped = sample_track("/music/skip.flac", "Skip");
+        skipped.artist = "Skipper".to_string();
+        db.insert_track(&skipped, None).unwrap();
+        db.insert_track(&sample_track("/music/full.flac", "Full"), None).unwrap();
+
+        let play = |song: &str, timestamp: i64, duration_ms: i64| crate::stats::PlaybackEvent {
+            song_id: song.to_string(),
+            timestamp,
+            duration_ms,
+            start_timestamp: None,
+            end_timestamp: None,
+            output: "desktop".to_string(),
+        };
+        for ts in 0..3 {
+            db.insert_playback_event(&play("/music/skip.flac", ts, 10_000)).unwrap();
+        }
+        db.insert_playback_event(&play("/music/full.flac", 10, 180_000)).unwrap();
+
+        let songs = db.top_songs(None, None, 10).unwrap();
+        assert_eq!(songs[0].song_id, "/music/full.flac");
+        assert_eq!(songs[1].play_count, 3);
+
+        let artists = db.top_artists(None, None, 10).unwrap();
+        assert_eq!(artists[0].artist, "Artist");
+        assert_eq!(artists[1].total_listen_ms, 30_000);
+
+        assert_eq!(db.top_albums(None, None, 10).unwrap().len(), 2);
+        assert_eq!(db.total_listening_ms(None, None).unwrap(), 210_000);
+        assert_eq!(db.total_listening_ms(Some(5), None).unwrap(), 180_000);
+    }
+
+    #[test]
+    fn reinsert_keeps_favorite_flag() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/a.flac", "Old Title"), None)
+            .unwrap();
+
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE tracks SET is_favorite = 1 WHERE path = ?1",
+                params!["/music/a.flac"],
+            )
+            .unwrap();
+
+        db.insert_track(&sample_track("/music/a.flac", "New Title"), None)
+            .unwrap();
+
+        let conn = db.conn.lock().unwrap();
+        let (title, is_favorite): (String, bool) = conn
+            .query_row(
+                "SELECT title, is_favorite FROM tracks WHERE path = ?1",
+                params!["/music/a.flac"],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(title, "New Title");
+        assert!(is_favorite);
+    }
+
+    #[test]
+    fn search_matches_words_in_any_field_order() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(
+            &TrackInfo {
+                artist: "New Order".to_string(),
+                album: "Power, Corruption & Lies".to_string(),
+                ..sample_track("/music/blue_monday.flac", "Blue Monday")
+            },
+            None,
+        )
+        .unwrap();
+        db.insert_track(&sample_track("/music/other.flac", "Monday Morning"), None)
+            .unwrap();
+
+        let hits = db.search_tracks("blue monday order", 10, 0).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Blue Monday");
+
+        // Prefix match on the last word while typing
+        let partial = db.search_tracks("order blu", 10, 0).unwrap();
+        assert_eq!(partial.len(), 1);
+    }
+
+    #[test]
+    fn rows_from_before_genre_are_reread_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO tracks (path, title, artist, album, duration_secs)
+             VALUES ('/music/a.mp3', 'A', 'Artist', 'Album', 1.0)",
+            [],
+        )
+        .unwrap();
+        DatabaseManager::migrate(&conn);
+        conn.execute("UPDATE tracks SET title_romaji = ''", []).unwrap();
+        let db = DatabaseManager {
+            conn: Arc::new(Mutex::new(conn)),
+            covers_dir: std::env::temp_dir(),
+        };
+
+        assert_eq!(db.get_tracks_missing_metadata().unwrap(), vec!["/music/a.mp3".to_string()]);
+        db.insert_track(&sample_track("/music/a.mp3", "A"), None).unwrap();
+        assert!(db.get_tracks_missing_metadata().unwrap().is_empty());
+    }
+
+    #[test]
+    fn drive_letter_duplicates_merge_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        DatabaseManager::migrate(&conn);
+        conn.execute_batch(
+            "INSERT INTO tracks (path, title, artist, album, duration_secs, is_favorite, play_count)
+             VALUES ('c:/music/a.mp3', 'A', 'Artist', 'Album', 1.0, 1, 2),
+                    ('C:/music/a.mp3', 'A', 'Artist', 'Album', 1.0, 0, 3),
+                    ('d:/music/b.mp3', 'B', 'Artist', 'Album', 1.0, 0, 0);
+             INSERT INTO playlists (id, name) VALUES ('p', 'P');
+             INSERT INTO playlist_tracks (playlist_id, track_path, position) VALUES ('p', 'c:/music/a.mp3', 0);
+             PRAGMA user_version = 0;",
+        )
+        .unwrap();
+        DatabaseManager::migrate(&conn);
+
+        let rows: Vec<(String, i64, i64)> = conn
+            .prepare("SELECT path, is_favorite, play_count FROM tracks ORDER BY path")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![("C:/music/a.mp3".to_string(), 1, 5), ("D:/music/b.mp3".to_string(), 0, 0)]
+        );
+        let linked: String = conn
+            .query_row("SELECT track_path FROM playlist_tracks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(linked, "C:/music/a.mp3");
+
+        // Done once; later startups leave the table alone
+        conn.execute(
+            "INSERT INTO tracks (path, title, artist, album, duration_secs)
+             VALUES ('e:/music/c.mp3', 'C', 'Artist', 'Album', 1.0)",
+            [],
+        )
+        .unwrap();
+        DatabaseManager::migrate(&conn);
+        let kept: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tracks WHERE path = 'e:/music/c.mp3'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kept, 1);
+    }
+
+    #[test]
+    fn fts_query_quotes_words() {
+        assert_eq!(build_fts_query("  "), None);
+        assert_eq!(
+            build_fts_query("rock \"n\" roll"),
+            Some(SearchTerms {
+                fts: Some("\"rock\" \"roll\"".to_string()),
+                short: vec!["n".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn search_matches_cjk_substrings() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(
+            &TrackInfo {
+                artist: "米津玄師".to_string(),
+                ..sample_track("/music/lemon.flac", "打上花火")
+            },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(db.search_tracks("玄師", 10, 0).unwrap().len(), 1);
+        assert_eq!(db.search_tracks("上花火", 10, 0).unwrap().len(), 1);
+        assert_eq!(db.search_tracks_in("花火", ARTIST_SEARCH_COLUMNS).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn genres_group_case_insensitively() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        for (path, genre) in [("/a.mp3", "Jazz"), ("/b.mp3", "jazz"), ("/c.mp3", "Rock")] {
+            let track = TrackInfo {
+                genre: Some(genre.to_string()),
+                ..sample_track(path, path)
+            };
+            db.insert_track(&track, None).unwrap();
+        }
+
+        let genres = db.get_genres().unwrap();
+        assert_eq!(genres.len(), 2);
+        assert_eq!(genres[0].1, 2);
+        assert_eq!(db.get_tracks_by_genre("JAZZ").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn compilation_groups_by_album_artist() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        for (i, artist) in ["Artist A", "Artist B", "Artist C"].iter().enumerate() {
+            let track = TrackInfo {
+                artist: artist.to_string(),
+                album: "Now That's What I Call Tests".to_string(),
+                album_artist: Some("Various Artists".to_string()),
+                track_number: Some(i as u32 + 1),
+                ..sample_track(&format!("/va/{}.mp3", i), &format!("Song {}", i))
+            };
+            db.insert_track(&track, None).unwrap();
+        }
+        // Regular album without an album-artist tag keeps grouping by track artist
+        db.insert_track(&sample_track("/solo/1.mp3", "Solo"), None)
+            .unwrap();
+
+        let (albums, total) = db.get_albums_paginated(50, 0).unwrap();
+        assert_eq!(total, 2);
+        let va = albums
+            .iter()
+            .find(|a| a.artist == "Various Artists")
+            .expect("compilation album");
+        assert_eq!(va.track_count, 3);
+        assert!(albums.iter().any(|a| a.artist == "Artist" && a.track_count == 1));
+    }
+
+    #[test]
+    fn album_cover_lookup_starts_at_the_first_track() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        for (number, path) in [(2, "/a/2.mp3"), (1, "/a/1.mp3")] {
+            let track = TrackInfo {
+                track_number: Some(number),
+                ..sample_track(path, path)
+            };
+            db.insert_track(&track, None).unwrap();
+        }
+
+        assert_eq!(
+            db.get_album_cover("Album", "Artist").unwrap(),
+            Some((None, "/a/1.mp3".to_string()))
+        );
+        db.update_album_cover("Album", "Artist", "cover.jpg").unwrap();
+        assert_eq!(
+            db.get_album_cover("Album", "Artist").unwrap().unwrap().0.as_deref(),
+            Some("cover.jpg")
+        );
+        assert_eq!(db.get_album_cover("Album", "Someone Else").unwrap(), None);
+    }
+
+    #[test]
+    fn untagged_compilation_groups_as_various_artists() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        for (i, artist) in ["Artist A", "Artist B"].iter().enumerate() {
+            let track = TrackInfo {
+                artist: artist.to_string(),
+                album: "Soundtrack".to_string(),
+                is_compilation: true,
+                ..sample_track(&format!("/ost/{}.mp3", i), &format!("Cue {}", i))
+            };
+            db.insert_track(&track, None).unwrap();
+        }
+
+        let (albums, total) = db.get_albums_paginated(50, 0).unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(albums[0].artist, VARIOUS_ARTISTS);
+        assert_eq!(albums[0].track_count, 2);
+        assert!(albums[0].is_compilation);
+        assert!(db.get_track("/ost/0.mp3").unwrap().unwrap().is_compilation);
+    }
+
+    #[test]
+    fn duplicates_ignore_edition_suffixes() {
+        assert_eq!(normalize_title("Hey Jude (Remastered 2015)"), "hey jude");
+        assert_eq!(normalize_title("Hey Jude - 2009 Remaster"), "hey jude");
+        assert_eq!(normalize_title("Song (Live)"), "song (live)");
+
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/old/hey.mp3", "Hey Jude"), None).unwrap();
+        let remaster = TrackInfo {
+            duration_secs: 181.0,
+            ..sample_track("/new/hey.flac", "Hey Jude (Remastered)")
+        };
+        db.insert_track(&remaster, None).unwrap();
+        db.insert_track(&sample_track("/other.mp3", "Other"), None).unwrap();
+
+        let duplicates = db.find_duplicate_tracks().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].len(), 2);
+
+        db.remove_tracks(&["/old/hey.mp3".to_string()]).unwrap();
+        assert!(db.find_duplicate_tracks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn removing_last_track_drops_its_album() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/a.flac", "A"), Some(&[1, 2, 3])).unwrap();
+        db.insert_track(&sample_track("/music/b.flac", "B"), None).unwrap();
+        let album_rows = || -> i64 {
+            db.conn
+                .lock()
+                .unwrap()
+                .query_row("SELECT COUNT(*) FROM albums", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(album_rows(), 1);
+
+        assert_eq!(db.remove_tracks(&["/music/a.flac".to_string()]).unwrap(), 1);
+        assert_eq!(album_rows(), 1);
+
+        let removed = db
+            .remove_tracks(&["/music/b.flac".to_string(), "/missing.flac".to_string()])
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(album_rows(), 0);
+        assert!(db.get_all_tracks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn moved_file_keeps_its_row_and_play_count() {
+        let root = std::env::temp_dir().join(format!("vibe-on-move-{}", std::process::id()));
+        let (old_dir, new_dir) = (root.join("old"), root.join("new"));
+        fs::create_dir_all(&old_dir).unwrap();
+        fs::create_dir_all(&new_dir).unwrap();
+        let old_file = old_dir.join("song.mp3");
+        let new_file = new_dir.join("song.mp3");
+        fs::write(&old_file, b"not really audio").unwrap();
+
+        let db = DatabaseManager::open_in_memory().unwrap();
+        let old_path = old_file.to_string_lossy().replace("\\", "/");
+        let new_path = new_file.to_string_lossy().replace("\\", "/");
+        db.insert_track(&sample_track(&old_path, "Song"), None).unwrap();
+        db.increment_play_count(&old_path).unwrap();
+
+        fs::rename(&old_file, &new_file).unwrap();
+        let moved = sample_track(&new_path, "Song");
+        assert_eq!(db.adopt_moved_track(&moved).unwrap(), Some(old_path.clone()));
+        db.insert_track(&moved, None).unwrap();
+
+        let rows: Vec<(String, i64)> = {
+            let conn = db.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT path, play_count FROM tracks").unwrap();
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            rows
+        };
+        assert_eq!(rows, vec![(new_path.clone(), 1)]);
+
+        // A second scan finds the row already at its new path
+        assert_eq!(db.adopt_moved_track(&moved).unwrap(), None);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn rescan_keeps_added_at_and_newest_come_first() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/old.flac", "Old"), None).unwrap();
+        db.insert_track(&sample_track("/music/new.flac", "New"), None).unwrap();
+        {
+            let conn = db.conn.lock().unwrap();
+            conn.execute("UPDATE tracks SET added_at = 1000 WHERE path = '/music/old.flac'", [])
+                .unwrap();
+            conn.execute("UPDATE tracks SET added_at = 2000 WHERE path = '/music/new.flac'", [])
+                .unwrap();
+        }
+
+        // A rescan upserts the row but must not make it look newly imported
+        db.insert_track(&sample_track("/music/old.flac", "Old (retagged)"), None)
+            .unwrap();
+
+        let recent = db.get_recently_added(10).unwrap();
+        let titles: Vec<&str> = recent.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["New", "Old (retagged)"]);
+        assert_eq!(db.get_recently_added(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn combined_track_tags_sort_before_untagged_tracks() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        // Raw TRACKNUMBER values as found in the files; None is an untagged file
+        let tags = [
+            ("/music/x.flac", "Intro", Some("1")),
+            ("/music/y.flac", "Hidden", None),
+            ("/music/z.flac", "Third", Some("3/10")),
+            ("/music/w.flac", "Second", Some("02/10")),
+        ];
+        for (path, title, tag) in tags {
+            let mut track = sample_track(path, title);
+            track.track_number = tag.and_then(crate::parse_tag_number);
+            db.insert_track(&track, None).unwrap();
+        }
+
+        let titles: Vec<String> = db.get_all_tracks().unwrap().into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, vec!["Intro", "Second", "Third", "Hidden"]);
+    }
+
+    #[test]
+    fn track_paths_normalize_separators_and_drive_letters() {
+        assert_eq!(normalize_track_path(r"c:\Music\a.flac"), "C:/Music/a.flac");
+        assert_eq!(normalize_track_path("C:/Music//./a.flac"), "C:/Music/a.flac");
+        assert_eq!(normalize_track_path(r"d:/Music\Album/b.mp3"), "D:/Music/Album/b.mp3");
+        assert_eq!(normalize_track_path(r"\\nas\music\c.flac"), "//nas/music/c.flac");
+        assert_eq!(normalize_track_path("/home/me/music/d.flac"), "/home/me/music/d.flac");
+    }
+
+    #[test]
+    fn removing_a_folder_keeps_sibling_folders_with_the_same_prefix() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/a.mp3", "A"), None).unwrap();
+        db.insert_track(&sample_track("/music2/b.mp3", "B"), None).unwrap();
+        db.remove_folder("/music").unwrap();
+
+        let paths: Vec<String> = db.get_all_tracks().unwrap().into_iter().map(|t| t.path).collect();
+        assert_eq!(paths, vec!["/music2/b.mp3"]);
+    }
+
+    #[test]
+    fn rescan_with_other_separators_and_drive_case_reuses_the_row() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track(r"c:\Music\a.flac", "A"), None).unwrap();
+        db.insert_track(&sample_track("C:/Music/a.flac", "A"), None).unwrap();
+
+        let paths = db.get_all_track_paths().unwrap();
+        assert_eq!(paths, std::collections::HashSet::from(["C:/Music/a.flac".to_string()]));
+        assert!(db.get_track(r"c:\Music\a.flac").unwrap().is_some());
+    }
+
+    #[test]
+    fn inserted_names_are_transliterated_through_the_shared_cache() {
+        let cache = crate::lyrics_transliteration::cache();
+        // Not used by any other test, so only these inserts touch its entry
+        let artist = "캐시테스트가수";
+        let hits_before = cache.hits();
+
+        let db = DatabaseManager::open_in_memory().unwrap();
+        for i in 0..3 {
+            let track = TrackInfo {
+                artist: artist.to_string(),
+                ..sample_track(&format!("/music/cache-{}.flac", i), "A")
+            };
+            db.insert_track(&track, None).unwrap();
+        }
+
+        let cached = cache.get(artist).flatten().expect("artist was transliterated");
+        let row = db.get_track("/music/cache-2.flac").unwrap().unwrap();
+        assert_eq!(row.artist_romaji.as_deref(), Some(cached.as_str()));
+        // The first insert computes it; the other two are answered from the cache
+        assert!(cache.hits() >= hits_before + 2);
+    }
+
+    #[test]
+    fn stations_are_keyed_by_url() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        let jazz = db.add_station("jazz", "http://radio.example/jazz").unwrap();
+        let ambient = db.add_station("Ambient", "http://radio.example/ambient").unwrap();
+        // Adding a saved URL again renames it instead of duplicating it
+        assert_eq!(db.add_station("Jazz FM", "http://radio.example/jazz").unwrap(), jazz);
+
+        let names: Vec<String> = db.get_stations().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["Ambient", "Jazz FM"]);
+
+        db.remove_station(ambient).unwrap();
+        assert_eq!(
+            db.get_stations().unwrap(),
+            vec![DbStation {
+                id: jazz,
+                name: "Jazz FM".to_string(),
+                url: "http://radio.example/jazz".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn albums_missing_cover_only_lists_requested_tracks_of_uncovered_albums() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        db.insert_track(&sample_track("/music/a.flac", "A"), None).unwrap();
+        db.insert_track(&sample_track("/music/b.flac", "B"), None).unwrap();
+        let covered = TrackInfo {
+            album: "Covered".to_string(),
+            ..sample_track("/music/c.flac", "C")
+        };
+        db.insert_track(&covered, Some(b"jpeg")).unwrap();
+
+        let paths = vec!["/music/a.flac".to_string(), "/music/c.flac".to_string()];
+        assert_eq!(
+            db.albums_missing_cover(&paths).unwrap(),
+            vec![("Album".to_string(), "Artist".to_string(), "/music/a.flac".to_string())]
+        );
+
+        let filename = db.save_cover_data(b"jpeg").unwrap();
+        db.update_album_cover("Album", "Artist", &filename).unwrap();
+        assert!(db.albums_missing_cover(&paths).unwrap().is_empty());
+
+        for album in db.get_albums_paginated(10, 0).unwrap().0 {
+            if let Some(cover) = album.cover_image_path {
+                let _ = fs::remove_file(std::env::temp_dir().join(cover));
+            }
+        }
+    }
+}
@@ -64,6 +64,70 @@ CREATE TABLE IF NOT EXISTS playback_events (
 
 CREATE INDEX IF NOT EXISTS idx_playback_events_song ON playback_events(song_id);
 CREATE INDEX IF NOT EXISTS idx_playback_events_ts ON playback_events(timestamp_ms);
+
+CREATE TABLE IF NOT EXISTS eq_presets (
+    name TEXT PRIMARY KEY,
+    gains TEXT NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS lyrics (
+    track_path TEXT PRIMARY KEY,
+    synced_lyrics TEXT,
+    plain_lyrics TEXT,
+    instrumental INTEGER NOT NULL DEFAULT 0,
+    fetched_at_ms INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS lyrics_offsets (
+    track_path TEXT PRIMARY KEY,
+    offset_ms INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS stations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+/// Full-text index over the searchable track columns. It is an external-content
+/// table kept in sync by triggers, so it must be created after the romaji/English
+/// column migrations have run.
+pub const TRACKS_FTS_SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS tracks_fts USING fts5(
+    title, artist, album,
+    title_romaji, artist_romaji, album_romaji,
+    title_en, artist_en, album_en,
+    content='tracks',
+    content_rowid='id',
+    tokenize='trigram remove_diacritics 1'
+);
+
+CREATE TRIGGER IF NOT EXISTS tracks_fts_insert AFTER INSERT ON tracks BEGIN
+    INSERT INTO tracks_fts(rowid, title, artist, album, title_romaji, artist_romaji, album_romaji, title_en, artist_en, album_en)
+    VALUES (new.id, new.title, new.artist, new.album, new.title_romaji, new.artist_romaji, new.album_romaji, new.title_en, new.artist_en, new.album_en);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tracks_fts_delete AFTER DELETE ON tracks BEGIN
+    INSERT INTO tracks_fts(tracks_fts, rowid, title, artist, album, title_romaji, artist_romaji, album_romaji, title_en, artist_en, album_en)
+    VALUES ('delete', old.id, old.title, old.artist, old.album, old.title_romaji, old.artist_romaji, old.album_romaji, old.title_en, old.artist_en, old.album_en);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tracks_fts_update AFTER UPDATE OF
+    title, artist, album, title_romaji, artist_romaji, album_romaji, title_en, artist_en, album_en
+ON tracks BEGIN
+    INSERT INTO tracks_fts(tracks_fts, rowid, title, artist, album, title_romaji, artist_romaji, album_romaji, title_en, artist_en, album_en)
+    VALUES ('delete', old.id, old.title, old.artist, old.album, old.title_romaji, old.artist_romaji, old.album_romaji, old.title_en, old.artist_en, old.album_en);
+    INSERT INTO tracks_fts(rowid, title, artist, album, title_romaji, artist_romaji, album_romaji, title_en, artist_en, album_en)
+    VALUES (new.id, new.title, new.artist, new.album, new.title_romaji, new.artist_romaji, new.album_romaji, new.title_en, new.artist_en, new.album_en);
+END;
 "#;
 
 pub fn init_db(conn: &Connection) -> Result<()> {
@@ -1,27 +1,125 @@
 use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
 use std::{
-    sync::mpsc::{self, Sender},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
     thread,
     time::{Duration, Instant},
 };
 
+/// Discord shows at most two buttons per activity
+pub const MAX_BUTTONS: usize = 2;
+/// Longest button label Discord accepts, in characters
+const MAX_BUTTON_LABEL_CHARS: usize = 32;
+/// Longest button URL Discord accepts, in bytes
+const MAX_BUTTON_URL_LEN: usize = 512;
+
+/// Buttons shown until the user configures their own
+fn default_buttons() -> Vec<(String, String)> {
+    vec![(
+        "View on GitHub".to_string(),
+        "https://github.com/MemestaVedas/vibe-on".to_string(),
+    )]
+}
+
+/// Keep the (label, url) pairs Discord will accept: labels are trimmed and cut to
+/// the length limit, entries with an empty label or a non-http(s) URL are dropped,
+/// and only the first two survive.
+pub fn sanitize_buttons(buttons: Vec<(String, String)>) -> Vec<(String, String)> {
+    buttons
+        .into_iter()
+        .filter_map(|(label, url)| {
+            let label: String = label.trim().chars().take(MAX_BUTTON_LABEL_CHARS).collect();
+            let url = url.trim().to_string();
+            let valid_url = url.len() <= MAX_BUTTON_URL_LEN
+                && reqwest::Url::parse(&url)
+                    .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some());
+            (!label.is_empty() && valid_url).then_some((label, url))
+        })
+        .take(MAX_BUTTONS)
+        .collect()
+}
+
+/// First delay between reconnect attempts while a track is active
+const RECONNECT_INITIAL: Duration = Duration::from_secs(15);
+/// Backoff cap for reconnect attempts
+const RECONNECT_MAX: Duration = Duration::from_secs(120);
+
+/// Activity fields, kept by the Discord thread so it can re-push after reconnecting
+struct ActivityData {
+    details: String,
+    state: String,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    image_url: Option<String>,
+    album_name: Option<String>,
+}
+
 // Internal commands for the Discord thread
 enum DiscordCommand {
     Connect,
-    SetActivity {
-        details: String,
-        state: String,
-        start_timestamp: Option<i64>,
-        end_timestamp: Option<i64>,
-        image_url: Option<String>,
-        album_name: Option<String>,
-    },
+    SetActivity(ActivityData),
     Clear,
 }
 
+fn send_activity(
+    client: &mut DiscordIpcClient,
+    data: &ActivityData,
+    buttons: &[(String, String)],
+) -> Result<(), String> {
+    let mut assets = activity::Assets::new();
+
+    // Use album art URL if available, otherwise use app icon
+    if let Some(ref url) = data.image_url {
+        assets = assets
+            .large_image(url)
+            .large_text(data.album_name.as_deref().unwrap_or("Vibe Music Player"));
+    } else {
+        assets = assets
+            .large_image("logo_key")
+            .large_text(data.album_name.as_deref().unwrap_or("Vibe Music Player"));
+    }
+
+    let mut activity_payload = activity::Activity::new()
+        .activity_type(activity::ActivityType::Listening)
+        .details(&data.details)
+        .state(&data.state)
+        .assets(assets);
+
+    if !buttons.is_empty() {
+        activity_payload = activity_payload.buttons(
+            buttons
+                .iter()
+                .map(|(label, url)| activity::Button::new(label, url))
+                .collect(),
+        );
+    }
+
+    if data.start_timestamp.is_some() || data.end_timestamp.is_some() {
+        let mut timestamps = activity::Timestamps::new();
+        if let Some(start) = data.start_timestamp {
+            timestamps = timestamps.start(start);
+        }
+        if let Some(end) = data.end_timestamp {
+            timestamps = timestamps.end(end);
+        }
+        activity_payload = activity_payload.timestamps(timestamps);
+    }
+
+    client.set_activity(activity_payload).map_err(|e| e.to_string())
+}
+
 #[derive(Clone)]
 pub struct DiscordRpc {
     tx: Sender<DiscordCommand>,
+    /// User preference; while off every call is a no-op
+    enabled: Arc<AtomicBool>,
+    /// Whether the IPC connection to Discord is currently up
+    connected: Arc<AtomicBool>,
+    /// (label, url) pairs added to every activity
+    buttons: Arc<Mutex<Vec<(String, String)>>>,
     // We keep app_id just in case, though it's used in the thread
     #[allow(dead_code)]
     app_id: String,
@@ -31,10 +129,17 @@ impl DiscordRpc {
     pub fn new(app_id: &str) -> Self {
         let (tx, rx) = mpsc::channel();
         let app_id_clone = app_id.to_string();
+        let buttons = Arc::new(Mutex::new(default_buttons()));
+        let buttons_thread = buttons.clone();
+        let connected = Arc::new(AtomicBool::new(false));
+        let connected_thread = connected.clone();
 
         thread::spawn(move || {
             let mut client: Option<DiscordIpcClient> = None;
             let mut last_connect_attempt = Instant::now() - Duration::from_secs(60);
+            // Last activity for the current track; cleared when playback stops
+            let mut current: Option<ActivityData> = None;
+            let mut retry_delay = RECONNECT_INITIAL;
 
             // Helper to try connecting
             let mut try_connect = |client_opt: &mut Option<DiscordIpcClient>, id: &str| -> bool {
@@ -63,86 +168,107 @@ impl DiscordRpc {
                 }
             };
 
-            while let Ok(cmd) = rx.recv() {
-                match cmd {
-                    DiscordCommand::Connect => {
-                        try_connect(&mut client, &app_id_clone);
+            // Push the current activity, dropping the connection if it turns out to be dead
+            let push = |client_opt: &mut Option<DiscordIpcClient>, data: &ActivityData| {
+                if let Some(c) = client_opt.as_mut() {
+                    let buttons = buttons_thread.lock().unwrap().clone();
+                    if let Err(e) = send_activity(c, data, &buttons) {
+                        eprintln!("[Discord] Failed to set activity: {}", e);
+                        let _ = c.close();
+                        *client_opt = None;
                     }
-                    DiscordCommand::SetActivity {
-                        details,
-                        state,
-                        start_timestamp,
-                        end_timestamp,
-                        image_url,
-                        album_name,
-                    } => {
-                        // Auto-connect if needed
-                        if !try_connect(&mut client, &app_id_clone) {
-                            continue;
-                        }
-
-                        if let Some(c) = client.as_mut() {
-                            let mut assets = activity::Assets::new();
-
-                            // Use album art URL if available, otherwise use app icon
-                            if let Some(ref url) = image_url {
-                                assets = assets.large_image(url).large_text(
-                                    album_name.as_deref().unwrap_or("Vibe Music Player"),
-                                );
-                            } else {
-                                assets = assets.large_image("logo_key").large_text(
-                                    album_name.as_deref().unwrap_or("Vibe Music Player"),
-                                );
-                            }
+                }
+            };
 
-                            // Add GitHub button
-                            let buttons = vec![activity::Button::new(
-                                "View on GitHub",
-                                "https://github.com/MemestaVedas/vibe-on",
-                            )];
-
-                            let mut activity_payload = activity::Activity::new()
-                                .activity_type(activity::ActivityType::Listening)
-                                .details(&details)
-                                .state(&state)
-                                .assets(assets)
-                                .buttons(buttons);
-
-                            if start_timestamp.is_some() || end_timestamp.is_some() {
-                                let mut timestamps = activity::Timestamps::new();
-                                if let Some(start) = start_timestamp {
-                                    timestamps = timestamps.start(start);
-                                }
-                                if let Some(end) = end_timestamp {
-                                    timestamps = timestamps.end(end);
-                                }
-                                activity_payload = activity_payload.timestamps(timestamps);
-                            }
+            loop {
+                // While a track is active but Discord is unreachable, wake up
+                // periodically to retry with backoff
+                let cmd = if current.is_some() && client.is_none() {
+                    match rx.recv_timeout(retry_delay) {
+                        Ok(cmd) => Some(cmd),
+                        Err(mpsc::RecvTimeoutError::Timeout) => None,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match rx.recv() {
+                        Ok(cmd) => Some(cmd),
+                        Err(_) => break,
+                    }
+                };
 
-                            if let Err(e) = c.set_activity(activity_payload) {
-                                eprintln!("[Discord] Failed to set activity: {}", e);
-                                // If we failed, connection might be dead
-                                let _ = c.close();
-                                client = None;
+                match cmd {
+                    None => {
+                        if try_connect(&mut client, &app_id_clone) {
+                            retry_delay = RECONNECT_INITIAL;
+                            if let Some(ref data) = current {
+                                push(&mut client, data);
                             }
+                        } else {
+                            retry_delay = (retry_delay * 2).min(RECONNECT_MAX);
                         }
                     }
-                    DiscordCommand::Clear => {
+                    Some(DiscordCommand::Connect) => {
+                        try_connect(&mut client, &app_id_clone);
+                    }
+                    Some(DiscordCommand::SetActivity(data)) => {
+                        // Auto-connect if needed
+                        if try_connect(&mut client, &app_id_clone) {
+                            push(&mut client, &data);
+                        }
+                        current = Some(data);
+                    }
+                    Some(DiscordCommand::Clear) => {
+                        current = None;
+                        retry_delay = RECONNECT_INITIAL;
                         if let Some(mut c) = client.take() {
                             let _ = c.close();
                         }
                     }
                 }
+
+                connected_thread.store(client.is_some(), Ordering::SeqCst);
             }
         });
 
         Self {
             tx,
+            enabled: Arc::new(AtomicBool::new(true)),
+            connected,
+            buttons,
             app_id: app_id.to_string(),
         }
     }
 
+    /// True while presence is being shown (connected to a running Discord client)
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Turn Rich Presence on or off. Turning it off clears the activity and
+    /// disconnects from Discord.
+    pub fn set_enabled(&self, enabled: bool) {
+        let was_enabled = self.enabled.swap(enabled, Ordering::SeqCst);
+        if was_enabled && !enabled {
+            let _ = self.tx.send(DiscordCommand::Clear);
+        }
+    }
+
+    /// Replace the activity buttons; invalid entries are dropped (see `sanitize_buttons`).
+    /// Takes effect with the next activity update. Returns the buttons kept.
+    pub fn set_buttons(&self, buttons: Vec<(String, String)>) -> Vec<(String, String)> {
+        let buttons = sanitize_buttons(buttons);
+        *self.buttons.lock().unwrap() = buttons.clone();
+        buttons
+    }
+
     pub fn connect(&self) -> Result<(), String> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
         self.tx
             .send(DiscordCommand::Connect)
             .map_err(|e| e.to_string())
@@ -157,15 +283,18 @@ impl DiscordRpc {
         image_url: Option<String>,
         album_name: Option<String>,
     ) -> Result<(), String> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
         self.tx
-            .send(DiscordCommand::SetActivity {
+            .send(DiscordCommand::SetActivity(ActivityData {
                 details: details.to_string(),
                 state: state.to_string(),
                 start_timestamp,
                 end_timestamp,
                 image_url,
                 album_name,
-            })
+            }))
             .map_err(|e| e.to_string())
     }
 
@@ -175,3 +304,24 @@ impl DiscordRpc {
             .map_err(|e| e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_buttons_are_dropped_and_labels_capped() {
+        let long_label = "a".repeat(40);
+        let buttons = sanitize_buttons(vec![
+            ("  ".to_string(), "https://example.com".to_string()),
+            ("Bad".to_string(), "javascript:alert(1)".to_string()),
+            (long_label, "https://example.com/a".to_string()),
+            ("Search".to_string(), "https://example.com/search?q=song".to_string()),
+            ("Third".to_string(), "https://example.com/c".to_string()),
+        ]);
+
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0].0.chars().count(), 32);
+        assert_eq!(buttons[1], ("Search".to_string(), "https://example.com/search?q=song".to_string()));
+    }
+}
@@ -4,10 +4,15 @@ mod stats_v2;
 mod cover_fetcher;
 mod database;
 mod discord_rpc;
+mod library_watcher;
+mod lyrics_cache;
 mod lyrics_fetcher;
 pub mod lyrics_transliteration;
 mod p2p;
+mod queue;
+mod scrobbler;
 mod server;
+mod settings;
 #[cfg(target_os = "windows")]
 mod taskbar_controls;
 mod torrent;
@@ -19,10 +24,10 @@ use tauri::{AppHandle, Emitter, Manager, State, Listener};
 use serde::Serialize;
 
 use audio::state::PlayerStatus;
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux", target_os = "macos"))]
 use audio::MediaControlService;
 use audio::{AudioPlayer, MediaCmd, TrackInfo};
-use crate::database::db::DbPlaylist;
+use crate::database::db::{folder_prefix, normalize_track_path, DbPlaylist, DbStation};
 use database::DatabaseManager;
 use discord_rpc::DiscordRpc;
 use p2p::P2PManager;
@@ -31,18 +36,17 @@ use tokio::sync::RwLock as TokioRwLock;
 // Discord App ID
 const DISCORD_APP_ID: &str = "1463457295974535241";
 
+/// Sent with requests to third-party web services; MusicBrainz wants the
+/// application name, version and a contact URL
+pub(crate) const USER_AGENT: &str = concat!(
+    "vibe-on/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/MemestaVedas/vibe-on)"
+);
+
 use std::sync::mpsc::Sender;
 
-/// Cached lyrics for current track
-#[derive(Clone, Default)]
-pub struct CachedLyrics {
-    pub track_path: String,
-    pub synced_lyrics: Option<String>,
-    pub plain_lyrics: Option<String>,
-    pub instrumental: bool,
-    pub is_fetching: bool,
-    pub error: Option<String>,
-}
+pub use lyrics_cache::CachedLyrics;
 
 /// Global player state managed by Tauri
 pub struct AppState {
@@ -56,8 +60,13 @@ pub struct AppState {
     lyrics_cache: Arc<Mutex<CachedLyrics>>,
     torrent_manager: Arc<Mutex<Option<torrent::TorrentManager>>>,
     p2p_manager: Arc<TokioRwLock<Option<P2PManager>>>,
+    p2p_status: Arc<Mutex<p2p::P2PStatus>>,
     server_running: Arc<Mutex<bool>>,
     server_shutdown_tx: Arc<Mutex<Option<tokio::sync::broadcast::Sender<()>>>>,
+    /// Port the companion server actually bound, once it is listening
+    server_port: Arc<Mutex<Option<u16>>>,
+    /// Preferences from `settings.json`, loaded at startup
+    settings: Arc<Mutex<settings::Settings>>,
     /// WebSocket broadcast sender — set when the HTTP/WS server starts.
     /// Tauri commands use this to push state changes to mobile clients immediately.
     pub ws_broadcast_tx: Arc<Mutex<Option<tokio::sync::broadcast::Sender<server::ServerEvent>>>>,
@@ -67,8 +76,20 @@ pub struct AppState {
     pub queue: Arc<Mutex<VecDeque<TrackInfo>>>,
     pub current_queue_index: Arc<Mutex<usize>>,
     pub shuffle: Arc<Mutex<bool>>,
+    /// Traversal order used by Next/Previous while shuffle is on
+    pub shuffle_order: Arc<Mutex<queue::ShuffleOrder>>,
     pub repeat_mode: Arc<Mutex<String>>, // "off", "one", "all"
     pub stats_tracker: Arc<Mutex<stats::StatsTracker>>,
+    /// Every playback event recorded, for live stats subscribers
+    pub stats_updates: tokio::sync::broadcast::Sender<stats::PlaybackEvent>,
+    /// Bumped on every start/cancel so stale sleep timer tasks exit
+    sleep_timer_generation: Arc<Mutex<u64>>,
+    /// Bumped whenever a new track starts so an earlier pending play count is dropped
+    play_count_generation: Arc<Mutex<u64>>,
+    /// Filesystem watcher keeping watched library folders in sync
+    library_watcher: library_watcher::LibraryWatcher,
+    /// Set by `cancel_library_scan`; cleared when the next scan starts
+    library_scan_cancelled: std::sync::atomic::AtomicBool,
 }
 
 impl Default for AppState {
@@ -83,15 +104,24 @@ impl Default for AppState {
             lyrics_cache: Arc::new(Mutex::new(CachedLyrics::default())),
             torrent_manager: Arc::new(Mutex::new(None)),
             p2p_manager: Arc::new(TokioRwLock::new(None)),
+            p2p_status: Arc::new(Mutex::new(p2p::P2PStatus::default())),
             server_running: Arc::new(Mutex::new(false)),
             server_shutdown_tx: Arc::new(Mutex::new(None)),
+            server_port: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(settings::Settings::default())),
             ws_broadcast_tx: Arc::new(Mutex::new(None)),
             active_output: Arc::new(TokioRwLock::new("desktop".to_string())),
             queue: Arc::new(Mutex::new(VecDeque::new())),
             current_queue_index: Arc::new(Mutex::new(0)),
             shuffle: Arc::new(Mutex::new(false)),
+            shuffle_order: Arc::new(Mutex::new(queue::ShuffleOrder::default())),
             repeat_mode: Arc::new(Mutex::new("off".to_string())),
             stats_tracker: Arc::new(Mutex::new(stats::StatsTracker::default())),
+            stats_updates: tokio::sync::broadcast::channel(64).0,
+            sleep_timer_generation: Arc::new(Mutex::new(0)),
+            play_count_generation: Arc::new(Mutex::new(0)),
+            library_watcher: library_watcher::LibraryWatcher::default(),
+            library_scan_cancelled: std::sync::atomic::AtomicBool::new(false),
         }
     }
 }
@@ -156,20 +186,300 @@ fn broadcast_state_to_ws(state: &AppState) {
         volume: volume as f64, shuffle, repeat_mode, output,
     });
 }
+/// How long a track has to play before it counts (tracks shorter than 60s need half)
+const PLAY_COUNT_THRESHOLD_SECS: f64 = 30.0;
+
+/// Count a play for `path` once it has actually been heard for the threshold, so
+/// skips don't inflate play counts. A newer call supersedes a pending one.
+pub(crate) fn schedule_play_count(app_handle: &AppHandle, path: String) {
+    let generation = {
+        let state = app_handle.state::<AppState>();
+        let mut guard = state.play_count_generation.lock().unwrap();
+        *guard += 1;
+        *guard
+    };
+    let app_handle = app_handle.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            if *state.play_count_generation.lock().unwrap() != generation {
+                return;
+            }
+            let status = match state.player.lock() {
+                Ok(g) => match *g {
+                    Some(ref p) => p.get_status(),
+                    None => return,
+                },
+                Err(_) => return,
+            };
+            let Some(track) = status.track else { return };
+            if track.path != path || status.state == audio::PlayerState::Stopped {
+                return;
+            }
+            let threshold = if track.duration_secs > 0.0 {
+                PLAY_COUNT_THRESHOLD_SECS.min(track.duration_secs * 0.5)
+            } else {
+                PLAY_COUNT_THRESHOLD_SECS
+            };
+            if status.position_secs >= threshold {
+                break;
+            }
+        }
+
+        let db_guard = state.db.lock().unwrap();
+        if let Some(ref db) = *db_guard {
+            if let Err(e) = db.increment_play_count(&path) {
+                eprintln!("[PlayCount] Failed to record play for {}: {}", path, e);
+            }
+        }
+    });
+}
+
+/// Point the backend queue index at the track the player is actually on, then hand
+/// the player the track that follows it so crossfades can start ahead of time.
+/// Returns true when the player moved to another queue entry on its own.
+pub(crate) fn sync_next_track(state: &AppState) -> bool {
+    let current_path = state.player.lock().ok().and_then(|guard| {
+        guard
+            .as_ref()
+            .and_then(|p| p.get_status().track.map(|t| t.path))
+    });
+    let Some(current_path) = current_path else {
+        return false;
+    };
+
+    let advanced = {
+        let queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+
+        let mut advanced = false;
+        if queue.get(*index).map(|t| t.path != current_path).unwrap_or(true) {
+            if let Some(pos) = queue.iter().position(|t| t.path == current_path) {
+                *index = pos;
+                advanced = true;
+            }
+        }
+        advanced
+    };
+    let next = queue::peek_next(state);
+
+    if let Ok(guard) = state.player.lock() {
+        if let Some(ref player) = *guard {
+            let _ = player.set_next_track(next);
+        }
+    }
+
+    advanced
+}
+
 /// Initialize the audio player
 fn get_or_init_player(state: &AppState) -> Result<(), String> {
+    if state.player.lock().unwrap().is_some() {
+        return Ok(());
+    }
+    // Read before taking the player lock so the two locks never nest
+    let settings = state.settings.lock().unwrap().clone();
     let mut player_guard = state.player.lock().unwrap();
     if player_guard.is_none() {
         println!("[Backend] Initializing AudioPlayer...");
-        *player_guard = Some(AudioPlayer::new()?);
+        let player = AudioPlayer::new()?;
+        apply_player_settings(&player, &settings);
+        *player_guard = Some(player);
+    }
+    Ok(())
+}
+
+/// Push the saved audio preferences to a player
+fn apply_player_settings(player: &AudioPlayer, settings: &settings::Settings) {
+    let _ = player.set_preamp(settings.preamp_db);
+    let _ = player.set_normalization_mode(settings.normalization_mode, settings.default_gain_db);
+    let _ = player.set_volume_curve(settings.volume_curve);
+}
+
+fn settings_path(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
+    app_handle
+        .path()
+        .app_data_dir()
+        .map(|dir| dir.join("settings.json"))
+        .map_err(|e| e.to_string())
+}
+
+/// Load `settings.json` into `AppState` and apply it. Without a file yet, the
+/// values older versions kept in the database are imported and saved. Values
+/// moved out of the database after that are taken over once, then deleted there.
+fn load_settings(state: &AppState, app_handle: &AppHandle) {
+    let path = settings_path(app_handle);
+    let _ = get_or_init_db(state, app_handle);
+    let settings = {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref();
+        let mut settings = match path {
+            Ok(ref path) if path.exists() => settings::Settings::load(path),
+            Ok(_) => db.map(settings::Settings::import_legacy).unwrap_or_default(),
+            Err(ref e) => {
+                eprintln!("[Settings] No app data dir, using defaults: {}", e);
+                settings::Settings::default()
+            }
+        };
+        let moved = db.is_some_and(|db| settings.import_moved(db));
+
+        if let Ok(ref path) = path {
+            if moved || !path.exists() {
+                match settings.save(path) {
+                    Ok(()) => {
+                        for key in settings::MOVED_DB_KEYS.iter().filter(|_| moved) {
+                            if let Some(Err(e)) = db.map(|db| db.delete_setting(key)) {
+                                eprintln!("[Settings] Failed to delete moved key {}: {}", key, e);
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("[Settings] Failed to write {}: {}", path.display(), e),
+                }
+            }
+        }
+        settings
+    };
+
+    state.discord.set_enabled(settings.discord_enabled);
+    state.discord.set_buttons(settings.discord_buttons.clone());
+    audio::formats::set(settings.scan_extensions.clone());
+    if let Some(ref player) = *state.player.lock().unwrap() {
+        apply_player_settings(player, &settings);
+    }
+    *state.settings.lock().unwrap() = settings;
+}
+
+/// Change the settings and write them to `settings.json`. Nothing changes if
+/// `change` fails or the file can't be written. Returns the new settings.
+fn save_settings(
+    state: &AppState,
+    app_handle: &AppHandle,
+    change: impl FnOnce(&mut settings::Settings) -> Result<(), String>,
+) -> Result<settings::Settings, String> {
+    let path = settings_path(app_handle)?;
+    let mut guard = state.settings.lock().unwrap();
+    let mut settings = guard.clone();
+    change(&mut settings)?;
+    settings.save(&path)?;
+    *guard = settings.clone();
+    Ok(settings)
+}
+
+#[tauri::command]
+fn get_settings(state: State<AppState>) -> settings::Settings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Change any subset of the settings and apply them right away. Returns the
+/// settings as saved (e.g. with the pre-amp clamped and invalid Discord
+/// buttons dropped).
+#[tauri::command]
+fn update_settings(
+    mut patch: settings::SettingsPatch,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<settings::Settings, String> {
+    if let Some(buttons) = patch.discord_buttons.take() {
+        patch.discord_buttons = Some(discord_rpc::sanitize_buttons(buttons));
+    }
+    let before = state.settings.lock().unwrap().clone();
+    let settings = save_settings(&state, &app_handle, |s| s.apply(patch))?;
+
+    state.discord.set_buttons(settings.discord_buttons.clone());
+    audio::formats::set(settings.scan_extensions.clone());
+    if settings.discord_enabled != before.discord_enabled {
+        apply_discord_enabled(&state, settings.discord_enabled);
+    }
+    if let Some(ref player) = *state.player.lock().unwrap() {
+        apply_player_settings(player, &settings);
+    }
+    broadcast_state_to_ws(&state);
+    Ok(settings)
+}
+
+/// Track info for a saved session path, or `None` once the file is gone
+fn last_session_track(state: &AppState, path: &str) -> Option<TrackInfo> {
+    if !std::path::Path::new(path).exists() {
+        return None;
+    }
+    let from_db = state
+        .db
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|db| db.get_track(path).ok().flatten());
+    from_db.or_else(|| get_track_metadata_helper_fast(path).ok())
+}
+
+/// The track and position playback was at when last saved, for offering
+/// "resume" at startup
+#[tauri::command]
+fn get_last_session(
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<Option<(TrackInfo, f64)>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let Some(session) = state.settings.lock().unwrap().last_session.clone() else {
+        return Ok(None);
+    };
+    Ok(last_session_track(&state, &session.track_path).map(|track| (track, session.position_secs)))
+}
+
+/// Load the last session's track paused at its saved position; `resume`
+/// starts it. Seeds the queue with the track if the queue is empty.
+#[tauri::command]
+fn restore_last_session(state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    let session = state
+        .settings
+        .lock()
+        .unwrap()
+        .last_session
+        .clone()
+        .ok_or("No saved session")?;
+    let track = last_session_track(&state, &session.track_path)
+        .ok_or_else(|| format!("File not found: {}", session.track_path))?;
+
+    get_or_init_player(&state)?;
+    {
+        let player_guard = state.player.lock().unwrap();
+        let player = player_guard.as_ref().ok_or("Player not initialized")?;
+        player.load_track(track.clone())?;
+        // Loading builds no sink; seeking (even to 0) builds a paused one for `resume`
+        player.seek(session.position_secs.max(0.0))?;
+    }
+
+    let seeded = {
+        let mut queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+        if queue.is_empty() {
+            *queue = VecDeque::from(vec![track]);
+            *index = 0;
+            true
+        } else {
+            if let Some(i) = queue.iter().position(|t| t.path == session.track_path) {
+                *index = i;
+            }
+            false
+        }
+    };
+    if seeded {
+        queue::queue_changed(&state);
     }
+    sync_next_track(&state);
+
+    broadcast_state_to_ws(&state);
+    let _ = app_handle.emit("refresh-player-state", ());
     Ok(())
 }
 
 fn get_or_init_db(state: &AppState, app_handle: &AppHandle) -> Result<(), String> {
     let mut db_guard = state.db.lock().unwrap();
     if db_guard.is_none() {
-        *db_guard = Some(DatabaseManager::new(app_handle).map_err(|e| e.to_string())?);
+        let db = DatabaseManager::new(app_handle).map_err(|e| e.to_string())?;
+        *db_guard = Some(db);
         // One-time migration of legacy JSON stats into SQLite
         drop(db_guard);
         match stats::migrate_json_to_sqlite(state, app_handle) {
@@ -181,6 +491,130 @@ fn get_or_init_db(state: &AppState, app_handle: &AppHandle) -> Result<(), String
     Ok(())
 }
 
+/// Master gain ahead of the EQ, separate from the user-facing volume. Clamped to
+/// -24..+12 dB and saved so it is re-applied on the next launch.
+#[tauri::command]
+fn set_preamp(db: f32, state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    if !db.is_finite() {
+        return Err("Invalid preamp gain".to_string());
+    }
+    get_or_init_player(&state)?;
+    {
+        let player_guard = state.player.lock().unwrap();
+        if let Some(ref player) = *player_guard {
+            player.set_preamp(db)?;
+        }
+    }
+
+    let (min, max) = audio::player::PREAMP_RANGE_DB;
+    save_settings(&state, &app_handle, |s| {
+        s.preamp_db = db.clamp(min, max);
+        Ok(())
+    })?;
+    broadcast_state_to_ws(&state);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_discord_enabled(state: State<AppState>) -> bool {
+    state.discord.is_enabled()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiscordStatus {
+    enabled: bool,
+    /// Presence is live; false while Discord isn't running (reconnects are retried)
+    connected: bool,
+}
+
+#[tauri::command]
+fn get_discord_status(state: State<AppState>) -> DiscordStatus {
+    DiscordStatus {
+        enabled: state.discord.is_enabled(),
+        connected: state.discord.is_connected(),
+    }
+}
+
+/// Set up to two (label, url) buttons on the Discord activity. Invalid entries are
+/// dropped; an empty list removes the buttons. Returns the buttons that were kept.
+#[tauri::command]
+fn set_discord_buttons(
+    buttons: Vec<(String, String)>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<(String, String)>, String> {
+    let kept = discord_rpc::sanitize_buttons(buttons);
+    save_settings(&state, &app_handle, |s| {
+        s.discord_buttons = kept.clone();
+        Ok(())
+    })?;
+    state.discord.set_buttons(kept.clone());
+    Ok(kept)
+}
+
+/// Turn Discord Rich Presence on or off and remember the choice.
+/// Re-enabling during playback shows the current track straight away.
+#[tauri::command]
+fn set_discord_enabled(
+    enabled: bool,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    save_settings(&state, &app_handle, |s| {
+        s.discord_enabled = enabled;
+        Ok(())
+    })?;
+    apply_discord_enabled(&state, enabled);
+    Ok(())
+}
+
+fn apply_discord_enabled(state: &AppState, enabled: bool) {
+    let was_enabled = state.discord.is_enabled();
+    state.discord.set_enabled(enabled);
+    if !enabled || was_enabled {
+        return;
+    }
+
+    let status = match state.player.lock().unwrap().as_ref() {
+        Some(player) => player.get_status(),
+        None => return,
+    };
+    let Some(track) = status.track else {
+        return;
+    };
+
+    let cover_url = state.current_cover_url.lock().unwrap().clone();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let pos = status.position_secs as i64;
+    match status.state {
+        crate::audio::PlayerState::Playing => {
+            let _ = state.discord.set_activity(
+                &track.title,
+                &format!("by {}", track.artist),
+                Some(now - pos),
+                Some(now + (track.duration_secs as i64 - pos)),
+                cover_url,
+                Some(track.album),
+            );
+        }
+        crate::audio::PlayerState::Paused => {
+            let _ = state.discord.set_activity(
+                &track.title,
+                &format!("by {} (Paused)", track.artist),
+                Some(now - pos),
+                None,
+                cover_url,
+                Some(track.album),
+            );
+        }
+        _ => {}
+    }
+}
+
 #[tauri::command]
 async fn set_album_main_color(
     album: String,
@@ -197,6 +631,68 @@ async fn set_album_main_color(
         .map_err(|e| e.to_string())
 }
 
+/// Replace an album's cover with a user-chosen PNG/JPEG and tell the UI to reload it
+fn override_album_cover(
+    state: &AppState,
+    app_handle: &AppHandle,
+    album: &str,
+    artist: &str,
+    image_path: &str,
+) -> Result<String, String> {
+    let filename = {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        db.set_album_cover_from_file(album, artist, Path::new(image_path))?
+    };
+
+    let _ = app_handle.emit("cover-updated", serde_json::json!({
+        "album": album,
+        "artist": artist,
+        "coverImagePath": filename,
+    }));
+    Ok(filename)
+}
+
+#[tauri::command]
+async fn set_album_cover(
+    album: String,
+    artist: String,
+    image_path: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    get_or_init_db(&state, &app_handle)?;
+    override_album_cover(&state, &app_handle, &album, &artist, &image_path)
+}
+
+/// Covers belong to albums, so this sets the cover of the track's album
+#[tauri::command]
+async fn set_track_cover(
+    track_path: String,
+    image_path: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    get_or_init_db(&state, &app_handle)?;
+
+    let track = {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        db.get_track(&track_path)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Track not found in library".to_string())?
+    };
+    // Same album key as insert_track: album artist, else the track artist
+    let album_artist = track
+        .album_artist
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&track.artist);
+
+    override_album_cover(&state, &app_handle, &track.album, album_artist, &image_path)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct AlbumColorBackfillResult {
@@ -268,10 +764,20 @@ async fn play_file(
         }
     }
 
+    sync_next_track(&state);
+    schedule_play_count(&app_handle, path.clone());
+
     // Broadcast state change to WebSocket clients + frontend immediately
     broadcast_state_to_ws(&state);
     let _ = app_handle.emit("refresh-player-state", ());
 
+    // Reset the lyrics cache before any background work starts, so a thread
+    // still running for the previous track can't claim it afterwards
+    if let Ok(mut lyrics_guard) = state.lyrics_cache.lock() {
+        println!("[Lyrics] Initializing cache for new track: {}", path);
+        lyrics_guard.begin(&path);
+    }
+
     // Now spawn background operations (Discord, lyrics, cover, media controls)
     // These don't block audio playback
     let path_clone = path.clone();
@@ -287,16 +793,6 @@ async fn play_file(
             *url_guard = None;
         }
 
-        // Reset lyrics cache and mark as fetching
-        if let Ok(mut lyrics_guard) = lyrics_cache.lock() {
-            println!("[Lyrics] Initializing cache for new track: {}", path_clone);
-            *lyrics_guard = CachedLyrics {
-                track_path: path_clone.clone(),
-                is_fetching: true,
-                ..Default::default()
-            };
-        }
-
         // Try to get metadata for Discord/lyrics/covers (single call, not duplicate)
         if let Ok((info, _)) = get_track_metadata_helper(&path_clone) {
             // Connect to Discord
@@ -350,38 +846,14 @@ async fn play_file(
                 let cb1 = move |msg: &str| {
                     let _ = app_h_1.emit("lyrics-loading-status", msg);
                 };
-                let app_h_2 = app_h_lyrics.clone();
-                let cb2 = move |msg: &str| {
-                    let _ = app_h_2.emit("lyrics-loading-status", msg);
-                };
-
-                let result =
-                    match lyrics_fetcher::fetch_lyrics(&artist, &track_title, duration, cb1) {
-                        Ok(lyrics) => lyrics,
-                        Err(_) => {
-                            match lyrics_fetcher::fetch_lyrics_fallback(&artist, &track_title, cb2)
-                            {
-                                Ok(lyrics) => lyrics,
-                                Err(e) => {
-                                    if let Ok(mut guard) = lyrics_cache_clone.lock() {
-                                        if guard.track_path == track_path {
-                                            guard.is_fetching = false;
-                                            guard.error = Some(e);
-                                        }
-                                    }
-                                    return;
-                                }
-                            }
-                        }
-                    };
 
+                let result = load_lyrics(&app_h_lyrics, &track_path, &artist, &track_title, duration, cb1);
                 if let Ok(mut guard) = lyrics_cache_clone.lock() {
-                    if guard.track_path == track_path {
-                        guard.synced_lyrics = result.synced_lyrics;
-                        guard.plain_lyrics = result.plain_lyrics;
-                        guard.instrumental = result.instrumental.unwrap_or(false);
-                        guard.is_fetching = false;
-                        guard.error = None;
+                    let applied = match result {
+                        Ok(lyrics) => guard.complete(&track_path, lyrics),
+                        Err(e) => guard.fail(&track_path, e),
+                    };
+                    if applied {
                         println!(
                             "[Lyrics] Prefetch complete for: {} - {}",
                             artist, track_title
@@ -437,43 +909,189 @@ async fn play_file(
     Ok(())
 }
 
+/// Stream a remote http(s) file or radio station. Title and genre come from the
+/// stream's ICY headers, or the file name in the URL.
 #[tauri::command]
-fn pause(state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
-    let player_guard = state.player.lock().unwrap();
-    if let Some(ref player) = *player_guard {
-        let status = player.get_status();
-        let cover_url = state.current_cover_url.lock().unwrap().clone();
+async fn play_url(
+    url: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if !audio::stream::is_remote(&url) {
+        return Err("Only http(s) URLs can be streamed".to_string());
+    }
+    get_or_init_player(&state)?;
 
-        if let Some(track) = status.track {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs() as i64;
-            let pos = status.position_secs as i64;
-            let _ = state.discord.set_activity(
-                &track.title,
-                &format!("by {} (Paused)", track.artist),
-                Some(now - pos),
-                None,
-                cover_url,
-                Some(track.album),
-            );
-        } else {
-            let _ = state
-                .discord
-                .set_activity("Paused", "Vibe Music Player", None, None, None, None);
-        }
+    // A saved station's name beats whatever the stream calls itself
+    let station = {
+        let db_guard = state.db.lock().unwrap();
+        db_guard
+            .as_ref()
+            .and_then(|db| db.get_stations().ok())
+            .and_then(|stations| stations.into_iter().find(|s| s.url == url))
+    };
+    let track = match station {
+        Some(station) => TrackInfo {
+            path: url.clone(),
+            title: station.name.clone(),
+            album: station.name,
+            ..TrackInfo::default()
+        },
+        None => TrackInfo {
+            path: url.clone(),
+            ..TrackInfo::default()
+        },
+    };
 
-        // Update Windows Media Controls
-        if let Ok(tx_guard) = state.media_cmd_tx.lock() {
-            if let Some(ref tx) = *tx_guard {
-                let _ = tx.send(MediaCmd::SetPaused);
-            }
-        }
+    // The player opens the stream, so once it answers the status request the
+    // metadata from the response headers is in place. The answer is awaited
+    // with the player unlocked, so other commands go on while it connects.
+    let status_rx = {
+        let player_guard = state.player.lock().unwrap();
+        let Some(ref player) = *player_guard else {
+            return Err("Player not initialized".to_string());
+        };
+        player.play_track(track)?;
+        player.request_status()
+    };
+    let status = tauri::async_runtime::spawn_blocking(move || status_rx.recv().unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(info) = status.track.filter(|track| track.path == url) else {
+        return Err(format!("Failed to open stream: {}", url));
+    };
+
+    broadcast_state_to_ws(&state);
+    let _ = app_handle.emit("refresh-player-state", ());
+
+    if let Ok(mut lyrics_guard) = state.lyrics_cache.lock() {
+        lyrics_guard.begin(&url);
+        lyrics_guard.fail(&url, "No lyrics for streams".to_string());
+    }
+    if let Ok(mut url_guard) = state.current_cover_url.lock() {
+        *url_guard = None;
+    }
+
+    let discord = state.discord.clone();
+    let media_cmd_tx = state.media_cmd_tx.lock().unwrap().clone();
+    std::thread::spawn(move || {
+        let _ = discord.connect();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let _ = discord.set_activity(&info.title, "Streaming", Some(now), None, None, None);
+
+        if let Some(ref tx) = media_cmd_tx {
+            let _ = tx.send(MediaCmd::SetMetadata {
+                title: info.title.clone(),
+                artist: info.artist.clone(),
+                album: info.album.clone(),
+            });
+            let _ = tx.send(MediaCmd::SetPlaying);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn add_station(
+    name: String,
+    url: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<i64, String> {
+    let name = name.trim();
+    let url = url.trim();
+    if name.is_empty() {
+        return Err("Station name cannot be empty".to_string());
+    }
+    if !audio::stream::is_remote(url) {
+        return Err("Station URL must be an http(s) URL".to_string());
+    }
+
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.add_station(name, url).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_stations(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<DbStation>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.get_stations().map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn remove_station(
+    id: i64,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.remove_station(id).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+fn pause(state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    pause_playback(&state, &app_handle)
+}
+
+/// Pause the player and update Discord, media controls and connected clients.
+/// Shared by the `pause` command and the sleep timer.
+fn pause_playback(state: &AppState, app_handle: &AppHandle) -> Result<(), String> {
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        let status = player.get_status();
+        let cover_url = state.current_cover_url.lock().unwrap().clone();
+
+        if let Some(track) = status.track {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            let pos = status.position_secs as i64;
+            let _ = state.discord.set_activity(
+                &track.title,
+                &format!("by {} (Paused)", track.artist),
+                Some(now - pos),
+                None,
+                cover_url,
+                Some(track.album),
+            );
+        } else {
+            let _ = state
+                .discord
+                .set_activity("Paused", "Vibe Music Player", None, None, None, None);
+        }
+
+        // Update Windows Media Controls
+        if let Ok(tx_guard) = state.media_cmd_tx.lock() {
+            if let Some(ref tx) = *tx_guard {
+                let _ = tx.send(MediaCmd::SetPaused);
+            }
+        }
 
         let result = player.pause();
         drop(player_guard);
-        broadcast_state_to_ws(&state);
+        broadcast_state_to_ws(state);
         let _ = app_handle.emit("refresh-player-state", ());
         result
     } else {
@@ -551,6 +1169,36 @@ fn stop(state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
     }
 }
 
+/// Shift output between speakers: -1.0 is full left, 1.0 full right
+#[tauri::command]
+fn set_balance(balance: f32, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        let result = player.set_balance(balance);
+        drop(player_guard);
+        broadcast_state_to_ws(&state);
+        result
+    } else {
+        Ok(())
+    }
+}
+
+/// Downmix to mono; combine with `set_balance` to play through one speaker
+#[tauri::command]
+fn set_mono(enabled: bool, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        let result = player.set_mono(enabled);
+        drop(player_guard);
+        broadcast_state_to_ws(&state);
+        result
+    } else {
+        Ok(())
+    }
+}
+
 #[tauri::command]
 fn set_volume(value: f32, state: State<AppState>) -> Result<(), String> {
     get_or_init_player(&state)?;
@@ -654,14 +1302,329 @@ fn set_speed(value: f32, state: State<AppState>) -> Result<(), String> {
     }
 }
 
+/// Set the crossfade window in seconds. 0 restores hard cuts between tracks.
 #[tauri::command]
-fn get_player_state(state: State<AppState>) -> PlayerStatus {
+fn set_crossfade(duration_secs: f32, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    {
+        let player_guard = state.player.lock().unwrap();
+        if let Some(ref player) = *player_guard {
+            player.set_crossfade(duration_secs)?;
+        }
+    }
+    sync_next_track(&state);
+    Ok(())
+}
+
+/// Gapless playback between consecutive queue tracks with the same sample rate.
+/// Ignored while a crossfade is configured.
+#[tauri::command]
+fn set_gapless(enabled: bool, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    {
+        let player_guard = state.player.lock().unwrap();
+        if let Some(ref player) = *player_guard {
+            player.set_gapless(enabled)?;
+        }
+    }
+    sync_next_track(&state);
+    Ok(())
+}
+
+/// Exclusive output (Windows): the device is held in WASAPI exclusive mode at
+/// each track's sample rate and bit depth, and ReplayGain, EQ, effects, speed
+/// and volume are bypassed. A track whose format the device rejects is not
+/// played; the error is reported instead. Errors if the current track can't be
+/// played exclusively, or off Windows.
+#[tauri::command]
+fn set_exclusive_output(enabled: bool, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    match *player_guard {
+        Some(ref player) => player.set_exclusive_output(enabled),
+        None => Ok(()),
+    }
+}
+
+/// Jump past leading and trailing silence (over ~1s) in each track. The detected
+/// trim points are reported in `PlayerStatus::silence_trim`.
+#[tauri::command]
+fn set_skip_silence(enabled: bool, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    match *player_guard {
+        Some(ref player) => player.set_skip_silence(enabled),
+        None => Ok(()),
+    }
+}
+
+/// Length of the short fades that smooth play, pause, resume, seek and stop.
+/// Defaults to 30 ms; 0 disables them.
+#[tauri::command]
+fn set_click_fade_ms(ms: u32, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    match *player_guard {
+        Some(ref player) => player.set_click_fade_ms(ms),
+        None => Ok(()),
+    }
+}
+
+/// How the volume slider maps to output gain: "linear", "log" (default) or
+/// "cubic". See `VolumeCurve` for the formulas.
+#[tauri::command]
+fn set_volume_curve(curve: String, state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    let curve = audio::state::VolumeCurve::parse(&curve)?;
+    save_settings(&state, &app_handle, |s| {
+        s.volume_curve = curve;
+        Ok(())
+    })?;
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        let result = player.set_volume_curve(curve);
+        drop(player_guard);
+        broadcast_state_to_ws(&state);
+        result
+    } else {
+        Ok(())
+    }
+}
+
+/// Loop the current track between `start_secs` and `end_secs`
+#[tauri::command]
+fn set_ab_loop(start_secs: f64, end_secs: f64, state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
     let player_guard = state.player.lock().unwrap();
     if let Some(ref player) = *player_guard {
+        player.set_ab_loop(start_secs, end_secs)
+    } else {
+        Ok(())
+    }
+}
+
+#[tauri::command]
+fn clear_ab_loop(state: State<AppState>) -> Result<(), String> {
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        player.clear_ab_loop()
+    } else {
+        Ok(())
+    }
+}
+
+/// ReplayGain normalization: "off", "track" or "album".
+/// Tracks without tags use `default_gain_db` (0 dB when omitted).
+#[tauri::command]
+fn set_normalization_mode(
+    mode: String,
+    default_gain_db: Option<f32>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let mode = audio::replaygain::NormalizationMode::parse(&mode)?;
+    let default_gain_db = default_gain_db.unwrap_or(0.0);
+    save_settings(&state, &app_handle, |s| {
+        s.normalization_mode = mode;
+        s.default_gain_db = default_gain_db;
+        Ok(())
+    })?;
+    get_or_init_player(&state)?;
+    let player_guard = state.player.lock().unwrap();
+    if let Some(ref player) = *player_guard {
+        player.set_normalization_mode(mode, default_gain_db)
+    } else {
+        Ok(())
+    }
+}
+
+/// Start a sleep timer that fades the volume out over `fade_secs` and then pauses.
+/// With `end_of_track` set, `minutes` is ignored and playback pauses once the
+/// current song has played to its end instead, faded out over its last
+/// `fade_secs`. Starting a new timer replaces the old one.
+#[tauri::command]
+fn start_sleep_timer(
+    minutes: u32,
+    fade_secs: u32,
+    end_of_track: Option<bool>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let end_of_track = end_of_track.unwrap_or(false);
+    if !end_of_track && minutes == 0 {
+        return Err("Sleep timer needs at least one minute".to_string());
+    }
+
+    let generation = {
+        let mut guard = state.sleep_timer_generation.lock().unwrap();
+        *guard += 1;
+        *guard
+    };
+    let generation_arc = state.sleep_timer_generation.clone();
+    println!(
+        "[SleepTimer] Started: {} (fade {}s)",
+        if end_of_track { "end of track".to_string() } else { format!("{} min", minutes) },
+        fade_secs
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let is_current = || *generation_arc.lock().unwrap() == generation;
+        let state = app_handle.state::<AppState>();
+
+        if end_of_track {
+            if !sleep_after_track(&state, &app_handle, fade_secs, is_current).await {
+                return;
+            }
+        } else {
+            let deadline = std::time::Instant::now()
+                + std::time::Duration::from_secs(minutes as u64 * 60);
+            while std::time::Instant::now() < deadline {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                if !is_current() {
+                    return;
+                }
+            }
+
+            // Ramp the volume down, pause, then restore it so resuming isn't silent
+            let original_volume = state
+                .player
+                .lock()
+                .ok()
+                .and_then(|g| g.as_ref().map(|p| p.get_status().volume))
+                .unwrap_or(1.0);
+            let steps = (fade_secs * 20).max(1);
+            for step in 1..=steps {
+                if !is_current() {
+                    if let Some(ref p) = *state.player.lock().unwrap() {
+                        let _ = p.set_volume(original_volume);
+                    }
+                    return;
+                }
+                if fade_secs > 0 {
+                    let factor = 1.0 - step as f32 / steps as f32;
+                    if let Some(ref p) = *state.player.lock().unwrap() {
+                        let _ = p.set_volume(original_volume * factor);
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                }
+            }
+
+            let _ = pause_playback(&state, &app_handle);
+            if let Some(ref p) = *state.player.lock().unwrap() {
+                let _ = p.set_volume(original_volume);
+            }
+        }
+        println!("[SleepTimer] Finished, playback paused");
+        let _ = app_handle.emit("sleep-timer-finished", ());
+    });
+
+    Ok(())
+}
+
+/// End-of-track sleep timer. The player pauses by itself when the current
+/// track ends rather than moving on; the volume follows the time left in the
+/// track down to silence over its last `fade_secs` and is restored afterwards.
+/// Returns false if the timer was cancelled first.
+async fn sleep_after_track(
+    state: &AppState,
+    app_handle: &AppHandle,
+    fade_secs: u32,
+    is_current: impl Fn() -> bool,
+) -> bool {
+    let requested = state
+        .player
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|p| (p.stop_after_track(), p.get_status()));
+    let Some((Ok(track_ended), status)) = requested else {
+        return false;
+    };
+    let start_path = status.track.map(|t| t.path);
+    let fade = fade_secs as f64;
+    // Volume from before the fade started
+    let mut faded_from: Option<f32> = None;
+
+    let finished = loop {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        if !is_current() {
+            break false;
+        }
+        if track_ended.try_recv().is_ok() {
+            break true;
+        }
+        let Some(status) = state.player.lock().unwrap().as_ref().map(|p| p.get_status()) else {
+            break false;
+        };
+        // Something else started playing, or playback was stopped, before the end
+        if status.track.as_ref().map(|t| &t.path) != start_path.as_ref()
+            || status.state == audio::PlayerState::Stopped
+        {
+            let _ = pause_playback(state, app_handle);
+            break true;
+        }
+        if fade_secs == 0 || status.state != audio::PlayerState::Playing {
+            continue;
+        }
+
+        let remaining = status
+            .track
+            .as_ref()
+            .map(|t| {
+                let end = status.silence_trim.map_or(t.duration_secs, |trim| trim.end_secs);
+                end - status.position_secs
+            })
+            .unwrap_or(0.0);
+        let volume = match faded_from {
+            Some(original) if remaining > fade => {
+                // Seeked back out of the fade
+                faded_from = None;
+                original
+            }
+            None if remaining > fade => continue,
+            _ => {
+                let original = *faded_from.get_or_insert(status.volume);
+                original * (remaining / fade).clamp(0.0, 1.0) as f32
+            }
+        };
+        if let Some(ref p) = *state.player.lock().unwrap() {
+            let _ = p.set_volume(volume);
+        }
+    };
+
+    if let Some(ref p) = *state.player.lock().unwrap() {
+        if !finished {
+            let _ = p.cancel_stop_after_track();
+        }
+        if let Some(original) = faded_from {
+            let _ = p.set_volume(original);
+        }
+    }
+    finished
+}
+
+#[tauri::command]
+fn cancel_sleep_timer(state: State<AppState>) -> Result<(), String> {
+    *state.sleep_timer_generation.lock().unwrap() += 1;
+    println!("[SleepTimer] Cancelled");
+    Ok(())
+}
+
+#[tauri::command]
+fn get_player_state(state: State<AppState>) -> PlayerStatus {
+    let player_guard = state.player.lock().unwrap();
+    let status = if let Some(ref player) = *player_guard {
         player.get_status()
     } else {
         PlayerStatus::default()
-    }
+    };
+
+    // The frontend polls this, which keeps the taskbar progress bar moving
+    #[cfg(target_os = "windows")]
+    taskbar_controls::update_progress(&status);
+
+    status
 }
 
 /// Return the current backend queue + index so the frontend can stay in sync.
@@ -695,6 +1658,131 @@ fn get_queue_state(state: State<AppState>) -> serde_json::Value {
     })
 }
 
+/// Send the backend queue to WebSocket clients and have the frontend re-read it
+fn broadcast_queue_to_ws(state: &AppState, app_handle: &AppHandle) {
+    let tx = state.ws_broadcast_tx.lock().unwrap().clone();
+    if let Some(tx) = tx {
+        let (tracks, index) = server::websocket::read_queue(state);
+        let _ = tx.send(server::ServerEvent::QueueUpdate {
+            tracks,
+            current_index: index as i32,
+        });
+    }
+    let _ = app_handle.emit("refresh-player-state", ());
+}
+
+/// Insert a track right after the one playing
+#[tauri::command]
+fn queue_insert_next(track: TrackInfo, state: State<AppState>, app_handle: AppHandle) {
+    queue::insert_next(&state, track);
+    sync_next_track(&state);
+    broadcast_queue_to_ws(&state, &app_handle);
+}
+
+/// Append a track to the end of the queue
+#[tauri::command]
+fn queue_add(track: TrackInfo, state: State<AppState>, app_handle: AppHandle) {
+    queue::append(&state, track);
+    sync_next_track(&state);
+    broadcast_queue_to_ws(&state, &app_handle);
+}
+
+/// Remove the entry at `index`. Removing the playing track moves playback on to
+/// the track that takes its place, or stops at the end of the queue.
+#[tauri::command]
+async fn queue_remove(
+    index: usize,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let removed = queue::remove(&state, index)?;
+    broadcast_queue_to_ws(&state, &app_handle);
+
+    let active = state
+        .player
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|p| p.get_status().state != audio::PlayerState::Stopped);
+    match removed {
+        queue::Removed::Current(Some(path)) if active => play_file(path, state, app_handle).await,
+        queue::Removed::Current(None) if active => stop(state, app_handle),
+        _ => {
+            sync_next_track(&state);
+            Ok(())
+        }
+    }
+}
+
+/// Move the entry at `from` to position `to`
+#[tauri::command]
+fn queue_move(
+    from: usize,
+    to: usize,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    queue::move_entry(&state, from, to)?;
+    sync_next_track(&state);
+    broadcast_queue_to_ws(&state, &app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+fn queue_clear(state: State<AppState>, app_handle: AppHandle) {
+    queue::clear(&state);
+    sync_next_track(&state);
+    broadcast_queue_to_ws(&state, &app_handle);
+}
+
+/// Queue `tracks` (shuffled first if asked) and play from the top
+async fn play_tracks(
+    tracks: Vec<TrackInfo>,
+    shuffle: bool,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let tracks = if shuffle { queue::shuffled(tracks) } else { tracks };
+    let first = tracks[0].path.clone();
+    queue::replace(&state, tracks);
+    broadcast_queue_to_ws(&state, &app_handle);
+    play_file(first, state, app_handle).await
+}
+
+/// Replace the queue with an album in disc/track order and play it. An empty
+/// `artist` matches the album under any artist.
+#[tauri::command]
+async fn play_album(
+    album: String,
+    artist: String,
+    shuffle: bool,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    let tracks = queue::album_tracks(&state, &album, &artist);
+    if tracks.is_empty() {
+        return Err("Album not found or empty".to_string());
+    }
+    play_tracks(tracks, shuffle, state, app_handle).await
+}
+
+/// Replace the queue with all of an artist's tracks, album by album, and play them
+#[tauri::command]
+async fn play_artist(
+    artist: String,
+    shuffle: bool,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    let tracks = queue::artist_tracks(&state, &artist);
+    if tracks.is_empty() {
+        return Err("Artist not found or empty".to_string());
+    }
+    play_tracks(tracks, shuffle, state, app_handle).await
+}
+
 /// Smart shuffle with scope-aware spacing behavior.
 /// - album: shuffle inside current album queue
 /// - artist: shuffle inside current artist queue
@@ -835,19 +1923,152 @@ fn get_stats_events(
 }
 
 #[tauri::command]
-fn get_top_tracks(
+fn get_top_tracks(
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<stats::TrackAnalytics>, String> {
+    stats::get_top_tracks(&state, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn get_recently_played(
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<stats::PlaybackEvent>, String> {
+    stats::get_recently_played(&state, limit.unwrap_or(30))
+}
+
+#[tauri::command]
+fn get_top_songs(
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<stats::SongRanking>, String> {
+    stats::top_songs(&state, start_ms, end_ms, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn get_top_artists(
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<stats::ArtistRanking>, String> {
+    stats::top_artists(&state, start_ms, end_ms, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn get_top_albums(
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<Vec<stats::AlbumRanking>, String> {
+    stats::top_albums(&state, start_ms, end_ms, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn get_total_listening_ms(
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    state: State<AppState>,
+) -> Result<i64, String> {
+    stats::total_listening_ms(&state, start_ms, end_ms)
+}
+
+/// Listened milliseconds per UTC day (midnight ms), for the activity heatmap
+#[tauri::command]
+fn get_listening_by_day(
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    state: State<AppState>,
+) -> Result<std::collections::BTreeMap<i64, i64>, String> {
+    stats::listening_by_day(&state, start_ms, end_ms)
+}
+
+/// Write the playback history to `path` as "csv" or "json"; returns the number of events
+#[tauri::command]
+fn export_stats(
+    path: String,
+    format: String,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<usize, String> {
+    get_or_init_db(&state, &app_handle)?;
+    stats::export_stats(&state, std::path::Path::new(&path), &format)
+}
+
+/// Link a Last.fm account; plays from now on are scrobbled
+#[tauri::command]
+async fn set_lastfm_session(
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+    username: Option<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    let session = scrobbler::LastfmSession { api_key, api_secret, session_key, username };
+    scrobbler::link_session(&state, &app_handle, &session)
+}
+
+/// Plays recorded but not yet accepted by Last.fm (e.g. while offline)
+#[tauri::command]
+fn get_scrobble_queue_len(state: State<AppState>, app_handle: AppHandle) -> Result<usize, String> {
+    get_or_init_db(&state, &app_handle)?;
+    scrobbler::queue_len(&state)
+}
+
+/// Library tracks ordered by play count (plays counted after 30s of listening)
+#[tauri::command]
+async fn get_most_played(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<TrackInfo>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.get_most_played(limit.unwrap_or(50)).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Newest library additions first. Rescans don't bump a track's `added_at`.
+#[tauri::command]
+async fn get_recently_added(
     limit: Option<usize>,
-    state: State<AppState>,
-) -> Result<Vec<stats::TrackAnalytics>, String> {
-    stats::get_top_tracks(&state, limit.unwrap_or(50))
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<TrackInfo>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.get_recently_added(limit.unwrap_or(50)).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
 }
 
+/// Library tracks ordered by when they were last played. `get_recently_played`
+/// already serves the stats view with raw playback events.
 #[tauri::command]
-fn get_recently_played(
+async fn get_recently_played_tracks(
     limit: Option<usize>,
-    state: State<AppState>,
-) -> Result<Vec<stats::PlaybackEvent>, String> {
-    stats::get_recently_played(&state, limit.unwrap_or(30))
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<TrackInfo>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.get_recently_played_tracks(limit.unwrap_or(30))
+            .map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
 }
 
 #[tauri::command]
@@ -863,6 +2084,59 @@ fn get_stats_v2(
 // Tauri Commands - Library Management
 // ============================================================================
 
+/// Minimum time between two `library-scan-progress` events
+const SCAN_PROGRESS_INTERVAL_MS: u64 = 250;
+
+/// Emits `library-scan-progress` for one scan, at most every
+/// `SCAN_PROGRESS_INTERVAL_MS`. Safe to call from all rayon workers at once.
+struct ScanProgress<'a> {
+    app_handle: &'a AppHandle,
+    started: std::time::Instant,
+    last_emit_ms: std::sync::atomic::AtomicU64,
+}
+
+impl<'a> ScanProgress<'a> {
+    fn new(app_handle: &'a AppHandle) -> Self {
+        Self {
+            app_handle,
+            started: std::time::Instant::now(),
+            last_emit_ms: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    /// The first and last update of a phase always go out
+    fn report(&self, phase: &str, processed: usize, total: usize) {
+        use std::sync::atomic::Ordering;
+
+        let now_ms = self.started.elapsed().as_millis() as u64;
+        if processed != 0 && processed != total {
+            let last = self.last_emit_ms.load(Ordering::Relaxed);
+            if now_ms.saturating_sub(last) < SCAN_PROGRESS_INTERVAL_MS {
+                return;
+            }
+            // Only one of the workers that saw the slot open gets to emit
+            if self
+                .last_emit_ms
+                .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+            {
+                return;
+            }
+        } else {
+            self.last_emit_ms.store(now_ms, Ordering::Relaxed);
+        }
+
+        let _ = self.app_handle.emit(
+            "library-scan-progress",
+            serde_json::json!({
+                "processed": processed,
+                "total": total,
+                "phase": phase,
+            }),
+        );
+    }
+}
+
 #[tauri::command]
 async fn init_library(
     path: String,
@@ -875,6 +2149,8 @@ async fn init_library(
     
     // 1. Init DB if needed
     get_or_init_db(&state, &app_handle)?;
+    state.library_scan_cancelled.store(false, Ordering::Relaxed);
+    let cancelled = || state.library_scan_cancelled.load(Ordering::Relaxed);
 
     let path_obj = Path::new(&path);
     if !path_obj.is_dir() {
@@ -882,36 +2158,22 @@ async fn init_library(
     }
 
     println!("[Library] Scanning folder: {:?}", path_obj);
-    let mut files = scan_music_folder_helper(path_obj);
+    let mut files = scan_music_folder_until(path_obj, &cancelled);
     println!("[Library] Found {} files. Processing in parallel...", files.len());
 
     // Optimization: Skip existing files
     {
         let db_lock = state.db.lock().unwrap();
         if let Some(ref db) = *db_lock {
-            if let Ok(existing_paths) = db.get_all_track_paths() {
+            if let Ok(existing_set) = known_track_paths(db) {
                 let initial_count = files.len();
-                println!("[Library] Checking against {} existing tracks in DB...", existing_paths.len());
-                
-                // Create normalized set for robust comparison
-                // Normalize by stripping unnecessary components (./) and using platform separators consistently
-                let existing_set: std::collections::HashSet<std::path::PathBuf> = existing_paths.iter()
-                    .map(|p| Path::new(p).components().as_path().to_path_buf())
-                    .collect();
-
-                // Log if we have potential matches that string comparison missed
-                if !existing_paths.is_empty() && !files.is_empty() {
-                    let total_normalized_matches = files.iter()
-                        .filter(|f| existing_set.contains(Path::new(f).components().as_path()))
-                        .count();
-                    println!("[Library] Debug: Found {} normalized matches out of {} files.", total_normalized_matches, files.len());
-                }
+                println!("[Library] Checking against {} existing tracks in DB...", existing_set.len());
 
-                files.retain(|f| !existing_set.contains(Path::new(f).components().as_path()));
+                files.retain(|f| !existing_set.contains(&normalize_track_path(f)));
 
                 // Debug logging for retained files
                 if !files.is_empty() {
-                    println!("[Library] Retained {} files after checking {} existing paths.", files.len(), existing_paths.len());
+                    println!("[Library] Retained {} files after checking {} existing paths.", files.len(), existing_set.len());
                     if files.len() < 10 {
                         println!("[Library] Sample retained files: {:?}", files);
                     }
@@ -923,8 +2185,9 @@ async fn init_library(
                 if let Ok(missing_metadata_paths) = db.get_tracks_missing_metadata() {
                     if !missing_metadata_paths.is_empty() {
                          println!("[Library] Found {} tracks missing Romaji metadata. Forcing re-scan for these.", missing_metadata_paths.len());
+                         let root = folder_prefix(&path);
                          for missing_path in missing_metadata_paths {
-                             if missing_path.starts_with(&path) 
+                             if missing_path.starts_with(&root)
                                  && !files.contains(&missing_path) {
                                      // Verify file still exists on disk before adding
                                      if Path::new(&missing_path).exists() {
@@ -941,7 +2204,7 @@ async fn init_library(
                 let skipped = initial_count - files.len();
                 if skipped > 0 {
                     println!("[Library] Optimized Scan: Skipped {} existing files. Processing {} files (new + metadata updates).", skipped, files.len());
-                } else if !existing_paths.is_empty() {
+                } else if !existing_set.is_empty() {
                     println!("[Library] No files skipped. Re-inserting all found files ({}).", files.len());
                 }
             }
@@ -950,18 +2213,21 @@ async fn init_library(
 
     let processed = AtomicUsize::new(0);
     let total = files.len();
+    let progress = ScanProgress::new(&app_handle);
+    progress.report("metadata", 0, total);
     
     // 2. Process metadata IN PARALLEL (skip cover extraction for speed)
     let tracks: Vec<TrackInfo> = files.par_iter()
         .filter_map(|file_path| {
+            // Remaining files are drained without being read
+            if cancelled() {
+                return None;
+            }
             let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
             if count.is_multiple_of(100) || count == total {
                 println!("[Library] Processed {}/{} files...", count, total);
-                let _ = app_handle.emit("library-scan-progress", serde_json::json!({
-                    "processed": count,
-                    "total": total
-                }));
             }
+            progress.report("metadata", count, total);
             
             // Extract metadata WITHOUT cover art (much faster)
             get_track_metadata_helper_fast(file_path).ok()
@@ -975,16 +2241,43 @@ async fn init_library(
     if let Some(ref db) = *db_guard {
         let total_tracks = tracks.len();
         let mut inserted_count = 0;
-        for mut track in tracks {
+        let mut inserted_paths = Vec::new();
+        progress.report("database", 0, total_tracks);
+        for (i, mut track) in tracks.into_iter().enumerate() {
+            if cancelled() {
+                break;
+            }
             // Normalize path for DB
-            track.path = track.path.replace("\\", "/");
+            track.path = normalize_track_path(&track.path);
+            // A file that was moved takes over its old row instead of becoming a new track
+            if let Err(e) = db.adopt_moved_track(&track) {
+                eprintln!("[Library] Move detection failed for {}: {}", track.path, e);
+            }
             // Insert without cover data initially (covers loaded lazily on demand)
             match db.insert_track(&track, None) {
-                Ok(_) => inserted_count += 1,
+                Ok(_) => {
+                    inserted_count += 1;
+                    inserted_paths.push(track.path);
+                }
                 Err(e) => eprintln!("[Library] Failed to insert track {}: {}", track.path, e),
             }
+            progress.report("database", i + 1, total_tracks);
         }
         println!("[Library] Successfully inserted {}/{} tracks.", inserted_count, total_tracks);
+        let event = if cancelled() {
+            println!("[Library] Scan cancelled");
+            "library-scan-cancelled"
+        } else {
+            if !inserted_paths.is_empty() {
+                let handle = app_handle.clone();
+                std::thread::spawn(move || cache_embedded_covers(&handle, &inserted_paths));
+            }
+            "library-scan-complete"
+        };
+        let _ = app_handle.emit(
+            event,
+            serde_json::json!({ "total": total, "inserted": inserted_count }),
+        );
         
         db.get_all_tracks().map_err(|e| e.to_string())
     } else {
@@ -992,6 +2285,197 @@ async fn init_library(
     }
 }
 
+/// File extensions library scans treat as audio
+#[tauri::command]
+fn get_scan_extensions() -> Vec<String> {
+    audio::formats::current()
+}
+
+/// Change which extensions library scans pick up, e.g. to add "dsf". An empty
+/// list restores the built-in set. Applies to scans started from now on;
+/// returns the list in effect.
+#[tauri::command]
+fn set_scan_extensions(
+    exts: Vec<String>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<String>, String> {
+    let patch = settings::SettingsPatch {
+        scan_extensions: Some(exts),
+        ..Default::default()
+    };
+    let settings = save_settings(&state, &app_handle, |s| s.apply(patch))?;
+    audio::formats::set(settings.scan_extensions);
+    Ok(audio::formats::current())
+}
+
+/// Paths already in the library, normalized the way they are stored so
+/// separator and drive-letter differences don't count as new files
+fn known_track_paths(db: &DatabaseManager) -> rusqlite::Result<std::collections::HashSet<String>> {
+    Ok(db
+        .get_all_track_paths()?
+        .iter()
+        .map(|p| normalize_track_path(p))
+        .collect())
+}
+
+/// Add individual files (e.g. dropped onto the window) without scanning their
+/// folders. Non-audio files and tracks already in the library are skipped.
+/// Returns the tracks that were added.
+#[tauri::command]
+async fn add_files(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<TrackInfo>, String> {
+    get_or_init_db(&state, &app_handle)?;
+
+    let known = {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        known_track_paths(db).map_err(|e| e.to_string())?
+    };
+    let mut files: Vec<String> = paths
+        .into_iter()
+        .filter(|p| Path::new(p).is_file() && is_audio_file(Path::new(p)))
+        .filter(|p| !known.contains(&normalize_track_path(p)))
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let tracks: Vec<TrackInfo> = files
+        .iter()
+        .filter_map(|path| match get_track_metadata_helper_fast(path) {
+            Ok(track) => Some(track),
+            Err(e) => {
+                eprintln!("[Library] Skipping {}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    let mut added = Vec::new();
+    {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        for mut track in tracks {
+            track.path = normalize_track_path(&track.path);
+            if let Err(e) = db.adopt_moved_track(&track) {
+                eprintln!("[Library] Move detection failed for {}: {}", track.path, e);
+            }
+            match db.insert_track(&track, None) {
+                Ok(_) => added.push(track),
+                Err(e) => eprintln!("[Library] Failed to insert track {}: {}", track.path, e),
+            }
+        }
+    }
+
+    if !added.is_empty() {
+        println!("[Library] Added {} files", added.len());
+        let paths: Vec<String> = added.iter().map(|t| t.path.clone()).collect();
+        let handle = app_handle.clone();
+        std::thread::spawn(move || cache_embedded_covers(&handle, &paths));
+        let _ = app_handle.emit(
+            "library-changed",
+            serde_json::json!({ "added": added.len(), "removed": 0 }),
+        );
+    }
+    Ok(added)
+}
+
+/// Second pass after a scan: save the embedded art of newly inserted tracks as
+/// their album cover, for albums that don't have one yet. Emits `cover-cached`
+/// for each album covered. Blocking, runs on its own thread.
+fn cache_embedded_covers(app_handle: &AppHandle, paths: &[String]) {
+    let state = app_handle.state::<AppState>();
+    let candidates = {
+        let db_lock = state.db.lock().unwrap();
+        match db_lock.as_ref().map(|db| db.albums_missing_cover(paths)) {
+            Some(Ok(candidates)) => candidates,
+            Some(Err(e)) => {
+                eprintln!("[Library] Failed to list albums without covers: {}", e);
+                return;
+            }
+            None => return,
+        }
+    };
+
+    let mut covered = std::collections::HashSet::new();
+    for (album, artist, path) in candidates {
+        if covered.contains(&(album.clone(), artist.clone())) {
+            continue;
+        }
+        // Read tags without holding the DB lock
+        let Ok((_, Some(data))) = get_track_metadata_helper(&path) else {
+            continue;
+        };
+
+        let db_lock = state.db.lock().unwrap();
+        let Some(db) = db_lock.as_ref() else {
+            return;
+        };
+        let Some(filename) = db.save_cover_data(&data) else {
+            eprintln!("[Library] Failed to save cover for {}", path);
+            continue;
+        };
+        if let Err(e) = db.update_album_cover(&album, &artist, &filename) {
+            eprintln!("[Library] Failed to set cover for {}: {}", album, e);
+            continue;
+        }
+        drop(db_lock);
+
+        let _ = app_handle.emit(
+            "cover-cached",
+            serde_json::json!({ "album": album, "artist": artist, "cover": filename, "path": path }),
+        );
+        covered.insert((album, artist));
+    }
+    println!("[Library] Cached {} album covers from embedded art", covered.len());
+}
+
+/// Stop a running `init_library` scan. It keeps the tracks saved so far and
+/// returns early with the library as it stands.
+#[tauri::command]
+fn cancel_library_scan(state: State<AppState>) {
+    state
+        .library_scan_cancelled
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Decode a track into cached min/max peaks for a waveform seek bar
+#[tauri::command]
+async fn generate_waveform(
+    path: String,
+    app_handle: AppHandle,
+) -> Result<audio::waveform::Waveform, String> {
+    let cache_dir = app_handle.path().app_cache_dir().map_err(|e| e.to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        audio::waveform::waveform_cached(&cache_dir, Path::new(&path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Keep a library folder in sync with the filesystem, now and on future launches
+#[tauri::command]
+fn watch_folder(path: String, state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    state.library_watcher.watch(&app_handle, Path::new(&path))?;
+    library_watcher::save_watched_folders(&state, &app_handle)
+}
+
+#[tauri::command]
+fn unwatch_folder(path: String, state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    state.library_watcher.unwatch(Path::new(&path))?;
+    library_watcher::save_watched_folders(&state, &app_handle)
+}
+
+#[tauri::command]
+fn get_watched_folders(state: State<AppState>) -> Vec<String> {
+    state.library_watcher.watched_folders()
+}
+
 #[tauri::command]
 fn get_library_tracks(
     state: State<AppState>,
@@ -1019,21 +2503,40 @@ fn get_covers_dir(state: State<AppState>, app_handle: AppHandle) -> Result<Strin
     }
 }
 
+/// Whether library scans and the folder watcher pick up `path`, going by the
+/// configured extensions
+fn is_audio_file(path: &Path) -> bool {
+    audio::formats::is_audio_path(path)
+}
+
 // Helper functions (extracted from previous commands)
 fn scan_music_folder_helper(path: &Path) -> Vec<String> {
-    let audio_extensions = ["mp3", "flac", "wav", "ogg", "m4a", "aac", "opus"];
+    scan_music_folder_until(path, &|| false)
+}
+
+/// Like `scan_music_folder_helper`, but stops walking once `cancelled` returns
+/// true and returns the files found so far
+fn scan_music_folder_until(path: &Path, cancelled: &dyn Fn() -> bool) -> Vec<String> {
     let mut files = Vec::new();
 
-    fn scan_recursive(dir: &Path, extensions: &[&str], files: &mut Vec<String>) {
+    fn scan_recursive(
+        dir: &Path,
+        extensions: &[String],
+        files: &mut Vec<String>,
+        cancelled: &dyn Fn() -> bool,
+    ) {
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
+                if cancelled() {
+                    return;
+                }
                 let path = entry.path();
                 if path.is_dir() {
                     // println!("Entering directory: {:?}", path);
-                    scan_recursive(&path, extensions, files);
+                    scan_recursive(&path, extensions, files, cancelled);
                 } else if let Some(ext) = path.extension() {
                     if let Some(ext_str) = ext.to_str() {
-                        if extensions.contains(&ext_str.to_lowercase().as_str()) {
+                        if extensions.contains(&ext_str.to_lowercase()) {
                             if let Some(path_str) = path.to_str() {
                                 files.push(path_str.to_string());
                             }
@@ -1046,7 +2549,7 @@ fn scan_music_folder_helper(path: &Path) -> Vec<String> {
         }
     }
 
-    scan_recursive(path, &audio_extensions, &mut files);
+    scan_recursive(path, &audio::formats::current(), &mut files, cancelled);
     files.sort();
     files
 }
@@ -1070,6 +2573,25 @@ fn find_external_cover(dir: &Path) -> Option<std::path::PathBuf> {
     None
 }
 
+/// Leading number of a track/disc tag. Some taggers write "3/10" into a single
+/// field, which lofty's numeric accessors reject.
+pub(crate) fn parse_tag_number(value: &str) -> Option<u32> {
+    value.split('/').next()?.trim().parse().ok()
+}
+
+/// Whether a tag marks its track as part of a compilation. Taggers write the
+/// flag as "1", but "true"/"yes" turn up too.
+fn is_compilation_tag(tag: &lofty::tag::Tag) -> bool {
+    tag.get_string(&lofty::tag::ItemKey::FlagCompilation)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Track or disc number from a tag, falling back to parsing the raw "n/total" text
+fn tag_number(tag: &lofty::tag::Tag, key: &lofty::tag::ItemKey, parsed: Option<u32>) -> Option<u32> {
+    parsed.or_else(|| tag.get_string(key).and_then(parse_tag_number))
+}
+
 fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8>>), String> {
     use lofty::prelude::*;
     use lofty::probe::Probe;
@@ -1083,10 +2605,26 @@ fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8
     // For now we error out if read fails, as before.
     let tagged_file = tagged_file_res.map_err(|e| format!("Failed to read metadata: {}", e))?;
 
+    let (replaygain_track_gain, replaygain_album_gain) = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+        .map(audio::replaygain::read_replaygain)
+        .unwrap_or((None, None));
+
+    let album_artist = tagged_file
+        .primary_tag()
+        .and_then(|tag| tag.get_string(&lofty::tag::ItemKey::AlbumArtist))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let is_compilation = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+        .is_some_and(is_compilation_tag);
+
     let properties = tagged_file.properties();
     let duration_secs = properties.duration().as_secs_f64();
 
-    let (title, artist, album, disc_number, track_number) =
+    let (title, artist, album, disc_number, track_number, genre, year) =
         if let Some(tag) = tagged_file.primary_tag() {
             (
                 tag.title().map(|s| s.to_string()).unwrap_or_else(|| {
@@ -1101,8 +2639,12 @@ fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8
                 tag.album()
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| "Unknown Album".to_string()),
-                tag.disk(),
-                tag.track(),
+                tag_number(tag, &lofty::tag::ItemKey::DiscNumber, tag.disk()),
+                tag_number(tag, &lofty::tag::ItemKey::TrackNumber, tag.track()),
+                tag.genre()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                tag.year(),
             )
         } else {
             (
@@ -1114,6 +2656,8 @@ fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8
                 "Unknown Album".to_string(),
                 None,
                 None,
+                None,
+                None,
             )
         };
 
@@ -1136,7 +2680,7 @@ fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8
 
     Ok((
         TrackInfo {
-            path: path.to_string_lossy().to_string().replace("\\", "/"),
+            path: normalize_track_path(&path.to_string_lossy()),
             title,
             artist,
             album,
@@ -1152,6 +2696,12 @@ fn get_track_metadata_helper(path_str: &str) -> Result<(TrackInfo, Option<Vec<u8
             album_romaji: None,
             album_en: None,
             playlist_track_id: None,
+            replaygain_track_gain,
+            replaygain_album_gain,
+            genre,
+            year,
+            album_artist,
+            is_compilation,
         },
         cover_data,
     ))
@@ -1169,10 +2719,26 @@ fn get_track_metadata_helper_fast(path_str: &str) -> Result<TrackInfo, String> {
 
     let tagged_file = tagged_file_res.map_err(|e| format!("{}", e))?;
 
+    let (replaygain_track_gain, replaygain_album_gain) = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+        .map(audio::replaygain::read_replaygain)
+        .unwrap_or((None, None));
+
+    let album_artist = tagged_file
+        .primary_tag()
+        .and_then(|tag| tag.get_string(&lofty::tag::ItemKey::AlbumArtist))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let is_compilation = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+        .is_some_and(is_compilation_tag);
+
     let properties = tagged_file.properties();
     let duration_secs = properties.duration().as_secs_f64();
 
-    let (title, artist, album, disc_number, track_number) =
+    let (title, artist, album, disc_number, track_number, genre, year) =
         if let Some(tag) = tagged_file.primary_tag() {
             (
                 tag.title().map(|s| s.to_string()).unwrap_or_else(|| {
@@ -1187,8 +2753,12 @@ fn get_track_metadata_helper_fast(path_str: &str) -> Result<TrackInfo, String> {
                 tag.album()
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| "Unknown Album".to_string()),
-                tag.disk(),
-                tag.track(),
+                tag_number(tag, &lofty::tag::ItemKey::DiscNumber, tag.disk()),
+                tag_number(tag, &lofty::tag::ItemKey::TrackNumber, tag.track()),
+                tag.genre()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                tag.year(),
             )
         } else {
             (
@@ -1200,6 +2770,8 @@ fn get_track_metadata_helper_fast(path_str: &str) -> Result<TrackInfo, String> {
                 "Unknown Album".to_string(),
                 None,
                 None,
+                None,
+                None,
             )
         };
 
@@ -1226,7 +2798,7 @@ fn get_track_metadata_helper_fast(path_str: &str) -> Result<TrackInfo, String> {
     }
 
     Ok(TrackInfo {
-        path: path.to_string_lossy().to_string().replace("\\", "/"),
+        path: normalize_track_path(&path.to_string_lossy()),
         title,
         artist,
         album,
@@ -1242,6 +2814,12 @@ fn get_track_metadata_helper_fast(path_str: &str) -> Result<TrackInfo, String> {
         album_romaji: None,
         album_en: None,
         playlist_track_id: None,
+        replaygain_track_gain,
+        replaygain_album_gain,
+        genre,
+        year,
+        album_artist,
+        is_compilation,
     })
 }
 
@@ -1303,23 +2881,45 @@ pub struct CachedLyricsResponse {
     pub synced_lyrics: Option<String>,
     pub plain_lyrics: Option<String>,
     pub instrumental: bool,
+    pub word_timed_lyrics: Option<Vec<lyrics_fetcher::WordTimedLine>>,
     pub is_fetching: bool,
     pub error: Option<String>,
     pub track_path: String,
 }
 
+/// Manual lyrics offset for a track, 0 when unset or the DB is unavailable
+pub(crate) fn lyrics_offset_ms(state: &AppState, track_path: &str) -> i64 {
+    state
+        .db
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|db| db.get_lyrics_offset(track_path).ok())
+        .unwrap_or(0)
+}
+
 /// Get cached lyrics for the currently playing track
 /// Returns immediately with whatever is in the cache (may still be fetching)
 #[tauri::command]
 fn get_cached_lyrics(track_path: String, state: State<AppState>) -> CachedLyricsResponse {
+    let offset_ms = lyrics_offset_ms(&state, &track_path);
+
     if let Ok(guard) = state.lyrics_cache.lock() {
 
         // Only return if the cached lyrics are for the requested track
         if guard.track_path == track_path {
+            let mut word_timed_lyrics = guard.word_timed_lyrics.clone();
+            if let Some(ref mut lines) = word_timed_lyrics {
+                lyrics_fetcher::shift_word_timed(lines, offset_ms);
+            }
             return CachedLyricsResponse {
-                synced_lyrics: guard.synced_lyrics.clone(),
+                synced_lyrics: guard
+                    .synced_lyrics
+                    .as_deref()
+                    .map(|lrc| lyrics_fetcher::shift_lrc_timestamps(lrc, offset_ms)),
                 plain_lyrics: guard.plain_lyrics.clone(),
                 instrumental: guard.instrumental,
+                word_timed_lyrics,
                 is_fetching: guard.is_fetching,
                 error: guard.error.clone(),
                 track_path: guard.track_path.clone(),
@@ -1334,21 +2934,108 @@ fn get_cached_lyrics(track_path: String, state: State<AppState>) -> CachedLyrics
         synced_lyrics: None,
         plain_lyrics: None,
         instrumental: false,
+        word_timed_lyrics: None,
         is_fetching: false,
         error: Some("No lyrics cached for this track".to_string()),
         track_path,
     }
 }
 
+/// Persist a per-track lyrics offset; negative values show lyrics earlier
+#[tauri::command]
+fn set_lyrics_offset(
+    track_path: String,
+    offset_ms: i64,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    get_or_init_db(&state, &app_handle)?;
+    if let Some(db) = state.db.lock().unwrap().as_ref() {
+        db.set_lyrics_offset(&track_path, offset_ms).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+fn get_lyrics_offset(
+    track_path: String,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<i64, String> {
+    get_or_init_db(&state, &app_handle)?;
+    if let Some(db) = state.db.lock().unwrap().as_ref() {
+        db.get_lyrics_offset(&track_path).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
 #[tauri::command]
 fn convert_lyrics_to_romaji(texts: Vec<String>) -> Vec<Option<String>> {
-    texts.iter().map(|text| {
-        if lyrics_transliteration::has_japanese(text) {
-            Some(lyrics_transliteration::to_romaji(text))
-        } else {
-            None
+    lyrics_transliteration::transliterate_lines(&texts)
+}
+
+/// Language code ("ja", "ko", "zh") of the lyrics' script, for labelling the romanization
+#[tauri::command]
+fn detect_lyrics_language(content: String) -> Option<String> {
+    lyrics_transliteration::detect_lyrics_language(&content).map(|script| script.code().to_string())
+}
+
+/// Lyrics for a track. A local LRC file always wins; otherwise lyrics stored in
+/// the database are used, and only then are the lyrics providers queried (with and
+/// without the duration constraint). Successful API results are written back to the database.
+pub(crate) fn load_lyrics<F: Fn(&str)>(
+    app_handle: &AppHandle,
+    track_path: &str,
+    artist: &str,
+    title: &str,
+    duration: u32,
+    on_progress: F,
+) -> Result<lyrics_fetcher::LyricsResponse, String> {
+    if let Some(local) = lyrics_fetcher::find_local_lrc(track_path) {
+        on_progress("Using local LRC file");
+        return Ok(local);
+    }
+
+    let state = app_handle.state::<AppState>();
+    let stored = {
+        let db_guard = state.db.lock().unwrap();
+        db_guard
+            .as_ref()
+            .and_then(|db| db.get_stored_lyrics(track_path).ok().flatten())
+    };
+    if let Some(stored) = stored {
+        on_progress("Using cached lyrics");
+        return Ok(lyrics_fetcher::with_word_timing(lyrics_fetcher::LyricsResponse {
+            id: None,
+            track_name: Some(title.to_string()),
+            artist_name: Some(artist.to_string()),
+            album_name: None,
+            duration: Some(duration as f64),
+            instrumental: Some(stored.instrumental),
+            plain_lyrics: stored.plain_lyrics,
+            synced_lyrics: stored.synced_lyrics,
+            word_timed_lyrics: None,
+            source: None,
+        }));
+    }
+
+    let lyrics = lyrics_fetcher::fetch_lyrics(artist, title, duration, &on_progress)
+        .or_else(|_| lyrics_fetcher::fetch_lyrics_fallback(artist, title, &on_progress))?;
+
+    if let Some(ref db) = *state.db.lock().unwrap() {
+        if let Err(e) = db.save_lyrics(
+            track_path,
+            lyrics.synced_lyrics.as_deref(),
+            lyrics.plain_lyrics.as_deref(),
+            lyrics.instrumental.unwrap_or(false),
+        ) {
+            eprintln!("[Lyrics] Failed to cache lyrics for {}: {}", track_path, e);
         }
-    }).collect()
+    }
+
+    Ok(lyrics)
 }
 
 #[tauri::command]
@@ -1368,36 +3055,40 @@ async fn get_lyrics(
             let _ = app_h1.emit("lyrics-loading-status", msg);
         };
 
-        // First check for local LRC file (instant!)
-        // Check for local LRC file manually
-        if let Some(local) = lyrics_fetcher::find_local_lrc(&audio_path) {
-            cb1("Using local LRC file");
-            return Ok(local);
-        }
-
-        let app_h2 = app_handle_thread.clone();
-        let cb2 = move |msg: &str| {
-            let _ = app_h2.emit("lyrics-loading-status", msg);
-        };
-
-        let app_h3 = app_handle_thread.clone();
-        let cb3 = move |msg: &str| {
-            let _ = app_h3.emit("lyrics-loading-status", msg);
-        };
-
-        // Then try API with duration
-        match lyrics_fetcher::fetch_lyrics(&artist, &track, duration, cb2) {
-            Ok(lyrics) => Ok(lyrics),
-            Err(_) => {
-                // Fallback: search without duration constraint
-                lyrics_fetcher::fetch_lyrics_fallback(&artist, &track, cb3)
-            }
+        let state = app_handle_thread.state::<AppState>();
+        let result = load_lyrics(&app_handle_thread, &audio_path, &artist, &track, duration, cb1);
+        // Settle the shared cache too, in case this fetch beats the prefetch
+        if let Ok(mut guard) = state.lyrics_cache.lock() {
+            match result {
+                Ok(ref lyrics) => guard.complete(&audio_path, lyrics.clone()),
+                Err(ref e) => guard.fail(&audio_path, e.clone()),
+            };
         }
+        let mut lyrics = result?;
+        let offset_ms = lyrics_offset_ms(&state, &audio_path);
+        lyrics_fetcher::apply_offset(&mut lyrics, offset_ms);
+        Ok(lyrics)
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// Drop all persisted lyrics so they are fetched again on next play
+#[tauri::command]
+fn clear_lyrics_cache(state: State<AppState>, app_handle: AppHandle) -> Result<usize, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let removed = if let Some(db) = state.db.lock().unwrap().as_ref() {
+        db.clear_lyrics_cache().map_err(|e| e.to_string())?
+    } else {
+        return Err("Database not initialized".to_string());
+    };
+
+    if let Ok(mut lyrics_guard) = state.lyrics_cache.lock() {
+        *lyrics_guard = CachedLyrics::default();
+    }
+    Ok(removed)
+}
+
 #[tauri::command]
 fn remove_folder(
     path: String,
@@ -1408,7 +3099,84 @@ fn remove_folder(
     if let Some(db) = state.db.lock().unwrap().as_ref() {
         db.remove_folder(&path).map_err(|e| e.to_string())
     } else {
-        Err("Database not initialized".to_string())
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Clusters of tracks that are probably the same song
+#[tauri::command]
+fn find_duplicate_tracks(
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<Vec<TrackInfo>>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    if let Some(db) = state.db.lock().unwrap().as_ref() {
+        db.find_duplicate_tracks().map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Remove a track from the library, and from disk when `delete_file` is set
+#[tauri::command]
+fn remove_track(
+    path: String,
+    delete_file: Option<bool>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    remove_tracks(vec![path], delete_file, state, app_handle).map(|_| ())
+}
+
+/// Remove several tracks from the library, optionally deleting the files.
+/// Stops playback when the current track is among them. Tracks whose file
+/// could not be deleted stay in the library and are reported in the error.
+#[tauri::command]
+fn remove_tracks(
+    paths: Vec<String>,
+    delete_file: Option<bool>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<usize, String> {
+    get_or_init_db(&state, &app_handle)?;
+
+    let mut failed = Vec::new();
+    let paths: Vec<String> = if delete_file.unwrap_or(false) {
+        paths
+            .into_iter()
+            .filter(|path| match std::fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+                Err(e) => {
+                    failed.push(format!("{}: {}", path, e));
+                    false
+                }
+            })
+            .collect()
+    } else {
+        paths
+    };
+
+    let playing = state
+        .player
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|player| player.get_status().track)
+        .map(|track| normalize_track_path(&track.path));
+    if playing.is_some_and(|current| paths.iter().any(|p| normalize_track_path(p) == current)) {
+        stop(state.clone(), app_handle.clone())?;
+    }
+
+    let removed = match state.db.lock().unwrap().as_ref() {
+        Some(db) => db.remove_tracks(&paths).map_err(|e| e.to_string())?,
+        None => return Err("Database not initialized".to_string()),
+    };
+
+    if failed.is_empty() {
+        Ok(removed)
+    } else {
+        Err(format!("Failed to delete {} file(s): {}", failed.len(), failed.join("; ")))
     }
 }
 
@@ -1464,6 +3232,16 @@ fn clear_all_data(state: State<AppState>, app_handle: AppHandle) -> Result<(), S
         
         println!("[clear_all_data] App data directory cleaned");
     }
+
+    // Back to the default settings, in effect as well as on disk
+    let defaults = settings::Settings::default();
+    state.discord.set_enabled(defaults.discord_enabled);
+    state.discord.set_buttons(defaults.discord_buttons.clone());
+    audio::formats::set(None);
+    if let Some(ref player) = *state.player.lock().unwrap() {
+        apply_player_settings(player, &defaults);
+    }
+    *state.settings.lock().unwrap() = defaults;
     
     println!("[clear_all_data] Complete! All data cleared successfully.");
     Ok(())
@@ -1492,17 +3270,50 @@ fn apply_lrc_file(
     std::fs::copy(lrc_source_path, &dest_path)
         .map_err(|e| format!("Failed to copy LRC file: {}", e))?;
 
-    // Invalidate/Update cache if current track
+    reload_local_lyrics(&state, &track_path.to_string_lossy());
+    Ok(())
+}
+
+/// Load a just-written `.lrc` sidecar into the cache if it belongs to the current track
+fn reload_local_lyrics(state: &AppState, track_path: &str) {
+    let Some(local) = lyrics_fetcher::find_local_lrc(track_path) else {
+        return;
+    };
     if let Ok(mut guard) = state.lyrics_cache.lock() {
-        if guard.track_path == track_path.to_string_lossy() {
-            // We can either clear it or try to reload immediately.
-            // Clearing it is safer, frontend will re-fetch.
-            guard.is_fetching = true;
-            // Ideally we should reload the content here but reading file again is easy enough for next fetch
-        }
+        guard.complete(track_path, local);
     }
+}
 
-    Ok(())
+/// Save lyrics permanently next to the track as a `.lrc` sidecar, backing up any
+/// existing one. With `embed`, they are also written into the file's lyrics tag.
+#[tauri::command]
+fn save_lyrics_to_file(
+    track_path: String,
+    synced: String,
+    embed: Option<bool>,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let audio_path = Path::new(&track_path);
+    if !audio_path.exists() {
+        return Err("Track file does not exist".to_string());
+    }
+    if synced.trim().is_empty() {
+        return Err("No lyrics to save".to_string());
+    }
+
+    let (lrc_path, backup) = lyrics_fetcher::write_lrc_sidecar(audio_path, &synced)
+        .map_err(|e| format!("Failed to write LRC file: {}", e))?;
+    if let Some(backup) = backup {
+        println!("[Lyrics] Backed up existing LRC to {:?}", backup);
+    }
+
+    if embed.unwrap_or(false) {
+        lyrics_fetcher::embed_lyrics(audio_path, &synced)?;
+    }
+
+    reload_local_lyrics(&state, &track_path);
+
+    Ok(lrc_path.to_string_lossy().to_string())
 }
 
 // ============================================================================
@@ -1571,7 +3382,7 @@ async fn add_magnet_link(
         let download_path =
             path.unwrap_or_else(|| manager.download_dir.to_string_lossy().to_string());
         manager
-            .add_torrent(Some(magnet), None, download_path, None)
+            .add_torrent(Some(magnet), None, download_path, None, false)
             .await
     } else {
         Err("Torrent backend not initialized".to_string())
@@ -1641,6 +3452,7 @@ async fn add_torrent_with_options(
     file_bytes: Option<Vec<u8>>,
     path: String,
     selected_files: Option<Vec<usize>>,
+    sequential: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<usize, String> {
     let manager = {
@@ -1649,7 +3461,7 @@ async fn add_torrent_with_options(
     };
     if let Some(manager) = manager {
         manager
-            .add_torrent(magnet, file_bytes, path, selected_files)
+            .add_torrent(magnet, file_bytes, path, selected_files, sequential.unwrap_or(false))
             .await
     } else {
         Err("Torrent backend not initialized".to_string())
@@ -1686,6 +3498,105 @@ async fn pause_torrent(id: usize, state: State<'_, AppState>) -> Result<(), Stri
     }
 }
 
+/// Limit torrent download/upload speed in KiB/s; `None` means unlimited
+#[tauri::command]
+async fn set_torrent_speed_limits(
+    download_kbps: Option<u32>,
+    upload_kbps: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = {
+        let guard = state.torrent_manager.lock().unwrap();
+        guard.clone()
+    };
+    if let Some(manager) = manager {
+        manager.set_speed_limits(download_kbps, upload_kbps).await
+    } else {
+        Err("Torrent backend not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_torrent_speed_limits(
+    state: State<'_, AppState>,
+) -> Result<torrent::SpeedLimits, String> {
+    let guard = state.torrent_manager.lock().unwrap();
+    guard
+        .as_ref()
+        .map(|manager| manager.get_speed_limits())
+        .ok_or_else(|| "Torrent backend not initialized".to_string())
+}
+
+/// Pause finished torrents once they reach `ratio`; 0 seeds forever
+#[tauri::command]
+async fn set_seed_ratio_limit(ratio: f64, state: State<'_, AppState>) -> Result<(), String> {
+    let manager = {
+        let guard = state.torrent_manager.lock().unwrap();
+        guard.clone()
+    };
+    if let Some(manager) = manager {
+        manager.set_seed_ratio_limit(Some(ratio)).await
+    } else {
+        Err("Torrent backend not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_seed_ratio_limit(state: State<'_, AppState>) -> Result<Option<f64>, String> {
+    let guard = state.torrent_manager.lock().unwrap();
+    guard
+        .as_ref()
+        .map(|manager| manager.get_seed_ratio_limit())
+        .ok_or_else(|| "Torrent backend not initialized".to_string())
+}
+
+/// Per-torrent seed ratio override; `None` falls back to the global limit
+#[tauri::command]
+async fn set_torrent_seed_ratio_limit(
+    id: usize,
+    ratio: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = {
+        let guard = state.torrent_manager.lock().unwrap();
+        guard.clone()
+    };
+    if let Some(manager) = manager {
+        manager.set_torrent_seed_ratio_limit(id, ratio).await
+    } else {
+        Err("Torrent backend not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn get_torrent_files(
+    id: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<torrent::TorrentFileProgress>, String> {
+    let guard = state.torrent_manager.lock().unwrap();
+    guard
+        .as_ref()
+        .ok_or_else(|| "Torrent backend not initialized".to_string())?
+        .get_files(id)
+}
+
+#[tauri::command]
+async fn update_selected_files(
+    id: usize,
+    selected: Vec<usize>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = {
+        let guard = state.torrent_manager.lock().unwrap();
+        guard.clone()
+    };
+    if let Some(manager) = manager {
+        manager.update_selected_files(id, selected).await
+    } else {
+        Err("Torrent backend not initialized".to_string())
+    }
+}
+
 #[tauri::command]
 async fn resume_torrent(id: usize, state: State<'_, AppState>) -> Result<(), String> {
     let manager = {
@@ -1724,110 +3635,387 @@ async fn get_torrent_details(
 fn get_local_ip() -> Option<String> {
     use std::net::UdpSocket;
 
-    // Prefer route-selected LAN IPv4.
-    if let Ok(socket) = UdpSocket::bind("0.0.0.0:0") {
-        if socket.connect("8.8.8.8:80").is_ok() {
-            if let Ok(addr) = socket.local_addr() {
-                let ip = addr.ip();
-                let ip_str = ip.to_string();
-                if ip.is_ipv4() && !ip.is_loopback() && !ip_str.starts_with("169.254.") {
-                    return Some(ip_str);
-                }
-            }
-        }
-    }
+    // Prefer route-selected LAN IPv4.
+    if let Ok(socket) = UdpSocket::bind("0.0.0.0:0") {
+        if socket.connect("8.8.8.8:80").is_ok() {
+            if let Ok(addr) = socket.local_addr() {
+                let ip = addr.ip();
+                let ip_str = ip.to_string();
+                if ip.is_ipv4() && !ip.is_loopback() && !ip_str.starts_with("169.254.") {
+                    return Some(ip_str);
+                }
+            }
+        }
+    }
+
+    // Fallback: enumerate interfaces for a usable LAN IPv4.
+    if let Ok(ifaces) = if_addrs::get_if_addrs() {
+        for iface in &ifaces {
+            let ip = iface.addr.ip();
+            let ip_str = ip.to_string();
+            if ip.is_ipv4() && !ip.is_loopback() && !ip_str.starts_with("169.254.") {
+                return Some(ip_str);
+            }
+        }
+    }
+
+    None
+}
+
+#[tauri::command]
+async fn start_mobile_server(
+    state: State<'_, AppState>,
+    _app_handle: AppHandle,
+    use_tls: Option<bool>,
+) -> Result<(), String> {
+    // Check if already running
+    {
+        let running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
+        if *running {
+            return Ok(());
+        }
+    }
+    
+    // Mark as running
+    {
+        let mut running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
+        *running = true;
+    }
+
+    // Create shutdown channel
+    let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+    
+    // Store shutdown sender
+    {
+        let mut tx_guard = state.server_shutdown_tx.lock().map_err(|_| "Failed to lock server_shutdown_tx".to_string())?;
+        *tx_guard = Some(shutdown_tx);
+    }
+    
+    // Start server in background with the real app handle
+    let mut config = server::ServerConfig {
+        port: configured_server_port(&state),
+        ..Default::default()
+    };
+    if let Some(use_tls) = use_tls {
+        config.use_tls = use_tls;
+    }
+    let port = config.port;
+    let server_running = state.server_running.clone();
+    let app_handle_clone = _app_handle.clone();
+    
+    tokio::spawn(async move {
+        if let Err(e) = server::start_server(app_handle_clone, config, shutdown_rx).await {
+            eprintln!("[Server] Failed to start: {}", e);
+            if let Ok(mut running) = server_running.lock() {
+                *running = false;
+            }
+        }
+    });
+    
+    // The server logs the port it actually binds; this one may be taken
+    println!("[Server] Mobile companion server starting (preferred port {})", port);
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_mobile_server(state: State<'_, AppState>) -> Result<(), String> {
+    // Send shutdown signal
+    {
+        let tx_guard = state.server_shutdown_tx.lock().map_err(|_| "Failed to lock server_shutdown_tx".to_string())?;
+        if let Some(ref tx) = *tx_guard {
+            let _ = tx.send(());
+        }
+    }
+    
+    // Mark as not running
+    let mut running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
+    *running = false;
+    *state.server_port.lock().unwrap() = None;
+    println!("[Server] Mobile companion server stopped");
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct ServerStatus {
+    running: bool,
+    /// Bound port; `None` until the server is listening
+    port: Option<u16>,
+}
+
+#[tauri::command]
+async fn get_server_status(state: State<'_, AppState>) -> Result<ServerStatus, String> {
+    let running = *state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
+    let port = if running { *state.server_port.lock().unwrap() } else { None };
+    Ok(ServerStatus { running, port })
+}
+
+/// Port the companion server should try first (the saved choice, else the default)
+fn configured_server_port(state: &AppState) -> u16 {
+    state
+        .settings
+        .lock()
+        .unwrap()
+        .server_port
+        .unwrap_or_else(|| server::ServerConfig::default().port)
+}
+
+/// Save the preferred companion server port; applies the next time the server starts
+#[tauri::command]
+fn set_server_port(port: u16, state: State<AppState>, app_handle: AppHandle) -> Result<(), String> {
+    let patch = settings::SettingsPatch {
+        server_port: Some(port),
+        ..Default::default()
+    };
+    save_settings(&state, &app_handle, |s| s.apply(patch)).map(|_| ())
+}
+
+/// Token mobile clients must present to the companion server (e.g. via QR code)
+#[tauri::command]
+async fn get_server_token(app_handle: AppHandle) -> Result<String, String> {
+    server::auth::load_or_create_token(&app_handle)
+}
+
+fn set_p2p_status(app_handle: &AppHandle, status: p2p::P2PStatus) {
+    let state = app_handle.state::<AppState>();
+    *state.p2p_status.lock().unwrap() = status.clone();
+    let _ = app_handle.emit("p2p-status-changed", status);
+}
+
+/// Start the P2P manager and record the outcome. The caller marks the status
+/// `Initializing` first, so a retry can't start a second attempt alongside.
+async fn init_p2p(app_handle: AppHandle) -> p2p::P2PStatus {
+    let device_name = p2p::get_device_name();
+    let status = match P2PManager::new(device_name).await {
+        Ok(mut p2p) => {
+            println!("[P2P] Manager initialized successfully");
+            if let Some(events) = p2p.take_event_receiver() {
+                tauri::async_runtime::spawn(forward_p2p_events(app_handle.clone(), events));
+            }
+            let state = app_handle.state::<AppState>();
+            p2p.set_paired_peers(load_paired_peers(&state)).await;
+            let handle = app_handle.clone();
+            p2p.set_library_check(Arc::new(move |path: &str| {
+                let state = handle.state::<AppState>();
+                if get_or_init_db(&state, &handle).is_err() {
+                    return false;
+                }
+                let db_guard = state.db.lock().unwrap();
+                db_guard.as_ref().is_some_and(|db| matches!(db.get_track(path), Ok(Some(_))))
+            })).await;
+            let mut p2p_guard = state.p2p_manager.write().await;
+            *p2p_guard = Some(p2p);
+            p2p::P2PStatus::Ready
+        }
+        Err(e) => {
+            eprintln!("[P2P] Failed to initialize: {}", e);
+            p2p::P2PStatus::Failed(e.to_string())
+        }
+    };
+    set_p2p_status(&app_handle, status.clone());
+    status
+}
+
+/// Re-emit P2P manager events to the frontend until the manager goes away.
+/// Peer events are `p2p-peer-*`; `get_p2p_peers` still gives a full snapshot
+/// for views opened after discovery happened.
+async fn forward_p2p_events(
+    app_handle: AppHandle,
+    mut events: tokio::sync::mpsc::Receiver<p2p::P2PEvent>,
+) {
+    while let Some(event) = events.recv().await {
+        emit_p2p_event(&app_handle, event);
+    }
+}
+
+fn emit_p2p_event(app_handle: &AppHandle, event: p2p::P2PEvent) {
+    if let Some((name, payload)) = event.ui_event() {
+        let _ = app_handle.emit(name, payload);
+    }
+}
+
+/// Whether P2P is available; explains an empty `get_p2p_peers`
+#[tauri::command]
+fn get_p2p_status(state: State<AppState>) -> p2p::P2PStatus {
+    state.p2p_status.lock().unwrap().clone()
+}
+
+/// Try to start P2P again after it failed, e.g. once a port or permission
+/// problem is fixed. Returns the new status.
+#[tauri::command]
+async fn retry_p2p_init(app_handle: AppHandle) -> Result<p2p::P2PStatus, String> {
+    {
+        let state = app_handle.state::<AppState>();
+        let mut status = state.p2p_status.lock().unwrap();
+        match *status {
+            p2p::P2PStatus::Ready => return Ok(p2p::P2PStatus::Ready),
+            p2p::P2PStatus::Initializing => {
+                return Err("P2P is already initializing".to_string())
+            }
+            _ => *status = p2p::P2PStatus::Initializing,
+        }
+    }
+    let _ = app_handle.emit("p2p-status-changed", p2p::P2PStatus::Initializing);
+    Ok(init_p2p(app_handle).await)
+}
+
+#[tauri::command]
+async fn get_p2p_peers(state: State<'_, AppState>) -> Result<Vec<p2p::discovery::DiscoveredPeer>, String> {
+    let p2p_guard = state.p2p_manager.read().await;
+    if let Some(ref p2p) = *p2p_guard {
+        Ok(p2p.get_peers().await)
+    } else {
+        Ok(vec![])
+    }
+}
+
+fn load_paired_peers(state: &AppState) -> Vec<libp2p::PeerId> {
+    let settings = state.settings.lock().unwrap();
+    settings.paired_peers.iter().filter_map(|id| id.parse().ok()).collect()
+}
+
+fn save_paired_peers(
+    state: &AppState,
+    app_handle: &AppHandle,
+    peers: &[libp2p::PeerId],
+) -> Result<(), String> {
+    let ids: Vec<String> = peers.iter().map(|p| p.to_base58()).collect();
+    save_settings(state, app_handle, |s| {
+        s.paired_peers = ids;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Allow a discovered peer to stream tracks from this device
+#[tauri::command]
+async fn pair_peer(
+    peer_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let peer: libp2p::PeerId = peer_id.parse().map_err(|_| format!("Invalid peer ID: {}", peer_id))?;
+    let p2p_guard = state.p2p_manager.read().await;
+    let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+
+    p2p.pair_peer(peer).await;
+    save_paired_peers(&state, &app_handle, &p2p.paired_peers().await)
+}
+
+#[tauri::command]
+async fn unpair_peer(
+    peer_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<bool, String> {
+    let peer: libp2p::PeerId = peer_id.parse().map_err(|_| format!("Invalid peer ID: {}", peer_id))?;
+    let p2p_guard = state.p2p_manager.read().await;
+    let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+
+    let removed = p2p.unpair_peer(&peer).await;
+    save_paired_peers(&state, &app_handle, &p2p.paired_peers().await)?;
+    Ok(removed)
+}
 
-    // Fallback: enumerate interfaces for a usable LAN IPv4.
-    if let Ok(ifaces) = if_addrs::get_if_addrs() {
-        for iface in &ifaces {
-            let ip = iface.addr.ip();
-            let ip_str = ip.to_string();
-            if ip.is_ipv4() && !ip.is_loopback() && !ip_str.starts_with("169.254.") {
-                return Some(ip_str);
-            }
-        }
+#[tauri::command]
+async fn get_paired_peers(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let p2p_guard = state.p2p_manager.read().await;
+    if let Some(ref p2p) = *p2p_guard {
+        Ok(p2p.paired_peers().await.iter().map(|p| p.to_base58()).collect())
+    } else {
+        Ok(vec![])
     }
+}
 
-    None
+/// Dial a peer by `ip:port` when mDNS can't see it (e.g. across VLANs)
+#[tauri::command]
+async fn connect_p2p_peer(address: String, state: State<'_, AppState>) -> Result<(), String> {
+    let multiaddr = p2p::discovery::parse_peer_address(&address)?;
+    let p2p_guard = state.p2p_manager.read().await;
+    let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+    p2p.connect_peer(multiaddr)
+        .await
+        .map_err(|e| format!("Failed to dial peer: {}", e))
 }
 
+/// Offer the given track to a peer, e.g. to hand playback over to it
 #[tauri::command]
-async fn start_mobile_server(
+async fn p2p_stream_to_peer(
+    peer_id: String,
+    track_path: String,
     state: State<'_, AppState>,
-    _app_handle: AppHandle,
 ) -> Result<(), String> {
-    // Check if already running
-    {
-        let running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
-        if *running {
-            return Ok(());
-        }
-    }
-    
-    // Mark as running
-    {
-        let mut running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
-        *running = true;
-    }
+    let peer: libp2p::PeerId = peer_id.parse().map_err(|_| format!("Invalid peer ID: {}", peer_id))?;
+    let p2p_guard = state.p2p_manager.read().await;
+    let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+    p2p.stream_to_peer(peer, std::path::PathBuf::from(track_path), 0)
+        .await
+        .map_err(|e| format!("Failed to start stream: {}", e))
+}
 
-    // Create shutdown channel
-    let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
-    
-    // Store shutdown sender
-    {
-        let mut tx_guard = state.server_shutdown_tx.lock().map_err(|_| "Failed to lock server_shutdown_tx".to_string())?;
-        *tx_guard = Some(shutdown_tx);
-    }
-    
-    // Start server in background with the real app handle
-    let config = server::ServerConfig::default();
-    let port = config.port;
-    let server_running = state.server_running.clone();
-    let app_handle_clone = _app_handle.clone();
-    
-    tokio::spawn(async move {
-        if let Err(e) = server::start_server(app_handle_clone, config, shutdown_rx).await {
-            eprintln!("[Server] Failed to start: {}", e);
-            if let Ok(mut running) = server_running.lock() {
-                *running = false;
-            }
-        }
-    });
-    
-    println!("[Server] Mobile companion server started on port {}", port);
-    Ok(())
+/// Ask a peer to stream one of its tracks to us; progress arrives as
+/// `p2p-stream-ready` / `p2p-stream-ended` events
+#[tauri::command]
+async fn p2p_request_stream(
+    peer_id: String,
+    track_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let peer: libp2p::PeerId = peer_id.parse().map_err(|_| format!("Invalid peer ID: {}", peer_id))?;
+    let p2p_guard = state.p2p_manager.read().await;
+    let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+    p2p.request_stream(peer, track_path, 0)
+        .await
+        .map_err(|e| format!("Failed to request stream: {}", e))
 }
 
+/// Copy a track from a paired peer into `dest_dir`, along with its lyrics and
+/// cover, and add it to the library. Returns the saved paths, audio file first.
 #[tauri::command]
-async fn stop_mobile_server(state: State<'_, AppState>) -> Result<(), String> {
-    // Send shutdown signal
+async fn p2p_request_file_bundle(
+    peer_id: String,
+    track_path: String,
+    dest_dir: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<String>, String> {
+    let peer: libp2p::PeerId = peer_id.parse().map_err(|_| format!("Invalid peer ID: {}", peer_id))?;
+    let reply = {
+        let p2p_guard = state.p2p_manager.read().await;
+        let p2p = p2p_guard.as_ref().ok_or("P2P not initialized")?;
+        p2p.request_file_bundle(peer, track_path, std::path::PathBuf::from(dest_dir))
+            .await
+            .map_err(|e| format!("Failed to request files: {}", e))?
+    };
+    let saved = reply
+        .await
+        .map_err(|_| "Transfer was cancelled".to_string())??;
+
+    let audio_path = saved[0].to_string_lossy().to_string();
+    let (mut track, cover) = get_track_metadata_helper(&audio_path)?;
+    track.path = normalize_track_path(&track.path);
+    get_or_init_db(&state, &app_handle)?;
     {
-        let tx_guard = state.server_shutdown_tx.lock().map_err(|_| "Failed to lock server_shutdown_tx".to_string())?;
-        if let Some(ref tx) = *tx_guard {
-            let _ = tx.send(());
-        }
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or("Database not initialized")?;
+        db.insert_track(&track, cover.as_deref())
+            .map_err(|e| e.to_string())?;
     }
-    
-    // Mark as not running
-    let mut running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
-    *running = false;
-    println!("[Server] Mobile companion server stopped");
-    Ok(())
-}
+    println!("[P2P] Added {} from {} to the library", track.path, peer);
+    let _ = app_handle.emit("library-changed", serde_json::json!({ "added": 1, "removed": 0 }));
 
-#[tauri::command]
-async fn get_server_status(state: State<'_, AppState>) -> Result<bool, String> {
-    let running = state.server_running.lock().map_err(|_| "Failed to lock server_running".to_string())?;
-    Ok(*running)
+    Ok(saved.iter().map(|p| p.to_string_lossy().to_string()).collect())
 }
 
+/// Latest progress of a track being sent to a peer
 #[tauri::command]
-async fn get_p2p_peers(state: State<'_, AppState>) -> Result<Vec<p2p::discovery::DiscoveredPeer>, String> {
+async fn get_p2p_transfer_progress(
+    state: State<'_, AppState>,
+) -> Result<Option<p2p::TransferProgress>, String> {
     let p2p_guard = state.p2p_manager.read().await;
     if let Some(ref p2p) = *p2p_guard {
-        Ok(p2p.get_peers().await)
+        Ok(p2p.transfer_progress().await)
     } else {
-        Ok(vec![])
+        Ok(None)
     }
 }
 
@@ -1866,6 +4054,21 @@ async fn stop_mobile_playback(state: State<'_, AppState>, app_handle: AppHandle)
             log::info!("▶️ PC playback unmuted and resumed");
         }
     }
+
+    // The phone's listening session ends here, as with its own StopMobilePlayback
+    let event = state
+        .stats_tracker
+        .lock()
+        .ok()
+        .and_then(|mut t| t.stop_mobile(stats::current_time_ms()));
+    if let Some(event) = event {
+        match stats::record_stats_event(&state, event) {
+            Ok(()) => {
+                let _ = app_handle.emit("stats-updated", ());
+            }
+            Err(e) => eprintln!("[Stats] Mobile session not recorded: {}", e),
+        }
+    }
     
     // Emit output-changed event for frontend
     let _ = app_handle.emit("output-changed", serde_json::json!({
@@ -1879,7 +4082,9 @@ async fn stop_mobile_playback(state: State<'_, AppState>, app_handle: AppHandle)
 // Tauri Commands - Playlist Management
 // ============================================================================
 
+// Each argument is a field of the frontend's invoke payload
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn create_playlist(
     name: String,
     songs: Option<Vec<String>>,
@@ -1968,17 +4173,23 @@ async fn add_track_to_playlist(
     }
 }
 
+/// `playlist_track_id` is the row id carried on each playlist track, so the same
+/// song added twice can be removed individually. `playlist_id` is optional.
 #[tauri::command]
 async fn remove_track_from_playlist(
-    playlist_id: String,
     playlist_track_id: i64,
+    playlist_id: Option<String>,
     state: State<'_, AppState>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
     get_or_init_db(&state, &app_handle)?;
     let db_guard = state.db.lock().unwrap();
     if let Some(ref db) = *db_guard {
-        db.remove_track_from_playlist(&playlist_id, playlist_track_id).map_err(|e| e.to_string())
+        match playlist_id {
+            Some(playlist_id) => db.remove_track_from_playlist(&playlist_id, playlist_track_id),
+            None => db.remove_playlist_track(playlist_track_id),
+        }
+        .map_err(|e| e.to_string())
     } else {
         Err("Database not initialized".to_string())
     }
@@ -2015,6 +4226,188 @@ async fn get_playlist_tracks(
     }
 }
 
+// ============================================================================
+// Tauri Commands - Favorites
+// ============================================================================
+
+/// Flip a track's favorite flag, returning the new value. Mobile clients are notified.
+#[tauri::command]
+async fn toggle_favorite(
+    path: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<bool, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let is_favorite = {
+        let db_guard = state.db.lock().unwrap();
+        let db = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+        db.toggle_favorite(&path).map_err(|e| e.to_string())?
+    };
+
+    if let Some(tx) = state.ws_broadcast_tx.lock().unwrap().as_ref() {
+        let _ = tx.send(server::ServerEvent::FavoriteChanged {
+            path,
+            is_favorite,
+        });
+    }
+    Ok(is_favorite)
+}
+
+#[tauri::command]
+async fn get_favorites(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<TrackInfo>, String> {
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.get_favorites().map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+// ============================================================================
+// Tauri Commands - EQ Presets
+// ============================================================================
+
+#[tauri::command]
+async fn save_eq_preset(
+    name: String,
+    gains: Vec<f32>,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if audio::equalizer::builtin_preset(&name).is_some() {
+        return Err(format!("'{}' is a built-in preset and cannot be overwritten", name));
+    }
+    if gains.len() != 10 || gains.iter().any(|g| !g.is_finite()) {
+        return Err("A preset needs exactly 10 finite band gains".to_string());
+    }
+
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.save_eq_preset(&name, &gains).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Built-in presets first, followed by user presets sorted by name
+#[tauri::command]
+async fn get_eq_presets(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<(String, Vec<f32>)>, String> {
+    let mut presets: Vec<(String, Vec<f32>)> = audio::equalizer::BUILTIN_PRESETS
+        .iter()
+        .map(|(name, gains)| (name.to_string(), gains.to_vec()))
+        .collect();
+
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        presets.extend(db.get_eq_presets().map_err(|e| e.to_string())?);
+        Ok(presets)
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+#[tauri::command]
+async fn delete_eq_preset(
+    name: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if audio::equalizer::builtin_preset(&name).is_some() {
+        return Err(format!("'{}' is a built-in preset and cannot be deleted", name));
+    }
+
+    get_or_init_db(&state, &app_handle)?;
+    let db_guard = state.db.lock().unwrap();
+    if let Some(ref db) = *db_guard {
+        db.delete_eq_preset(&name).map_err(|e| e.to_string())
+    } else {
+        Err("Database not initialized".to_string())
+    }
+}
+
+/// Look up a preset (built-in or saved) and apply it through `set_eq_all`
+#[tauri::command]
+async fn apply_eq_preset(
+    name: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let gains = match audio::equalizer::builtin_preset(&name) {
+        Some(gains) => gains.to_vec(),
+        None => {
+            get_or_init_db(&state, &app_handle)?;
+            let db_guard = state.db.lock().unwrap();
+            let db = db_guard.as_ref().ok_or_else(|| "Database not initialized".to_string())?;
+            db.get_eq_presets()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|(preset, _)| *preset == name)
+                .map(|(_, gains)| gains)
+                .ok_or_else(|| format!("EQ preset '{}' not found", name))?
+        }
+    };
+
+    set_eq_all(gains, state)
+}
+
+/// Wind down as the app exits: record the listening time of the track in
+/// progress, clear the Discord activity, stop the player and signal the
+/// companion server to shut down
+fn shutdown(app_handle: &AppHandle) {
+    println!("[App] Shutting down");
+    let state = app_handle.state::<AppState>();
+
+    let status = state
+        .player
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|p| p.get_status()));
+    let events = match state.stats_tracker.lock() {
+        Ok(mut tracker) => {
+            let now_ms = stats::current_time_ms();
+            // Count the time since the last poll before closing the session; a
+            // track change since then finishes the previous track's session too
+            let mut events = Vec::new();
+            if let Some(status) = status {
+                let is_playing = status.state == audio::PlayerState::Playing;
+                let song_id = status.track.map(|t| t.path);
+                events.extend(tracker.update_desktop(song_id, status.position_secs, is_playing, now_ms));
+            }
+            events.extend(tracker.finalize_all(now_ms));
+            events
+        }
+        Err(_) => Vec::new(),
+    };
+    for event in events {
+        if let Err(e) = stats::record_stats_event(&state, event) {
+            eprintln!("[Stats] Failed to record the final session: {}", e);
+        }
+    }
+
+    let _ = state.discord.clear_activity();
+    if let Ok(guard) = state.player.lock() {
+        if let Some(ref player) = *guard {
+            let _ = player.stop();
+        }
+    }
+    if let Some(tx) = state.server_shutdown_tx.lock().ok().and_then(|mut tx| tx.take()) {
+        let _ = tx.send(());
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     #[cfg(target_os = "windows")]
@@ -2028,8 +4421,12 @@ pub fn run() {
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+                let mut now_playing: Option<String> = None;
+                let mut ticks: u32 = 0;
+                let mut was_playing = false;
                 loop {
                     interval.tick().await;
+                    ticks = ticks.wrapping_add(1);
                     let state = app_handle.state::<AppState>();
                     let (song_id, position_secs, is_playing) = {
                         if let Ok(player_guard) = state.player.lock() {
@@ -2047,6 +4444,38 @@ pub fn run() {
                         }
                     };
 
+                    // Last.fm "now playing" when a new track starts
+                    if is_playing && song_id != now_playing {
+                        now_playing = song_id.clone();
+                        if let Some(path) = song_id.clone() {
+                            let handle = app_handle.clone();
+                            tauri::async_runtime::spawn_blocking(move || {
+                                let state = handle.state::<AppState>();
+                                if let Err(e) = scrobbler::update_now_playing(&state, &path) {
+                                    eprintln!("[Lastfm] Now playing update failed: {}", e);
+                                }
+                            });
+                        }
+                    }
+
+                    // Remember where playback is for "resume" on the next launch:
+                    // every third tick while playing, and once more on pausing
+                    if (is_playing && ticks.is_multiple_of(3)) || (was_playing && !is_playing) {
+                        if let Some(path) = song_id.clone().filter(|p| !audio::stream::is_remote(p)) {
+                            let session = settings::LastSession {
+                                track_path: path,
+                                position_secs,
+                            };
+                            if let Err(e) = save_settings(&state, &app_handle, |s| {
+                                s.last_session = Some(session);
+                                Ok(())
+                            }) {
+                                eprintln!("[Settings] Failed to save last session: {}", e);
+                            }
+                        }
+                    }
+                    was_playing = is_playing;
+
                     let now_ms = stats::current_time_ms();
                     let maybe_event = {
                         if let Ok(mut tracker) = state.stats_tracker.lock() {
@@ -2061,10 +4490,76 @@ pub fn run() {
                         }
                     };
 
+                    let recorded = maybe_event.is_some();
                     if let Some(event) = maybe_event {
                         let _ = stats::record_stats_event(&state, event);
                         let _ = app_handle.emit("stats-updated", ());
                     }
+
+                    // Scrobble new plays right away, and retry queued ones every 5 minutes
+                    if recorded || ticks.is_multiple_of(150) {
+                        let handle = app_handle.clone();
+                        tauri::async_runtime::spawn_blocking(move || {
+                            let state = handle.state::<AppState>();
+                            if let Err(e) = scrobbler::flush(&state, &handle) {
+                                eprintln!("[Lastfm] Scrobble flush failed, will retry: {}", e);
+                            }
+                        });
+                    }
+
+                    // The player may have crossfaded into the next track by itself;
+                    // keep the queue pointer and preloaded next track current.
+                    if sync_next_track(&state) {
+                        let new_path = state.player.lock().ok().and_then(|g| {
+                            g.as_ref().and_then(|p| p.get_status().track.map(|t| t.path))
+                        });
+                        if let Some(new_path) = new_path {
+                            schedule_play_count(&app_handle, new_path);
+                        }
+                        broadcast_state_to_ws(&state);
+                        let _ = app_handle.emit("refresh-player-state", ());
+                    }
+
+                    // The audio thread pauses by itself when its output device goes away
+                    let device_error = state
+                        .player
+                        .lock()
+                        .ok()
+                        .and_then(|g| g.as_ref().and_then(|p| p.take_device_error()));
+                    if let Some(message) = device_error {
+                        let _ = app_handle.emit(
+                            "playback-device-error",
+                            serde_json::json!({ "message": message }),
+                        );
+                        broadcast_state_to_ws(&state);
+                        let _ = app_handle.emit("refresh-player-state", ());
+                    }
+
+                    // Radio streams announce each new song in-band
+                    let live_track = state
+                        .player
+                        .lock()
+                        .ok()
+                        .and_then(|g| g.as_ref().and_then(|p| p.take_live_update()));
+                    if let Some(track) = live_track {
+                        let _ = state.discord.set_activity(
+                            &track.title,
+                            &format!("by {}", track.artist),
+                            None,
+                            None,
+                            None,
+                            Some(track.album.clone()),
+                        );
+                        if let Some(ref tx) = *state.media_cmd_tx.lock().unwrap() {
+                            let _ = tx.send(MediaCmd::SetMetadata {
+                                title: track.title.clone(),
+                                artist: track.artist.clone(),
+                                album: track.album.clone(),
+                            });
+                        }
+                        broadcast_state_to_ws(&state);
+                        let _ = app_handle.emit("refresh-player-state", ());
+                    }
                 }
             });
             Ok(())
@@ -2076,34 +4571,94 @@ pub fn run() {
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
             play_file,
+            play_url,
+            add_station,
+            get_stations,
+            remove_station,
             pause,
             resume,
             stop,
             set_volume,
             seek,
             set_eq_all,
+            save_eq_preset,
+            get_eq_presets,
+            delete_eq_preset,
+            apply_eq_preset,
             set_eq,
             set_reverb,
             set_speed,
+            set_crossfade,
+            set_gapless,
+            set_exclusive_output,
+            set_skip_silence,
+            set_click_fade_ms,
+            set_volume_curve,
+            get_settings,
+            update_settings,
+            get_last_session,
+            restore_last_session,
+            set_preamp,
+            set_balance,
+            set_mono,
+            set_ab_loop,
+            clear_ab_loop,
+            set_normalization_mode,
+            start_sleep_timer,
+            cancel_sleep_timer,
             get_player_state,
             get_queue_state,
+            queue_insert_next,
+            queue_add,
+            queue_remove,
+            queue_move,
+            queue_clear,
+            play_album,
+            play_artist,
             smart_shuffle_queue,
             get_stats_events,
             get_top_tracks,
             get_recently_played,
+            get_top_songs,
+            get_top_artists,
+            get_top_albums,
+            get_total_listening_ms,
+            get_listening_by_day,
+            export_stats,
+            set_lastfm_session,
+            get_scrobble_queue_len,
+            get_most_played,
+            get_recently_played_tracks,
+            get_recently_added,
             get_stats_v2,
             scan_music_folder,
             get_track_metadata,
             get_audio_quality,
             init_library,
+            add_files,
+            get_scan_extensions,
+            set_scan_extensions,
+            cancel_library_scan,
             get_library_tracks,
             get_covers_dir,
             get_lyrics,
+            clear_lyrics_cache,
+            set_lyrics_offset,
+            get_lyrics_offset,
             convert_lyrics_to_romaji,
+            detect_lyrics_language,
             get_cached_lyrics,
             remove_folder,
+            find_duplicate_tracks,
+            watch_folder,
+            generate_waveform,
+            unwatch_folder,
+            get_watched_folders,
+            remove_track,
+            remove_tracks,
             clear_all_data,
             apply_lrc_file,
+            save_lyrics_to_file,
             init_torrent_backend,
             add_magnet_link,
             get_torrents,
@@ -2113,14 +4668,39 @@ pub fn run() {
             delete_torrent,
             pause_torrent,
             resume_torrent,
+            set_torrent_speed_limits,
+            get_torrent_speed_limits,
+            set_seed_ratio_limit,
+            get_seed_ratio_limit,
+            set_torrent_seed_ratio_limit,
+            get_torrent_files,
+            update_selected_files,
             search_torrents,
             get_torrent_details,
             set_album_main_color,
+            get_discord_enabled,
+            get_discord_status,
+            set_discord_enabled,
+            set_discord_buttons,
+            set_album_cover,
+            set_track_cover,
             generate_missing_album_main_colors,
             start_mobile_server,
             stop_mobile_server,
             get_server_status,
+            set_server_port,
+            get_server_token,
             get_p2p_peers,
+            get_p2p_status,
+            retry_p2p_init,
+            pair_peer,
+            unpair_peer,
+            get_paired_peers,
+            get_p2p_transfer_progress,
+            p2p_request_file_bundle,
+            p2p_stream_to_peer,
+            p2p_request_stream,
+            connect_p2p_peer,
             get_local_ip,
             start_mobile_playback,
             stop_mobile_playback,
@@ -2133,6 +4713,8 @@ pub fn run() {
             remove_track_from_playlist,
             reorder_playlist_tracks,
             get_playlist_tracks,
+            toggle_favorite,
+            get_favorites,
         ])
         .setup(|_app| {
             // Initialize Windows Media Controls with the main window handle
@@ -2166,11 +4748,28 @@ pub fn run() {
                 }
             }
             
+            // MPRIS (Linux) / Now Playing (macOS) media controls
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            {
+                let tx = MediaControlService::start(_app.handle().clone(), 0);
+                match _app.state::<AppState>().media_cmd_tx.lock() {
+                    Ok(mut tx_guard) => {
+                        *tx_guard = Some(tx);
+                        println!("[MediaControls] Service started successfully");
+                    }
+                    Err(e) => {
+                        eprintln!("[MediaControls] Failed to lock mutex: {}", e);
+                    }
+                };
+            }
+
             // Start mobile companion server and P2P in background
             let app_handle = _app.handle().clone();
 
-            // Auto-start HTTP Server for cover art fallback & mobile companion
             let state = app_handle.state::<AppState>();
+            load_settings(&state, &app_handle);
+
+            // Auto-start HTTP Server for cover art fallback & mobile companion
             // check if already running to be safe (though this is startup)
             let should_start = {
                 let mut running = state.server_running.lock().unwrap();
@@ -2186,7 +4785,10 @@ pub fn run() {
                 let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
                 *state.server_shutdown_tx.lock().unwrap() = Some(shutdown_tx);
 
-                let config = server::ServerConfig::default();
+                let config = server::ServerConfig {
+                    port: configured_server_port(&state),
+                    ..Default::default()
+                };
                 let port = config.port;
                 let server_running = state.server_running.clone();
                 let app_handle_server = app_handle.clone();
@@ -2199,9 +4801,20 @@ pub fn run() {
                          }
                     }
                 });
-                println!("[Server] Auto-started on port {}", port);
+                println!("[Server] Auto-starting (preferred port {})", port);
             }
 
+            // Re-arm watchers for library folders saved in a previous session
+            let app_handle_watcher = app_handle.clone();
+            std::thread::spawn(move || {
+                let state = app_handle_watcher.state::<AppState>();
+                if let Err(e) = get_or_init_db(&state, &app_handle_watcher) {
+                    eprintln!("[Library] Cannot restore folder watchers: {}", e);
+                    return;
+                }
+                library_watcher::restore_watched_folders(&state, &app_handle_watcher);
+            });
+
             let app_handle_for_queue = app_handle.clone();
             // Listen for queue updates from frontend
             _app.listen("queue-updated", move |event: tauri::Event| {
@@ -2244,6 +4857,12 @@ pub fn run() {
                                 album_romaji: t.get("albumRomaji").and_then(|v| v.as_str()).map(|s| s.to_string()),
                                 album_en: t.get("albumEn").and_then(|v| v.as_str()).map(|s| s.to_string()),
                                 playlist_track_id: None,
+                                replaygain_track_gain: None,
+                                replaygain_album_gain: None,
+                                genre: None,
+                                year: None,
+                                album_artist: None,
+                                is_compilation: false,
                             })
                         }).collect::<Vec<_>>();
                         
@@ -2262,36 +4881,27 @@ pub fn run() {
                                 }
                             }
                         }
+                        queue::queue_changed(&state);
+                        sync_next_track(&state);
                     }
                 }
             });
 
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-                rt.block_on(async {
-                    // Initialize P2P manager
-                    let device_name = p2p::get_device_name();
-                    match P2PManager::new(device_name).await {
-                        Ok(p2p) => {
-                            println!("[P2P] Manager initialized successfully");
-                            let state = app_handle.state::<AppState>();
-                            let mut p2p_guard = state.p2p_manager.write().await;
-                            *p2p_guard = Some(p2p);
-                        }
-                        Err(e) => {
-                            eprintln!("[P2P] Failed to initialize: {}", e);
-                        }
-                    }
-                });
-            });
+            set_p2p_status(&app_handle, p2p::P2PStatus::Initializing);
+            tauri::async_runtime::spawn(init_p2p(app_handle));
             
             Ok(())
         })
         .plugin(tauri_plugin_single_instance::init(|_app, _args, _cwd| {
             println!("Second instance launched");
         }))
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown(app_handle);
+            }
+        });
 }
 
 
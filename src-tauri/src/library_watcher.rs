@@ -0,0 +1,243 @@
+//! Keeps watched library folders in sync with the filesystem.
+//!
+//! `notify` events are collected on a worker thread and applied once the folder
+//! has been quiet for `DEBOUNCE`, so copying an album in produces one batch of
+//! inserts instead of one per write. New audio files are read with the fast
+//! metadata path used by `init_library`; deleted files and folders are removed
+//! from the DB. Every applied batch emits `library-changed`.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::database::db::{folder_prefix, normalize_track_path};
+use crate::AppState;
+
+/// Quiet period before a burst of filesystem events is applied
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Upper bound on how long a continuous stream of events can postpone an update
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct LibraryWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    folders: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl LibraryWatcher {
+    /// Start watching `path` recursively. The worker thread is spawned on first use.
+    pub fn watch(&self, app: &AppHandle, path: &Path) -> Result<(), String> {
+        if !path.is_dir() {
+            return Err(format!("Not a directory: {}", path.display()));
+        }
+
+        let mut guard = self.watcher.lock().map_err(|_| "watcher lock poisoned".to_string())?;
+        if guard.is_none() {
+            let (tx, rx) = channel();
+            let watcher = notify::recommended_watcher(move |res| {
+                let _ = tx.send(res);
+            })
+            .map_err(|e| format!("Failed to start folder watcher: {}", e))?;
+
+            let app = app.clone();
+            std::thread::spawn(move || run_debouncer(app, rx));
+            *guard = Some(watcher);
+        }
+
+        if let Some(watcher) = guard.as_mut() {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+        }
+        self.folders.lock().unwrap().insert(path.to_path_buf());
+        println!("[Library] Watching {:?}", path);
+        Ok(())
+    }
+
+    pub fn unwatch(&self, path: &Path) -> Result<(), String> {
+        if !self.folders.lock().unwrap().remove(path) {
+            return Err(format!("Folder is not being watched: {}", path.display()));
+        }
+        if let Some(watcher) = self.watcher.lock().unwrap().as_mut() {
+            // The folder may already be gone; the watch is then dropped anyway
+            let _ = watcher.unwatch(path);
+        }
+        println!("[Library] Stopped watching {:?}", path);
+        Ok(())
+    }
+
+    pub fn watched_folders(&self) -> Vec<String> {
+        self.folders
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    }
+}
+
+pub fn save_watched_folders(state: &AppState, app: &AppHandle) -> Result<(), String> {
+    let folders = state.library_watcher.watched_folders();
+    crate::save_settings(state, app, |s| {
+        s.watched_folders = folders;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Watch the folders saved by a previous session and pick up changes made while
+/// the app was closed.
+pub fn restore_watched_folders(state: &AppState, app: &AppHandle) {
+    let saved = state.settings.lock().unwrap().watched_folders.clone();
+
+    for folder in saved {
+        let root = Path::new(&folder);
+        match state.library_watcher.watch(app, root) {
+            Ok(()) => sync_folder(app, root),
+            Err(e) => eprintln!("[Library] {}", e),
+        }
+    }
+}
+
+/// Reconcile the DB with the current contents of a watched folder
+fn sync_folder(app: &AppHandle, root: &Path) {
+    let on_disk: HashSet<String> = crate::scan_music_folder_helper(root)
+        .into_iter()
+        .map(|p| normalize_track_path(&p))
+        .collect();
+    let prefix = folder_prefix(&root.to_string_lossy());
+    let in_db: HashSet<String> = {
+        let state = app.state::<AppState>();
+        let guard = state.db.lock().unwrap();
+        guard
+            .as_ref()
+            .and_then(|db| db.get_all_track_paths().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.starts_with(&prefix))
+            .collect()
+    };
+
+    let added = on_disk.difference(&in_db).cloned().collect();
+    let removed = in_db.difference(&on_disk).cloned().collect();
+    apply(app, added, removed);
+}
+
+fn run_debouncer(app: AppHandle, rx: Receiver<notify::Result<Event>>) {
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    let mut first_event = Instant::now();
+
+    loop {
+        let received = if pending.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else if first_event.elapsed() >= MAX_DELAY {
+            Err(RecvTimeoutError::Timeout)
+        } else {
+            rx.recv_timeout(DEBOUNCE)
+        };
+
+        match received {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                if pending.is_empty() {
+                    first_event = Instant::now();
+                }
+                pending.extend(event.paths);
+            }
+            Ok(Err(e)) => eprintln!("[Library] Watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {
+                let changed = std::mem::take(&mut pending);
+                apply_events(&app, changed);
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+/// Turn a batch of changed paths into inserts and removals
+fn apply_events(app: &AppHandle, changed: HashSet<PathBuf>) {
+    let mut added = Vec::new();
+    let mut gone = Vec::new();
+    for path in changed {
+        if path.is_dir() {
+            added.extend(crate::scan_music_folder_helper(&path));
+        } else if path.is_file() {
+            if crate::is_audio_file(&path) {
+                added.push(path.to_string_lossy().to_string());
+            }
+        } else {
+            gone.push(normalize_track_path(&path.to_string_lossy()));
+        }
+    }
+
+    // A removed path may have been a file or a whole folder
+    let removed = if gone.is_empty() {
+        Vec::new()
+    } else {
+        let state = app.state::<AppState>();
+        let guard = state.db.lock().unwrap();
+        guard
+            .as_ref()
+            .and_then(|db| db.get_all_track_paths().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| {
+                gone.iter()
+                    .any(|g| p == g || p.starts_with(&folder_prefix(g)))
+            })
+            .collect()
+    };
+
+    apply(app, added, removed);
+}
+
+fn apply(app: &AppHandle, added: Vec<String>, removed: Vec<String>) {
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+
+    // Read tags before taking the DB lock; files still being copied fail here and
+    // are picked up again by the next write event
+    let tracks: Vec<_> = added
+        .iter()
+        .filter_map(|path| crate::get_track_metadata_helper_fast(path).ok())
+        .collect();
+
+    let state = app.state::<AppState>();
+    let (inserted, deleted) = {
+        let guard = state.db.lock().unwrap();
+        let Some(db) = guard.as_ref() else { return };
+
+        let mut inserted = 0;
+        for mut track in tracks {
+            track.path = normalize_track_path(&track.path);
+            if let Err(e) = db.adopt_moved_track(&track) {
+                eprintln!("[Library] Move detection failed for {}: {}", track.path, e);
+            }
+            match db.insert_track(&track, None) {
+                Ok(_) => inserted += 1,
+                Err(e) => eprintln!("[Library] Failed to insert track {}: {}", track.path, e),
+            }
+        }
+        let deleted = db.remove_tracks(&removed).unwrap_or_else(|e| {
+            eprintln!("[Library] Failed to remove tracks: {}", e);
+            0
+        });
+        (inserted, deleted)
+    };
+
+    if inserted > 0 || deleted > 0 {
+        println!("[Library] Watcher: {} added/updated, {} removed", inserted, deleted);
+        let _ = app.emit(
+            "library-changed",
+            serde_json::json!({ "added": inserted, "removed": deleted }),
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Lyrics of the current track, shared by the prefetch thread and commands
+//!
+//! Several fetches can be in flight at once: the prefetch started by
+//! `play_file`, a manual `get_lyrics`, and reloads after an LRC file is applied.
+//! They all report through the methods below, which only accept a result for
+//! the track the cache currently belongs to, so a slow fetch for a previous
+//! track can neither overwrite the current lyrics nor leave them "fetching".
+
+use crate::lyrics_fetcher::{LyricsResponse, WordTimedLine};
+
+/// Cached lyrics for current track
+#[derive(Clone, Default)]
+pub struct CachedLyrics {
+    pub track_path: String,
+    pub synced_lyrics: Option<String>,
+    pub plain_lyrics: Option<String>,
+    pub instrumental: bool,
+    pub word_timed_lyrics: Option<Vec<WordTimedLine>>,
+    pub is_fetching: bool,
+    pub error: Option<String>,
+}
+
+impl CachedLyrics {
+    /// Start over for a newly playing track, marked as fetching
+    pub fn begin(&mut self, track_path: &str) {
+        *self = Self {
+            track_path: track_path.to_string(),
+            is_fetching: true,
+            ..Default::default()
+        };
+    }
+
+    /// Store fetched lyrics. Returns false, leaving the cache untouched, if it
+    /// has moved on to another track.
+    pub fn complete(&mut self, track_path: &str, lyrics: LyricsResponse) -> bool {
+        if self.track_path != track_path {
+            return false;
+        }
+        self.synced_lyrics = lyrics.synced_lyrics;
+        self.plain_lyrics = lyrics.plain_lyrics;
+        self.instrumental = lyrics.instrumental.unwrap_or(false);
+        self.word_timed_lyrics = lyrics.word_timed_lyrics;
+        self.is_fetching = false;
+        self.error = None;
+        true
+    }
+
+    /// Record a failed fetch, with the same track check as `complete`. Lyrics
+    /// another fetch already stored are kept.
+    pub fn fail(&mut self, track_path: &str, error: String) -> bool {
+        if self.track_path != track_path {
+            return false;
+        }
+        self.is_fetching = false;
+        if self.synced_lyrics.is_none() && self.plain_lyrics.is_none() && !self.instrumental {
+            self.error = Some(error);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lyrics(synced: &str) -> LyricsResponse {
+        LyricsResponse {
+            id: None,
+            track_name: None,
+            artist_name: None,
+            album_name: None,
+            duration: None,
+            instrumental: Some(false),
+            plain_lyrics: None,
+            synced_lyrics: Some(synced.to_string()),
+            word_timed_lyrics: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn fast_track_switch_drops_the_stale_prefetch() {
+        let mut cache = CachedLyrics::default();
+        cache.begin("a.flac");
+        // Track B starts before the prefetch for A returns
+        cache.begin("b.flac");
+
+        assert!(!cache.complete("a.flac", lyrics("[00:01.00]from a")));
+        assert!(!cache.fail("a.flac", "timed out".to_string()));
+        assert_eq!(cache.track_path, "b.flac");
+        assert!(cache.is_fetching);
+        assert_eq!(cache.synced_lyrics, None);
+
+        assert!(cache.complete("b.flac", lyrics("[00:01.00]from b")));
+        assert!(!cache.is_fetching);
+        assert_eq!(cache.synced_lyrics.as_deref(), Some("[00:01.00]from b"));
+
+        // A duplicate fetch failing afterwards keeps what is already there
+        assert!(cache.fail("b.flac", "timed out".to_string()));
+        assert_eq!(cache.error, None);
+        assert_eq!(cache.synced_lyrics.as_deref(), Some("[00:01.00]from b"));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde::de::DeserializeOwned;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 /// LRCLIB API response structure
@@ -15,6 +15,218 @@ pub struct LyricsResponse {
     pub instrumental: Option<bool>,
     pub plain_lyrics: Option<String>,
     pub synced_lyrics: Option<String>,
+    /// Per-word timing parsed from enhanced LRC (`<mm:ss.xx>` tags), if present
+    #[serde(default)]
+    pub word_timed_lyrics: Option<Vec<WordTimedLine>>,
+    /// Provider that supplied the lyrics ("lrclib", "netease", "local")
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// A single word with its start time (and end time when known)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimedWord {
+    pub start_ms: u64,
+    pub end_ms: Option<u64>,
+    pub text: String,
+}
+
+/// One lyric line from an enhanced LRC file, split into timed words
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordTimedLine {
+    pub start_ms: u64,
+    pub text: String,
+    pub words: Vec<TimedWord>,
+}
+
+/// Parse `mm:ss.xx` / `mm:ss.xxx` (without brackets) to milliseconds
+fn parse_time_ms(content: &str) -> Option<u64> {
+    let parts: Vec<&str> = content.trim().split(':').collect();
+    if parts.len() != 2 { return None; }
+
+    let min: u64 = parts[0].parse().ok()?;
+
+    let sec_parts: Vec<&str> = parts[1].split('.').collect();
+    if sec_parts.len() != 2 { return None; }
+
+    let sec: u64 = sec_parts[0].parse().ok()?;
+    let frac_str = sec_parts[1];
+
+    // Handle .xx (centiseconds) vs .xxx (milliseconds)
+    let ms: u64 = if frac_str.len() == 2 {
+        frac_str.parse::<u64>().ok()? * 10
+    } else if frac_str.len() >= 3 {
+        frac_str[..3].parse::<u64>().ok()?
+    } else {
+        frac_str.parse::<u64>().ok()?
+    };
+
+    Some(min * 60000 + sec * 1000 + ms)
+}
+
+/// Parse enhanced LRC word timestamps, e.g.
+/// `[00:12.00]<00:12.00>Hello <00:12.50>world<00:13.10>`.
+/// Returns `None` when the lyrics only carry line-level timing.
+pub fn parse_word_timed_lyrics(lrc: &str) -> Option<Vec<WordTimedLine>> {
+    let mut lines = Vec::new();
+    let mut has_word_timing = false;
+
+    for raw in lrc.lines() {
+        let raw = raw.trim();
+        let Some(rest) = raw.strip_prefix('[') else { continue };
+        let Some(close) = rest.find(']') else { continue };
+        // Skip metadata tags like [ar:Artist]
+        let Some(line_start) = parse_time_ms(&rest[..close]) else { continue };
+        let mut body = &rest[close + 1..];
+
+        // Extra line timestamps ([00:01.00][00:30.00]text) share the same words
+        while let Some(next) = body.strip_prefix('[') {
+            match next.find(']') {
+                Some(end) if parse_time_ms(&next[..end]).is_some() => body = &next[end + 1..],
+                _ => break,
+            }
+        }
+
+        let mut words: Vec<TimedWord> = Vec::new();
+        let mut plain = String::new();
+        let mut cursor = body;
+        let mut pending: Option<(u64, String)> = None;
+        let mut tagged = false;
+
+        while !cursor.is_empty() {
+            let tag = cursor.strip_prefix('<').and_then(|tail| {
+                let end = tail.find('>')?;
+                parse_time_ms(&tail[..end]).map(|ms| (ms, &tail[end + 1..]))
+            });
+
+            if let Some((ms, after)) = tag {
+                if let Some((start, text)) = pending.take() {
+                    if !text.trim().is_empty() {
+                        words.push(TimedWord { start_ms: start, end_ms: Some(ms), text: text.trim().to_string() });
+                    }
+                }
+                pending = Some((ms, String::new()));
+                tagged = true;
+                cursor = after;
+                continue;
+            }
+
+            let next_tag = cursor
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| c == '<')
+                .map(|(i, _)| i)
+                .unwrap_or(cursor.len());
+            let chunk = &cursor[..next_tag];
+            plain.push_str(chunk);
+            match pending.as_mut() {
+                Some((_, text)) => text.push_str(chunk),
+                None => pending = Some((line_start, chunk.to_string())),
+            }
+            cursor = &cursor[next_tag..];
+        }
+
+        if let Some((start, text)) = pending {
+            if !text.trim().is_empty() {
+                words.push(TimedWord { start_ms: start, end_ms: None, text: text.trim().to_string() });
+            }
+        }
+
+        has_word_timing |= tagged;
+        lines.push(WordTimedLine {
+            start_ms: line_start,
+            text: plain.split_whitespace().collect::<Vec<_>>().join(" "),
+            words,
+        });
+    }
+
+    has_word_timing.then_some(lines)
+}
+
+fn format_time_ms(ms: u64) -> String {
+    format!("{:02}:{:02}.{:03}", ms / 60_000, (ms / 1000) % 60, ms % 1000)
+}
+
+fn shift_ms(ms: u64, offset_ms: i64) -> u64 {
+    (ms as i64 + offset_ms).max(0) as u64
+}
+
+/// Shift every `[mm:ss.xx]` line tag and `<mm:ss.xx>` word tag by `offset_ms`
+/// (negative values move lyrics earlier, clamped at zero). Metadata tags such as
+/// `[ar:...]` are left alone.
+pub fn shift_lrc_timestamps(lrc: &str, offset_ms: i64) -> String {
+    if offset_ms == 0 {
+        return lrc.to_string();
+    }
+
+    let mut result = String::with_capacity(lrc.len());
+    let mut rest = lrc;
+    while let Some(open_at) = rest.find(['[', '<']) {
+        result.push_str(&rest[..open_at]);
+        let open = &rest[open_at..open_at + 1];
+        let close = if open == "[" { ']' } else { '>' };
+        let tail = &rest[open_at + 1..];
+
+        match tail.find(close).and_then(|end| parse_time_ms(&tail[..end]).map(|ms| (end, ms))) {
+            Some((end, ms)) => {
+                result.push_str(open);
+                result.push_str(&format_time_ms(shift_ms(ms, offset_ms)));
+                result.push(close);
+                rest = &tail[end + 1..];
+            }
+            None => {
+                result.push_str(open);
+                rest = tail;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Language code of the lyrics' script ("ja", "ko", "zh"), if they need romanizing
+pub fn detect_language(lyrics: &LyricsResponse) -> Option<String> {
+    lyrics
+        .synced_lyrics
+        .as_deref()
+        .or(lyrics.plain_lyrics.as_deref())
+        .and_then(crate::lyrics_transliteration::detect_lyrics_language)
+        .map(|script| script.code().to_string())
+}
+
+/// Apply a per-track offset to both synced and word-timed lyrics
+pub fn apply_offset(lyrics: &mut LyricsResponse, offset_ms: i64) {
+    if offset_ms == 0 {
+        return;
+    }
+    if let Some(ref synced) = lyrics.synced_lyrics {
+        lyrics.synced_lyrics = Some(shift_lrc_timestamps(synced, offset_ms));
+    }
+    if let Some(ref mut lines) = lyrics.word_timed_lyrics {
+        shift_word_timed(lines, offset_ms);
+    }
+}
+
+/// Shift parsed word-timed lines in place
+pub fn shift_word_timed(lines: &mut [WordTimedLine], offset_ms: i64) {
+    for line in lines {
+        line.start_ms = shift_ms(line.start_ms, offset_ms);
+        for word in &mut line.words {
+            word.start_ms = shift_ms(word.start_ms, offset_ms);
+            word.end_ms = word.end_ms.map(|end| shift_ms(end, offset_ms));
+        }
+    }
+}
+
+/// Fill `word_timed_lyrics` from the synced lyrics
+pub fn with_word_timing(mut lyrics: LyricsResponse) -> LyricsResponse {
+    lyrics.word_timed_lyrics = lyrics
+        .synced_lyrics
+        .as_deref()
+        .and_then(parse_word_timed_lyrics);
+    lyrics
 }
 
 /// Try to find a local .lrc file next to the audio file
@@ -40,7 +252,7 @@ pub fn find_local_lrc(audio_path: &str) -> Option<LyricsResponse> {
                 content
             };
 
-            return Some(LyricsResponse {
+            return Some(with_word_timing(LyricsResponse {
                 id: None,
                 track_name: path
                     .file_stem()
@@ -52,7 +264,9 @@ pub fn find_local_lrc(audio_path: &str) -> Option<LyricsResponse> {
                 instrumental: Some(false),
                 plain_lyrics: None,
                 synced_lyrics: Some(final_content),
-            });
+                word_timed_lyrics: None,
+                source: Some("local".to_string()),
+            }));
         }
     }
 
@@ -80,7 +294,7 @@ pub fn find_local_lrc(audio_path: &str) -> Option<LyricsResponse> {
                             content
                         };
 
-                        return Some(LyricsResponse {
+                        return Some(with_word_timing(LyricsResponse {
                             id: None,
                             track_name: Some(stem.to_string()),
                             artist_name: None,
@@ -89,7 +303,9 @@ pub fn find_local_lrc(audio_path: &str) -> Option<LyricsResponse> {
                             instrumental: Some(false),
                             plain_lyrics: None,
                             synced_lyrics: Some(final_content),
-                        });
+                            word_timed_lyrics: None,
+                            source: Some("local".to_string()),
+                        }));
                     }
                 }
             }
@@ -99,6 +315,60 @@ pub fn find_local_lrc(audio_path: &str) -> Option<LyricsResponse> {
     None
 }
 
+/// Write `content` as the `.lrc` sidecar next to `audio_path`, so `find_local_lrc`
+/// picks it up. An existing sidecar with different content is renamed to
+/// `<stem>.lrc.bak` (or `<stem>.lrc.<n>.bak` if that is taken) first.
+/// Returns the sidecar path and the backup path, if one was made.
+pub fn write_lrc_sidecar(
+    audio_path: &Path,
+    content: &str,
+) -> std::io::Result<(PathBuf, Option<PathBuf>)> {
+    let lrc_path = audio_path.with_extension("lrc");
+    let mut backup = None;
+
+    if lrc_path.exists() {
+        if std::fs::read_to_string(&lrc_path).is_ok_and(|existing| existing == content) {
+            return Ok((lrc_path, None));
+        }
+
+        let mut candidate = audio_path.with_extension("lrc.bak");
+        let mut n = 1;
+        while candidate.exists() {
+            candidate = audio_path.with_extension(format!("lrc.{}.bak", n));
+            n += 1;
+        }
+        std::fs::rename(&lrc_path, &candidate)?;
+        backup = Some(candidate);
+    }
+
+    std::fs::write(&lrc_path, content)?;
+    Ok((lrc_path, backup))
+}
+
+/// Store lyrics in the file's own lyrics tag (ID3 `USLT`, Vorbis `LYRICS`, MP4 `©lyr`)
+pub fn embed_lyrics(audio_path: &Path, content: &str) -> Result<(), String> {
+    use lofty::config::WriteOptions;
+    use lofty::prelude::*;
+    use lofty::probe::Probe;
+    use lofty::tag::{ItemKey, Tag};
+
+    let mut tagged_file = Probe::open(audio_path)
+        .and_then(|probe| probe.read())
+        .map_err(|e| format!("Failed to read tags: {}", e))?;
+
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or_else(|| "File format does not support tags".to_string())?;
+
+    tag.insert_text(ItemKey::Lyrics, content.to_string());
+    tag.save_to_path(audio_path, WriteOptions::default())
+        .map_err(|e| format!("Failed to write tags: {}", e))
+}
+
 /// Helper to merge main LRC with translation/romaji LRC
 fn merge_lrc_content(main: &str, romaji: &str) -> String {
     use std::collections::HashMap;
@@ -107,28 +377,7 @@ fn merge_lrc_content(main: &str, romaji: &str) -> String {
     fn parse_timestamp_ms(s: &str) -> Option<u64> {
         let s = s.trim();
         if !s.starts_with('[') || !s.ends_with(']') { return None; }
-        let content = &s[1..s.len()-1];
-        let parts: Vec<&str> = content.split(':').collect();
-        if parts.len() != 2 { return None; }
-        
-        let min: u64 = parts[0].parse().ok()?;
-        
-        let sec_parts: Vec<&str> = parts[1].split('.').collect();
-        if sec_parts.len() != 2 { return None; }
-        
-        let sec: u64 = sec_parts[0].parse().ok()?;
-        let frac_str = sec_parts[1];
-        
-        // Handle .xx (centiseconds) vs .xxx (milliseconds)
-        let ms: u64 = if frac_str.len() == 2 {
-            frac_str.parse::<u64>().ok()? * 10
-        } else if frac_str.len() >= 3 {
-            frac_str[..3].parse::<u64>().ok()?
-        } else {
-            frac_str.parse::<u64>().ok()?
-        };
-        
-        Some(min * 60000 + sec * 1000 + ms)
+        parse_time_ms(&s[1..s.len()-1])
     }
 
     // Parse romaji into Map: MS -> Text
@@ -338,95 +587,339 @@ fn try_generic_search(client: &reqwest::blocking::Client, query: &str) -> Option
         })
 }
 
-/// Main function - LOCAL LRC FIRST, then API search
-pub fn fetch_lyrics<F: Fn(&str)>(
-    artist: &str,
-    track: &str,
-    duration_secs: u32,
-    on_progress: F,
-) -> Result<LyricsResponse, String> {
-    println!("[Lyrics] Searching: {} - {}", artist, track);
+/// Track being looked up by a [`LyricsProvider`].
+/// The deadline bounds LRCLIB's cascade of progressively looser searches.
+pub struct LyricsQuery<'a> {
+    pub artist: &'a str,
+    pub track: &'a str,
+    /// Track length in seconds; `None` for the looser name-only search
+    pub duration_secs: Option<u32>,
+    deadline: Instant,
+}
 
-    let start = Instant::now();
-    let timeout = Duration::from_secs(10);
-    let client = create_client()?;
+impl<'a> LyricsQuery<'a> {
+    pub fn new(artist: &'a str, track: &'a str, duration_secs: Option<u32>) -> Self {
+        Self {
+            artist,
+            track,
+            duration_secs,
+            deadline: Instant::now() + Duration::from_secs(10),
+        }
+    }
 
-    let clean_track = clean_track_name(track);
-    let primary_artist = extract_primary_artist(artist);
+    fn timed_out(&self) -> bool {
+        Instant::now() > self.deadline
+    }
+}
+
+/// A lyrics source. Providers are tried in order until one returns synced lyrics.
+pub trait LyricsProvider {
+    /// Identifier reported in `LyricsResponse::source`
+    fn name(&self) -> &'static str;
 
-    macro_rules! check_timeout {
-        () => {
-            if start.elapsed() > timeout {
-                println!("[Lyrics] ✗ Timeout");
-                return Err("Timeout".to_string());
+    fn search(
+        &self,
+        client: &reqwest::blocking::Client,
+        query: &LyricsQuery,
+        on_progress: &dyn Fn(&str),
+    ) -> Option<LyricsResponse>;
+}
+
+/// lrclib.net, matched by duration first and then by increasingly loose searches
+pub struct Lrclib;
+
+impl LyricsProvider for Lrclib {
+    fn name(&self) -> &'static str {
+        "lrclib"
+    }
+
+    fn search(
+        &self,
+        client: &reqwest::blocking::Client,
+        query: &LyricsQuery,
+        on_progress: &dyn Fn(&str),
+    ) -> Option<LyricsResponse> {
+        let (artist, track) = (query.artist, query.track);
+
+        let Some(duration_secs) = query.duration_secs else {
+            on_progress("Fallback search: Artist + Track...");
+            if let Some(lyrics) = try_artist_track_search(client, artist, track) {
+                return Some(lyrics);
             }
+
+            on_progress("Fallback search: Generic query...");
+            return try_generic_search(client, &format!("{} {}", artist, track));
         };
-    }
 
-    // Strategy 1: Exact match
-    on_progress("Searching exact match...");
-    if let Some(lyrics) = try_exact_match(&client, artist, track, duration_secs) {
-        println!("[Lyrics] ✓ Found exact match!");
-        return Ok(lyrics);
-    }
-    check_timeout!();
+        let clean_track = clean_track_name(track);
+        let primary_artist = extract_primary_artist(artist);
 
-    // Strategy 2: Clean track
-    if clean_track != track {
-        on_progress("Searching with cleaned track name...");
-        if let Some(lyrics) = try_exact_match(&client, artist, &clean_track, duration_secs) {
-            println!("[Lyrics] ✓ Found with clean track!");
-            return Ok(lyrics);
+        macro_rules! check_timeout {
+            () => {
+                if query.timed_out() {
+                    println!("[Lyrics] ✗ Timeout");
+                    return None;
+                }
+            };
+        }
+
+        // Strategy 1: Exact match
+        on_progress("Searching exact match...");
+        if let Some(lyrics) = try_exact_match(client, artist, track, duration_secs) {
+            println!("[Lyrics] ✓ Found exact match!");
+            return Some(lyrics);
         }
         check_timeout!();
-    }
 
-    // Strategy 3: Primary artist
-    if primary_artist != artist {
-        on_progress("Searching for primary artist...");
-        if let Some(lyrics) = try_exact_match(&client, &primary_artist, track, duration_secs) {
-            println!("[Lyrics] ✓ Found with primary artist!");
-            return Ok(lyrics);
+        // Strategy 2: Clean track
+        if clean_track != track {
+            on_progress("Searching with cleaned track name...");
+            if let Some(lyrics) = try_exact_match(client, artist, &clean_track, duration_secs) {
+                println!("[Lyrics] ✓ Found with clean track!");
+                return Some(lyrics);
+            }
+            check_timeout!();
+        }
+
+        // Strategy 3: Primary artist
+        if primary_artist != artist {
+            on_progress("Searching for primary artist...");
+            if let Some(lyrics) = try_exact_match(client, &primary_artist, track, duration_secs) {
+                println!("[Lyrics] ✓ Found with primary artist!");
+                return Some(lyrics);
+            }
+            check_timeout!();
+        }
+
+        // Strategy 4: Search
+        on_progress("Searching via LrcLib API...");
+        if let Some(lyrics) = try_artist_track_search(client, artist, track) {
+            println!("[Lyrics] ✓ Found via search!");
+            return Some(lyrics);
+        }
+        check_timeout!();
+
+        // Strategy 5: Clean search
+        if clean_track != track || primary_artist != artist {
+            on_progress("Retrying with cleaned metadata...");
+            if let Some(lyrics) = try_artist_track_search(client, &primary_artist, &clean_track) {
+                println!("[Lyrics] ✓ Found via clean search!");
+                return Some(lyrics);
+            }
+            check_timeout!();
+        }
+
+        // Strategy 6: Generic query
+        let generic = format!("{} {}", artist, track);
+        on_progress(&format!("Searching query: {}", generic));
+        if let Some(lyrics) = try_generic_search(client, &generic) {
+            println!("[Lyrics] ✓ Found via generic!");
+            return Some(lyrics);
         }
         check_timeout!();
+
+        // Strategy 7: Track only
+        on_progress("Final attempt: searching by track name only...");
+        if let Some(lyrics) = try_generic_search(client, track) {
+            println!("[Lyrics] ✓ Found via track only!");
+            return Some(lyrics);
+        }
+
+        None
+    }
+}
+
+#[derive(Deserialize)]
+struct NeteaseSearch {
+    result: Option<NeteaseSearchResult>,
+}
+
+#[derive(Deserialize)]
+struct NeteaseSearchResult {
+    #[serde(default)]
+    songs: Vec<NeteaseSong>,
+}
+
+#[derive(Deserialize)]
+struct NeteaseSong {
+    id: i64,
+    name: String,
+    #[serde(default)]
+    artists: Vec<NeteaseArtist>,
+    /// Milliseconds
+    #[serde(default)]
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+struct NeteaseArtist {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct NeteaseLyric {
+    lrc: Option<NeteaseLrc>,
+    #[serde(default)]
+    nolyric: bool,
+}
+
+#[derive(Deserialize)]
+struct NeteaseLrc {
+    lyric: Option<String>,
+}
+
+/// Songs further than this from the local duration are treated as different recordings
+const NETEASE_DURATION_TOLERANCE_MS: u64 = 3000;
+
+/// NetEase Cloud Music, which has good coverage for Chinese, Japanese and Korean releases
+pub struct Netease;
+
+impl Netease {
+    fn pick_song(songs: &[NeteaseSong], duration_secs: Option<u32>) -> Option<&NeteaseSong> {
+        match duration_secs {
+            Some(secs) => {
+                let target = secs as u64 * 1000;
+                songs
+                    .iter()
+                    .filter(|s| s.duration.abs_diff(target) <= NETEASE_DURATION_TOLERANCE_MS)
+                    .min_by_key(|s| s.duration.abs_diff(target))
+            }
+            None => songs.first(),
+        }
     }
+}
 
-    // Strategy 4: Search
-    on_progress("Searching via LrcLib API...");
-    if let Some(lyrics) = try_artist_track_search(&client, artist, track) {
-        println!("[Lyrics] ✓ Found via search!");
-        return Ok(lyrics);
+impl LyricsProvider for Netease {
+    fn name(&self) -> &'static str {
+        "netease"
     }
-    check_timeout!();
 
-    // Strategy 5: Clean search
-    if clean_track != track || primary_artist != artist {
-        on_progress("Retrying with cleaned metadata...");
-        if let Some(lyrics) = try_artist_track_search(&client, &primary_artist, &clean_track) {
-            println!("[Lyrics] ✓ Found via clean search!");
-            return Ok(lyrics);
+    fn search(
+        &self,
+        client: &reqwest::blocking::Client,
+        query: &LyricsQuery,
+        on_progress: &dyn Fn(&str),
+    ) -> Option<LyricsResponse> {
+        on_progress("Searching NetEase...");
+        let search_url = format!(
+            "https://music.163.com/api/search/get?s={}&type=1&limit=10",
+            urlencoding::encode(&format!("{} {}", query.artist, clean_track_name(query.track)))
+        );
+        let songs = get_json_with_retry::<NeteaseSearch>(client, &search_url, "netease search")?
+            .result?
+            .songs;
+        let song = Self::pick_song(&songs, query.duration_secs)?;
+
+        let lyric_url = format!("https://music.163.com/api/song/lyric?id={}&lv=1", song.id);
+        let lyric = get_json_with_retry::<NeteaseLyric>(client, &lyric_url, "netease lyric")?;
+        if lyric.nolyric {
+            return Some(LyricsResponse {
+                instrumental: Some(true),
+                ..netease_response(song, None)
+            });
         }
-        check_timeout!();
+
+        let text = lyric.lrc?.lyric.filter(|l| !l.trim().is_empty())?;
+        Some(netease_response(song, Some(text)))
     }
+}
 
-    // Strategy 6: Generic query
-    let query = format!("{} {}", artist, track);
-    on_progress(&format!("Searching query: {}", query));
-    if let Some(lyrics) = try_generic_search(&client, &query) {
-        println!("[Lyrics] ✓ Found via generic!");
-        return Ok(lyrics);
+fn netease_response(song: &NeteaseSong, lyric: Option<String>) -> LyricsResponse {
+    let synced = lyric.as_deref().is_some_and(|l| first_timestamp_ms(l).is_some());
+    LyricsResponse {
+        id: Some(song.id),
+        track_name: Some(song.name.clone()),
+        artist_name: song.artists.first().map(|a| a.name.clone()),
+        album_name: None,
+        duration: Some(song.duration as f64 / 1000.0),
+        instrumental: Some(false),
+        plain_lyrics: lyric.as_deref().map(strip_lrc_timestamps),
+        synced_lyrics: if synced { lyric } else { None },
+        word_timed_lyrics: None,
+        source: None,
     }
-    check_timeout!();
+}
+
+/// Start time of the first `[mm:ss.xx]` tag in an LRC document, if it has one
+fn first_timestamp_ms(lrc: &str) -> Option<u64> {
+    lrc.lines().find_map(|line| {
+        let rest = line.trim_start().strip_prefix('[')?;
+        parse_time_ms(&rest[..rest.find(']')?])
+    })
+}
+
+/// Plain text of an LRC document: timestamp and metadata tags removed
+fn strip_lrc_timestamps(lrc: &str) -> String {
+    lrc.lines()
+        .filter_map(|line| {
+            let mut rest = line.trim();
+            let mut tagged = false;
+            while let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']')?;
+                // Metadata tags like [ar:...] have no lyric text
+                parse_time_ms(&after[..end])?;
+                tagged = true;
+                rest = after[end + 1..].trim_start();
+            }
+            (tagged || !rest.is_empty()).then(|| rest.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Providers in the order they are tried
+pub fn default_providers() -> Vec<Box<dyn LyricsProvider>> {
+    vec![Box::new(Lrclib), Box::new(Netease)]
+}
+
+/// Providers searched again without a duration once `default_providers` found
+/// nothing. NetEase is left out: it would repeat the same search and only
+/// accept the first hit instead of a duration match.
+pub fn fallback_providers() -> Vec<Box<dyn LyricsProvider>> {
+    vec![Box::new(Lrclib)]
+}
+
+/// Try each provider in turn. The first synced result wins; otherwise the first
+/// plain-text result is used. `source` is set to the provider that answered.
+pub fn search_providers(
+    providers: &[Box<dyn LyricsProvider>],
+    client: &reqwest::blocking::Client,
+    query: &LyricsQuery,
+    on_progress: &dyn Fn(&str),
+) -> Result<LyricsResponse, String> {
+    println!("[Lyrics] Searching: {} - {}", query.artist, query.track);
 
-    // Strategy 7: Track only
-    on_progress("Final attempt: searching by track name only...");
-    if let Some(lyrics) = try_generic_search(&client, track) {
-        println!("[Lyrics] ✓ Found via track only!");
-        return Ok(lyrics);
+    let mut plain_only: Option<LyricsResponse> = None;
+    for provider in providers {
+        let Some(mut lyrics) = provider.search(client, query, on_progress) else {
+            continue;
+        };
+        lyrics.source = Some(provider.name().to_string());
+
+        if lyrics.synced_lyrics.is_some() {
+            return Ok(lyrics);
+        }
+        if plain_only.is_none() && has_lyrics(&lyrics) {
+            plain_only = Some(lyrics);
+        }
     }
 
-    println!("[Lyrics] ✗ Not found");
-    Err("No sources founded for lyrics changing to recents view".to_string())
+    plain_only.ok_or_else(|| {
+        println!("[Lyrics] ✗ Not found");
+        "No sources founded for lyrics changing to recents view".to_string()
+    })
+}
+
+/// Main function - searches every provider, matching on duration
+pub fn fetch_lyrics<F: Fn(&str)>(
+    artist: &str,
+    track: &str,
+    duration_secs: u32,
+    on_progress: F,
+) -> Result<LyricsResponse, String> {
+    let client = create_client()?;
+    let query = LyricsQuery::new(artist, track, Some(duration_secs));
+    search_providers(&default_providers(), &client, &query, &on_progress).map(with_word_timing)
 }
 
 pub fn fetch_lyrics_fallback<F: Fn(&str)>(
@@ -435,17 +928,148 @@ pub fn fetch_lyrics_fallback<F: Fn(&str)>(
     on_progress: F,
 ) -> Result<LyricsResponse, String> {
     let client = create_client()?;
+    let query = LyricsQuery::new(artist, track, None);
+    search_providers(&fallback_providers(), &client, &query, &on_progress).map(with_word_timing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_enhanced_lrc_word_timing() {
+        let lrc = "[ar:Someone]\n[00:12.00]<00:12.00>Hello <00:12.50>world<00:13.10>\n[00:14.00]<00:14.00>again";
+        let lines = parse_word_timed_lyrics(lrc).expect("word timing present");
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0].start_ms, 12_000);
+        assert_eq!(lines[0].text, "Hello world");
+        assert_eq!(
+            lines[0].words,
+            vec![
+                TimedWord { start_ms: 12_000, end_ms: Some(12_500), text: "Hello".to_string() },
+                TimedWord { start_ms: 12_500, end_ms: Some(13_100), text: "world".to_string() },
+            ]
+        );
+        assert_eq!(lines[1].words[0].end_ms, None);
+    }
+
+    #[test]
+    fn offset_shifts_line_and_word_tags() {
+        let lrc = "[ar:Someone]\n[00:01.00]<00:01.00>Hi <00:01.50>there\n[01:00.25]Later";
+        assert_eq!(
+            shift_lrc_timestamps(lrc, 500),
+            "[ar:Someone]\n[00:01.500]<00:01.500>Hi <00:02.000>there\n[01:00.750]Later"
+        );
+        assert_eq!(shift_lrc_timestamps("[00:00.30]Start", -1000), "[00:00.000]Start");
+        assert_eq!(shift_lrc_timestamps(lrc, 0), lrc);
+    }
+
+    #[test]
+    fn shifted_lyrics_survive_transliteration() {
+        let shifted = shift_lrc_timestamps("[00:10.00]こんにちは", -250);
+        let romaji = crate::lyrics_transliteration::transliterate_lyrics(&shifted);
+        assert!(romaji.starts_with("[00:09.750] こんにちは / "));
+    }
 
-    on_progress("Fallback search: Artist + Track...");
-    if let Some(lyrics) = try_artist_track_search(&client, artist, track) {
-        return Ok(lyrics);
+    struct FakeProvider(&'static str, Option<&'static str>, Option<&'static str>);
+
+    impl LyricsProvider for FakeProvider {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn search(
+            &self,
+            _client: &reqwest::blocking::Client,
+            _query: &LyricsQuery,
+            _on_progress: &dyn Fn(&str),
+        ) -> Option<LyricsResponse> {
+            if self.1.is_none() && self.2.is_none() {
+                return None;
+            }
+            Some(LyricsResponse {
+                id: None,
+                track_name: None,
+                artist_name: None,
+                album_name: None,
+                duration: None,
+                instrumental: Some(false),
+                plain_lyrics: self.1.map(str::to_string),
+                synced_lyrics: self.2.map(str::to_string),
+                word_timed_lyrics: None,
+                source: None,
+            })
+        }
     }
 
-    on_progress("Fallback search: Generic query...");
-    let query = format!("{} {}", artist, track);
-    if let Some(lyrics) = try_generic_search(&client, &query) {
-        return Ok(lyrics);
+    #[test]
+    fn provider_chain_prefers_first_synced_result() {
+        let client = reqwest::blocking::Client::new();
+        let query = LyricsQuery::new("Artist", "Track", Some(200));
+        let providers: Vec<Box<dyn LyricsProvider>> = vec![
+            Box::new(FakeProvider("empty", None, None)),
+            Box::new(FakeProvider("plain", Some("Line"), None)),
+            Box::new(FakeProvider("synced", None, Some("[00:01.00]Line"))),
+        ];
+
+        let lyrics = search_providers(&providers, &client, &query, &|_| {}).unwrap();
+        assert_eq!(lyrics.source.as_deref(), Some("synced"));
+
+        let lyrics = search_providers(&providers[..2], &client, &query, &|_| {}).unwrap();
+        assert_eq!(lyrics.source.as_deref(), Some("plain"));
+
+        assert!(search_providers(&providers[..1], &client, &query, &|_| {}).is_err());
     }
 
-    Err("No sources founded for lyrics changing to recents view".to_string())
+    #[test]
+    fn fallback_round_skips_netease() {
+        let names: Vec<_> = fallback_providers().iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["lrclib"]);
+    }
+
+    #[test]
+    fn netease_song_matched_by_duration() {
+        let song = |id, duration| NeteaseSong { id, name: String::new(), artists: Vec::new(), duration };
+        let songs = vec![song(1, 180_000), song(2, 241_500), song(3, 240_900)];
+        assert_eq!(Netease::pick_song(&songs, Some(241)).map(|s| s.id), Some(3));
+        assert_eq!(Netease::pick_song(&songs, Some(300)).map(|s| s.id), None);
+        assert_eq!(Netease::pick_song(&songs, None).map(|s| s.id), Some(1));
+    }
+
+    #[test]
+    fn strips_lrc_tags_for_plain_text() {
+        let lrc = "[ar:Someone]\n[00:01.00]First\n[00:02.00][00:10.00]Chorus\n[00:03.00]";
+        assert_eq!(strip_lrc_timestamps(lrc), "First\nChorus\n");
+    }
+
+    #[test]
+    fn sidecar_backs_up_existing_lrc() {
+        let dir = std::env::temp_dir().join(format!("vibe-on-lrc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let audio = dir.join("song.flac");
+
+        let (lrc, backup) = write_lrc_sidecar(&audio, "[00:01.00]One").unwrap();
+        assert_eq!(lrc, dir.join("song.lrc"));
+        assert_eq!(backup, None);
+
+        // Same content again: nothing to back up
+        assert_eq!(write_lrc_sidecar(&audio, "[00:01.00]One").unwrap().1, None);
+
+        let (_, backup) = write_lrc_sidecar(&audio, "[00:01.00]Two").unwrap();
+        assert_eq!(backup, Some(dir.join("song.lrc.bak")));
+        let (_, backup) = write_lrc_sidecar(&audio, "[00:01.00]Three").unwrap();
+        assert_eq!(backup, Some(dir.join("song.lrc.1.bak")));
+
+        assert_eq!(std::fs::read_to_string(dir.join("song.lrc")).unwrap(), "[00:01.00]Three");
+        assert_eq!(std::fs::read_to_string(dir.join("song.lrc.bak")).unwrap(), "[00:01.00]One");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn line_synced_lyrics_have_no_word_timing() {
+        let lrc = "[00:01.00]First line\n[00:05.50]Second line";
+        assert_eq!(parse_word_timed_lyrics(lrc), None);
+    }
 }
@@ -1,7 +1,10 @@
 use lindera_core::mode::Mode;
 use lindera_dictionary::{DictionaryConfig, DictionaryKind};
 use lindera_tokenizer::tokenizer::{Tokenizer, TokenizerConfig};
-use std::sync::OnceLock;
+use pinyin::ToPinyin;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
 use wana_kana::ConvertJapanese;
 
 // Global Tokenizer instance to avoid reloading dictionary (approx 100ms-500ms)
@@ -26,6 +29,63 @@ fn get_tokenizer() -> &'static Tokenizer {
     })
 }
 
+/// Entries kept before the cache is reset, so lyric lines can't grow it without bound
+const MAX_CACHE_ENTRIES: usize = 8192;
+
+/// Memoized transliterations keyed on the input string.
+/// Library scans repeat the same artist and album names on every track.
+#[derive(Default)]
+pub struct TransliterationCache {
+    entries: Mutex<HashMap<String, Option<String>>>,
+    hits: AtomicUsize,
+}
+
+impl TransliterationCache {
+    pub fn get_or_insert_with(
+        &self,
+        text: &str,
+        compute: impl FnOnce(&str) -> Option<String>,
+    ) -> Option<String> {
+        if let Some(cached) = self.entries.lock().unwrap().get(text) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return cached.clone();
+        }
+
+        // Compute outside the lock; tokenizing can take a while
+        let value = compute(text);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_CACHE_ENTRIES {
+            entries.clear();
+        }
+        entries.insert(text.to_string(), value.clone());
+        value
+    }
+
+    /// The memoized result for `text`, if it was computed
+    pub fn get(&self, text: &str) -> Option<Option<String>> {
+        self.entries.lock().unwrap().get(text).cloned()
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+static CACHE: OnceLock<TransliterationCache> = OnceLock::new();
+
+pub(crate) fn cache() -> &'static TransliterationCache {
+    CACHE.get_or_init(TransliterationCache::default)
+}
+
 /// Check if text contains Japanese characters (Hiragana, Katakana, Kanji)
 pub fn has_japanese(text: &str) -> bool {
     text.chars().any(|c| {
@@ -39,6 +99,256 @@ pub fn has_japanese(text: &str) -> bool {
     })
 }
 
+/// Script of a line of text that we know how to romanize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Japanese,
+    Korean,
+    Chinese,
+}
+
+impl Script {
+    /// ISO 639-1 code, used by the UI to label transliterations
+    pub fn code(self) -> &'static str {
+        match self {
+            Script::Japanese => "ja",
+            Script::Korean => "ko",
+            Script::Chinese => "zh",
+        }
+    }
+
+    /// Whether a character belongs to a span this script transliterates
+    fn contains(self, c: char) -> bool {
+        match self {
+            Script::Japanese => is_kana(c) || is_han(c) || c == '々',
+            Script::Korean => is_hangul(c),
+            Script::Chinese => is_han(c),
+        }
+    }
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF)
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF)
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c as u32, 0xAC00..=0xD7A3)
+}
+
+/// Check if text contains Hangul syllables
+pub fn has_korean(text: &str) -> bool {
+    text.chars().any(is_hangul)
+}
+
+/// Check if text contains Han characters without any kana (i.e. Chinese rather than Japanese)
+pub fn has_chinese(text: &str) -> bool {
+    text.chars().any(is_han) && !text.chars().any(is_kana)
+}
+
+/// Pick the transliteration route for a piece of text. Kana means Japanese,
+/// Hangul means Korean, and Han characters on their own are treated as Chinese.
+pub fn detect_script(text: &str) -> Option<Script> {
+    detect_script_as(text, Script::Chinese)
+}
+
+/// `detect_script` with Han-only text read as `han`
+fn detect_script_as(text: &str, han: Script) -> Option<Script> {
+    if text.chars().any(is_kana) {
+        Some(Script::Japanese)
+    } else if has_korean(text) {
+        Some(Script::Korean)
+    } else if has_chinese(text) {
+        Some(han)
+    } else {
+        None
+    }
+}
+
+/// How the Han-only lines of a song are read. Japanese lyrics often have
+/// kanji-only lines, so any kana in the song makes them Japanese.
+fn han_script_of<'a>(mut lines: impl Iterator<Item = &'a str>) -> Script {
+    if lines.any(|line| line.chars().any(is_kana)) {
+        Script::Japanese
+    } else {
+        Script::Chinese
+    }
+}
+
+/// Whether `text` has any CJK characters we can romanize
+pub fn needs_transliteration(text: &str) -> bool {
+    detect_script(text).is_some()
+}
+
+/// Most common script across the lines of a lyrics file
+pub fn detect_lyrics_language(content: &str) -> Option<Script> {
+    let han = han_script_of(content.lines());
+    let mut counts = [0usize; 3];
+    for line in content.lines() {
+        match detect_script_as(line, han) {
+            Some(Script::Japanese) => counts[0] += 1,
+            Some(Script::Korean) => counts[1] += 1,
+            Some(Script::Chinese) => counts[2] += 1,
+            None => {}
+        }
+    }
+    let (best, &count) = counts.iter().enumerate().max_by_key(|&(_, c)| *c)?;
+    if count == 0 {
+        return None;
+    }
+    Some([Script::Japanese, Script::Korean, Script::Chinese][best])
+}
+
+/// Romanize the CJK spans of `text` for whichever script it is written in,
+/// leaving Latin words and punctuation untouched, in title case for track
+/// metadata. Returns `None` for text without any CJK characters. Results are
+/// memoized.
+pub fn transliterate(text: &str) -> Option<String> {
+    transliterate_line(text, Script::Chinese).map(|romaji| to_title_case(&romaji))
+}
+
+/// Romanize one lyric line as written, reading Han-only text as `han`
+fn transliterate_line(text: &str, han: Script) -> Option<String> {
+    detect_script_as(text, han)?;
+    // The same line can read differently depending on the song around it
+    let key = match han {
+        Script::Chinese => text.to_string(),
+        _ => format!("{}\u{0}{}", han.code(), text),
+    };
+    cache().get_or_insert_with(&key, |_| transliterate_uncached(text, han))
+}
+
+/// Romanize the lines of one song, deciding how to read Han-only lines from
+/// the song as a whole
+pub fn transliterate_lines(lines: &[String]) -> Vec<Option<String>> {
+    let han = han_script_of(lines.iter().map(String::as_str));
+    lines.iter().map(|line| transliterate_line(line, han)).collect()
+}
+
+fn transliterate_uncached(text: &str, han: Script) -> Option<String> {
+    let script = detect_script_as(text, han)?;
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut span = String::new();
+    let mut other = String::new();
+
+    let flush_span = |span: &mut String, parts: &mut Vec<String>| {
+        if !span.is_empty() {
+            parts.push(match script {
+                Script::Japanese => to_romaji(span),
+                Script::Korean => romanize_korean(span),
+                Script::Chinese => to_pinyin(span),
+            });
+            span.clear();
+        }
+    };
+    let flush_other = |other: &mut String, parts: &mut Vec<String>| {
+        let trimmed = other.trim();
+        if !trimmed.is_empty() {
+            parts.push(trimmed.to_string());
+        }
+        other.clear();
+    };
+
+    for c in text.chars() {
+        if script.contains(c) {
+            flush_other(&mut other, &mut parts);
+            span.push(c);
+        } else {
+            flush_span(&mut span, &mut parts);
+            other.push(c);
+        }
+    }
+    flush_span(&mut span, &mut parts);
+    flush_other(&mut other, &mut parts);
+
+    Some(parts.join(" "))
+}
+
+const HANGUL_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+];
+const HANGUL_MEDIALS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+/// Final consonants at the end of a word or before another consonant
+const HANGUL_FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+/// Final consonants carried over into a following vowel-initial syllable
+const HANGUL_FINALS_LINKED: [&str; 28] = [
+    "", "g", "kk", "gs", "n", "nj", "nh", "d", "r", "lg", "lm", "lb", "ls", "lt", "lp", "lh", "m",
+    "b", "bs", "s", "ss", "ng", "j", "ch", "k", "t", "p", "h",
+];
+const HANGUL_SILENT_INITIAL: u32 = 11;
+const HANGUL_RIEUL_INITIAL: u32 = 5;
+const HANGUL_RIEUL_FINAL: u32 = 8;
+
+/// Romanize Hangul using the Revised Romanization of Korean.
+/// Handles final-consonant linking and ㄹㄹ → "ll"; other sound changes are not applied.
+pub fn romanize_korean(text: &str) -> String {
+    let syllables: Vec<Option<(u32, u32, u32)>> = text
+        .chars()
+        .map(|c| {
+            is_hangul(c).then(|| {
+                let index = c as u32 - 0xAC00;
+                (index / (21 * 28), (index % (21 * 28)) / 28, index % 28)
+            })
+        })
+        .collect();
+
+    let mut result = String::new();
+    for (i, (c, syllable)) in text.chars().zip(&syllables).enumerate() {
+        let Some((initial, medial, final_)) = *syllable else {
+            result.push(c);
+            continue;
+        };
+        let next = syllables.get(i + 1).copied().flatten();
+        let prev_final = i
+            .checked_sub(1)
+            .and_then(|p| syllables[p])
+            .map(|(_, _, f)| f);
+
+        // A preceding ㄹ turns this syllable's ㄹ into "l"
+        if initial == HANGUL_RIEUL_INITIAL && prev_final == Some(HANGUL_RIEUL_FINAL) {
+            result.push('l');
+        } else {
+            result.push_str(HANGUL_INITIALS[initial as usize]);
+        }
+        result.push_str(HANGUL_MEDIALS[medial as usize]);
+
+        let linked = next.is_some_and(|(n, _, _)| n == HANGUL_SILENT_INITIAL) && final_ != 21;
+        result.push_str(if linked {
+            HANGUL_FINALS_LINKED[final_ as usize]
+        } else {
+            HANGUL_FINALS[final_ as usize]
+        });
+    }
+    result
+}
+
+/// Convert Han characters to toned pinyin, one syllable per character
+pub fn to_pinyin(text: &str) -> String {
+    let mut result = String::new();
+    for (c, reading) in text.chars().zip(text.to_pinyin()) {
+        match reading {
+            Some(p) => {
+                if !result.is_empty() && !result.ends_with(' ') {
+                    result.push(' ');
+                }
+                result.push_str(p.with_tone());
+            }
+            None => result.push(c),
+        }
+    }
+    result
+}
+
 /// Transliterate Japanese text to Romaji
 pub fn to_romaji(text: &str) -> String {
     if !has_japanese(text) {
@@ -84,11 +394,8 @@ pub fn to_romaji(text: &str) -> String {
         result.push_str(&romaji);
     }
 
-    // Post-processing: Title Case?
-    // "aoi sora" -> "Aoi Sora" usually looks better for titles.
-    // Let's do simple capitalization of first letter of sentence for now, or each word?
-    // Title Case for metadata is safer.
-    to_title_case(result.trim())
+    // Casing is left to the caller: metadata gets title case, lyrics don't
+    result.trim().to_string()
 }
 
 fn to_title_case(s: &str) -> String {
@@ -105,8 +412,9 @@ fn to_title_case(s: &str) -> String {
 }
 
 /// Convert lyrics content (multiple lines) to Romaji
-/// Preserves structure but converts Japanese lines.
+/// Preserves structure and appends a romanization to each Japanese, Korean or Chinese line.
 pub fn transliterate_lyrics(content: &str) -> String {
+    let han = han_script_of(content.lines());
     let mut result = String::new();
 
     for line in content.lines() {
@@ -115,32 +423,14 @@ pub fn transliterate_lyrics(content: &str) -> String {
                 let timestamp = &line[start..=end];
                 let text = line[end + 1..].trim();
 
-                if has_japanese(text) {
-                    // Verify if it needs conversion.
-                    // Sometimes lyrics line is empty or just punctuation.
-                    if text.is_empty() {
-                        result.push_str(&format!("{}\n", line));
-                        continue;
-                    }
-
-                    let romaji = text.to_romaji();
-                    // Heuristic: if romaji is vastly different or just same, handle?
-                    // Verify " / " separator isn't already there?
-                    // The requirement is to MERGE.
-                    // Wait, if I'm transliterating, I probably want to REPLACE the line
-                    // OR append it like merge logic.
-                    // "Original / Romaji"
-
-                    // check if line already has " / "
-                    if text.contains(" / ") {
-                        // assume already merged? or maybe just update
-                        result.push_str(&format!("{}\n", line));
-                    } else {
-                        // Append Romaji
+                // Sometimes lyrics line is empty or just punctuation.
+                // Lines that already contain " / " are assumed to be merged.
+                match transliterate_line(text, han) {
+                    Some(romaji) if !text.contains(" / ") => {
+                        // Append Romaji: "Original / Romaji"
                         result.push_str(&format!("{} {} / {}\n", timestamp, text, romaji));
                     }
-                } else {
-                    result.push_str(&format!("{}\n", line));
+                    _ => result.push_str(&format!("{}\n", line)),
                 }
                 continue;
             }
@@ -150,3 +440,71 @@ pub fn transliterate_lyrics(content: &str) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_script_per_text() {
+        assert_eq!(detect_script("こんにちは世界"), Some(Script::Japanese));
+        assert_eq!(detect_script("사랑해"), Some(Script::Korean));
+        assert_eq!(detect_script("我爱你"), Some(Script::Chinese));
+        assert_eq!(detect_script("Hello"), None);
+        assert_eq!(
+            detect_lyrics_language("[00:01.00]안녕\n[00:02.00]Oh yeah\n[00:03.00]사랑해"),
+            Some(Script::Korean)
+        );
+    }
+
+    #[test]
+    fn romanizes_korean_with_linking() {
+        assert_eq!(romanize_korean("한국어"), "hangugeo");
+        assert_eq!(romanize_korean("사랑해"), "saranghae");
+        assert_eq!(romanize_korean("서울"), "seoul");
+        assert_eq!(romanize_korean("빨리"), "ppalli");
+    }
+
+    #[test]
+    fn converts_chinese_to_pinyin() {
+        assert_eq!(to_pinyin("我爱你"), "wǒ ài nǐ");
+    }
+
+    #[test]
+    fn mixed_lines_only_transliterate_cjk_spans() {
+        assert_eq!(transliterate("Love 사랑해 baby").as_deref(), Some("Love Saranghae Baby"));
+        assert_eq!(transliterate("Just English"), None);
+    }
+
+    #[test]
+    fn kanji_only_lines_follow_the_song() {
+        let lrc = "[00:01.00]夜に駆ける\n[00:02.00]世界\n[00:03.00]Oh yeah";
+        assert_eq!(detect_lyrics_language(lrc), Some(Script::Japanese));
+        let romanized = transliterate_lyrics(lrc);
+        assert!(romanized.contains("[00:02.00]世界 / sekai"), "{}", romanized);
+
+        // Without kana anywhere the same line stays Chinese
+        let lines = vec!["世界".to_string(), "我爱你".to_string()];
+        assert_eq!(transliterate_lines(&lines)[0].as_deref(), Some("shì jiè"));
+    }
+
+    #[test]
+    fn cache_computes_each_name_once() {
+        let cache = TransliterationCache::default();
+        let computed = AtomicUsize::new(0);
+        let names = ["ヨルシカ", "방탄소년단", "周杰倫"];
+
+        for i in 0..1000 {
+            let name = names[i % names.len()];
+            let value = cache.get_or_insert_with(name, |text| {
+                computed.fetch_add(1, Ordering::Relaxed);
+                transliterate_uncached(text, Script::Chinese)
+            });
+            assert_eq!(value, transliterate_uncached(name, Script::Chinese));
+        }
+
+        assert_eq!(computed.load(Ordering::Relaxed), names.len());
+        assert_eq!(cache.hits(), 1000 - names.len());
+        assert_eq!(cache.len(), names.len());
+    }
+}
@@ -2,8 +2,10 @@
 //!
 //! Discovers VIBE-ON! peers on the local network using mDNS
 
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
+use libp2p::multiaddr::Protocol;
 use libp2p::{mdns, Multiaddr, PeerId, Swarm};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
@@ -57,6 +59,25 @@ impl DiscoveredPeer {
     }
 }
 
+/// Parse a user-entered `ip:port` (IPv6 as `[addr]:port`) into a QUIC multiaddr
+pub fn parse_peer_address(address: &str) -> Result<Multiaddr, String> {
+    let socket: SocketAddr = address.trim().parse().map_err(|_| {
+        format!("Invalid address \"{}\": expected ip:port, e.g. 192.168.1.20:4001", address.trim())
+    })?;
+    if socket.port() == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+
+    let ip = match socket.ip() {
+        IpAddr::V4(ip) => Protocol::Ip4(ip),
+        IpAddr::V6(ip) => Protocol::Ip6(ip),
+    };
+    Ok(Multiaddr::empty()
+        .with(ip)
+        .with(Protocol::Udp(socket.port()))
+        .with(Protocol::QuicV1))
+}
+
 /// Handle mDNS events
 pub async fn handle_mdns_event(
     swarm: &mut Swarm<StreamingBehaviour>,
@@ -114,3 +135,23 @@ pub async fn handle_mdns_event(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ip_and_port_into_quic_multiaddr() {
+        assert_eq!(
+            parse_peer_address("192.168.1.20:4001").unwrap().to_string(),
+            "/ip4/192.168.1.20/udp/4001/quic-v1"
+        );
+        assert_eq!(
+            parse_peer_address("[::1]:4001").unwrap().to_string(),
+            "/ip6/::1/udp/4001/quic-v1"
+        );
+        assert!(parse_peer_address("192.168.1.20").is_err());
+        assert!(parse_peer_address("not-an-ip:4001").is_err());
+        assert!(parse_peer_address("10.0.0.1:0").is_err());
+    }
+}
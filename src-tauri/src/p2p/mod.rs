@@ -12,14 +12,16 @@ pub mod discovery;
 pub mod protocol;
 pub mod transport;
 
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use futures::StreamExt;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{Multiaddr, PeerId, Swarm};
-use tokio::sync::{mpsc, RwLock};
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 use self::discovery::DiscoveredPeer;
 use self::protocol::{StreamRequest, StreamingBehaviour};
@@ -40,6 +42,11 @@ pub enum P2PCommand {
         track_path: String,
         start_byte: u64,
     },
+    /// Request a track with its sidecars from a peer, to keep
+    RequestFileBundle {
+        peer_id: PeerId,
+        track_path: String,
+    },
     /// Stop current streaming session
     StopStream,
     /// Connect to a specific peer
@@ -72,8 +79,12 @@ pub enum P2PEvent {
         channels: u16,
         duration_secs: f64,
         prebuffered: bool,
+        /// Offset the data starts at; non-zero when an interrupted transfer resumed
+        start_byte: u64,
+        /// MD5 of the whole file (hex), if the sender provided one
+        checksum: Option<String>,
     },
-    /// Received audio data chunk
+    /// Received audio data chunk. Not forwarded to the frontend, see `ui_event`.
     AudioData {
         sequence: u64,
         data: Vec<u8>,
@@ -85,6 +96,77 @@ pub enum P2PEvent {
     Error(String),
     /// List of current peers
     PeerList(Vec<DiscoveredPeer>),
+    /// A requested file bundle was written to disk (audio file first)
+    BundleSaved {
+        peer_id: PeerId,
+        saved_paths: Vec<PathBuf>,
+    },
+    /// Periodic progress of the outgoing stream
+    TransferProgress {
+        peer_id: PeerId,
+        bytes_sent: u64,
+        total_bytes: u64,
+        bytes_per_sec: f64,
+    },
+}
+
+impl P2PEvent {
+    /// Name and payload of the frontend event this becomes. `AudioData` has
+    /// none: raw file bytes are too much for the UI and nothing plays them back
+    /// yet, so chunks of a transfer that later fails its whole-file checksum
+    /// never reach a consumer. Buffer them until it passes before adding one.
+    pub fn ui_event(self) -> Option<(&'static str, serde_json::Value)> {
+        use serde_json::json;
+        let event = match self {
+            P2PEvent::StreamReady { peer_id, format, file_size, duration_secs, prebuffered, start_byte, .. } => (
+                "p2p-stream-ready",
+                json!({
+                    "peerId": peer_id.to_base58(),
+                    "format": format,
+                    "fileSize": file_size,
+                    "durationSecs": duration_secs,
+                    "prebuffered": prebuffered,
+                    "startByte": start_byte,
+                }),
+            ),
+            P2PEvent::StreamEnded => ("p2p-stream-ended", json!(null)),
+            P2PEvent::Error(message) => ("p2p-error", json!(message)),
+            P2PEvent::TransferProgress { peer_id, bytes_sent, total_bytes, bytes_per_sec } => (
+                "p2p-transfer-progress",
+                json!(TransferProgress {
+                    peer_id: peer_id.to_base58(),
+                    bytes_sent,
+                    total_bytes,
+                    bytes_per_sec,
+                }),
+            ),
+            P2PEvent::BundleSaved { peer_id, saved_paths } => (
+                "p2p-bundle-saved",
+                json!({
+                    "peerId": peer_id.to_base58(),
+                    "savedPaths": saved_paths,
+                }),
+            ),
+            P2PEvent::PeerDiscovered(peer) => ("p2p-peer-discovered", json!(peer)),
+            P2PEvent::PeerLost(peer_id) => ("p2p-peer-lost", json!(peer_id.to_base58())),
+            P2PEvent::PeerConnected(peer_id) => ("p2p-peer-connected", json!(peer_id.to_base58())),
+            P2PEvent::PeerDisconnected(peer_id) => {
+                ("p2p-peer-disconnected", json!(peer_id.to_base58()))
+            }
+            P2PEvent::PeerList(peers) => ("p2p-peer-list", json!(peers)),
+            P2PEvent::AudioData { .. } => return None,
+        };
+        Some(event)
+    }
+}
+
+/// Latest progress of the outgoing stream, as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub peer_id: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub bytes_per_sec: f64,
 }
 
 /// Information about an active stream
@@ -97,6 +179,65 @@ pub struct ActiveStream {
     pub is_sending: bool,
 }
 
+/// Where an interrupted incoming transfer left off
+#[derive(Debug, Clone)]
+pub struct ResumePoint {
+    pub peer_id: PeerId,
+    pub track_path: String,
+    /// Offset of the next byte we expect
+    pub offset: u64,
+    /// Sequence number of the last chunk received
+    pub last_sequence: Option<u64>,
+    /// Total file size once the header arrived (0 before that)
+    pub file_size: u64,
+}
+
+impl ResumePoint {
+    pub fn new(peer_id: PeerId, track_path: String, offset: u64) -> Self {
+        Self {
+            peer_id,
+            track_path,
+            offset,
+            last_sequence: None,
+            file_size: 0,
+        }
+    }
+
+    /// Offset to request `track_path` from `peer_id` at. Anything other than
+    /// the cached file from the same peer restarts from the beginning.
+    pub fn offset_for(&self, peer_id: &PeerId, track_path: &str) -> u64 {
+        if self.peer_id == *peer_id && self.track_path == track_path {
+            self.offset
+        } else {
+            0
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.file_size > 0 && self.offset >= self.file_size
+    }
+}
+
+/// Whether a path a peer asked for is a track in our library
+pub type LibraryCheck = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Reply channel for `request_file_bundle`
+pub type BundleReply = oneshot::Sender<Result<Vec<PathBuf>, String>>;
+
+/// A file bundle we asked a peer for and where to save it
+pub struct PendingBundle {
+    pub peer_id: PeerId,
+    pub dest_dir: PathBuf,
+    reply: BundleReply,
+}
+
+impl PendingBundle {
+    /// Tell the requester how the transfer ended
+    pub fn finish(self, result: Result<Vec<PathBuf>, String>) {
+        let _ = self.reply.send(result);
+    }
+}
+
 /// P2P Manager state
 pub struct P2PState {
     /// Currently discovered peers
@@ -109,6 +250,18 @@ pub struct P2PState {
     pub local_peer_id: PeerId,
     /// Device name for discovery
     pub device_name: String,
+    /// Peers allowed to request tracks from us
+    pub paired_peers: HashSet<PeerId>,
+    /// Limits requests to library tracks; nothing is served until it is set
+    pub library_check: Option<LibraryCheck>,
+    /// Most recent outgoing transfer progress
+    pub transfer_progress: Option<TransferProgress>,
+    /// Progress of the incoming transfer, kept across disconnects to resume it
+    pub resume_point: Option<ResumePoint>,
+    /// Running checksum of the incoming file, verified after the last chunk
+    pub incoming_checksum: Option<protocol::IncomingChecksum>,
+    /// File bundle awaiting a response; a newer request replaces it
+    pub pending_bundle: Option<PendingBundle>,
 }
 
 impl P2PState {
@@ -119,8 +272,41 @@ impl P2PState {
             incoming_stream: None,
             local_peer_id,
             device_name,
+            paired_peers: HashSet::new(),
+            library_check: None,
+            transfer_progress: None,
+            resume_point: None,
+            incoming_checksum: None,
+            pending_bundle: None,
         }
     }
+
+    /// Request for the rest of an interrupted transfer after reconnecting to
+    /// `peer_id`. A resume point whose transfer already finished is dropped.
+    pub fn resume_request(&mut self, peer_id: &PeerId) -> Option<StreamRequest> {
+        let point = self.resume_point.as_ref().filter(|point| point.peer_id == *peer_id)?;
+        if point.is_complete() {
+            self.resume_point = None;
+            return None;
+        }
+        Some(StreamRequest::RequestTrack {
+            track_path: point.track_path.clone(),
+            start_byte: point.offset,
+        })
+    }
+}
+
+/// Whether the P2P manager is up, so the UI can explain an empty peer list
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(tag = "status", content = "message")]
+pub enum P2PStatus {
+    /// Initialization has not started
+    #[default]
+    Disabled,
+    Initializing,
+    Ready,
+    /// Initialization failed with this error; it can be retried
+    Failed(String),
 }
 
 /// P2P Manager handles all peer-to-peer operations
@@ -129,8 +315,8 @@ pub struct P2PManager {
     state: Arc<RwLock<P2PState>>,
     /// Command sender
     cmd_tx: mpsc::Sender<P2PCommand>,
-    /// Event receiver
-    event_rx: mpsc::Receiver<P2PEvent>,
+    /// Event receiver, until taken by `take_event_receiver`
+    event_rx: Option<mpsc::Receiver<P2PEvent>>,
 }
 
 impl P2PManager {
@@ -151,7 +337,7 @@ impl P2PManager {
         Ok(Self {
             state,
             cmd_tx,
-            event_rx,
+            event_rx: Some(event_rx),
         })
     }
     
@@ -165,6 +351,36 @@ impl P2PManager {
         self.state.read().await.peers.values().cloned().collect()
     }
     
+    /// Latest progress of the outgoing stream
+    pub async fn transfer_progress(&self) -> Option<TransferProgress> {
+        self.state.read().await.transfer_progress.clone()
+    }
+
+    /// Peers allowed to stream from us
+    pub async fn paired_peers(&self) -> Vec<PeerId> {
+        self.state.read().await.paired_peers.iter().copied().collect()
+    }
+
+    /// Replace the allowlist (e.g. with the persisted one at startup)
+    pub async fn set_paired_peers(&self, peers: impl IntoIterator<Item = PeerId>) {
+        self.state.write().await.paired_peers = peers.into_iter().collect();
+    }
+
+    /// Set how requested paths are checked against the library
+    pub async fn set_library_check(&self, check: LibraryCheck) {
+        self.state.write().await.library_check = Some(check);
+    }
+
+    /// Allow a peer to request tracks
+    pub async fn pair_peer(&self, peer_id: PeerId) {
+        self.state.write().await.paired_peers.insert(peer_id);
+    }
+
+    /// Revoke a peer's streaming access; returns whether it was paired
+    pub async fn unpair_peer(&self, peer_id: &PeerId) -> bool {
+        self.state.write().await.paired_peers.remove(peer_id)
+    }
+
     /// Send a command to the P2P manager
     pub async fn send_command(&self, cmd: P2PCommand) -> Result<(), mpsc::error::SendError<P2PCommand>> {
         self.cmd_tx.send(cmd).await
@@ -172,12 +388,19 @@ impl P2PManager {
     
     /// Try to receive the next event (non-blocking)
     pub fn try_recv_event(&mut self) -> Option<P2PEvent> {
-        self.event_rx.try_recv().ok()
+        self.event_rx.as_mut()?.try_recv().ok()
     }
     
     /// Receive the next event (blocking)
     pub async fn recv_event(&mut self) -> Option<P2PEvent> {
-        self.event_rx.recv().await
+        self.event_rx.as_mut()?.recv().await
+    }
+
+    /// Hand the event stream to a consumer that outlives borrows of the
+    /// manager, such as the app's event bridge. Afterwards `recv_event` and
+    /// `try_recv_event` return `None`.
+    pub fn take_event_receiver(&mut self) -> Option<mpsc::Receiver<P2PEvent>> {
+        self.event_rx.take()
     }
     
     /// Request a stream from a peer
@@ -208,6 +431,20 @@ impl P2PManager {
         }).await
     }
     
+    /// Ask a peer for a track plus its lyrics and cover, saved into `dest_dir`.
+    /// The receiver resolves with the saved paths once the transfer ends.
+    pub async fn request_file_bundle(
+        &self,
+        peer_id: PeerId,
+        track_path: String,
+        dest_dir: PathBuf,
+    ) -> Result<oneshot::Receiver<Result<Vec<PathBuf>, String>>, mpsc::error::SendError<P2PCommand>> {
+        let (reply, rx) = oneshot::channel();
+        self.state.write().await.pending_bundle = Some(PendingBundle { peer_id, dest_dir, reply });
+        self.send_command(P2PCommand::RequestFileBundle { peer_id, track_path }).await?;
+        Ok(rx)
+    }
+
     /// Stop current stream
     pub async fn stop_stream(&self) -> Result<(), mpsc::error::SendError<P2PCommand>> {
         self.send_command(P2PCommand::StopStream).await
@@ -233,11 +470,25 @@ async fn run_event_loop(
         return;
     }
     
+    // Responses that needed a file read come back here to be sent
+    let (reply_tx, mut reply_rx) = mpsc::channel(32);
+    let mut progress_tick = tokio::time::interval(protocol::PROGRESS_INTERVAL);
+    let mut progress_tracker = protocol::ProgressTracker::default();
+
     loop {
         tokio::select! {
+            // Report outgoing transfer progress
+            _ = progress_tick.tick() => {
+                protocol::report_transfer_progress(&state, &event_tx, &mut progress_tracker).await;
+            }
+
             // Handle incoming swarm events
             event = swarm.select_next_some() => {
-                handle_swarm_event(&mut swarm, &state, &event_tx, event).await;
+                handle_swarm_event(&mut swarm, &state, &event_tx, &reply_tx, event).await;
+            }
+
+            Some(pending) = reply_rx.recv() => {
+                protocol::send_pending_response(&mut swarm, &state, pending).await;
             }
             
             // Handle commands
@@ -256,6 +507,27 @@ async fn run_event_loop(
                         let _ = event_tx.send(P2PEvent::PeerList(peers)).await;
                     }
                     P2PCommand::RequestStream { peer_id, track_path, start_byte } => {
+                        let start_byte = {
+                            let mut state = state.write().await;
+                            // Continue an interrupted transfer of the same file instead of restarting
+                            let start_byte = match state.resume_point {
+                                Some(ref point) if start_byte == 0 => point.offset_for(&peer_id, &track_path),
+                                _ => start_byte,
+                            };
+                            state.resume_point = Some(ResumePoint::new(peer_id, track_path.clone(), start_byte));
+                            if start_byte == 0 {
+                                state.incoming_checksum = None;
+                            }
+                            state.incoming_stream = Some(ActiveStream {
+                                peer_id,
+                                track_path: PathBuf::from(&track_path),
+                                file_size: 0,
+                                bytes_sent: start_byte,
+                                is_sending: false,
+                            });
+                            start_byte
+                        };
+
                         // Send stream request to peer
                         let request = StreamRequest::RequestTrack {
                             track_path,
@@ -263,6 +535,10 @@ async fn run_event_loop(
                         };
                         swarm.behaviour_mut().streaming.send_request(&peer_id, request);
                     }
+                    P2PCommand::RequestFileBundle { peer_id, track_path } => {
+                        let request = StreamRequest::RequestFileBundle { track_path };
+                        swarm.behaviour_mut().streaming.send_request(&peer_id, request);
+                    }
                     P2PCommand::StreamToPeer { peer_id, track_path, start_byte: _ } => {
                         // Start streaming to peer (handled in protocol)
                         let mut state = state.write().await;
@@ -278,6 +554,8 @@ async fn run_event_loop(
                         let mut state = state.write().await;
                         state.outgoing_stream = None;
                         state.incoming_stream = None;
+                        state.resume_point = None;
+                        state.incoming_checksum = None;
                         let _ = event_tx.send(P2PEvent::StreamEnded).await;
                     }
                     P2PCommand::Seek { byte_offset } => {
@@ -303,13 +581,34 @@ async fn handle_swarm_event(
     swarm: &mut Swarm<StreamingBehaviour>,
     state: &Arc<RwLock<P2PState>>,
     event_tx: &mpsc::Sender<P2PEvent>,
+    reply_tx: &mpsc::Sender<protocol::PendingResponse>,
     event: SwarmEvent<protocol::StreamingBehaviourEvent>,
 ) {
     match event {
         SwarmEvent::NewListenAddr { address, .. } => {
             log::info!("Listening on {}", address);
         }
-        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+        SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+            // Peers dialed by address never went through mDNS; list them like discovered ones
+            if endpoint.is_dialer() {
+                let mut state = state.write().await;
+                if let Entry::Vacant(slot) = state.peers.entry(peer_id) {
+                    let peer = DiscoveredPeer {
+                        is_local: false,
+                        ..DiscoveredPeer::new(peer_id, vec![endpoint.get_remote_address().clone()])
+                    };
+                    slot.insert(peer.clone());
+                    drop(state);
+                    let _ = event_tx.send(P2PEvent::PeerDiscovered(peer)).await;
+                }
+            }
+
+            // Reconnected mid-transfer: ask for the rest of the file
+            let resume = state.write().await.resume_request(&peer_id);
+            if let Some(request) = resume {
+                log::info!("Resuming transfer from {}: {:?}", peer_id, request);
+                swarm.behaviour_mut().streaming.send_request(&peer_id, request);
+            }
             let _ = event_tx.send(P2PEvent::PeerConnected(peer_id)).await;
         }
         SwarmEvent::ConnectionClosed { peer_id, .. } => {
@@ -321,7 +620,7 @@ async fn handle_swarm_event(
                     discovery::handle_mdns_event(swarm, state, event_tx, mdns_event).await;
                 }
                 protocol::StreamingBehaviourEvent::Streaming(streaming_event) => {
-                    protocol::handle_streaming_event(swarm, state, event_tx, streaming_event).await;
+                    protocol::handle_streaming_event(swarm, state, event_tx, reply_tx, streaming_event).await;
                 }
                 protocol::StreamingBehaviourEvent::Identify(libp2p::identify::Event::Received { peer_id, info, connection_id: _ }) => {
                     log::debug!("Identified peer {}: {:?}", peer_id, info.agent_version);
@@ -339,3 +638,46 @@ pub fn get_device_name() -> String {
         .map(|h| h.to_string_lossy().to_string())
         .unwrap_or_else(|_| "VIBE-ON Desktop".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_resumes_from_the_last_byte_received() {
+        let peer = PeerId::random();
+        let mut state = P2PState::new(PeerId::random(), "test".to_string());
+        let mut point = ResumePoint::new(peer, "/music/song.flac".to_string(), 0);
+        point.offset = 131072;
+        point.file_size = 500000;
+        state.resume_point = Some(point);
+
+        // Another peer connecting doesn't touch the transfer
+        assert!(state.resume_request(&PeerId::random()).is_none());
+        match state.resume_request(&peer) {
+            Some(StreamRequest::RequestTrack { track_path, start_byte }) => {
+                assert_eq!(track_path, "/music/song.flac");
+                assert_eq!(start_byte, 131072);
+            }
+            other => panic!("expected a track request, got {:?}", other),
+        }
+        // A different file from the same peer starts over
+        let point = state.resume_point.as_ref().unwrap();
+        assert_eq!(point.offset_for(&peer, "/music/other.flac"), 0);
+
+        // Once everything arrived there is nothing to resume, and the point goes away
+        state.resume_point.as_mut().unwrap().offset = 500000;
+        assert!(state.resume_request(&peer).is_none());
+        assert!(state.resume_point.is_none());
+    }
+
+    #[test]
+    fn audio_data_is_not_forwarded_to_the_frontend() {
+        let chunk = P2PEvent::AudioData { sequence: 0, data: vec![0; 16], is_last: true };
+        assert!(chunk.ui_event().is_none());
+
+        let (name, payload) = P2PEvent::Error("corrupted".to_string()).ui_event().unwrap();
+        assert_eq!(name, "p2p-error");
+        assert_eq!(payload, "corrupted");
+    }
+}
@@ -3,12 +3,15 @@
 //! Implements the `/vibe-on/stream/1.0.0` protocol:
 //! - StreamRequest: Request track, seek, or stop
 //! - StreamResponse: Header with metadata, chunks with audio data
+//!
+//! Each request gets a single response, so the receiver pulls a track one
+//! `RequestChunk` at a time after its header arrives.
 
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::prelude::*;
 use libp2p::{
@@ -20,7 +23,7 @@ use libp2p::{
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, RwLock};
 
-use super::{P2PEvent, P2PState};
+use super::{P2PEvent, P2PState, TransferProgress};
 
 /// Chunk size for streaming (64KB)
 pub const CHUNK_SIZE: usize = 65536;
@@ -28,6 +31,12 @@ pub const CHUNK_SIZE: usize = 65536;
 /// Threshold for pre-buffering entire file (20MB)
 pub const PREBUFFER_THRESHOLD: u64 = 20 * 1024 * 1024;
 
+/// Largest file bundle we send; responses are read with a 100MB cap
+pub const MAX_BUNDLE_BYTES: u64 = 96 * 1024 * 1024;
+
+/// How often the sending side reports transfer progress
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Request messages for the streaming protocol
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StreamRequest {
@@ -36,10 +45,19 @@ pub enum StreamRequest {
         track_path: String,
         start_byte: u64,
     },
+    /// The chunk of a track starting at `offset`, once its header arrived
+    RequestChunk {
+        track_path: String,
+        offset: u64,
+    },
     /// Seek to a byte offset (for large files)
     Seek {
         byte_offset: u64,
     },
+    /// Request a track with its sidecar lyrics and cover, to keep
+    RequestFileBundle {
+        track_path: String,
+    },
     /// Stop the current stream
     Stop,
     /// Ping for keepalive
@@ -61,6 +79,12 @@ pub enum StreamResponse {
         duration_secs: f64,
         /// Total file size in bytes
         file_size: u64,
+        /// Offset the streamed data starts at
+        #[serde(default)]
+        start_byte: u64,
+        /// MD5 of the whole file (hex); absent from older peers
+        #[serde(default)]
+        checksum: Option<String>,
         /// Track title
         title: String,
         /// Track artist
@@ -76,6 +100,13 @@ pub enum StreamResponse {
         data: Vec<u8>,
         /// Is this the last chunk?
         is_last: bool,
+        /// CRC32 of `data`; absent from older peers
+        #[serde(default)]
+        checksum: Option<u32>,
+    },
+    /// A track and its sidecars, for adding to the receiver's library
+    FileBundle {
+        files: Vec<BundleFile>,
     },
     /// Seek acknowledgment
     SeekAck {
@@ -92,6 +123,129 @@ pub enum StreamResponse {
     },
 }
 
+/// What a bundled file is; the audio file always comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BundleFileKind {
+    Audio,
+    Lyrics,
+    Cover,
+}
+
+/// One file of a `FileBundle`, split into sequenced `Chunk`s
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub kind: BundleFileKind,
+    /// Bare file name; the receiver chooses the folder
+    pub file_name: String,
+    pub chunks: Vec<StreamResponse>,
+}
+
+impl BundleFile {
+    fn read(kind: BundleFileKind, path: &Path) -> std::io::Result<Self> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let data = std::fs::read(path)?;
+        let count = data.len().div_ceil(CHUNK_SIZE).max(1);
+        let chunks = (0..count)
+            .map(|i| {
+                let end = ((i + 1) * CHUNK_SIZE).min(data.len());
+                let chunk = data[i * CHUNK_SIZE..end].to_vec();
+                StreamResponse::chunk(i as u64, chunk, i + 1 == count)
+            })
+            .collect();
+        Ok(Self { kind, file_name, chunks })
+    }
+
+    /// Reassemble the file, checking sequence numbers and CRCs
+    fn assemble(self) -> Result<Vec<u8>, String> {
+        let mut data = Vec::new();
+        let count = self.chunks.len();
+        for (i, chunk) in self.chunks.into_iter().enumerate() {
+            let StreamResponse::Chunk { sequence, data: bytes, is_last, checksum } = chunk else {
+                return Err(format!("{}: unexpected message in bundle", self.file_name));
+            };
+            let crc_ok = checksum.is_none_or(|crc| crc == crc32fast::hash(&bytes));
+            if sequence != i as u64 || is_last != (i + 1 == count) || !crc_ok {
+                return Err(format!("{}: chunk {} is corrupted or out of order", self.file_name, i));
+            }
+            data.extend_from_slice(&bytes);
+        }
+        Ok(data)
+    }
+}
+
+impl StreamResponse {
+    /// A data chunk carrying its CRC32
+    pub fn chunk(sequence: u64, data: Vec<u8>, is_last: bool) -> Self {
+        let checksum = Some(crc32fast::hash(&data));
+        StreamResponse::Chunk { sequence, data, is_last, checksum }
+    }
+}
+
+/// Running MD5 of an incoming file, compared with the header's once the last
+/// chunk is in. Kept across a resume so the whole file is still covered.
+pub struct IncomingChecksum {
+    expected: String,
+    context: md5::Context,
+    /// Bytes hashed so far, i.e. the offset the next chunk must start at
+    bytes: u64,
+}
+
+impl IncomingChecksum {
+    pub fn new(expected: String) -> Self {
+        Self {
+            expected,
+            context: md5::Context::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Continue a checksum for a transfer resumed at `start_byte`. Returns
+    /// `None` when it doesn't cover everything before that offset.
+    pub fn resume(previous: Option<Self>, expected: &str, start_byte: u64) -> Option<Self> {
+        previous.filter(|c| c.expected == expected && c.bytes == start_byte)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.context.consume(data);
+        self.bytes += data.len() as u64;
+    }
+
+    pub fn matches(self) -> bool {
+        format!("{:x}", self.context.compute()).eq_ignore_ascii_case(&self.expected)
+    }
+}
+
+/// A response prepared off the swarm loop (file reads and hashing block),
+/// sent once the loop picks it up
+pub struct PendingResponse {
+    peer: PeerId,
+    channel: request_response::ResponseChannel<StreamResponse>,
+    response: StreamResponse,
+    /// Offset of the outgoing stream once this response is out, for chunks
+    sent_up_to: Option<u64>,
+}
+
+/// Send a response finished by a blocking task, counting chunk bytes toward
+/// the outgoing stream's progress only once they're handed to the connection
+pub async fn send_pending_response(
+    swarm: &mut libp2p::Swarm<StreamingBehaviour>,
+    state: &Arc<RwLock<P2PState>>,
+    pending: PendingResponse,
+) {
+    let PendingResponse { peer, channel, response, sent_up_to } = pending;
+    if swarm.behaviour_mut().streaming.send_response(channel, response).is_err() {
+        return;
+    }
+    if let Some(offset) = sent_up_to {
+        if let Some(stream) = state.write().await.outgoing_stream.as_mut().filter(|s| s.peer_id == peer) {
+            stream.bytes_sent = offset;
+        }
+    }
+}
+
 /// CBOR codec for the streaming protocol
 #[derive(Debug, Clone, Default)]
 pub struct StreamingCodec;
@@ -217,20 +371,24 @@ pub async fn handle_streaming_event(
     swarm: &mut libp2p::Swarm<StreamingBehaviour>,
     state: &Arc<RwLock<P2PState>>,
     event_tx: &mpsc::Sender<P2PEvent>,
+    reply_tx: &mpsc::Sender<PendingResponse>,
     event: request_response::Event<StreamRequest, StreamResponse>,
 ) {
     match event {
         request_response::Event::Message { peer, message } => {
             match message {
                 request_response::Message::Request { request, channel, .. } => {
-                    handle_incoming_request(swarm, state, peer, request, channel).await;
+                    handle_incoming_request(swarm, state, event_tx, reply_tx, peer, request, channel).await;
                 }
                 request_response::Message::Response { response, .. } => {
-                    handle_incoming_response(state, event_tx, peer, response).await;
+                    handle_incoming_response(swarm, state, event_tx, peer, response).await;
                 }
             }
         }
         request_response::Event::OutboundFailure { peer, error, .. } => {
+            if let Some(pending) = state.write().await.pending_bundle.take_if(|b| b.peer_id == peer) {
+                pending.finish(Err(format!("Request failed: {:?}", error)));
+            }
             let _ = event_tx.send(P2PEvent::Error(format!(
                 "Outbound request to {} failed: {:?}", peer, error
             ))).await;
@@ -248,42 +406,114 @@ pub async fn handle_streaming_event(
 async fn handle_incoming_request(
     swarm: &mut libp2p::Swarm<StreamingBehaviour>,
     state: &Arc<RwLock<P2PState>>,
+    event_tx: &mpsc::Sender<P2PEvent>,
+    reply_tx: &mpsc::Sender<PendingResponse>,
     peer: PeerId,
     request: StreamRequest,
     channel: request_response::ResponseChannel<StreamResponse>,
 ) {
+    // Only paired peers may pull files; discovery alone isn't enough
+    let wants_file = matches!(
+        request,
+        StreamRequest::RequestTrack { .. }
+            | StreamRequest::RequestChunk { .. }
+            | StreamRequest::RequestFileBundle { .. }
+    );
+    if wants_file && !state.read().await.paired_peers.contains(&peer) {
+        let _ = swarm.behaviour_mut().streaming.send_response(
+            channel,
+            StreamResponse::Error { message: "Peer is not paired with this device".to_string() },
+        );
+        let _ = event_tx.send(P2PEvent::Error(format!(
+            "Rejected stream request from unpaired peer {}", peer
+        ))).await;
+        return;
+    }
+
     match request {
         StreamRequest::RequestTrack { track_path, start_byte } => {
-            // Read the file and get metadata
+            if !is_library_track(state, &track_path).await {
+                let _ = swarm.behaviour_mut().streaming.send_response(
+                    channel,
+                    StreamResponse::Error { message: format!("{} is not in the library", track_path) },
+                );
+                return;
+            }
+
             let path = PathBuf::from(&track_path);
-            
-            match read_track_for_streaming(&path, start_byte) {
-                Ok((header, data)) => {
-                    // First send the header
-                    let _ = swarm.behaviour_mut().streaming.send_response(channel, header);
-                    
-                    // Then send chunks via new requests
-                    // Note: In a real implementation, we'd use a streaming subprotocol
-                    // For now, we send the full file in the header response for small files
-                    // and use multiple request/response cycles for large files
-                    
-                    // Update state
-                    let mut state = state.write().await;
-                    state.outgoing_stream = Some(super::ActiveStream {
-                        peer_id: peer,
-                        track_path: path,
-                        file_size: data.len() as u64,
-                        bytes_sent: data.len() as u64,
-                        is_sending: true,
-                    });
-                }
-                Err(e) => {
-                    let _ = swarm.behaviour_mut().streaming.send_response(
-                        channel,
-                        StreamResponse::Error { message: e.to_string() },
-                    );
-                }
+            let state = Arc::clone(state);
+            let reply_tx = reply_tx.clone();
+            tokio::spawn(async move {
+                let read_path = path.clone();
+                let read = tokio::task::spawn_blocking(move || read_track_header(&read_path, start_byte)).await;
+                let response = match read {
+                    Ok(Ok(header)) => {
+                        let file_size = match header {
+                            StreamResponse::Header { file_size, .. } => file_size,
+                            _ => 0,
+                        };
+                        // Nothing but the header has gone out yet; chunks count as they're sent
+                        state.write().await.outgoing_stream = Some(super::ActiveStream {
+                            peer_id: peer,
+                            track_path: path,
+                            file_size,
+                            bytes_sent: start_byte,
+                            is_sending: true,
+                        });
+                        header
+                    }
+                    Ok(Err(e)) => StreamResponse::Error { message: e.to_string() },
+                    Err(e) => StreamResponse::Error { message: format!("Failed to read track: {}", e) },
+                };
+                let _ = reply_tx.send(PendingResponse { peer, channel, response, sent_up_to: None }).await;
+            });
+        }
+        StreamRequest::RequestChunk { track_path, offset } => {
+            if !is_library_track(state, &track_path).await {
+                let _ = swarm.behaviour_mut().streaming.send_response(
+                    channel,
+                    StreamResponse::Error { message: format!("{} is not in the library", track_path) },
+                );
+                return;
+            }
+
+            let reply_tx = reply_tx.clone();
+            tokio::spawn(async move {
+                let read = tokio::task::spawn_blocking(move || read_track_chunk(Path::new(&track_path), offset)).await;
+                let (response, sent_up_to) = match read {
+                    Ok(Ok(chunk)) => {
+                        let end = match chunk {
+                            StreamResponse::Chunk { ref data, .. } => Some(offset + data.len() as u64),
+                            _ => None,
+                        };
+                        (chunk, end)
+                    }
+                    Ok(Err(e)) => (StreamResponse::Error { message: e.to_string() }, None),
+                    Err(e) => (StreamResponse::Error { message: format!("Failed to read track: {}", e) }, None),
+                };
+                let _ = reply_tx.send(PendingResponse { peer, channel, response, sent_up_to }).await;
+            });
+        }
+        StreamRequest::RequestFileBundle { track_path } => {
+            // Sidecars are derived from the track path, so vetting the track covers them
+            if !is_library_track(state, &track_path).await {
+                let _ = swarm.behaviour_mut().streaming.send_response(
+                    channel,
+                    StreamResponse::Error { message: format!("{} is not in the library", track_path) },
+                );
+                return;
             }
+
+            let reply_tx = reply_tx.clone();
+            tokio::spawn(async move {
+                let read = tokio::task::spawn_blocking(move || read_file_bundle(Path::new(&track_path))).await;
+                let response = match read {
+                    Ok(Ok(files)) => StreamResponse::FileBundle { files },
+                    Ok(Err(e)) => StreamResponse::Error { message: e.to_string() },
+                    Err(e) => StreamResponse::Error { message: format!("Failed to read files: {}", e) },
+                };
+                let _ = reply_tx.send(PendingResponse { peer, channel, response, sent_up_to: None }).await;
+            });
         }
         StreamRequest::Seek { byte_offset } => {
             // Acknowledge seek and prepare to send from new offset
@@ -298,8 +528,9 @@ async fn handle_incoming_request(
                 StreamResponse::Stopped,
             );
             
+            // Only the receiving peer may end its own stream
             let mut state = state.write().await;
-            state.outgoing_stream = None;
+            state.outgoing_stream.take_if(|s| s.peer_id == peer);
         }
         StreamRequest::Ping => {
             let _ = swarm.behaviour_mut().streaming.send_response(
@@ -310,17 +541,134 @@ async fn handle_incoming_request(
     }
 }
 
+/// Peers may only pull tracks that are in our library, never arbitrary files
+async fn is_library_track(state: &Arc<RwLock<P2PState>>, track_path: &str) -> bool {
+    let check = state.read().await.library_check.clone();
+    check.is_some_and(|check| check(track_path))
+}
+
+/// Bytes sent at the previous progress report, for the transfer rate
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    last: Option<(PeerId, u64, Instant)>,
+}
+
+/// Report progress of the outgoing stream, if one is active. The last report
+/// is kept in state for polling; the event is dropped rather than blocking the
+/// swarm loop when nobody is draining the event channel.
+pub async fn report_transfer_progress(
+    state: &Arc<RwLock<P2PState>>,
+    event_tx: &mpsc::Sender<P2PEvent>,
+    tracker: &mut ProgressTracker,
+) {
+    let mut state = state.write().await;
+    let Some(stream) = state.outgoing_stream.as_mut().filter(|s| s.is_sending) else {
+        tracker.last = None;
+        return;
+    };
+
+    let now = Instant::now();
+    let bytes_per_sec = match tracker.last {
+        Some((peer, bytes, at)) if peer == stream.peer_id && stream.bytes_sent >= bytes => {
+            let secs = now.duration_since(at).as_secs_f64();
+            if secs > 0.0 {
+                (stream.bytes_sent - bytes) as f64 / secs
+            } else {
+                0.0
+            }
+        }
+        _ => 0.0,
+    };
+    tracker.last = Some((stream.peer_id, stream.bytes_sent, now));
+
+    let peer_id = stream.peer_id;
+    let bytes_sent = stream.bytes_sent;
+    let total_bytes = stream.file_size;
+    // Stop reporting once everything has gone out
+    if total_bytes > 0 && bytes_sent >= total_bytes {
+        stream.is_sending = false;
+    }
+
+    state.transfer_progress = Some(TransferProgress {
+        peer_id: peer_id.to_base58(),
+        bytes_sent,
+        total_bytes,
+        bytes_per_sec,
+    });
+    drop(state);
+
+    let _ = event_tx.try_send(P2PEvent::TransferProgress {
+        peer_id,
+        bytes_sent,
+        total_bytes,
+        bytes_per_sec,
+    });
+}
+
 /// Handle an incoming stream response
 async fn handle_incoming_response(
+    swarm: &mut libp2p::Swarm<StreamingBehaviour>,
     state: &Arc<RwLock<P2PState>>,
     event_tx: &mpsc::Sender<P2PEvent>,
     peer: PeerId,
     response: StreamResponse,
 ) {
     match response {
-        StreamResponse::Header { format, sample_rate, channels, duration_secs, file_size, .. } => {
+        StreamResponse::Header { format, sample_rate, channels, duration_secs, file_size, start_byte, checksum, .. } => {
             let prebuffered = file_size <= PREBUFFER_THRESHOLD;
-            
+            let complete = start_byte >= file_size;
+
+            let next_chunk = {
+                let mut state = state.write().await;
+                let previous = state.incoming_checksum.take();
+                state.incoming_checksum = match checksum {
+                    Some(ref expected) if start_byte == 0 => Some(IncomingChecksum::new(expected.clone())),
+                    Some(ref expected) => {
+                        let resumed = IncomingChecksum::resume(previous, expected, start_byte);
+                        if resumed.is_none() {
+                            log::warn!("Resumed transfer from {} can't be verified as a whole", peer);
+                        }
+                        resumed
+                    }
+                    None => None,
+                };
+                if let Some(ref mut point) = state.resume_point {
+                    if point.peer_id == peer {
+                        // The sender decides where the data starts; if it didn't honor
+                        // our offset, what we cached is discarded and we restart from there
+                        point.offset = start_byte;
+                        point.file_size = file_size;
+                    }
+                }
+                if let Some(ref mut stream) = state.incoming_stream {
+                    stream.file_size = file_size;
+                    stream.bytes_sent = start_byte;
+                }
+                if complete {
+                    // Resumed at the end: no chunks follow, so finish the transfer here
+                    state.resume_point.take_if(|point| point.peer_id == peer);
+                    if !state.incoming_checksum.take().is_none_or(|c| c.matches()) {
+                        state.incoming_stream = None;
+                        drop(state);
+                        let _ = event_tx.send(P2PEvent::Error(format!(
+                            "Transfer from {} is corrupted (checksum mismatch)", peer
+                        ))).await;
+                        return;
+                    }
+                }
+                state
+                    .resume_point
+                    .as_ref()
+                    .filter(|point| point.peer_id == peer && !complete)
+                    .map(|point| StreamRequest::RequestChunk {
+                        track_path: point.track_path.clone(),
+                        offset: start_byte,
+                    })
+            };
+            if let Some(request) = next_chunk {
+                swarm.behaviour_mut().streaming.send_request(&peer, request);
+            }
+
             let _ = event_tx.send(P2PEvent::StreamReady {
                 peer_id: peer,
                 format,
@@ -329,9 +677,60 @@ async fn handle_incoming_response(
                 channels,
                 duration_secs,
                 prebuffered,
+                start_byte,
+                checksum,
             }).await;
+            if complete {
+                let _ = event_tx.send(P2PEvent::StreamEnded).await;
+            }
         }
-        StreamResponse::Chunk { sequence, data, is_last } => {
+        StreamResponse::Chunk { sequence, data, is_last, checksum } => {
+            let next_chunk = {
+                let mut state = state.write().await;
+                let mut corrupt = checksum.is_some_and(|crc| crc != crc32fast::hash(&data));
+                if corrupt {
+                    log::warn!("Chunk {} from {} failed its CRC check", sequence, peer);
+                } else if let Some(ref mut digest) = state.incoming_checksum {
+                    digest.update(&data);
+                    if is_last {
+                        corrupt = !state.incoming_checksum.take().is_some_and(|c| c.matches());
+                    }
+                }
+                if corrupt {
+                    // Hand nothing more to the player; what arrived can't be trusted
+                    state.incoming_stream = None;
+                    state.resume_point = None;
+                    state.incoming_checksum = None;
+                    drop(state);
+                    let _ = event_tx.send(P2PEvent::Error(format!(
+                        "Transfer from {} is corrupted (chunk {} failed verification)", peer, sequence
+                    ))).await;
+                    return;
+                }
+
+                if let Some(ref mut point) = state.resume_point {
+                    point.offset += data.len() as u64;
+                    point.last_sequence = Some(sequence);
+                }
+                if let Some(ref mut stream) = state.incoming_stream {
+                    stream.bytes_sent += data.len() as u64;
+                }
+                if is_last {
+                    state.resume_point = None;
+                }
+                state
+                    .resume_point
+                    .as_ref()
+                    .filter(|point| point.peer_id == peer)
+                    .map(|point| StreamRequest::RequestChunk {
+                        track_path: point.track_path.clone(),
+                        offset: point.offset,
+                    })
+            };
+            if let Some(request) = next_chunk {
+                swarm.behaviour_mut().streaming.send_request(&peer, request);
+            }
+
             let _ = event_tx.send(P2PEvent::AudioData {
                 sequence,
                 data,
@@ -342,6 +741,35 @@ async fn handle_incoming_response(
                 let _ = event_tx.send(P2PEvent::StreamEnded).await;
             }
         }
+        StreamResponse::FileBundle { files } => {
+            let pending = state.write().await.pending_bundle.take_if(|b| b.peer_id == peer);
+            let Some(pending) = pending else {
+                log::warn!("Ignoring unrequested file bundle from {}", peer);
+                return;
+            };
+            // Write off the swarm loop, like the sender reads them
+            let event_tx = event_tx.clone();
+            tokio::spawn(async move {
+                let dest_dir = pending.dest_dir.clone();
+                let result = tokio::task::spawn_blocking(move || save_file_bundle(&dest_dir, files))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Failed to save files: {}", e)));
+                match result {
+                    Ok(ref saved_paths) => {
+                        let _ = event_tx.send(P2PEvent::BundleSaved {
+                            peer_id: peer,
+                            saved_paths: saved_paths.clone(),
+                        }).await;
+                    }
+                    Err(ref e) => {
+                        let _ = event_tx.send(P2PEvent::Error(format!(
+                            "Failed to save files from {}: {}", peer, e
+                        ))).await;
+                    }
+                }
+                pending.finish(result);
+            });
+        }
         StreamResponse::SeekAck { byte_offset } => {
             // Ready to receive from new position
             log::debug!("Seek acknowledged at byte {}", byte_offset);
@@ -349,9 +777,14 @@ async fn handle_incoming_response(
         StreamResponse::Stopped => {
             let mut state = state.write().await;
             state.incoming_stream = None;
+            state.resume_point = None;
+            state.incoming_checksum = None;
             let _ = event_tx.send(P2PEvent::StreamEnded).await;
         }
         StreamResponse::Error { message } => {
+            if let Some(pending) = state.write().await.pending_bundle.take_if(|b| b.peer_id == peer) {
+                pending.finish(Err(message.clone()));
+            }
             let _ = event_tx.send(P2PEvent::Error(message)).await;
         }
         StreamResponse::Pong => {
@@ -360,11 +793,76 @@ async fn handle_incoming_response(
     }
 }
 
-/// Read a track file and prepare it for streaming
-fn read_track_for_streaming(
-    path: &PathBuf,
+/// Collect a track and the sidecars `find_local_lrc` and `find_external_cover`
+/// would pick up next to it
+fn read_file_bundle(path: &Path) -> Result<Vec<BundleFile>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut sources = vec![(BundleFileKind::Audio, path.to_path_buf())];
+    for ext in ["lrc", "romaji.lrc"] {
+        let lrc = path.with_extension(ext);
+        if lrc.is_file() {
+            sources.push((BundleFileKind::Lyrics, lrc));
+        }
+    }
+    if let Some(cover) = path.parent().and_then(crate::find_external_cover) {
+        sources.push((BundleFileKind::Cover, cover));
+    }
+
+    let total: u64 = sources
+        .iter()
+        .map(|(_, p)| std::fs::metadata(p).map(|m| m.len()))
+        .sum::<std::io::Result<u64>>()?;
+    if total > MAX_BUNDLE_BYTES {
+        return Err(format!("{} is too large to send as a bundle ({} bytes)", path.display(), total).into());
+    }
+
+    sources
+        .into_iter()
+        .map(|(kind, p)| BundleFile::read(kind, &p).map_err(Into::into))
+        .collect()
+}
+
+/// Write a received bundle into `dest_dir`. Returns the saved paths, audio
+/// file first. An existing audio file is an error; an existing cover or
+/// lyrics file is kept and the received one skipped.
+pub fn save_file_bundle(dest_dir: &Path, files: Vec<BundleFile>) -> Result<Vec<PathBuf>, String> {
+    if files.first().map(|f| f.kind) != Some(BundleFileKind::Audio) {
+        return Err("Bundle has no audio file".to_string());
+    }
+    std::fs::create_dir_all(dest_dir).map_err(|e| e.to_string())?;
+
+    let mut saved = Vec::new();
+    for file in files {
+        // Only the bare name is trusted, never a path from the peer
+        let Some(name) = Path::new(&file.file_name).file_name().map(|n| n.to_owned()) else {
+            return Err(format!("Invalid file name in bundle: {}", file.file_name));
+        };
+        let target = dest_dir.join(name);
+        if target.exists() {
+            if file.kind == BundleFileKind::Audio {
+                return Err(format!("{} already exists", target.display()));
+            }
+            continue;
+        }
+        let kind = file.kind;
+        let written = file
+            .assemble()
+            .and_then(|data| std::fs::write(&target, data).map_err(|e| format!("Failed to write {}: {}", target.display(), e)));
+        match written {
+            Ok(()) => saved.push(target),
+            Err(e) if kind == BundleFileKind::Audio => return Err(e),
+            // A missing sidecar isn't worth losing the track over
+            Err(e) => log::warn!("Skipping bundled file: {}", e),
+        }
+    }
+    Ok(saved)
+}
+
+/// Read a track's metadata and checksum for its stream header. Hashes the
+/// whole file, so call it off the swarm loop.
+fn read_track_header(
+    path: &Path,
     start_byte: u64,
-) -> Result<(StreamResponse, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<StreamResponse, Box<dyn std::error::Error + Send + Sync>> {
     use lofty::prelude::*;
     use lofty::probe::Probe;
     
@@ -388,29 +886,136 @@ fn read_track_for_streaming(
         .unwrap_or("unknown")
         .to_lowercase();
     
-    // Read file bytes
     let mut file = File::open(path)?;
     let file_size = file.metadata()?.len();
     
-    // Seek to start position if needed
-    if start_byte > 0 {
-        file.seek(SeekFrom::Start(start_byte))?;
+    if start_byte > file_size {
+        return Err(format!("Start byte {} is past the end of the file ({} bytes)", start_byte, file_size).into());
     }
+
+    // The checksum covers the whole file, so a resumed receiver can verify too
+    let mut context = md5::Context::new();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        context.consume(&buf[..n]);
+    }
+    let checksum = format!("{:x}", context.compute());
     
-    // Read remaining bytes
-    let mut data = Vec::with_capacity((file_size - start_byte) as usize);
-    file.read_to_end(&mut data)?;
-    
-    let header = StreamResponse::Header {
+    Ok(StreamResponse::Header {
         format,
         sample_rate,
         channels,
         duration_secs,
         file_size,
+        start_byte,
+        checksum: Some(checksum),
         title,
         artist,
         album,
-    };
-    
-    Ok((header, data))
+    })
+}
+
+/// Read the chunk of a track starting at `offset`
+fn read_track_chunk(
+    path: &Path,
+    offset: u64,
+) -> Result<StreamResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    if offset >= file_size {
+        return Err(format!("Offset {} is past the end of the file ({} bytes)", offset, file_size).into());
+    }
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(CHUNK_SIZE);
+    file.take(CHUNK_SIZE as u64).read_to_end(&mut data)?;
+    let is_last = offset + data.len() as u64 >= file_size;
+    Ok(StreamResponse::chunk(offset / CHUNK_SIZE as u64, data, is_last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_survives_a_resumed_transfer() {
+        let file = b"0123456789abcdef".to_vec();
+        let expected = format!("{:x}", md5::compute(&file));
+
+        let mut digest = IncomingChecksum::new(expected.clone());
+        digest.update(&file[..6]);
+        // Reconnected at the byte we stopped at: keep hashing from there
+        assert!(IncomingChecksum::resume(None, &expected, 6).is_none());
+        let mut digest = IncomingChecksum::resume(Some(digest), &expected, 6).unwrap();
+        digest.update(&file[6..]);
+        assert!(digest.matches());
+
+        let mut corrupted = IncomingChecksum::new(expected);
+        corrupted.update(b"0123456789abcdeF");
+        assert!(!corrupted.matches());
+
+        let StreamResponse::Chunk { checksum, .. } = StreamResponse::chunk(0, file.clone(), true) else {
+            unreachable!();
+        };
+        assert_eq!(checksum, Some(crc32fast::hash(&file)));
+    }
+
+    #[test]
+    fn track_chunks_cover_the_file_from_any_offset() {
+        let path = std::env::temp_dir().join(format!("vibe-on-chunks-{}.bin", std::process::id()));
+        let file: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        std::fs::write(&path, &file).unwrap();
+
+        // Pull chunks the way the receiver does, resuming mid-file
+        let mut offset = 100u64;
+        let mut received = file[..100].to_vec();
+        loop {
+            let StreamResponse::Chunk { data, is_last, checksum, .. } = read_track_chunk(&path, offset).unwrap() else {
+                unreachable!();
+            };
+            assert_eq!(checksum, Some(crc32fast::hash(&data)));
+            offset += data.len() as u64;
+            received.extend_from_slice(&data);
+            if is_last {
+                break;
+            }
+        }
+        assert_eq!(received, file);
+        assert!(read_track_chunk(&path, offset).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn file_bundle_round_trips_with_sidecars() {
+        let root = std::env::temp_dir().join(format!("vibe-on-bundle-{}", std::process::id()));
+        let src = root.join("src");
+        let dest = root.join("dest");
+        std::fs::create_dir_all(&src).unwrap();
+        let audio: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect();
+        std::fs::write(src.join("song.flac"), &audio).unwrap();
+        std::fs::write(src.join("song.lrc"), "[00:01.00]Hi").unwrap();
+        std::fs::write(src.join("cover.jpg"), b"jpeg").unwrap();
+
+        let files = read_file_bundle(&src.join("song.flac")).unwrap();
+        assert_eq!(files[0].chunks.len(), 3);
+        let saved = save_file_bundle(&dest, files.clone()).unwrap();
+        assert_eq!(saved, vec![dest.join("song.flac"), dest.join("song.lrc"), dest.join("cover.jpg")]);
+        assert_eq!(std::fs::read(dest.join("song.flac")).unwrap(), audio);
+
+        // Never overwrite a track that's already there
+        assert!(save_file_bundle(&dest, files.clone()).is_err());
+
+        let mut corrupted = files;
+        if let StreamResponse::Chunk { ref mut data, .. } = corrupted[0].chunks[1] {
+            data[0] ^= 0xff;
+        }
+        assert!(save_file_bundle(&root.join("other"), corrupted).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,512 @@
+//! Queue navigation shared by the WebSocket handlers, the server autoplay task
+//! and next-track preloading.
+//!
+//! `AppState::current_queue_index` always points into the natural queue order.
+//! When shuffle is on, `ShuffleOrder` holds a permutation of queue indices that
+//! Next/Previous walk through instead, seeded once per enable so the random
+//! order stays stable while navigating back and forth.
+
+use crate::audio::TrackInfo;
+use crate::AppState;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Shuffled traversal order over the queue (empty while shuffle is off)
+#[derive(Debug, Clone, Default)]
+pub struct ShuffleOrder {
+    seed: u64,
+    order: Vec<usize>,
+}
+
+impl ShuffleOrder {
+    /// Build a new order with `current` first so playback continues from it
+    pub fn new(len: usize, current: usize, seed: u64) -> Self {
+        let mut shuffle = Self { seed, order: Vec::new() };
+        shuffle.rebuild(len, current);
+        shuffle
+    }
+
+    /// Regenerate for a changed queue, keeping the seed
+    pub fn rebuild(&mut self, len: usize, current: usize) {
+        let mut order = permutation(len, self.seed);
+        if let Some(pos) = order.iter().position(|&i| i == current) {
+            order.remove(pos);
+            order.insert(0, current);
+        }
+        self.order = order;
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.order.is_empty()
+    }
+
+    fn as_slice(&self) -> Option<&[usize]> {
+        self.is_active().then_some(self.order.as_slice())
+    }
+}
+
+/// Fisher–Yates permutation of `0..len` driven by a SplitMix64 stream
+fn permutation(len: usize, seed: u64) -> Vec<usize> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+
+    let mut order: Vec<usize> = (0..len).collect();
+    for i in (1..len).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+fn new_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Queue index `delta` steps away from `current`, walking `order` when shuffled.
+/// Past either end the position wraps when `wrap` is set; otherwise stepping
+/// forward stops (`None`) and stepping back stays on the first track.
+fn step_index(
+    len: usize,
+    current: usize,
+    order: Option<&[usize]>,
+    delta: i32,
+    wrap: bool,
+) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+
+    let position = match order {
+        Some(order) => order.iter().position(|&i| i == current).unwrap_or(0),
+        None => current.min(len - 1),
+    };
+    let len_i = len as i32;
+
+    let mut next = position as i32 + delta;
+    if next >= len_i {
+        if !wrap {
+            return None;
+        }
+        next = 0;
+    } else if next < 0 {
+        next = if wrap { len_i - 1 } else { 0 };
+    }
+
+    let next = next as usize;
+    Some(order.map(|order| order[next]).unwrap_or(next))
+}
+
+/// Index for an explicit Next/Previous tap. Always moves to a different track,
+/// even with repeat "one"; wraps around when any repeat mode is on.
+pub fn manual_skip_index(
+    len: usize,
+    current: usize,
+    order: Option<&[usize]>,
+    delta: i32,
+    repeat: &str,
+) -> Option<usize> {
+    step_index(len, current, order, delta, repeat != "off")
+}
+
+/// Index to continue with when a track finishes on its own.
+/// Repeat "one" replays the current track; `None` means playback stops.
+pub fn auto_advance_index(
+    len: usize,
+    current: usize,
+    order: Option<&[usize]>,
+    repeat: &str,
+) -> Option<usize> {
+    match repeat {
+        "one" if current < len => Some(current),
+        "all" => step_index(len, current, order, 1, true),
+        _ => step_index(len, current, order, 1, false),
+    }
+}
+
+/// Move the queue index for a user Next (`1`) / Previous (`-1`) and return the path to play
+pub fn manual_skip(state: &AppState, delta: i32) -> Option<String> {
+    let queue = state.queue.lock().unwrap();
+    let mut index = state.current_queue_index.lock().unwrap();
+    let repeat = state.repeat_mode.lock().unwrap().clone();
+    let shuffle = state.shuffle_order.lock().unwrap();
+
+    let next = manual_skip_index(queue.len(), *index, shuffle.as_slice(), delta, &repeat)?;
+    *index = next;
+    queue.get(next).map(|t| t.path.clone())
+}
+
+/// Move the queue index after the current track ended and return the path to play
+pub fn auto_advance(state: &AppState) -> Option<String> {
+    let queue = state.queue.lock().unwrap();
+    let mut index = state.current_queue_index.lock().unwrap();
+    let repeat = state.repeat_mode.lock().unwrap().clone();
+    let shuffle = state.shuffle_order.lock().unwrap();
+
+    let next = auto_advance_index(queue.len(), *index, shuffle.as_slice(), &repeat)?;
+    *index = next;
+    queue.get(next).map(|t| t.path.clone())
+}
+
+/// The track that will follow the current one when it ends, without moving the index
+pub fn peek_next(state: &AppState) -> Option<crate::audio::TrackInfo> {
+    let queue = state.queue.lock().unwrap();
+    let index = state.current_queue_index.lock().unwrap();
+    let repeat = state.repeat_mode.lock().unwrap().clone();
+    let shuffle = state.shuffle_order.lock().unwrap();
+
+    auto_advance_index(queue.len(), *index, shuffle.as_slice(), &repeat)
+        .and_then(|next| queue.get(next).cloned())
+}
+
+/// Turn shuffle on (fresh seed, current track first) or off (natural order)
+pub fn set_shuffle(state: &AppState, enabled: bool) {
+    let queue = state.queue.lock().unwrap();
+    let index = state.current_queue_index.lock().unwrap();
+    let mut shuffle = state.shuffle_order.lock().unwrap();
+
+    *state.shuffle.lock().unwrap() = enabled;
+    *shuffle = if enabled {
+        ShuffleOrder::new(queue.len(), *index, new_seed())
+    } else {
+        ShuffleOrder::default()
+    };
+}
+
+/// Current index after inserting an entry at `at`
+fn index_after_insert(current: usize, at: usize, len_before: usize) -> usize {
+    if len_before > 0 && at <= current {
+        current + 1
+    } else {
+        current
+    }
+}
+
+/// Current index after removing the entry at `at`. Removing the current entry
+/// keeps the index, which then points at the track that shifted into its place.
+fn index_after_remove(current: usize, at: usize, len_after: usize) -> usize {
+    let current = if at < current { current - 1 } else { current };
+    current.min(len_after.saturating_sub(1))
+}
+
+/// Current index after moving the entry at `from` to `to`; the current track
+/// stays current wherever it ends up
+fn index_after_move(current: usize, from: usize, to: usize) -> usize {
+    if from == current {
+        to
+    } else if from < current && to >= current {
+        current - 1
+    } else if from > current && to <= current {
+        current + 1
+    } else {
+        current
+    }
+}
+
+/// Current index after reordering by `order`, where `order[i]` is the old
+/// position of the entry that ends up at `i`. `None` unless `order` is a
+/// permutation of `0..len`.
+fn index_after_reorder(current: usize, order: &[usize], len: usize) -> Option<usize> {
+    let mut seen = vec![false; len];
+    for &old in order {
+        if old >= len || std::mem::replace(&mut seen[old], true) {
+            return None;
+        }
+    }
+    if order.len() != len {
+        return None;
+    }
+    Some(order.iter().position(|&old| old == current).unwrap_or(0))
+}
+
+/// Insert `track` right after the current entry (at the front of an empty queue)
+pub fn insert_next(state: &AppState, track: TrackInfo) {
+    {
+        let mut queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+        let at = if queue.is_empty() { 0 } else { (*index + 1).min(queue.len()) };
+        *index = index_after_insert(*index, at, queue.len());
+        queue.insert(at, track);
+    }
+    queue_changed(state);
+}
+
+pub fn append(state: &AppState, track: TrackInfo) {
+    state.queue.lock().unwrap().push_back(track);
+    queue_changed(state);
+}
+
+/// What `remove` took out of the queue
+pub enum Removed {
+    /// Another entry; playback is unaffected
+    Other,
+    /// The current entry, with the path of the track that shifted into its
+    /// place (`None` when it was the last one)
+    Current(Option<String>),
+}
+
+/// Remove the entry at `at`
+pub fn remove(state: &AppState, at: usize) -> Result<Removed, String> {
+    let removed = {
+        let mut queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+        if at >= queue.len() {
+            return Err(format!("Queue index {} out of range (len {})", at, queue.len()));
+        }
+        let was_current = at == *index;
+        queue.remove(at);
+        *index = index_after_remove(*index, at, queue.len());
+        if was_current {
+            Removed::Current(queue.get(at).map(|t| t.path.clone()))
+        } else {
+            Removed::Other
+        }
+    };
+    queue_changed(state);
+    Ok(removed)
+}
+
+/// Move the entry at `from` so it ends up at position `to`
+pub fn move_entry(state: &AppState, from: usize, to: usize) -> Result<(), String> {
+    {
+        let mut queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+        if from >= queue.len() || to >= queue.len() {
+            return Err(format!(
+                "Cannot move {} to {}: queue has {} tracks",
+                from,
+                to,
+                queue.len()
+            ));
+        }
+        if let Some(track) = queue.remove(from) {
+            queue.insert(to, track);
+        }
+        *index = index_after_move(*index, from, to);
+    }
+    queue_changed(state);
+    Ok(())
+}
+
+/// Rearrange the whole queue; `order` lists the current positions in their new order
+pub fn reorder(state: &AppState, order: &[usize]) -> Result<(), String> {
+    {
+        let mut queue = state.queue.lock().unwrap();
+        let mut index = state.current_queue_index.lock().unwrap();
+        let new_index = index_after_reorder(*index, order, queue.len()).ok_or_else(|| {
+            format!("Order must list each of the {} queue positions once", queue.len())
+        })?;
+        let old: Vec<TrackInfo> = queue.drain(..).collect();
+        queue.extend(order.iter().map(|&i| old[i].clone()));
+        *index = new_index;
+    }
+    queue_changed(state);
+    Ok(())
+}
+
+/// Replace the whole queue, starting from its first entry
+pub fn replace(state: &AppState, tracks: Vec<TrackInfo>) {
+    {
+        let mut queue = state.queue.lock().unwrap();
+        *queue = VecDeque::from(tracks);
+        *state.current_queue_index.lock().unwrap() = 0;
+    }
+    queue_changed(state);
+}
+
+pub fn clear(state: &AppState) {
+    state.queue.lock().unwrap().clear();
+    *state.current_queue_index.lock().unwrap() = 0;
+    queue_changed(state);
+}
+
+/// Album, then disc, then track order. Untagged discs count as disc 1;
+/// untagged tracks go after numbered ones.
+fn album_order(a: &TrackInfo, b: &TrackInfo) -> Ordering {
+    a.album
+        .cmp(&b.album)
+        .then(a.disc_number.unwrap_or(1).cmp(&b.disc_number.unwrap_or(1)))
+        .then(a.track_number.is_none().cmp(&b.track_number.is_none()))
+        .then(a.track_number.cmp(&b.track_number))
+}
+
+/// Library tracks matching `predicate`, in album order
+fn library_tracks(state: &AppState, predicate: impl Fn(&TrackInfo) -> bool) -> Vec<TrackInfo> {
+    let db_guard = state.db.lock().unwrap();
+    let Some(all) = db_guard.as_ref().and_then(|db| db.get_all_tracks().ok()) else {
+        return Vec::new();
+    };
+    let mut tracks: Vec<_> = all.into_iter().filter(|t| predicate(t)).collect();
+    tracks.sort_by(album_order);
+    tracks
+}
+
+/// Tracks of an album in play order; an empty `artist` matches any artist
+pub fn album_tracks(state: &AppState, album: &str, artist: &str) -> Vec<TrackInfo> {
+    library_tracks(state, |t| t.album == album && (artist.is_empty() || t.artist == artist))
+}
+
+/// All tracks of an artist, album by album
+pub fn artist_tracks(state: &AppState, artist: &str) -> Vec<TrackInfo> {
+    library_tracks(state, |t| t.artist == artist)
+}
+
+/// `tracks` in a fresh random order
+pub fn shuffled(tracks: Vec<TrackInfo>) -> Vec<TrackInfo> {
+    let mut slots: Vec<Option<TrackInfo>> = tracks.into_iter().map(Some).collect();
+    permutation(slots.len(), new_seed())
+        .into_iter()
+        .filter_map(|i| slots[i].take())
+        .collect()
+}
+
+/// Regenerate the shuffle order after the queue contents changed
+pub fn queue_changed(state: &AppState) {
+    let queue = state.queue.lock().unwrap();
+    let index = state.current_queue_index.lock().unwrap();
+    let mut shuffle = state.shuffle_order.lock().unwrap();
+
+    if *state.shuffle.lock().unwrap() {
+        shuffle.rebuild(queue.len(), *index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permutation_is_stable_per_seed() {
+        let a = permutation(20, 42);
+        assert_eq!(a, permutation(20, 42));
+        assert_ne!(a, permutation(20, 43));
+
+        let mut sorted = a.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shuffle_order_starts_at_current_track() {
+        let shuffle = ShuffleOrder::new(10, 7, 1);
+        assert_eq!(shuffle.order[0], 7);
+        assert_eq!(shuffle.order.len(), 10);
+    }
+
+    #[test]
+    fn next_and_previous_walk_the_same_shuffled_order() {
+        let shuffle = ShuffleOrder::new(5, 2, 99);
+        let order = shuffle.as_slice();
+
+        let mut visited = vec![2];
+        let mut current = 2;
+        while let Some(next) = manual_skip_index(5, current, order, 1, "off") {
+            visited.push(next);
+            current = next;
+        }
+        assert_eq!(visited, shuffle.order);
+
+        let back = manual_skip_index(5, current, order, -1, "off").unwrap();
+        assert_eq!(back, shuffle.order[3]);
+    }
+
+    #[test]
+    fn manual_skip_with_repeat_off() {
+        assert_eq!(manual_skip_index(3, 0, None, 1, "off"), Some(1));
+        assert_eq!(manual_skip_index(3, 2, None, 1, "off"), None);
+        assert_eq!(manual_skip_index(3, 0, None, -1, "off"), Some(0));
+    }
+
+    #[test]
+    fn manual_skip_with_repeat_all() {
+        assert_eq!(manual_skip_index(3, 1, None, 1, "all"), Some(2));
+        assert_eq!(manual_skip_index(3, 2, None, 1, "all"), Some(0));
+        assert_eq!(manual_skip_index(3, 0, None, -1, "all"), Some(2));
+    }
+
+    #[test]
+    fn manual_skip_with_repeat_one_moves_to_another_track() {
+        assert_eq!(manual_skip_index(3, 0, None, 1, "one"), Some(1));
+        assert_eq!(manual_skip_index(3, 2, None, 1, "one"), Some(0));
+        assert_eq!(manual_skip_index(3, 1, None, -1, "one"), Some(0));
+    }
+
+    #[test]
+    fn auto_advance_with_repeat_off() {
+        assert_eq!(auto_advance_index(3, 0, None, "off"), Some(1));
+        assert_eq!(auto_advance_index(3, 2, None, "off"), None);
+    }
+
+    #[test]
+    fn auto_advance_with_repeat_all() {
+        assert_eq!(auto_advance_index(3, 1, None, "all"), Some(2));
+        assert_eq!(auto_advance_index(3, 2, None, "all"), Some(0));
+    }
+
+    #[test]
+    fn auto_advance_with_repeat_one_replays_current() {
+        assert_eq!(auto_advance_index(3, 1, None, "one"), Some(1));
+        assert_eq!(auto_advance_index(3, 2, None, "one"), Some(2));
+        assert_eq!(auto_advance_index(0, 0, None, "one"), None);
+    }
+
+    #[test]
+    fn edits_keep_the_current_track_current() {
+        // Inserting before or at the current entry shifts it back
+        assert_eq!(index_after_insert(2, 0, 5), 3);
+        assert_eq!(index_after_insert(2, 3, 5), 2);
+        assert_eq!(index_after_insert(0, 0, 0), 0);
+
+        assert_eq!(index_after_remove(2, 0, 4), 1);
+        assert_eq!(index_after_remove(2, 4, 4), 2);
+        // Removing the current entry lands on the one that shifted into its place
+        assert_eq!(index_after_remove(2, 2, 4), 2);
+        assert_eq!(index_after_remove(4, 4, 4), 3);
+
+        assert_eq!(index_after_move(2, 2, 0), 0);
+        assert_eq!(index_after_move(2, 0, 4), 1);
+        assert_eq!(index_after_move(2, 4, 1), 3);
+        assert_eq!(index_after_move(2, 3, 4), 2);
+    }
+
+    #[test]
+    fn album_order_sorts_by_disc_then_track_with_untagged_last() {
+        let track = |disc: Option<u32>, number: Option<u32>| TrackInfo {
+            album: "A".to_string(),
+            disc_number: disc,
+            track_number: number,
+            ..TrackInfo::default()
+        };
+        let mut tracks = [
+            track(Some(2), Some(1)),
+            track(None, None),
+            track(Some(1), Some(2)),
+            track(None, Some(1)),
+        ];
+        tracks.sort_by(album_order);
+        let order: Vec<_> = tracks.iter().map(|t| (t.disc_number, t.track_number)).collect();
+        assert_eq!(
+            order,
+            vec![(None, Some(1)), (Some(1), Some(2)), (None, None), (Some(2), Some(1))]
+        );
+    }
+
+    #[test]
+    fn reorder_follows_the_current_track_and_rejects_bad_orders() {
+        assert_eq!(index_after_reorder(1, &[2, 0, 1], 3), Some(2));
+        assert_eq!(index_after_reorder(0, &[0, 1, 2], 3), Some(0));
+        assert_eq!(index_after_reorder(0, &[], 0), Some(0));
+
+        assert_eq!(index_after_reorder(0, &[0, 1], 3), None);
+        assert_eq!(index_after_reorder(0, &[0, 0, 1], 3), None);
+        assert_eq!(index_after_reorder(0, &[0, 1, 3], 3), None);
+    }
+}
@@ -0,0 +1,263 @@
+//! Last.fm scrobbling driven by the playback stats.
+//!
+//! Finalized `PlaybackEvent`s are already persisted in SQLite, so the scrobble
+//! queue is every event newer than the last submitted timestamp (kept in
+//! `settings.json`). A failed submission leaves that watermark in place and the
+//! events are retried on the next flush, so scrobbles made offline go out once
+//! connectivity returns. Credentials live in the OS credential store.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::AppHandle;
+
+use crate::stats::PlaybackEvent;
+use crate::AppState;
+
+const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+
+const KEYRING_SERVICE: &str = "vibe-on";
+const KEYRING_USER: &str = "lastfm";
+
+/// Last.fm accepts at most 50 scrobbles per request
+const MAX_BATCH: usize = 50;
+
+/// Last.fm rejects scrobbles older than two weeks
+const MAX_SCROBBLE_AGE_MS: i64 = 14 * 24 * 60 * 60 * 1000;
+
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+/// Credentials for an authorized Last.fm session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastfmSession {
+    pub api_key: String,
+    pub api_secret: String,
+    pub session_key: String,
+    pub username: Option<String>,
+}
+
+/// Track fields Last.fm needs for now-playing and scrobble calls
+#[derive(Debug, Clone)]
+struct ScrobbleTrack {
+    artist: String,
+    title: String,
+    album: String,
+    duration_secs: u32,
+    /// When playback started, in seconds since the epoch
+    started_at: i64,
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("Credential store unavailable: {}", e))
+}
+
+pub fn load_session() -> Option<LastfmSession> {
+    let stored = keyring_entry().ok()?.get_password().ok()?;
+    serde_json::from_str(&stored).ok()
+}
+
+/// Store the session and start scrobbling from now on
+pub fn link_session(state: &AppState, app: &AppHandle, session: &LastfmSession) -> Result<(), String> {
+    let value = serde_json::to_string(session).map_err(|e| e.to_string())?;
+    keyring_entry()?
+        .set_password(&value)
+        .map_err(|e| format!("Failed to store Last.fm credentials: {}", e))?;
+
+    // History from before the account was linked is not submitted
+    if scrobbled_until(state).is_none() {
+        set_scrobbled_until(state, app, crate::stats::current_time_ms())?;
+    }
+    println!("[Lastfm] Session linked for {}", session.username.as_deref().unwrap_or("unknown user"));
+    Ok(())
+}
+
+fn scrobbled_until(state: &AppState) -> Option<i64> {
+    state.settings.lock().unwrap().lastfm_scrobbled_until
+}
+
+fn set_scrobbled_until(state: &AppState, app: &AppHandle, timestamp_ms: i64) -> Result<(), String> {
+    crate::save_settings(state, app, |s| {
+        s.lastfm_scrobbled_until = Some(timestamp_ms);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Events recorded since the last successful submission, oldest first
+fn pending_events(state: &AppState) -> Result<Vec<PlaybackEvent>, String> {
+    let Some(since) = scrobbled_until(state) else {
+        return Ok(Vec::new());
+    };
+    let mut events = crate::stats::load_stats_events(state, Some(since + 1), None)?;
+    events.reverse();
+    Ok(events)
+}
+
+/// Number of plays waiting to be scrobbled
+pub fn queue_len(state: &AppState) -> Result<usize, String> {
+    if load_session().is_none() {
+        return Ok(0);
+    }
+    Ok(pending_events(state)?.len())
+}
+
+/// Look up tags for a played file; tracks without title/artist can't be scrobbled
+fn resolve_track(state: &AppState, song_id: &str, started_at_ms: i64) -> Option<ScrobbleTrack> {
+    let guard = state.db.lock().ok()?;
+    let track = guard.as_ref()?.get_track(song_id).ok()??;
+    if track.title.trim().is_empty() || track.artist.trim().is_empty() {
+        return None;
+    }
+    Some(ScrobbleTrack {
+        artist: track.artist,
+        title: track.title,
+        album: track.album,
+        duration_secs: track.duration_secs.max(0.0) as u32,
+        started_at: started_at_ms / 1000,
+    })
+}
+
+/// `api_sig`: md5 of the parameters sorted by name, concatenated, plus the secret
+fn api_signature(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut base: String = sorted.iter().map(|(k, v)| format!("{}{}", k, v)).collect();
+    base.push_str(secret);
+    format!("{:x}", md5::compute(base))
+}
+
+fn create_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent(crate::USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Signed POST to the Last.fm API
+fn call(
+    client: &reqwest::blocking::Client,
+    session: &LastfmSession,
+    method: &str,
+    mut params: Vec<(String, String)>,
+) -> Result<(), String> {
+    params.push(("method".into(), method.into()));
+    params.push(("api_key".into(), session.api_key.clone()));
+    params.push(("sk".into(), session.session_key.clone()));
+    let signature = api_signature(&params, &session.api_secret);
+    params.push(("api_sig".into(), signature));
+    params.push(("format".into(), "json".into()));
+
+    let body: serde_json::Value = client
+        .post(API_URL)
+        .form(&params)
+        .send()
+        .map_err(|e| format!("Last.fm request failed: {}", e))?
+        .json()
+        .map_err(|e| format!("Invalid Last.fm response: {}", e))?;
+
+    if let Some(code) = body.get("error") {
+        let message = body.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+        return Err(format!("Last.fm error {}: {}", code, message));
+    }
+    Ok(())
+}
+
+fn track_params(track: &ScrobbleTrack, index: Option<usize>) -> Vec<(String, String)> {
+    let key = |name: &str| match index {
+        Some(i) => format!("{}[{}]", name, i),
+        None => name.to_string(),
+    };
+    let mut params = vec![
+        (key("artist"), track.artist.clone()),
+        (key("track"), track.title.clone()),
+    ];
+    if !track.album.trim().is_empty() {
+        params.push((key("album"), track.album.clone()));
+    }
+    if track.duration_secs > 0 {
+        params.push((key("duration"), track.duration_secs.to_string()));
+    }
+    params
+}
+
+/// Tell Last.fm what started playing. No-op without a linked session.
+pub fn update_now_playing(state: &AppState, song_id: &str) -> Result<(), String> {
+    let Some(session) = load_session() else {
+        return Ok(());
+    };
+    let Some(track) = resolve_track(state, song_id, crate::stats::current_time_ms()) else {
+        return Ok(());
+    };
+    call(&create_client()?, &session, "track.updateNowPlaying", track_params(&track, None))
+}
+
+/// Submit queued plays. Stops at the first failed batch so it is retried later;
+/// returns how many plays were scrobbled.
+pub fn flush(state: &AppState, app: &AppHandle) -> Result<usize, String> {
+    if FLUSHING.swap(true, Ordering::SeqCst) {
+        return Ok(0);
+    }
+    let result = flush_pending(state, app);
+    FLUSHING.store(false, Ordering::SeqCst);
+    result
+}
+
+fn flush_pending(state: &AppState, app: &AppHandle) -> Result<usize, String> {
+    let Some(session) = load_session() else {
+        return Ok(0);
+    };
+    let events = pending_events(state)?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let client = create_client()?;
+    let cutoff = crate::stats::current_time_ms() - MAX_SCROBBLE_AGE_MS;
+    let mut sent = 0;
+
+    for batch in events.chunks(MAX_BATCH) {
+        let tracks: Vec<ScrobbleTrack> = batch
+            .iter()
+            .filter(|e| e.timestamp >= cutoff)
+            .filter_map(|e| resolve_track(state, &e.song_id, e.start_timestamp.unwrap_or(e.timestamp)))
+            .collect();
+
+        if !tracks.is_empty() {
+            let mut params = Vec::new();
+            for (i, track) in tracks.iter().enumerate() {
+                params.extend(track_params(track, Some(i)));
+                params.push((format!("timestamp[{}]", i), track.started_at.to_string()));
+            }
+            call(&client, &session, "track.scrobble", params)?;
+            sent += tracks.len();
+        }
+
+        if let Some(last) = batch.last() {
+            set_scrobbled_until(state, app, last.timestamp)?;
+        }
+    }
+
+    println!("[Lastfm] Scrobbled {} plays", sent);
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_sorts_parameters_by_name() {
+        let params = vec![
+            ("track[0]".to_string(), "Song".to_string()),
+            ("timestamp[0]".to_string(), "100".to_string()),
+            ("method".to_string(), "track.scrobble".to_string()),
+            ("sk".to_string(), "SESSION".to_string()),
+            ("api_key".to_string(), "abc".to_string()),
+        ];
+        assert_eq!(api_signature(&params, "secret"), "4b59c591dafba200c5c58e89d899fafc");
+    }
+}
@@ -0,0 +1,127 @@
+//! Shared-secret token authentication for the companion server
+//!
+//! The token is read from `VIBE_ON_CONTROL_TOKEN` when set, otherwise it is
+//! generated once and persisted in the app data dir so paired clients keep
+//! working across restarts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tauri::{AppHandle, Manager};
+
+use super::ServerState;
+
+const TOKEN_FILE: &str = "server_token";
+
+/// Token supplied through the `VIBE_ON_CONTROL_TOKEN` environment variable
+pub fn env_token() -> Option<String> {
+    std::env::var("VIBE_ON_CONTROL_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// Resolve the server token, generating and persisting one on first use
+pub fn load_or_create_token(app_handle: &AppHandle) -> Result<String, String> {
+    if let Some(token) = env_token() {
+        return Ok(token);
+    }
+
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    let token_path = data_dir.join(TOKEN_FILE);
+
+    if let Ok(existing) = std::fs::read_to_string(&token_path) {
+        let existing = existing.trim();
+        if !existing.is_empty() {
+            return Ok(existing.to_string());
+        }
+    }
+
+    std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    std::fs::write(&token_path, &token).map_err(|e| e.to_string())?;
+    println!("[Server] Generated new server token at {:?}", token_path);
+    Ok(token)
+}
+
+/// Token from an `Authorization` header, with or without a `Bearer` prefix
+fn header_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?.trim();
+    Some(value.strip_prefix("Bearer ").unwrap_or(value).trim())
+}
+
+/// Compare tokens without short-circuiting on the first mismatched byte
+pub fn token_matches(expected: &str, provided: &str) -> bool {
+    let (expected, provided) = (expected.as_bytes(), provided.as_bytes());
+    expected.len() == provided.len()
+        && expected
+            .iter()
+            .zip(provided)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Token presented by a request, preferring the `token` query param
+pub fn provided_token<'a>(params: &'a HashMap<String, String>, headers: &'a HeaderMap) -> Option<&'a str> {
+    params
+        .get("token")
+        .map(String::as_str)
+        .or_else(|| header_token(headers))
+}
+
+/// Check a presented token against the configured one
+pub fn is_authorized(state: &ServerState, provided: Option<&str>) -> bool {
+    match state.config.control_token.as_deref() {
+        Some(expected) => provided.is_some_and(|token| token_matches(expected, token)),
+        None => true,
+    }
+}
+
+/// Middleware rejecting requests without a valid token with `401`
+pub async fn require_token(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_authorized(&state, provided_token(&params, request.headers())) {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid server token").into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn accepts_query_param_or_bearer_header() {
+        let mut params = HashMap::new();
+        let mut headers = HeaderMap::new();
+        assert_eq!(provided_token(&params, &headers), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc123"));
+        assert_eq!(provided_token(&params, &headers), Some("abc123"));
+
+        params.insert("token".to_string(), "fromquery".to_string());
+        assert_eq!(provided_token(&params, &headers), Some("fromquery"));
+    }
+
+    #[test]
+    fn token_comparison_requires_exact_match() {
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc123", "abc124"));
+        assert!(!token_matches("abc123", "abc12"));
+        assert!(!token_matches("abc123", ""));
+    }
+}
@@ -5,7 +5,11 @@
 //! - WebSocket for real-time control and state updates
 //! - mDNS advertisement for automatic discovery
 
+pub mod auth;
 pub mod routes;
+pub mod thumbnail;
+pub mod tls;
+pub mod transcode;
 pub mod websocket;
 
 pub const WS_PROTOCOL_VERSION: &str = "1.1";
@@ -15,6 +19,7 @@ pub fn ws_server_capabilities() -> Vec<String> {
         "lyrics.romaji".to_string(),
         "library.paged".to_string(),
         "playlists.basic".to_string(),
+        "favorites.basic".to_string(),
         "queue.sync".to_string(),
         "playback.output-switch".to_string(),
     ]
@@ -29,9 +34,11 @@ use axum::{
     Router,
 };
 use tokio::sync::{broadcast, RwLock};
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 
 use self::routes::*;
 use self::websocket::*;
@@ -43,21 +50,25 @@ pub struct ServerConfig {
     pub port: u16,
     /// Server name for mDNS
     pub server_name: String,
-    /// Optional shared token required for WebSocket control access
+    /// Shared token required on every route and the WebSocket handshake.
+    /// When unset, `start_server` loads (or generates) the persisted token.
     pub control_token: Option<String>,
+    /// Serve over HTTPS with a self-signed certificate
+    pub use_tls: bool,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
-        let control_token = std::env::var("VIBE_ON_CONTROL_TOKEN")
-            .ok()
-            .map(|token| token.trim().to_string())
-            .filter(|token| !token.is_empty());
+        let control_token = auth::env_token();
+        let use_tls = std::env::var("VIBE_ON_USE_TLS")
+            .map(|value| matches!(value.trim(), "1" | "true"))
+            .unwrap_or(false);
 
         Self {
             port: 5000,
             server_name: crate::p2p::get_device_name(),
             control_token,
+            use_tls,
         }
     }
 }
@@ -74,6 +85,9 @@ pub struct ServerState {
     pub active_output: Arc<RwLock<String>>,
     /// Server configuration
     pub config: ServerConfig,
+    /// Reply channel of the client playing mobile output, which alone is sent
+    /// stream URLs when the track changes on the server side
+    pub mobile_output_client: std::sync::Mutex<Option<tokio::sync::mpsc::Sender<ServerMessage>>>,
 }
 
 impl ServerState {
@@ -90,6 +104,7 @@ impl ServerState {
             clients: RwLock::new(Vec::new()),
             active_output,
             config,
+            mobile_output_client: std::sync::Mutex::new(None),
         }
     }
     
@@ -176,11 +191,10 @@ pub enum ServerEvent {
         #[serde(rename = "plainLyrics")]
         plain_lyrics: Option<String>,
         instrumental: bool,
-    },
-    /// P2P handoff preparation
-    HandoffPrepare {
-        sample: u64,
-        url: String,
+        #[serde(rename = "wordTimedLyrics")]
+        word_timed_lyrics: Option<Vec<crate::lyrics_fetcher::WordTimedLine>>,
+        #[serde(rename = "lyricsLanguage")]
+        lyrics_language: Option<String>,
     },
     /// Stream stopped (returned to desktop)
     StreamStopped,
@@ -195,6 +209,12 @@ pub enum ServerEvent {
     StatsUpdated {
         timestamp: i64,
     },
+    /// A track was added to or removed from favorites
+    #[serde(rename_all = "camelCase")]
+    FavoriteChanged {
+        path: String,
+        is_favorite: bool,
+    },
 }
 
 /// Track summary for queue updates
@@ -266,10 +286,27 @@ fn allowed_cors_origins() -> Vec<HeaderValue> {
 /// Start the HTTP/WebSocket server
 pub async fn start_server(
     app_handle: AppHandle,
-    config: ServerConfig,
+    mut config: ServerConfig,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Bind before anything else so the real port ends up in the config, the
+    // mDNS record and the stream URLs handed to clients
+    let listener = match std::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], config.port))) {
+        Ok(listener) => listener,
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            println!("[Server] Port {} is in use, falling back to an ephemeral port", config.port);
+            std::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], 0)))?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    listener.set_nonblocking(true)?;
+    let addr = listener.local_addr()?;
+    config.port = addr.port();
     let port = config.port;
+
+    if config.control_token.is_none() {
+        config.control_token = Some(auth::load_or_create_token(&app_handle)?);
+    }
     let server_state = Arc::new(ServerState::new(app_handle.clone(), config));
 
     // Wire the broadcast sender back into AppState so Tauri commands can use it
@@ -310,38 +347,7 @@ pub async fn start_server(
 
                         if should_autoplay {
                             // Check if we have anything in queue to play next
-                            let next_action = {
-                                let queue = app_state.queue.lock().unwrap();
-                                let mut index_guard = app_state.current_queue_index.lock().unwrap();
-                                let repeat_mode = app_state.repeat_mode.lock().unwrap();
-                                
-                                if queue.is_empty() {
-                                    None
-                                } else {
-                                    let mut next_idx = *index_guard + 1;
-                                    let mut do_play = true;
-                                    
-                                    if next_idx >= queue.len() {
-                                        if *repeat_mode == "all" {
-                                            next_idx = 0;
-                                        } else {
-                                            do_play = false;
-                                        }
-                                    }
-                                    
-                                    if *repeat_mode == "one" {
-                                        next_idx = *index_guard; // Keep same index
-                                        do_play = true;
-                                    }
-
-                                    if do_play {
-                                        *index_guard = next_idx;
-                                        Some(queue[next_idx].path.clone())
-                                    } else {
-                                        None
-                                    }
-                                }
-                            };
+                            let next_action = crate::queue::auto_advance(&app_state);
 
                             if let Some(path) = next_action {
                                 println!("[Autoplay] Automatically playing next track: {}", path);
@@ -375,11 +381,13 @@ pub async fn start_server(
                                         }
                                     }
                                 }
+                                crate::sync_next_track(&app_state);
+                                crate::schedule_play_count(&broadcast_handle, path.clone());
 
                                 // In mobile output mode, autoplay path must emit a fresh
                                 // handoff event so the phone switches to the new stream URL.
                                 if is_mobile {
-                                    websocket::broadcast_mobile_handoff_for_path(&broadcast_state, &path, 0);
+                                    websocket::send_mobile_handoff_for_path(&broadcast_state, &path, 0);
                                 }
                                 
                                 // Broadcast the update so mobile knows to fetch new stream URL if needed
@@ -404,6 +412,15 @@ pub async fn start_server(
         .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
         .allow_headers(Any);
 
+    // JSON responses are compressed per Accept-Encoding. Audio and covers are
+    // already compressed formats (images are skipped by the default predicate),
+    // and compressing audio would also break byte-range seeking.
+    let compression = CompressionLayer::new().compress_when(
+        DefaultPredicate::new()
+            .and(NotForContentType::const_new("audio/"))
+            .and(NotForContentType::const_new("application/octet-stream")),
+    );
+
     let app = Router::new()
         // Health check
         .route("/health", get(health_check))
@@ -412,36 +429,61 @@ pub async fn start_server(
         .route("/api/playback", get(get_playback_state))
         .route("/api/library", get(get_library))
         .route("/api/library/search", get(search_library))
+        .route("/api/recently-added", get(get_recently_added))
+        .route("/api/queue", get(get_queue).post(update_queue))
         .route("/api/albums", get(get_albums))
         .route("/api/albums/:name/:artist", get(get_album_detail))
         .route("/api/artists", get(get_artists))
         .route("/api/artists/:name", get(get_artist_detail))
+        .route("/api/genres", get(get_genres))
+        .route("/api/genres/:name", get(get_genre_detail))
         .route("/api/lyrics/*path", get(get_lyrics))
         .route("/api/stats", get(get_stats))
         .route("/api/stats/events", get(get_stats_events))
+        .route("/api/stats/heatmap", get(get_stats_heatmap))
+        .route("/api/stats/top", get(get_stats_top))
+        .route("/api/waveform/*path", get(get_waveform))
         // Cover art
         .route("/cover/*path", get(get_cover))
+        .route("/api/cover/album/:name/:artist", get(get_album_cover))
         // Audio streaming — use wildcard so Windows absolute paths (C:/...) work
         .route("/stream/*path", get(stream_audio_file))
         .route("/stream", get(stream_audio))
-        // WebSocket
+        // Everything above requires the server token
+        .route_layer(axum::middleware::from_fn_with_state(
+            server_state.clone(),
+            auth::require_token,
+        ))
+        .layer(compression)
+        // WebSocket (authenticates via query/header or the `hello` message)
         .route("/control", get(websocket_handler))
         // CORS
         .layer(cors)
         .with_state(server_state.clone());
     
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    log::info!("Starting VIBE-ON! server on http://{}", addr);
-    println!("[Server] HTTP/WS listening on http://{}", addr);
+    let use_tls = server_state.config.use_tls;
+    let scheme = if use_tls { "https" } else { "http" };
+    log::info!("Starting VIBE-ON! server on {}://{}", scheme, addr);
+    println!("[Server] HTTP/WS listening on {}://{}", scheme, addr);
+
+    {
+        let app_state: tauri::State<'_, crate::AppState> = app_handle.state();
+        *app_state.server_port.lock().unwrap() = Some(port);
+    }
+    let _ = app_handle.emit(
+        "server-started",
+        serde_json::json!({ "port": port, "scheme": scheme }),
+    );
     
     // Start mDNS advertisement
     let server_name = server_state.config.server_name.clone();
     
     // Use select to handle mDNS task with shutdown
     let mut mdns_shutdown = shutdown_rx.resubscribe();
+    let mdns_name = server_name.clone();
     tokio::spawn(async move {
         tokio::select! {
-            _ = advertise_mdns(&server_name, port) => {
+            _ = advertise_mdns(&mdns_name, port, use_tls) => {
                  log::error!("mDNS advertisement ended unexpectedly");
             }
             _ = mdns_shutdown.recv() => {
@@ -450,8 +492,29 @@ pub async fn start_server(
         }
     });
     
+    if use_tls {
+        let tls_dir = app_handle.path().app_data_dir()?.join("tls");
+        let (cert_path, key_path) = tls::load_or_create_cert(&tls_dir, &server_name)?;
+        let rustls_config =
+            axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_path, key_path).await?;
+
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
+            let _ = shutdown_rx.recv().await;
+            println!("[Server] Graceful shutdown signal received");
+            shutdown_handle.graceful_shutdown(Some(std::time::Duration::from_secs(5)));
+        });
+
+        axum_server::from_tcp_rustls(listener, rustls_config)
+            .handle(handle)
+            .serve(app.into_make_service())
+            .await?;
+        return Ok(());
+    }
+
     // Start server with graceful shutdown
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
     axum::serve(listener, app.into_make_service())
         .with_graceful_shutdown(async move {
             let _ = shutdown_rx.recv().await;
@@ -463,7 +526,7 @@ pub async fn start_server(
 }
 
 /// Advertise the server via mDNS
-async fn advertise_mdns(server_name: &str, port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+async fn advertise_mdns(server_name: &str, port: u16, use_tls: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use mdns_sd::{ServiceDaemon, ServiceInfo};
     
     log::info!("mDNS: Advertising _vibe-on._tcp on port {}", port);
@@ -493,6 +556,9 @@ async fn advertise_mdns(server_name: &str, port: u16) -> Result<(), Box<dyn std:
     
     log::info!("mDNS: Using IPv4 address: {}", ipv4_addr);
     
+    let scheme = if use_tls { "https" } else { "http" };
+    let port_txt = port.to_string();
+
     // Create service info with specific IPv4 address as hostname
     let service_info = ServiceInfo::new(
         service_type,
@@ -500,7 +566,7 @@ async fn advertise_mdns(server_name: &str, port: u16) -> Result<(), Box<dyn std:
         &format!("{}.local.", instance_name),
         &ipv4_addr, // Use IPv4 address directly
         port,
-        &[("version", "1")][..]
+        &[("version", "1"), ("scheme", scheme), ("port", port_txt.as_str())][..]
     )?;
     
     // Register the service
@@ -6,14 +6,20 @@ use axum::{
     body::Body,
     extract::{Path, Query, State},
     http::{header, HeaderMap, StatusCode},
-    response::Response,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::audio::TrackInfo;
-use super::{ServerState, TrackSummary};
+use crate::database::db::{image_type, ALBUM_SEARCH_COLUMNS, ARTIST_SEARCH_COLUMNS};
+use super::{thumbnail, transcode, ServerEvent, ServerState, TrackSummary};
+use tauri::{Emitter, Manager};
 use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
 
 /// Health check response
 #[derive(Serialize)]
@@ -54,6 +60,8 @@ pub struct PlaybackStateResponse {
     #[serde(rename = "repeatMode")]
     pub repeat_mode: String,
     pub queue: Vec<TrackSummary>,
+    #[serde(rename = "currentIndex")]
+    pub current_index: i32,
 }
 
 /// Detailed track info
@@ -100,6 +108,9 @@ pub struct AlbumInfo {
     pub album_main_color: Option<i64>,
     #[serde(rename = "trackCount")]
     pub track_count: usize,
+    /// Tagged as a compilation (`TCMP` / `COMPILATION`)
+    #[serde(rename = "isCompilation")]
+    pub is_compilation: bool,
 }
 
 /// Artist info
@@ -112,6 +123,14 @@ pub struct ArtistInfo {
     pub track_count: usize,
 }
 
+/// Genre info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenreInfo {
+    pub name: String,
+    #[serde(rename = "trackCount")]
+    pub track_count: usize,
+}
+
 /// Library response
 #[derive(Serialize)]
 pub struct LibraryResponse {
@@ -119,6 +138,28 @@ pub struct LibraryResponse {
     pub total: usize,
 }
 
+/// Recently added response
+#[derive(Serialize)]
+pub struct RecentlyAddedResponse {
+    pub tracks: Vec<TrackDetail>,
+}
+
+/// Queue response
+#[derive(Serialize)]
+pub struct QueueResponse {
+    pub tracks: Vec<TrackSummary>,
+    #[serde(rename = "currentIndex")]
+    pub current_index: i32,
+}
+
+/// Queue edit body: a full new ordering of the current positions, or one move
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum QueueEditRequest {
+    Reorder { order: Vec<usize> },
+    Move { from: usize, to: usize },
+}
+
 /// Search response
 #[derive(Serialize)]
 pub struct SearchResponse {
@@ -156,6 +197,19 @@ pub struct ArtistDetailResponse {
     pub tracks: Vec<TrackDetail>,
 }
 
+/// Genres response
+#[derive(Serialize)]
+pub struct GenresResponse {
+    pub genres: Vec<GenreInfo>,
+}
+
+/// Genre detail response
+#[derive(Serialize)]
+pub struct GenreDetailResponse {
+    pub genre: GenreInfo,
+    pub tracks: Vec<TrackDetail>,
+}
+
 /// Lyrics response
 #[derive(Serialize)]
 pub struct LyricsResponse {
@@ -168,6 +222,11 @@ pub struct LyricsResponse {
     #[serde(rename = "plainLyrics")]
     pub plain_lyrics: Option<String>,
     pub instrumental: bool,
+    #[serde(rename = "wordTimedLyrics")]
+    pub word_timed_lyrics: Option<Vec<crate::lyrics_fetcher::WordTimedLine>>,
+    /// "ja", "ko" or "zh" when the lyrics were romanized
+    #[serde(rename = "lyricsLanguage")]
+    pub lyrics_language: Option<String>,
 }
 
 /// Statistics response
@@ -198,6 +257,12 @@ pub struct SearchParams {
     pub limit: Option<usize>,
 }
 
+/// Result size query params
+#[derive(Debug, Deserialize)]
+pub struct LimitParams {
+    pub limit: Option<usize>,
+}
+
 /// Stream query params
 #[derive(Debug, Deserialize)]
 pub struct StreamParams {
@@ -213,6 +278,24 @@ pub struct StatsEventsParams {
     pub end_ms: Option<i64>,
 }
 
+/// `/api/stats/top` query params
+#[derive(Debug, Deserialize)]
+pub struct TopStatsParams {
+    /// `songs` (default) or `artists`
+    pub metric: Option<String>,
+    /// `week`, `month` or `all` (default)
+    pub range: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Ranked entries of `/api/stats/top`, serialized as a plain array
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum TopStatsResponse {
+    Songs(Vec<crate::stats::SongRanking>),
+    Artists(Vec<crate::stats::ArtistRanking>),
+}
+
 /// Stream range params for HTTP Range requests
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
@@ -325,6 +408,11 @@ pub async fn get_playback_state(
             (false, None, 0.0, 0.0, 1.0)
         }
     };
+
+    // Same queue/shuffle/repeat view the WebSocket clients get
+    let (queue, current_index) = super::websocket::read_queue(&app_state);
+    let shuffle = *app_state.shuffle.lock().unwrap();
+    let repeat_mode = app_state.repeat_mode.lock().unwrap().clone();
     
     Json(PlaybackStateResponse {
         is_playing,
@@ -332,9 +420,10 @@ pub async fn get_playback_state(
         position_secs,
         duration_secs,
         volume: volume as f64,
-        shuffle: false, // TODO: Get from frontend state
-        repeat_mode: "off".to_string(),
-        queue: vec![], // TODO: Implement queue sync
+        shuffle,
+        repeat_mode,
+        queue,
+        current_index: current_index as i32,
     })
 }
 
@@ -347,35 +436,17 @@ pub async fn get_library(
     let limit = params.limit.unwrap_or(50);
     
     let app_state = state.app_state();
-    
-    // Get total count (inefficient but needed for pagination UI, ideally separate count query)
-    // For now, let's just get all tracks count from DB or use a count method if added.
-    // db.rs doesn't have count method.
-    // Using get_all_tracks() just for count is bad but better than transferring all data.
-    // Actually, let's assume total is large and just return arbitrary large number or 
-    // implement `get_total_tracks_count` in DB.
-    // For this sprint, I'll stick to `get_all_tracks().len()` for total, 
-    // BUT use `get_tracks_paginated` for actual data. 
-    // This is still O(N) for count, but O(1) for data transfer. 
-    // Ideally user scrolls infinitely so total doesn't matter much or we can cache it.
-    
-    // OPTIMIZATION: We really should add `get_track_count` to db.rs.
-    // But for now, let's focus on the data fetch.
-    
-    let tracks = app_state.db.lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .as_ref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
+    let db_guard = app_state.db.lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let db = db_guard.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    let tracks = db
         .get_tracks_paginated(limit, offset)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    // We need total for the UI.
-    // Temporary hack: fetch all for total until get_count is added.
-    // Or just pass 99999 if UI handles it.
-    // Let's do a quick count query if possible or just use existing get_all_tracks for now 
-    // accepting the CPU cost for count but saving memory/transfer for data.
-    let total = app_state.db.lock()
-       .unwrap().as_ref().unwrap().get_all_tracks().map(|t| t.len()).unwrap_or(0);
+    let total = db
+        .get_track_count()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    drop(db_guard);
 
     let tracks: Vec<TrackDetail> = tracks
         .into_iter()
@@ -402,33 +473,102 @@ pub async fn get_library(
     Ok(Json(LibraryResponse { tracks, total }))
 }
 
+/// Newest tracks in the library, by when they were first added
+pub async fn get_recently_added(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<LimitParams>,
+) -> Result<Json<RecentlyAddedResponse>, StatusCode> {
+    let limit = params.limit.unwrap_or(50).min(500);
+
+    let app_state = state.app_state();
+    let recent = app_state.db.lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
+        .get_recently_added(limit)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let tracks: Vec<TrackDetail> = recent
+        .into_iter()
+        .map(|t| TrackDetail {
+            path: t.path.clone(),
+            title: t.title,
+            artist: t.artist,
+            album: t.album,
+            duration_secs: t.duration_secs,
+            disc_number: t.disc_number,
+            track_number: t.track_number,
+            cover_url: Some(cover_url_for(t.cover_image.as_deref(), &t.path)),
+            album_main_color: t.album_main_color,
+            title_romaji: t.title_romaji,
+            title_en: t.title_en,
+            artist_romaji: t.artist_romaji,
+            artist_en: t.artist_en,
+            album_romaji: t.album_romaji,
+            album_en: t.album_en,
+            playlist_track_id: t.playlist_track_id,
+        })
+        .collect();
+
+    Ok(Json(RecentlyAddedResponse { tracks }))
+}
+
+/// Get the play queue, the same view WebSocket clients get
+pub async fn get_queue(State(state): State<Arc<ServerState>>) -> Json<QueueResponse> {
+    let (tracks, current_index) = super::websocket::read_queue(&state.app_state());
+    Json(QueueResponse { tracks, current_index: current_index as i32 })
+}
+
+/// Reorder the play queue or move one entry; the current track stays current
+pub async fn update_queue(
+    State(state): State<Arc<ServerState>>,
+    Json(edit): Json<QueueEditRequest>,
+) -> Result<Json<QueueResponse>, StatusCode> {
+    let app_state = state.app_state();
+    let result = match edit {
+        QueueEditRequest::Reorder { order } => crate::queue::reorder(&app_state, &order),
+        QueueEditRequest::Move { from, to } => crate::queue::move_entry(&app_state, from, to),
+    };
+    result.map_err(|_| StatusCode::BAD_REQUEST)?;
+    crate::sync_next_track(&app_state);
+
+    let (tracks, current_index) = super::websocket::read_queue(&app_state);
+    state.broadcast(ServerEvent::QueueUpdate {
+        tracks: tracks.clone(),
+        current_index: current_index as i32,
+    });
+    let _ = state.app_handle.emit("refresh-player-state", ());
+
+    Ok(Json(QueueResponse { tracks, current_index: current_index as i32 }))
+}
+
 /// Search library
 pub async fn search_library(
     State(state): State<Arc<ServerState>>,
     Query(params): Query<SearchParams>,
 ) -> Result<Json<SearchResponse>, StatusCode> {
-    let query = params.q.to_lowercase();
     let offset = params.offset.unwrap_or(0);
     let limit = params.limit.unwrap_or(50);
     
+    // Albums and artists are grouped from the tracks the search index finds for them
     let app_state = state.app_state();
-    let all_tracks = app_state.db.lock()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .as_ref()
-        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
-        .get_all_tracks()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (matched_tracks, album_tracks, artist_tracks) = {
+        let db_guard = app_state.db.lock()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let db = db_guard.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+        (
+            db.search_tracks(&params.q, limit, offset)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+            db.search_tracks_in(&params.q, ALBUM_SEARCH_COLUMNS)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+            db.search_tracks_in(&params.q, ARTIST_SEARCH_COLUMNS)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        )
+    };
     
-    // Filter tracks
-    let tracks: Vec<TrackDetail> = all_tracks
+    // Tracks come from the full-text index, already ranked and paged
+    let tracks: Vec<TrackDetail> = matched_tracks
         .iter()
-        .filter(|t| {
-            t.title.to_lowercase().contains(&query) ||
-            t.artist.to_lowercase().contains(&query) ||
-            t.album.to_lowercase().contains(&query)
-        })
-        .skip(offset)
-        .take(limit)
         .map(|t| TrackDetail {
             path: t.path.clone(),
             title: t.title.clone(),
@@ -451,34 +591,34 @@ pub async fn search_library(
     
     // Get unique albums
     let mut albums_map = std::collections::HashMap::new();
-    for track in &all_tracks {
-        if track.album.to_lowercase().contains(&query) ||
-           track.artist.to_lowercase().contains(&query) {
-            let key = (track.album.clone(), track.artist.clone());
-            let entry = albums_map.entry(key).or_insert((0, track.path.clone()));
-            entry.0 += 1;
-        }
+    for track in &album_tracks {
+        // Same grouping as the album list, so compilations stay one album
+        let artist = track.album_artist.clone().unwrap_or_else(|| track.artist.clone());
+        let entry = albums_map
+            .entry((track.album.clone(), artist))
+            .or_insert((0, track.path.clone(), false));
+        entry.0 += 1;
+        entry.2 |= track.is_compilation;
     }
     let albums: Vec<AlbumInfo> = albums_map
         .into_iter()
         .take(limit)
-        .map(|((name, artist), (count, path))| AlbumInfo {
+        .map(|((name, artist), (count, path, is_compilation))| AlbumInfo {
             name,
             artist,
             cover_url: Some(format!("/cover/{}", urlencoding::encode(&path))),
             album_main_color: None,
             track_count: count,
+            is_compilation,
         })
         .collect();
     
     // Get unique artists
     let mut artists_map = std::collections::HashMap::new();
-    for track in &all_tracks {
-        if track.artist.to_lowercase().contains(&query) {
-            let entry = artists_map.entry(track.artist.clone()).or_insert((std::collections::HashSet::new(), 0));
-            entry.0.insert(track.album.clone());
-            entry.1 += 1;
-        }
+    for track in &artist_tracks {
+        let entry = artists_map.entry(track.artist.clone()).or_insert((std::collections::HashSet::new(), 0));
+        entry.0.insert(track.album.clone());
+        entry.1 += 1;
     }
     let artists: Vec<ArtistInfo> = artists_map
         .into_iter()
@@ -517,31 +657,116 @@ pub async fn get_albums(
             cover_url: a.cover_image_path.map(|p| format!("/cover/{}", urlencoding::encode(&p))),
             album_main_color: a.main_color,
             track_count: a.track_count,
+            is_compilation: a.is_compilation,
         })
         .collect();
     
     Ok(Json(AlbumsResponse { albums, total }))
 }
 
-/// Get album detail
-pub async fn get_album_detail(
+/// Get all genres with track counts
+pub async fn get_genres(
     State(state): State<Arc<ServerState>>,
-    Path((name, artist)): Path<(String, String)>,
-) -> Result<Json<AlbumDetailResponse>, StatusCode> {
+) -> Result<Json<GenresResponse>, StatusCode> {
+    let app_state = state.app_state();
+    let genres = app_state.db.lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_ref()
+        .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
+        .get_genres()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|(name, track_count)| GenreInfo { name, track_count })
+        .collect();
+
+    Ok(Json(GenresResponse { genres }))
+}
+
+/// Get tracks for a genre
+pub async fn get_genre_detail(
+    State(state): State<Arc<ServerState>>,
+    Path(name): Path<String>,
+) -> Result<Json<GenreDetailResponse>, StatusCode> {
     let name = urlencoding::decode(&name).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
-    let artist = urlencoding::decode(&artist).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
-    
+
     let app_state = state.app_state();
-    let all_tracks = app_state.db.lock()
+    let genre_tracks = app_state.db.lock()
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .as_ref()
         .ok_or(StatusCode::SERVICE_UNAVAILABLE)?
-        .get_all_tracks()
+        .get_tracks_by_genre(&name)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if genre_tracks.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let tracks: Vec<TrackDetail> = genre_tracks
+        .into_iter()
+        .map(|t| TrackDetail {
+            path: t.path.clone(),
+            title: t.title,
+            artist: t.artist,
+            album: t.album,
+            duration_secs: t.duration_secs,
+            disc_number: t.disc_number,
+            track_number: t.track_number,
+            cover_url: Some(format!("/cover/{}", urlencoding::encode(t.cover_image.as_deref().unwrap_or(&t.path)))),
+            album_main_color: t.album_main_color,
+            title_romaji: t.title_romaji,
+            title_en: t.title_en,
+            artist_romaji: t.artist_romaji,
+            artist_en: t.artist_en,
+            album_romaji: t.album_romaji,
+            album_en: t.album_en,
+            playlist_track_id: t.playlist_track_id,
+        })
+        .collect();
+
+    let genre = GenreInfo {
+        name,
+        track_count: tracks.len(),
+    };
+
+    Ok(Json(GenreDetailResponse { genre, tracks }))
+}
+
+/// `/cover/...` URL for a track: the cached cover filename when the scan resolved
+/// one, otherwise the audio path so the cover handler extracts embedded art.
+fn cover_url_for(cover_image: Option<&str>, path: &str) -> String {
+    format!("/cover/{}", urlencoding::encode(cover_image.unwrap_or(path)))
+}
+
+/// Get album detail
+pub async fn get_album_detail(
+    State(state): State<Arc<ServerState>>,
+    Path((name, artist)): Path<(String, String)>,
+) -> Result<Json<AlbumDetailResponse>, StatusCode> {
+    let app_state = state.app_state();
+    let db_guard = app_state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let db = db_guard.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    album_detail(db, &name, &artist).map(Json)
+}
+
+/// Tracks and summary of the album `name` by `artist`, both URL-encoded
+fn album_detail(
+    db: &crate::database::DatabaseManager,
+    name: &str,
+    artist: &str,
+) -> Result<AlbumDetailResponse, StatusCode> {
+    let name = urlencoding::decode(name).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
+    let artist = urlencoding::decode(artist).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
+
+    let all_tracks = db.get_all_tracks().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let tracks: Vec<TrackDetail> = all_tracks
+    let album_tracks: Vec<_> = all_tracks
+        .into_iter()
+        .filter(|t| t.album == name && t.album_artist.as_deref().unwrap_or(&t.artist) == artist)
+        .collect();
+    let is_compilation = album_tracks.iter().any(|t| t.is_compilation);
+
+    let tracks: Vec<TrackDetail> = album_tracks
         .into_iter()
-        .filter(|t| t.album == name && t.artist == artist)
         .map(|t| TrackDetail {
             path: t.path.clone(),
             title: t.title,
@@ -550,7 +775,7 @@ pub async fn get_album_detail(
             duration_secs: t.duration_secs,
             disc_number: t.disc_number,
             track_number: t.track_number,
-            cover_url: Some(format!("/cover/{}", urlencoding::encode(&t.path))),
+            cover_url: Some(cover_url_for(t.cover_image.as_deref(), &t.path)),
             album_main_color: t.album_main_color,
             title_romaji: t.title_romaji,
             title_en: t.title_en,
@@ -572,9 +797,10 @@ pub async fn get_album_detail(
         cover_url: tracks.first().and_then(|t| t.cover_url.clone()),
         album_main_color: tracks.first().and_then(|t| t.album_main_color),
         track_count: tracks.len(),
+        is_compilation,
     };
     
-    Ok(Json(AlbumDetailResponse { album, tracks }))
+    Ok(AlbumDetailResponse { album, tracks })
 }
 
 /// Get all artists
@@ -631,7 +857,7 @@ pub async fn get_artist_detail(
             duration_secs: t.duration_secs,
             disc_number: t.disc_number,
             track_number: t.track_number,
-            cover_url: Some(format!("/cover/{}", urlencoding::encode(&t.path))),
+            cover_url: Some(cover_url_for(t.cover_image.as_deref(), &t.path)),
             album_main_color: t.album_main_color,
             title_romaji: t.title_romaji.clone(),
             title_en: t.title_en.clone(),
@@ -650,15 +876,21 @@ pub async fn get_artist_detail(
     // Get unique albums
     let mut albums_map = std::collections::HashMap::new();
     for track in &tracks {
-        let entry = albums_map.entry(track.album.clone()).or_insert((0, track.path.clone()));
+        let entry = albums_map.entry(track.album.clone()).or_insert((0, track.cover_url.clone()));
         entry.0 += 1;
     }
+    let compilations: std::collections::HashSet<&str> = all_tracks
+        .iter()
+        .filter(|t| t.artist == name && t.is_compilation)
+        .map(|t| t.album.as_str())
+        .collect();
     let albums: Vec<AlbumInfo> = albums_map
         .into_iter()
-        .map(|(album_name, (count, path))| AlbumInfo {
+        .map(|(album_name, (count, cover_url))| AlbumInfo {
+            is_compilation: compilations.contains(album_name.as_str()),
             name: album_name,
             artist: name.clone(),
-            cover_url: Some(format!("/cover/{}", urlencoding::encode(&path))),
+            cover_url,
             album_main_color: None,
             track_count: count,
         })
@@ -697,11 +929,15 @@ pub async fn get_lyrics(
         return Err(StatusCode::NOT_FOUND);
     };
 
+    let offset_ms = crate::lyrics_offset_ms(&app_state, &track_path);
+
     // 2. Try Local LRC first (Instant)
     if let Some(mut local_lyrics) = crate::lyrics_fetcher::find_local_lrc(&track_path) {
+        crate::lyrics_fetcher::apply_offset(&mut local_lyrics, offset_ms);
+        let lyrics_language = crate::lyrics_fetcher::detect_language(&local_lyrics);
         // Transliterate if needed
         if let Some(ref synced) = local_lyrics.synced_lyrics {
-            if crate::lyrics_transliteration::has_japanese(synced) { // Check for JP characters
+            if crate::lyrics_transliteration::needs_transliteration(synced) { // Check for CJK characters
                 local_lyrics.synced_lyrics = Some(crate::lyrics_transliteration::transliterate_lyrics(synced));
             }
         }
@@ -711,37 +947,44 @@ pub async fn get_lyrics(
             synced_lyrics: local_lyrics.synced_lyrics,
             plain_lyrics: local_lyrics.plain_lyrics,
             instrumental: local_lyrics.instrumental.unwrap_or(false),
+            word_timed_lyrics: local_lyrics.word_timed_lyrics,
+            lyrics_language,
         }));
     }
 
-    // 3. Fetch from API (Blocking)
+    // 3. Lyrics stored in the database, else fetch from API (Blocking)
     let artist = track.artist.clone();
     let title = track.title.clone();
     let duration = track.duration_secs as u32;
+    let app_handle = state.app_handle.clone();
+    let lookup_path = track_path.clone();
 
     // Use spawn_blocking for network request
     let api_result = tokio::task::spawn_blocking(move || {
         // We pass a no-op closure for progress updates since we can't stream them easily over HTTP here
-        let mut lyrics = crate::lyrics_fetcher::fetch_lyrics(&artist, &title, duration, |_| {})
-            .or_else(|_| crate::lyrics_fetcher::fetch_lyrics_fallback(&artist, &title, |_| {}))?;
+        let mut lyrics = crate::load_lyrics(&app_handle, &lookup_path, &artist, &title, duration, |_| {})?;
+        crate::lyrics_fetcher::apply_offset(&mut lyrics, offset_ms);
         
+        let lyrics_language = crate::lyrics_fetcher::detect_language(&lyrics);
         // Transliterate if needed
         if let Some(ref synced) = lyrics.synced_lyrics {
-            if crate::lyrics_transliteration::has_japanese(synced) {
+            if crate::lyrics_transliteration::needs_transliteration(synced) {
                 lyrics.synced_lyrics = Some(crate::lyrics_transliteration::transliterate_lyrics(synced));
             }
         }
         
-        Ok::<_, String>(lyrics) // Return a Result from the blocking task
+        Ok::<_, String>((lyrics, lyrics_language)) // Return a Result from the blocking task
     }).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     match api_result {
-        Ok(lyrics) => Ok(Json(LyricsResponse {
+        Ok((lyrics, lyrics_language)) => Ok(Json(LyricsResponse {
             track_path: track_path.clone(),
             has_synced: lyrics.synced_lyrics.is_some(),
             synced_lyrics: lyrics.synced_lyrics,
             plain_lyrics: lyrics.plain_lyrics,
             instrumental: lyrics.instrumental.unwrap_or(false),
+            word_timed_lyrics: lyrics.word_timed_lyrics,
+            lyrics_language,
         })),
         Err(_) => {
             // Return empty response if not found, rather than error, so UI knows we tried
@@ -751,6 +994,8 @@ pub async fn get_lyrics(
                 synced_lyrics: None,
                 plain_lyrics: None,
                 instrumental: false,
+                word_timed_lyrics: None,
+                lyrics_language: None,
             }))
         }
     }
@@ -798,20 +1043,165 @@ pub async fn get_stats(
     }))
 }
 
+/// Recorded playback events as JSON, or, for clients that accept
+/// `text/event-stream`, a live stream with a `statsUpdated` event per
+/// playback event recorded from now on
 pub async fn get_stats_events(
     State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
     Query(params): Query<StatsEventsParams>,
-) -> Result<Json<Vec<crate::stats::PlaybackEvent>>, StatusCode> {
+) -> Response {
     let app_state = state.app_state();
-    crate::stats::load_stats_events(&app_state, params.start_ms, params.end_ms)
+    let wants_stream = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    if wants_stream {
+        return stats_update_stream(app_state.stats_updates.subscribe()).into_response();
+    }
+
+    match crate::stats::load_stats_events(&app_state, params.start_ms, params.end_ms) {
+        Ok(events) => Json(events).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// SSE stream of `StatsUpdated` events. Dropped by axum when the client
+/// disconnects, which drops the subscription with it.
+fn stats_update_stream(
+    rx: tokio::sync::broadcast::Receiver<crate::stats::PlaybackEvent>,
+) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let update = ServerEvent::StatsUpdated {
+                        timestamp: event.timestamp,
+                    };
+                    let Ok(sse) = Event::default().event("statsUpdated").json_data(&update) else {
+                        continue;
+                    };
+                    return Some((Ok(sse), rx));
+                }
+                // A slow client missed some updates; the next one still tells it to refresh
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Top songs or artists by listening time, using the same aggregation as the
+/// desktop stats screen
+pub async fn get_stats_top(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<TopStatsParams>,
+) -> Result<Json<TopStatsResponse>, StatusCode> {
+    let range = match params.range {
+        Some(range) => {
+            crate::stats_v2::StatsTimeRangeV2::from_name(&range).ok_or(StatusCode::BAD_REQUEST)?
+        }
+        None => crate::stats_v2::StatsTimeRangeV2::All,
+    };
+    let (start_ms, end_ms) = range.bounds_ms();
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+
+    let app_state = state.app_state();
+    let response = match params.metric.as_deref().unwrap_or("songs") {
+        "songs" => crate::stats::top_songs(&app_state, start_ms, Some(end_ms), limit)
+            .map(TopStatsResponse::Songs),
+        "artists" => crate::stats::top_artists(&app_state, start_ms, Some(end_ms), limit)
+            .map(TopStatsResponse::Artists),
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    response.map(Json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Listened milliseconds per UTC day, keyed by midnight ms
+pub async fn get_stats_heatmap(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<StatsEventsParams>,
+) -> Result<Json<std::collections::BTreeMap<i64, i64>>, StatusCode> {
+    let app_state = state.app_state();
+    crate::stats::listening_by_day(&app_state, params.start_ms, params.end_ms)
         .map(Json)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
 
-/// Get cover art for a track
+/// Cached waveform peaks for a library track
+pub async fn get_waveform(
+    State(state): State<Arc<ServerState>>,
+    Path(path): Path<String>,
+) -> Result<Json<crate::audio::waveform::Waveform>, StatusCode> {
+    let track_path = crate::database::db::normalize_track_path(
+        &urlencoding::decode(&path).map_err(|_| StatusCode::BAD_REQUEST)?,
+    );
+
+    // Only tracks in the library can be decoded
+    {
+        let app_state = state.app_state();
+        let db_lock = app_state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let db = db_lock.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+        if db.get_track(&track_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.is_none() {
+            return Err(StatusCode::NOT_FOUND);
+        }
+    }
+
+    let cache_dir = state.app_handle.path().app_cache_dir()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tokio::task::spawn_blocking(move || {
+        crate::audio::waveform::waveform_cached(&cache_dir, std::path::Path::new(&track_path))
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map(Json)
+    .map_err(|e| {
+        log::error!("❌ Waveform generation failed: {}", e);
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+/// Optional thumbnail size for `/cover/*path`
+#[derive(Deserialize)]
+pub struct CoverParams {
+    /// Longest edge in px, rounded to one of `thumbnail::THUMBNAIL_SIZES`
+    pub size: Option<u32>,
+}
+
+/// Resized JPEG of a cover file, or `None` to fall back to the full image
+async fn thumbnail_response(
+    covers_dir: std::path::PathBuf,
+    cover_path: std::path::PathBuf,
+    size: u32,
+) -> Option<Response<Body>> {
+    let size = thumbnail::nearest_size(size);
+    let thumb = tokio::task::spawn_blocking(move || {
+        thumbnail::cached_thumbnail(&covers_dir, &cover_path, size)
+    })
+    .await
+    .ok()?
+    .map_err(|e| log::warn!("Thumbnail generation failed: {}", e))
+    .ok()?;
+
+    let data = tokio::fs::read(&thumb).await.ok()?;
+    Some(
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "image/jpeg")
+            .header(header::CACHE_CONTROL, "public, max-age=86400")
+            .body(Body::from(data))
+            .unwrap(),
+    )
+}
+
+/// Get cover art for a track. `?size=N` returns a cached, resized JPEG.
 pub async fn get_cover(
     State(state): State<Arc<ServerState>>,
     Path(path): Path<String>,
+    Query(params): Query<CoverParams>,
 ) -> Result<Response<Body>, StatusCode> {
     let track_path = urlencoding::decode(&path)
         .map_err(|_| {
@@ -824,6 +1214,7 @@ pub async fn get_cover(
     log::info!("🖼️ Cover request for: {}", track_path);
     
     let app_state = state.app_state();
+    let mut covers_dir = None;
     
     let cover_file_path = {
         let db_guard = app_state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -831,7 +1222,7 @@ pub async fn get_cover(
             // 1. Check if it's a direct filename request (cached cover)
             // A direct filename should NOT contain slashes and should NOT be an absolute path.
             let is_filename_only = !track_path.contains('/') && !track_path.contains(':');
-            let covers_dir = db.get_covers_dir();
+            let covers_dir = covers_dir.insert(db.get_covers_dir());
             
             if is_filename_only {
                 let potential_cached_path = covers_dir.join(&track_path);
@@ -864,23 +1255,9 @@ pub async fn get_cover(
     };
     
     // Try to read from cover file (after releasing lock)
-    if let Some(cover_path) = cover_file_path {
-        if let Ok(data) = tokio::fs::read(&cover_path).await {
-            println!("[Server] Successfully read cover file");
-            let content_type = if cover_path.to_string_lossy().ends_with(".png") {
-                "image/png"
-            } else {
-                "image/jpeg"
-            };
-            
-            return Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, content_type)
-                .header(header::CACHE_CONTROL, "public, max-age=86400")
-                .body(Body::from(data))
-                .unwrap());
-        } else {
-            println!("[Server] Failed to read cover file at {:?}", cover_path);
+    if let (Some(cover_path), Some(dir)) = (cover_file_path, covers_dir) {
+        if let Some(response) = cached_cover_response(dir, cover_path, params.size).await {
+            return Ok(response);
         }
     }
     
@@ -902,30 +1279,33 @@ pub async fn get_cover(
         Some((data, mime)) => {
             println!("[Server] Successfully extracted cover!");
             // CACHE HIT: Save to disk and update DB
+            let mut saved_cover = None;
             let app_state = state.app_state();
             if let Ok(db_guard) = app_state.db.lock() {
                 if let Some(ref db) = *db_guard {
-                    let covers_dir = db.get_covers_dir();
-                    // Generate a unique filename
-                    let filename = format!("{}.jpg", uuid::Uuid::new_v4());
-                    let save_path = covers_dir.join(&filename);
-                    
-                    // Save to disk
-                    if let Ok(mut file) = std::fs::File::create(&save_path) {
-                        if std::io::Write::write_all(&mut file, &data).is_ok() {
-                            log::info!("💾 Cached cover for: {}", track_path);
-                            // Update DB
-                            // We need to know album and artist to update. 
-                            // extract_cover_from_file doesn't return metadata.
-                            // However, we can look up the track in the DB to get album/artist.
-                            if let Ok(Some(track)) = db.get_track(&track_path) {
-                                let _ = db.update_album_cover(&track.album, &track.artist, &filename);
-                            }
+                    // Save to disk under an extension matching the image data
+                    if let Some(filename) = db.save_cover_data(&data) {
+                        log::info!("💾 Cached cover for: {}", track_path);
+                        // Update DB
+                        // We need to know album and artist to update. 
+                        // extract_cover_from_file doesn't return metadata.
+                        // However, we can look up the track in the DB to get album/artist.
+                        if let Ok(Some(track)) = db.get_track(&track_path) {
+                            let _ = db.update_album_cover(&track.album, &track.artist, &filename);
                         }
+                        let covers_dir = db.get_covers_dir();
+                        let save_path = covers_dir.join(&filename);
+                        saved_cover = Some((covers_dir, save_path));
                     }
                 }
             }
 
+            if let (Some(size), Some((dir, cover_path))) = (params.size, saved_cover) {
+                if let Some(response) = thumbnail_response(dir, cover_path, size).await {
+                    return Ok(response);
+                }
+            }
+
             Ok(Response::builder()
                 .status(StatusCode::OK)
                 .header(header::CONTENT_TYPE, mime)
@@ -940,7 +1320,86 @@ pub async fn get_cover(
     }
 }
 
-/// Extract cover art from an audio file
+/// Serve a file from the covers dir, resized when `size` is given
+async fn cached_cover_response(
+    covers_dir: std::path::PathBuf,
+    cover_path: std::path::PathBuf,
+    size: Option<u32>,
+) -> Option<Response<Body>> {
+    if let Some(size) = size {
+        if let Some(response) = thumbnail_response(covers_dir, cover_path.clone(), size).await {
+            return Some(response);
+        }
+    }
+
+    let Ok(data) = tokio::fs::read(&cover_path).await else {
+        println!("[Server] Failed to read cover file at {:?}", cover_path);
+        return None;
+    };
+    // Older caches named every cover .jpg, so go by the bytes
+    let (content_type, _) = image_type(&data);
+    Some(
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::CACHE_CONTROL, "public, max-age=86400")
+            .body(Body::from(data))
+            .unwrap(),
+    )
+}
+
+/// Get an album's cover by album name and album artist. Serves the cached
+/// cover; an album without one gets its first track's embedded art, which is
+/// cached as the album cover for next time. `?size=N` as for `/cover`.
+pub async fn get_album_cover(
+    State(state): State<Arc<ServerState>>,
+    Path((name, artist)): Path<(String, String)>,
+    Query(params): Query<CoverParams>,
+) -> Result<Response<Body>, StatusCode> {
+    let name = urlencoding::decode(&name).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
+    let artist = urlencoding::decode(&artist).map_err(|_| StatusCode::BAD_REQUEST)?.to_string();
+
+    let app_state = state.app_state();
+    let (cover, first_track, covers_dir) = {
+        let db_guard = app_state.db.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let db = db_guard.as_ref().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+        let (cover, first_track) = db
+            .get_album_cover(&name, &artist)
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        (cover, first_track, db.get_covers_dir())
+    };
+
+    if let Some(cover) = cover {
+        let cover_path = covers_dir.join(&cover);
+        if let Some(response) = cached_cover_response(covers_dir.clone(), cover_path, params.size).await {
+            return Ok(response);
+        }
+    }
+
+    let (data, mime) = extract_cover_from_file(&first_track).ok_or(StatusCode::NOT_FOUND)?;
+    let saved = app_state.db.lock().ok().and_then(|guard| {
+        let db = guard.as_ref()?;
+        let filename = db.save_cover_data(&data)?;
+        db.update_album_cover(&name, &artist, &filename).ok()?;
+        Some(covers_dir.join(filename))
+    });
+
+    if let (Some(size), Some(cover_path)) = (params.size, saved) {
+        if let Some(response) = thumbnail_response(covers_dir, cover_path, size).await {
+            return Ok(response);
+        }
+    }
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::CACHE_CONTROL, "public, max-age=86400")
+        .body(Body::from(data))
+        .unwrap())
+}
+
+/// Extract cover art from an audio file, with its MIME type sniffed from the
+/// image bytes rather than trusted from the tag
 fn extract_cover_from_file(path: &str) -> Option<(Vec<u8>, &'static str)> {
     use lofty::prelude::*;
     use lofty::probe::Probe;
@@ -949,23 +1408,29 @@ fn extract_cover_from_file(path: &str) -> Option<(Vec<u8>, &'static str)> {
     let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag())?;
     
     if let Some(picture) = tag.pictures().iter().next() {
-        let mime = match picture.mime_type() {
-            Some(lofty::picture::MimeType::Png) => "image/png",
-            Some(lofty::picture::MimeType::Jpeg) => "image/jpeg",
-            Some(lofty::picture::MimeType::Gif) => "image/gif",
-            Some(lofty::picture::MimeType::Bmp) => "image/bmp",
-            _ => "image/jpeg",
-        };
+        let (mime, _) = image_type(picture.data());
         return Some((picture.data().to_vec(), mime));
     }
     
     None
 }
 
+/// Optional transcoding parameters for `/stream/*path`
+#[derive(Deserialize)]
+pub struct TranscodeParams {
+    /// Target codec (`mp3`, `opus`, `aac`); omitted means raw passthrough
+    pub format: Option<String>,
+    /// Target bitrate in kbps
+    pub bitrate: Option<u32>,
+}
+
 /// Stream audio to mobile client from a specific file path.
 /// Supports HTTP Range requests (RFC 7233) — required for ExoPlayer seeking on Android.
+/// With `?format=mp3&bitrate=128` the file is transcoded (and cached) first.
 pub async fn stream_audio_file(
+    State(state): State<Arc<ServerState>>,
     Path(encoded_path): Path<String>,
+    Query(params): Query<TranscodeParams>,
     headers: HeaderMap,
 ) -> Result<Response<Body>, StatusCode> {
     // Decode and normalise the path
@@ -993,6 +1458,29 @@ pub async fn stream_audio_file(
         return Err(StatusCode::NOT_FOUND);
     }
 
+    let mut transcoded_type = None;
+    let track_path = match params.format.as_deref() {
+        None => track_path,
+        Some(format) => {
+            let format = transcode::TranscodeFormat::parse(format).ok_or(StatusCode::BAD_REQUEST)?;
+            let bitrate = transcode::normalize_bitrate(params.bitrate);
+            let cache_dir = state.app_handle.path().app_cache_dir()
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            let output = transcode::transcode_cached(&cache_dir, std::path::Path::new(&track_path), format, bitrate)
+                .await
+                .map_err(|e| {
+                    log::error!("❌ Transcode to {:?}@{}k failed for {}: {}", format, bitrate, track_path, e);
+                    match e {
+                        transcode::TranscodeError::EncoderUnavailable => StatusCode::NOT_IMPLEMENTED,
+                        transcode::TranscodeError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                    }
+                })?;
+            transcoded_type = Some(format.content_type());
+            output.to_string_lossy().to_string()
+        }
+    };
+
     let file_metadata = tokio::fs::metadata(&track_path).await
         .map_err(|e| {
             log::error!("❌ Metadata error for {}: {}", track_path, e);
@@ -1000,7 +1488,9 @@ pub async fn stream_audio_file(
         })?;
     let file_size = file_metadata.len();
 
-    let content_type = if track_path.ends_with(".flac") {
+    let content_type = if let Some(content_type) = transcoded_type {
+        content_type
+    } else if track_path.ends_with(".flac") {
         "audio/flac"
     } else if track_path.ends_with(".mp3") {
         "audio/mpeg"
@@ -1014,37 +1504,45 @@ pub async fn stream_audio_file(
         "application/octet-stream"
     };
 
-    // Parse an optional Range header.
-    // FLAC on some Android devices fails to decode reliably from arbitrary byte ranges,
-    // so serve full-file responses for FLAC to keep mobile playback stable.
-    let has_range_header = headers.get(header::RANGE).is_some();
-    let range = if content_type == "audio/flac" {
-        if has_range_header {
-            log::warn!("⚠️ Ignoring Range request for FLAC stream: {}", track_path);
+    // Parse an optional Range header. Malformed headers are ignored (full 200 response),
+    // ranges starting past EOF are rejected with 416 per RFC 7233.
+    let range = match headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| parse_range(s, file_size))
+    {
+        Some(ByteRange::Unsatisfiable) => {
+            log::warn!("⚠️ Unsatisfiable range for {} ({} bytes)", track_path, file_size);
+            return Ok(Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", file_size))
+                .header(header::ACCEPT_RANGES, "bytes")
+                .body(Body::empty())
+                .unwrap());
         }
-        None
-    } else {
-        headers
-            .get(header::RANGE)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| parse_range(s, file_size))
+        Some(ByteRange::Satisfiable(start, end)) => Some((start, end)),
+        None => None,
     };
 
+    let mut file = tokio::fs::File::open(&track_path).await
+        .map_err(|e| {
+            log::error!("❌ Failed to open {}: {}", track_path, e);
+            StatusCode::NOT_FOUND
+        })?;
+
     match range {
         Some((start, end)) => {
             // --- Partial Content (206) ---
             let length = end - start + 1;
-            let mut buf = vec![0u8; length as usize];
 
-            let mut file = tokio::fs::File::open(&track_path).await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
             file.seek(std::io::SeekFrom::Start(start)).await
                 .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            file.read_exact(&mut buf).await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
             log::info!("✅ 206 range {}-{}/{} ({})", start, end, file_size, track_path);
 
+            // Stream only the requested slice instead of buffering it
+            let body = Body::from_stream(ReaderStream::new(file.take(length)));
+
             Ok(Response::builder()
                 .status(StatusCode::PARTIAL_CONTENT)
                 .header(header::CONTENT_TYPE, content_type)
@@ -1052,17 +1550,11 @@ pub async fn stream_audio_file(
                 .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, file_size))
                 .header(header::ACCEPT_RANGES, "bytes")
                 .header(header::CACHE_CONTROL, "no-cache")
-                .body(Body::from(buf))
+                .body(body)
                 .unwrap())
         }
         None => {
             // --- Full file (200) ---
-            let data = tokio::fs::read(&track_path).await
-                .map_err(|e| {
-                    log::error!("❌ Failed to read {}: {}", track_path, e);
-                    StatusCode::NOT_FOUND
-                })?;
-
             log::info!("✅ 200 full {} bytes for {}", file_size, track_path);
 
             Ok(Response::builder()
@@ -1071,37 +1563,58 @@ pub async fn stream_audio_file(
                 .header(header::CONTENT_LENGTH, file_size.to_string())
                 .header(header::ACCEPT_RANGES, "bytes")
                 .header(header::CACHE_CONTROL, "no-cache")
-                .body(Body::from(data))
+                .body(Body::from_stream(ReaderStream::new(file)))
                 .unwrap())
         }
     }
 }
 
-/// Parse `Range: bytes=start-end` header value → (start, inclusive_end).
-fn parse_range(range: &str, file_size: u64) -> Option<(u64, u64)> {
-    let s = range.strip_prefix("bytes=")?;
+/// Outcome of parsing a `Range` header against a known file size.
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// Servable range → (start, inclusive_end)
+    Satisfiable(u64, u64),
+    /// Well-formed, but starts at or beyond the end of the file
+    Unsatisfiable,
+}
+
+/// Parse `Range: bytes=start-end` header value.
+/// Returns `None` for malformed or multi-range headers so the caller can fall back to 200.
+fn parse_range(range: &str, file_size: u64) -> Option<ByteRange> {
+    let s = range.trim().strip_prefix("bytes=")?;
+    if s.contains(',') {
+        // Multipart ranges aren't supported; serve the full file instead
+        return None;
+    }
     let mut parts = s.splitn(2, '-');
     let start_str = parts.next()?.trim();
     let end_str   = parts.next()?.trim();
 
-    let start: u64 = if start_str.is_empty() {
+    if start_str.is_empty() {
         // Suffix range: "-N" → last N bytes
         let n: u64 = end_str.parse().ok()?;
-        file_size.saturating_sub(n)
-    } else {
-        start_str.parse().ok()?
-    };
+        if n == 0 || file_size == 0 {
+            return Some(ByteRange::Unsatisfiable);
+        }
+        return Some(ByteRange::Satisfiable(file_size.saturating_sub(n), file_size - 1));
+    }
+
+    let start: u64 = start_str.parse().ok()?;
+    if start >= file_size {
+        return Some(ByteRange::Unsatisfiable);
+    }
 
     let end: u64 = if end_str.is_empty() {
-        file_size.saturating_sub(1)
+        file_size - 1
     } else {
-        end_str.parse::<u64>().ok()?.min(file_size.saturating_sub(1))
+        let end: u64 = end_str.parse().ok()?;
+        if end < start {
+            return None;
+        }
+        end.min(file_size - 1)
     };
 
-    if start > end || start >= file_size {
-        return None;
-    }
-    Some((start, end))
+    Some(ByteRange::Satisfiable(start, end))
 }
 
 /// Stream audio to mobile client (legacy endpoint for current track)
@@ -1157,3 +1670,151 @@ pub async fn stream_audio(
         .body(Body::from(data))
         .unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{album_detail, cover_url_for, extract_cover_from_file, parse_range, ByteRange};
+
+    #[test]
+    fn parse_range_handles_closed_and_open_ended_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some(ByteRange::Satisfiable(0, 99)));
+        assert_eq!(parse_range("bytes=500-", 1000), Some(ByteRange::Satisfiable(500, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some(ByteRange::Satisfiable(900, 999)));
+        assert_eq!(parse_range("bytes=900-5000", 1000), Some(ByteRange::Satisfiable(900, 999)));
+    }
+
+    #[test]
+    fn parse_range_rejects_start_past_eof() {
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(ByteRange::Unsatisfiable));
+        assert_eq!(parse_range("bytes=2000-3000", 1000), Some(ByteRange::Unsatisfiable));
+    }
+
+    #[test]
+    fn parse_range_ignores_malformed_headers() {
+        assert_eq!(parse_range("items=0-10", 1000), None);
+        assert_eq!(parse_range("bytes=abc-", 1000), None);
+        assert_eq!(parse_range("bytes=0-10,20-30", 1000), None);
+        assert_eq!(parse_range("bytes=50-10", 1000), None);
+    }
+
+    #[tokio::test]
+    async fn album_detail_route_returns_cover_urls() {
+        use crate::audio::TrackInfo;
+        use crate::database::DatabaseManager;
+        use axum::body::{to_bytes, Body};
+        use axum::extract::Path;
+        use axum::http::{Request, StatusCode};
+        use axum::routing::get;
+        use axum::{Json, Router};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let db = Arc::new(DatabaseManager::open_in_memory().unwrap());
+        let track = |path: &str, album: &str| TrackInfo {
+            path: path.to_string(),
+            title: "Song".to_string(),
+            artist: "Artist".to_string(),
+            album: album.to_string(),
+            duration_secs: 180.0,
+            ..Default::default()
+        };
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(2, 2, image::Rgb([200, 40, 40]))
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        db.insert_track(&track("/music/covered.flac", "With Cover"), Some(png.get_ref()))
+            .unwrap();
+        db.insert_track(&track("/music/a b.flac", "No Cover"), None).unwrap();
+
+        let app = Router::new().route(
+            "/api/albums/:name/:artist",
+            get(move |Path((name, artist)): Path<(String, String)>| async move {
+                album_detail(&db, &name, &artist).map(Json)
+            }),
+        );
+        let fetch = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).ok())
+            }
+        };
+
+        let (status, body) = fetch("/api/albums/With%20Cover/Artist").await;
+        assert_eq!(status, StatusCode::OK);
+        let body = body.unwrap();
+        let cover = body["album"]["coverUrl"].as_str().unwrap();
+        assert!(cover.starts_with("/cover/") && cover.ends_with(".png"), "{}", cover);
+        assert!(!cover.contains("covered.flac"));
+
+        let (_, body) = fetch("/api/albums/No%20Cover/Artist").await;
+        let body = body.unwrap();
+        assert_eq!(body["album"]["coverUrl"], "/cover/%2Fmusic%2Fa%20b.flac");
+
+        let (status, _) = fetch("/api/albums/Missing/Artist").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn cover_url_prefers_cached_cover() {
+        assert_eq!(
+            cover_url_for(Some("3f2a.jpg"), "/music/a b.flac"),
+            "/cover/3f2a.jpg"
+        );
+        assert_eq!(
+            cover_url_for(None, "/music/a b.flac"),
+            "/cover/%2Fmusic%2Fa%20b.flac"
+        );
+    }
+
+    /// A short silent mono WAV for tagging in tests
+    fn silent_wav() -> Vec<u8> {
+        let samples = [0u8; 400];
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&samples);
+        wav
+    }
+
+    #[test]
+    fn embedded_png_cover_is_served_as_png() {
+        use lofty::config::WriteOptions;
+        use lofty::picture::{MimeType, Picture, PictureType};
+        use lofty::prelude::*;
+        use lofty::tag::{Tag, TagType};
+
+        let path = std::env::temp_dir().join(format!("vibe-on-png-cover-{}.wav", std::process::id()));
+        std::fs::write(&path, silent_wav()).unwrap();
+
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let mut tag = Tag::new(TagType::Id3v2);
+        // Mislabelled on purpose: the bytes decide the type
+        tag.push_picture(Picture::new_unchecked(
+            PictureType::CoverFront,
+            Some(MimeType::Jpeg),
+            None,
+            png.clone(),
+        ));
+        tag.save_to_path(&path, WriteOptions::default()).unwrap();
+
+        let extracted = extract_cover_from_file(&path.to_string_lossy());
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(extracted, Some((png, "image/png")));
+    }
+}
@@ -0,0 +1,104 @@
+//! Resized cover art for list views
+//!
+//! Thumbnails are JPEGs written to `<covers dir>/thumbs/` named after the
+//! source cover file and the target size, so each one is encoded only once.
+
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use image::codecs::jpeg::JpegEncoder;
+
+/// Sizes (longest edge, px) we generate; requests are rounded to one of these
+pub const THUMBNAIL_SIZES: [u32; 3] = [128, 256, 512];
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Smallest fixed size that covers the request, or the largest one
+pub fn nearest_size(requested: u32) -> u32 {
+    THUMBNAIL_SIZES
+        .iter()
+        .copied()
+        .find(|&size| size >= requested)
+        .unwrap_or(THUMBNAIL_SIZES[THUMBNAIL_SIZES.len() - 1])
+}
+
+/// Where the thumbnail of `cover` at `size` lives
+pub fn thumbnail_path(covers_dir: &Path, cover: &Path, size: u32) -> PathBuf {
+    let stem = cover
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    covers_dir.join("thumbs").join(format!("{}_{}.jpg", stem, size))
+}
+
+/// Path to the `size` thumbnail of `cover`, generating it on first use
+pub fn cached_thumbnail(covers_dir: &Path, cover: &Path, size: u32) -> Result<PathBuf, String> {
+    let target = thumbnail_path(covers_dir, cover, size);
+    if target.is_file() {
+        return Ok(target);
+    }
+
+    let image = image::open(cover).map_err(|e| format!("Failed to decode cover: {}", e))?;
+    let resized = if image.width() > size || image.height() > size {
+        image.thumbnail(size, size)
+    } else {
+        image
+    };
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    // Encode to a temp file of our own first, so a concurrent request for the
+    // same thumbnail never reads a partial JPEG or has its file renamed away
+    let partial = target.with_extension(format!("{}.part", uuid::Uuid::new_v4().simple()));
+    let written = std::fs::File::create(&partial)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            let encoder = JpegEncoder::new_with_quality(BufWriter::new(file), JPEG_QUALITY);
+            resized
+                .to_rgb8()
+                .write_with_encoder(encoder)
+                .map_err(|e| format!("Failed to encode thumbnail: {}", e))
+        })
+        .and_then(|_| std::fs::rename(&partial, &target).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_round_up_to_fixed_steps() {
+        assert_eq!(nearest_size(64), 128);
+        assert_eq!(nearest_size(128), 128);
+        assert_eq!(nearest_size(200), 256);
+        assert_eq!(nearest_size(2000), 512);
+    }
+
+    #[test]
+    fn thumbnail_is_generated_once_and_reused() {
+        let dir = std::env::temp_dir().join(format!("vibe-on-thumbs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cover = dir.join("cover.png");
+        image::RgbImage::from_pixel(600, 300, image::Rgb([10, 20, 30]))
+            .save(&cover)
+            .unwrap();
+
+        let thumb = cached_thumbnail(&dir, &cover, 256).unwrap();
+        assert_eq!(thumb, dir.join("thumbs").join("cover_256.jpg"));
+        assert_eq!(image::image_dimensions(&thumb).unwrap(), (256, 128));
+
+        let modified = std::fs::metadata(&thumb).unwrap().modified().unwrap();
+        assert_eq!(cached_thumbnail(&dir, &cover, 256).unwrap(), thumb);
+        assert_eq!(std::fs::metadata(&thumb).unwrap().modified().unwrap(), modified);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,39 @@
+//! Self-signed certificate management for the HTTPS server
+
+use std::path::{Path, PathBuf};
+
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+/// Return the cert/key pair under `dir`, generating a self-signed one on first run
+pub fn load_or_create_cert(
+    dir: &Path,
+    server_name: &str,
+) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error + Send + Sync>> {
+    let cert_path = dir.join(CERT_FILE);
+    let key_path = dir.join(KEY_FILE);
+
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    std::fs::create_dir_all(dir)?;
+
+    let mut subject_alt_names = vec!["localhost".to_string(), format!("{}.local", server_name)];
+    subject_alt_names.extend(
+        if_addrs::get_if_addrs()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|iface| iface.addr.ip())
+            .filter(|addr| addr.is_ipv4())
+            .map(|addr| addr.to_string()),
+    );
+
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(subject_alt_names)?;
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+
+    println!("[Server] Generated self-signed certificate at {:?}", cert_path);
+    Ok((cert_path, key_path))
+}
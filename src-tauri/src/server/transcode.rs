@@ -0,0 +1,182 @@
+//! On-the-fly transcoding for low-bandwidth streaming
+//!
+//! Transcoded files are written once to `<app cache>/transcode/` keyed by the
+//! source path, its modification time, the target format and bitrate, so that
+//! range requests (seeking) are served from disk instead of re-encoding.
+
+use std::path::{Path, PathBuf};
+
+const DEFAULT_BITRATE_KBPS: u32 = 128;
+const MIN_BITRATE_KBPS: u32 = 32;
+const MAX_BITRATE_KBPS: u32 = 320;
+
+/// Target codec for a transcoded stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscodeFormat {
+    Mp3,
+    Opus,
+    Aac,
+}
+
+impl TranscodeFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "mp3" => Some(Self::Mp3),
+            "opus" | "ogg" => Some(Self::Opus),
+            "aac" | "m4a" => Some(Self::Aac),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Opus => "ogg",
+            Self::Aac => "m4a",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::Opus => "audio/ogg",
+            // Encoded into an .m4a container, not raw ADTS
+            Self::Aac => "audio/mp4",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            Self::Mp3 => "libmp3lame",
+            Self::Opus => "libopus",
+            Self::Aac => "aac",
+        }
+    }
+}
+
+/// Clamp a requested bitrate (kbps) to a sane range
+pub fn normalize_bitrate(bitrate: Option<u32>) -> u32 {
+    bitrate
+        .unwrap_or(DEFAULT_BITRATE_KBPS)
+        .clamp(MIN_BITRATE_KBPS, MAX_BITRATE_KBPS)
+}
+
+/// Why a transcode could not be produced
+#[derive(Debug)]
+pub enum TranscodeError {
+    /// No `ffmpeg` binary could be launched
+    EncoderUnavailable,
+    Failed(String),
+}
+
+impl std::fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranscodeError::EncoderUnavailable => f.write_str("ffmpeg is not available"),
+            TranscodeError::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Cache file name for a source/format/bitrate combination
+fn cache_file_name(source: &Path, modified_secs: u64, format: TranscodeFormat, bitrate: u32) -> String {
+    // MD5 stays the same across Rust releases, unlike `DefaultHasher`
+    let key = format!("{}\n{}", source.to_string_lossy(), modified_secs);
+    format!("{:x}-{}k.{}", md5::compute(key), bitrate, format.extension())
+}
+
+fn ffmpeg_binary() -> String {
+    std::env::var("VIBE_ON_FFMPEG").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+/// Return the cached transcode of `source`, encoding it first if needed
+pub async fn transcode_cached(
+    cache_dir: &Path,
+    source: &Path,
+    format: TranscodeFormat,
+    bitrate: u32,
+) -> Result<PathBuf, TranscodeError> {
+    let modified_secs = tokio::fs::metadata(source)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let dir = cache_dir.join("transcode");
+    let output = dir.join(cache_file_name(source, modified_secs, format, bitrate));
+    if output.exists() {
+        return Ok(output);
+    }
+
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| TranscodeError::Failed(e.to_string()))?;
+
+    // Encode to a unique temp file so concurrent requests never serve a partial file
+    let temp = dir.join(format!("{}.part.{}", uuid::Uuid::new_v4().simple(), format.extension()));
+
+    let mut command = tokio::process::Command::new(ffmpeg_binary());
+    command
+        .arg("-v").arg("error")
+        .arg("-y")
+        .arg("-i").arg(source)
+        .arg("-vn")
+        .arg("-c:a").arg(format.codec())
+        .arg("-b:a").arg(format!("{}k", bitrate))
+        .arg(&temp)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped());
+    #[cfg(windows)]
+    command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+
+    let result = match command.output().await {
+        Ok(result) => result,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(TranscodeError::EncoderUnavailable)
+        }
+        Err(e) => return Err(TranscodeError::Failed(e.to_string())),
+    };
+
+    if !result.status.success() {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(TranscodeError::Failed(
+            String::from_utf8_lossy(&result.stderr).trim().to_string(),
+        ));
+    }
+
+    tokio::fs::rename(&temp, &output)
+        .await
+        .map_err(|e| TranscodeError::Failed(e.to_string()))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_formats_and_clamps_bitrate() {
+        assert_eq!(TranscodeFormat::parse("MP3"), Some(TranscodeFormat::Mp3));
+        assert_eq!(TranscodeFormat::parse("opus"), Some(TranscodeFormat::Opus));
+        assert_eq!(TranscodeFormat::parse("wma"), None);
+        assert_eq!(TranscodeFormat::Aac.content_type(), "audio/mp4");
+
+        assert_eq!(normalize_bitrate(None), 128);
+        assert_eq!(normalize_bitrate(Some(8)), 32);
+        assert_eq!(normalize_bitrate(Some(1000)), 320);
+    }
+
+    #[test]
+    fn cache_key_varies_with_format_bitrate_and_mtime() {
+        let path = Path::new("/music/a.flac");
+        let base = cache_file_name(path, 1, TranscodeFormat::Mp3, 128);
+        assert_eq!(base, cache_file_name(path, 1, TranscodeFormat::Mp3, 128));
+        assert!(base.ends_with("-128k.mp3"));
+        assert_ne!(base, cache_file_name(path, 2, TranscodeFormat::Mp3, 128));
+        assert_ne!(base, cache_file_name(path, 1, TranscodeFormat::Mp3, 192));
+        assert_ne!(base, cache_file_name(path, 1, TranscodeFormat::Opus, 128));
+    }
+}
@@ -24,14 +24,14 @@ use axum::{
         ws::{Message, WebSocket},
         State, WebSocketUpgrade,
     },
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager};
 
-use super::{ws_server_capabilities, ConnectedClient, ServerEvent, ServerState, WS_PROTOCOL_VERSION};
+use super::{auth, ws_server_capabilities, ConnectedClient, ServerEvent, ServerState, WS_PROTOCOL_VERSION};
 
 fn normalize_capabilities(capabilities: Vec<String>) -> Vec<String> {
     let mut normalized: Vec<String> = capabilities
@@ -67,6 +67,9 @@ pub enum ClientMessage {
         protocol_version: Option<String>,
         #[serde(default)]
         capabilities: Vec<String>,
+        /// Server token, for clients that could not pass it on the upgrade request
+        #[serde(default)]
+        token: Option<String>,
     },
     GetStatus,
 
@@ -90,6 +93,8 @@ pub enum ClientMessage {
     PlayAlbum { album: String, artist: String },
     PlayArtist { artist: String },
     AddToQueue { path: String },
+    /// Insert right after the current track instead of appending
+    PlayNext { path: String },
     SetQueue { paths: Vec<String> },
     ToggleFavorite { path: String },
 
@@ -232,6 +237,10 @@ pub enum ServerMessage {
         synced_lyrics_romaji: Option<String>,
         plain_lyrics: Option<String>,
         instrumental: bool,
+        /// Per-word timing when the source has enhanced LRC tags
+        word_timed_lyrics: Option<Vec<crate::lyrics_fetcher::WordTimedLine>>,
+        /// "ja", "ko" or "zh" when the lyrics have a romanization
+        lyrics_language: Option<String>,
     },
 
     /// Tells mobile to start streaming from the given URL at the given sample offset.
@@ -269,6 +278,10 @@ pub enum ServerMessage {
     #[serde(rename_all = "camelCase")]
     StatsUpdated { timestamp: i64 },
 
+    /// A track's favorite flag changed.
+    #[serde(rename_all = "camelCase")]
+    FavoriteChanged { path: String, is_favorite: bool },
+
     /// Acknowledge a successful action.
     Ack { action: String },
 
@@ -331,14 +344,14 @@ impl From<ServerEvent> for ServerMessage {
             ServerEvent::QueueUpdate { tracks, current_index } => {
                 ServerMessage::QueueUpdate { queue: tracks, current_index }
             }
-            ServerEvent::Lyrics { track_path, has_synced, synced_lyrics, synced_lyrics_romaji, plain_lyrics, instrumental } => {
-                ServerMessage::Lyrics { track_path, has_synced, synced_lyrics, synced_lyrics_romaji, plain_lyrics, instrumental }
-            }
-            ServerEvent::HandoffPrepare { sample, url } => {
-                ServerMessage::HandoffPrepare { sample, url }
+            ServerEvent::Lyrics { track_path, has_synced, synced_lyrics, synced_lyrics_romaji, plain_lyrics, instrumental, word_timed_lyrics, lyrics_language } => {
+                ServerMessage::Lyrics { track_path, has_synced, synced_lyrics, synced_lyrics_romaji, plain_lyrics, instrumental, word_timed_lyrics, lyrics_language }
             }
             ServerEvent::StreamStopped => ServerMessage::StreamStopped,
             ServerEvent::StatsUpdated { timestamp } => ServerMessage::StatsUpdated { timestamp },
+            ServerEvent::FavoriteChanged { path, is_favorite } => {
+                ServerMessage::FavoriteChanged { path, is_favorite }
+            }
             ServerEvent::Error { message } => ServerMessage::Error {
                 message,
                 code: None,
@@ -350,32 +363,73 @@ impl From<ServerEvent> for ServerMessage {
 
 // ─── WebSocket upgrade & connection lifecycle ────────────────────────────────
 
+/// How long a connection without a token on the upgrade has to send its `hello`
+const HELLO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+fn hello_is_authorized(state: &ServerState, msg: &ClientMessage) -> bool {
+    match msg {
+        ClientMessage::Hello { token, .. } => auth::is_authorized(state, token.as_deref()),
+        _ => false,
+    }
+}
+
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
     State(state): State<Arc<ServerState>>,
 ) -> Response {
-    if let Some(expected_token) = state.config.control_token.as_ref() {
-        let provided_token = params.get("token").map(String::as_str).unwrap_or_default();
-        if provided_token != expected_token {
-            log::warn!("[WS] Rejected unauthenticated websocket control request");
-            return (StatusCode::UNAUTHORIZED, "Missing or invalid control token").into_response();
-        }
+    // A token on the upgrade request must be valid; without one the client
+    // has to present it in `hello` before any other message is accepted.
+    let provided_token = auth::provided_token(&params, &headers);
+    let authenticated = auth::is_authorized(&state, provided_token);
+    if provided_token.is_some() && !authenticated {
+        log::warn!("[WS] Rejected unauthenticated websocket control request");
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid control token").into_response();
     }
 
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, authenticated))
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<ServerState>) {
+async fn handle_socket(socket: WebSocket, state: Arc<ServerState>, authenticated: bool) {
     let (mut sender, mut receiver) = socket.split();
 
-    let mut event_rx = state.event_tx.subscribe();
     let client_id = uuid::Uuid::new_v4().to_string();
     let client_id_for_cleanup = client_id.clone();
     let app_handle = state.app_handle.clone();
 
     log::info!("[WS] New connection, client_id={}", client_id);
 
+    // Without a token on the upgrade, the first message has to be a `hello`
+    // carrying one. Until then the socket gets no broadcasts; anything else
+    // closes it.
+    let mut hello = None;
+    if !authenticated {
+        let first = match tokio::time::timeout(HELLO_TIMEOUT, receiver.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => serde_json::from_str::<ClientMessage>(&text).ok(),
+            _ => None,
+        };
+        match first {
+            Some(msg @ ClientMessage::Hello { .. }) if hello_is_authorized(&state, &msg) => {
+                hello = Some(msg);
+            }
+            _ => {
+                log::warn!("[WS] Closing unauthenticated connection {}", client_id);
+                let rejection = ServerMessage::Error {
+                    message: "Missing or invalid control token".to_string(),
+                    code: Some("ERR_UNAUTHORIZED".to_string()),
+                };
+                if let Ok(json) = serde_json::to_string(&rejection) {
+                    let _ = sender.send(Message::Text(json)).await;
+                }
+                let _ = sender.send(Message::Close(None)).await;
+                return;
+            }
+        }
+    }
+
+    let mut event_rx = state.event_tx.subscribe();
+
     // Direct replies to this specific client
     let (reply_tx, mut reply_rx) = tokio::sync::mpsc::channel::<ServerMessage>(32);
     // Signal the send task to stop
@@ -383,43 +437,66 @@ async fn handle_socket(socket: WebSocket, state: Arc<ServerState>) {
 
     // ── Send task ────────────────────────────────────────────────────────────
     // Forwards broadcast events + direct replies + keepalive pings to the client.
+    let send_state = state.clone();
+    let send_client_id = client_id.clone();
     let send_task = tokio::spawn(async move {
         let mut keepalive = tokio::time::interval(std::time::Duration::from_secs(30));
 
         loop {
+            // Direct replies first, so the ones queued before a disconnect still go out
             tokio::select! {
-                event = event_rx.recv() => {
-                    match event {
-                        Ok(ev) => {
-                            match serde_json::to_string(&ServerMessage::from(ev)) {
+                biased;
+                reply = reply_rx.recv() => {
+                    match reply {
+                        Some(msg) => {
+                            match serde_json::to_string(&msg) {
                                 Ok(json) => {
                                     if sender.send(Message::Text(json)).await.is_err() { break; }
                                 }
                                 Err(e) => {
-                                    log::warn!("[WS] Failed to serialize broadcast event: {}", e);
+                                    log::warn!("[WS] Failed to serialize direct reply: {}", e);
                                 }
                             }
                         }
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                            log::warn!("[WS] Client lagged, skipped {} messages", n);
-                            // Continue — client will catch up via next broadcast
-                        }
-                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        None => break,
                     }
                 }
-                reply = reply_rx.recv() => {
-                    match reply {
-                        Some(msg) => {
-                            match serde_json::to_string(&msg) {
+                event = event_rx.recv() => {
+                    match event {
+                        Ok(ev) => {
+                            match serde_json::to_string(&ServerMessage::from(ev)) {
                                 Ok(json) => {
                                     if sender.send(Message::Text(json)).await.is_err() { break; }
                                 }
                                 Err(e) => {
-                                    log::warn!("[WS] Failed to serialize direct reply: {}", e);
+                                    log::warn!("[WS] Failed to serialize broadcast event: {}", e);
                                 }
                             }
                         }
-                        None => break,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                            // A slow client stays connected; the skipped updates are
+                            // replaced by a fresh snapshot so it doesn't show stale state
+                            log::warn!(
+                                "[WS] Client {} lagged, skipped {} messages; resending current state",
+                                send_client_id, n
+                            );
+                            let mut closed = false;
+                            for msg in state_snapshot(&send_state).await {
+                                match serde_json::to_string(&msg) {
+                                    Ok(json) => {
+                                        if sender.send(Message::Text(json)).await.is_err() {
+                                            closed = true;
+                                            break;
+                                        }
+                                    }
+                                    Err(e) => {
+                                        log::warn!("[WS] Failed to serialize state snapshot: {}", e);
+                                    }
+                                }
+                            }
+                            if closed { break; }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                     }
                 }
                 _ = keepalive.tick() => {
@@ -437,6 +514,10 @@ async fn handle_socket(socket: WebSocket, state: Arc<ServerState>) {
         }
     });
 
+    if let Some(hello) = hello {
+        handle_client_message(&state, &client_id, hello, &reply_tx).await;
+    }
+
     // ── Receive loop ─────────────────────────────────────────────────────────
     while let Some(Ok(msg)) = receiver.next().await {
         match msg {
@@ -462,7 +543,10 @@ async fn handle_socket(socket: WebSocket, state: Arc<ServerState>) {
 
     // ── Cleanup ──────────────────────────────────────────────────────────────
     let _ = stop_tx.send(()).await;
-    send_task.abort();
+    let mut send_task = send_task;
+    if tokio::time::timeout(std::time::Duration::from_secs(1), &mut send_task).await.is_err() {
+        send_task.abort();
+    }
 
     let mut clients = state.clients.write().await;
     let disconnected = clients.iter().find(|c| c.id == client_id_for_cleanup).cloned();
@@ -496,6 +580,7 @@ async fn handle_client_message(
             client_name,
             protocol_version,
             capabilities,
+            ..
         } => {
             let client_protocol_version = protocol_version.unwrap_or_else(|| "1.0".to_string());
             let client_capabilities = normalize_capabilities(capabilities);
@@ -564,7 +649,7 @@ async fn handle_client_message(
 
         // ── Next / Previous ──────────────────────────────────────────────
         ClientMessage::Next => {
-            let next_path = advance_queue(&app_state, 1);
+            let next_path = crate::queue::manual_skip(&app_state, 1);
             if let Some(path) = next_path {
                 play_track_internal(state, &app_state, path, reply_tx).await;
             } else {
@@ -576,7 +661,7 @@ async fn handle_client_message(
         }
 
         ClientMessage::Previous => {
-            let prev_path = advance_queue(&app_state, -1);
+            let prev_path = crate::queue::manual_skip(&app_state, -1);
             if let Some(path) = prev_path {
                 play_track_internal(state, &app_state, path, reply_tx).await;
             }
@@ -601,10 +686,9 @@ async fn handle_client_message(
 
         // ── Shuffle / Repeat ─────────────────────────────────────────────
         ClientMessage::ToggleShuffle => {
-            {
-                let mut s = app_state.shuffle.lock().unwrap();
-                *s = !*s;
-            }
+            let enabled = !*app_state.shuffle.lock().unwrap();
+            crate::queue::set_shuffle(&app_state, enabled);
+            crate::sync_next_track(&app_state);
             broadcast_player_state(state, &app_state).await;
         }
 
@@ -627,12 +711,10 @@ async fn handle_client_message(
 
         // ── Play all tracks of an album ──────────────────────────────────
         ClientMessage::PlayAlbum { album, artist } => {
-            let tracks = get_sorted_tracks(&app_state, |t| {
-                t.album == album && (artist.is_empty() || t.artist == artist)
-            });
+            let tracks = crate::queue::album_tracks(&app_state, &album, &artist);
             if let Some(first) = tracks.first() {
                 let first_path = first.path.clone();
-                set_queue(&app_state, tracks);
+                crate::queue::replace(&app_state, tracks);
                 play_track_internal(state, &app_state, first_path, reply_tx).await;
                 broadcast_queue(state, &app_state).await;
             } else {
@@ -645,10 +727,10 @@ async fn handle_client_message(
 
         // ── Play all tracks of an artist ─────────────────────────────────
         ClientMessage::PlayArtist { artist } => {
-            let tracks = get_sorted_tracks(&app_state, |t| t.artist == artist);
+            let tracks = crate::queue::artist_tracks(&app_state, &artist);
             if let Some(first) = tracks.first() {
                 let first_path = first.path.clone();
-                set_queue(&app_state, tracks);
+                crate::queue::replace(&app_state, tracks);
                 play_track_internal(state, &app_state, first_path, reply_tx).await;
                 broadcast_queue(state, &app_state).await;
             } else {
@@ -669,7 +751,7 @@ async fn handle_client_message(
                     } else { Vec::new() }
                 } else { Vec::new() }
             };
-            set_queue(&app_state, tracks);
+            crate::queue::replace(&app_state, tracks);
             broadcast_queue(state, &app_state).await;
         }
 
@@ -681,17 +763,59 @@ async fn handle_client_message(
                 })
             };
             if let Some(t) = track {
-                app_state.queue.lock().unwrap().push_back(t);
+                crate::queue::append(&app_state, t);
+                crate::sync_next_track(&app_state);
                 broadcast_queue(state, &app_state).await;
             }
         }
 
+        ClientMessage::PlayNext { path } => {
+            let track = {
+                let db_guard = app_state.db.lock().unwrap();
+                db_guard.as_ref().and_then(|db| db.get_track(&path).ok().flatten())
+            };
+            match track {
+                Some(t) => {
+                    crate::queue::insert_next(&app_state, t);
+                    crate::sync_next_track(&app_state);
+                    broadcast_queue(state, &app_state).await;
+                    let _ = state.app_handle.emit("refresh-player-state", ());
+                }
+                None => {
+                    let _ = reply_tx.send(ServerMessage::Error {
+                        message: format!("Track not found: {}", path),
+                        code: Some("ERR_TRACK_NOT_FOUND".to_string()),
+                    }).await;
+                }
+            }
+        }
+
         // ── Favorites ────────────────────────────────────────────────────
         ClientMessage::ToggleFavorite { path } => {
-            log::info!("[WS] ToggleFavorite for {} (not yet implemented in DB)", path);
-            let _ = reply_tx.send(ServerMessage::Ack {
-                action: "toggleFavorite".to_string(),
-            }).await;
+            let result = {
+                let db_guard = app_state.db.lock().unwrap();
+                db_guard
+                    .as_ref()
+                    .ok_or_else(|| "Database not initialized".to_string())
+                    .and_then(|db| db.toggle_favorite(&path).map_err(|e| e.to_string()))
+            };
+
+            match result {
+                Ok(is_favorite) => {
+                    log::info!("[WS] ToggleFavorite for {} -> {}", path, is_favorite);
+                    state.broadcast(ServerEvent::FavoriteChanged { path, is_favorite });
+                    let _ = reply_tx.send(ServerMessage::Ack {
+                        action: "toggleFavorite".to_string(),
+                    }).await;
+                }
+                Err(e) => {
+                    log::warn!("[WS] ToggleFavorite failed for {}: {}", path, e);
+                    let _ = reply_tx.send(ServerMessage::Error {
+                        message: format!("Failed to toggle favorite: {}", e),
+                        code: None,
+                    }).await;
+                }
+            }
         }
 
         // ── Library ──────────────────────────────────────────────────────
@@ -753,39 +877,48 @@ async fn handle_client_message(
                 let title = track.title.clone();
                 let duration = track.duration_secs as u32;
                 let path = track.path.clone();
+                let app_handle = state.app_handle.clone();
+
+                let offset_ms = crate::lyrics_offset_ms(&app_state, &path);
 
                 tokio::task::spawn_blocking(move || {
                     // Try local .lrc first
-                    if let Some(lrc) = crate::lyrics_fetcher::find_local_lrc(&path) {
+                    if let Some(mut lrc) = crate::lyrics_fetcher::find_local_lrc(&path) {
+                        crate::lyrics_fetcher::apply_offset(&mut lrc, offset_ms);
                         let _ = reply.blocking_send(ServerMessage::Lyrics {
                             track_path: path,
                             has_synced: lrc.synced_lyrics.is_some(),
                             synced_lyrics: lrc.synced_lyrics.clone(),
                             synced_lyrics_romaji: lrc.synced_lyrics.as_ref().and_then(|t| {
-                                if crate::lyrics_transliteration::has_japanese(t) {
+                                if crate::lyrics_transliteration::needs_transliteration(t) {
                                     Some(crate::lyrics_transliteration::transliterate_lyrics(t))
                                 } else { None }
                             }),
+                            lyrics_language: crate::lyrics_fetcher::detect_language(&lrc),
                             plain_lyrics: lrc.plain_lyrics,
                             instrumental: lrc.instrumental.unwrap_or(false),
+                            word_timed_lyrics: lrc.word_timed_lyrics,
                         });
                         return;
                     }
 
-                    // Fetch from API
-                    match crate::lyrics_fetcher::fetch_lyrics(&artist, &title, duration, |_| {}) {
-                        Ok(lyrics) => {
+                    // Stored lyrics, else fetch from API
+                    match crate::load_lyrics(&app_handle, &path, &artist, &title, duration, |_| {}) {
+                        Ok(mut lyrics) => {
+                            crate::lyrics_fetcher::apply_offset(&mut lyrics, offset_ms);
                             let _ = reply.blocking_send(ServerMessage::Lyrics {
                                 track_path: path,
                                 has_synced: lyrics.synced_lyrics.is_some(),
                                 synced_lyrics: lyrics.synced_lyrics.clone(),
                                 synced_lyrics_romaji: lyrics.synced_lyrics.as_ref().and_then(|t| {
-                                    if crate::lyrics_transliteration::has_japanese(t) {
-                                        Some(crate::lyrics_transliteration::to_romaji(t))
+                                    if crate::lyrics_transliteration::needs_transliteration(t) {
+                                        Some(crate::lyrics_transliteration::transliterate_lyrics(t))
                                     } else { None }
                                 }),
+                                lyrics_language: crate::lyrics_fetcher::detect_language(&lyrics),
                                 plain_lyrics: lyrics.plain_lyrics,
                                 instrumental: lyrics.instrumental.unwrap_or(false),
+                                word_timed_lyrics: lyrics.word_timed_lyrics,
                             });
                         }
                         Err(e) => {
@@ -812,8 +945,9 @@ async fn handle_client_message(
             // Finalize any active desktop stats session
             finalize_desktop_stats(&app_state, state).await;
 
-            // Switch output to mobile
+            // Switch output to mobile, played by this client
             *state.active_output.write().await = "mobile".to_string();
+            *state.mobile_output_client.lock().unwrap() = Some(reply_tx.clone());
 
             // Pause PC playback
             if let Ok(mut g) = app_state.player.lock() {
@@ -821,10 +955,10 @@ async fn handle_client_message(
             }
 
             // Build & send stream URL
-            if let Some((path, position)) = current_track_info(&app_state) {
+            if let Some((path, position, sample_rate)) = current_track_info(&app_state) {
                 let url = build_stream_url(state, &path);
                 let _ = reply_tx.send(ServerMessage::HandoffPrepare {
-                    sample: (position * 44100.0) as u64,
+                    sample: handoff_sample(position, sample_rate),
                     url,
                 }).await;
 
@@ -844,13 +978,10 @@ async fn handle_client_message(
             log::info!("[WS] StopMobilePlayback from {}", client_id);
 
             // Finalize mobile stats session
-            if let Ok(mut tracker) = app_state.stats_tracker.lock() {
-                let now_ms = crate::stats::current_time_ms();
-                if let Some(event) = tracker.stop_mobile(now_ms) {
-                    let _ = crate::stats::record_stats_event(&app_state, event);
-                    state.broadcast(ServerEvent::StatsUpdated { timestamp: now_ms });
-                    let _ = state.app_handle.emit("stats-updated", ());
-                }
+            let now_ms = crate::stats::current_time_ms();
+            let event = app_state.stats_tracker.lock().ok().and_then(|mut t| t.stop_mobile(now_ms));
+            if let Some(event) = event {
+                record_stats(state, &app_state, event, now_ms);
             }
 
             // Switch back to desktop
@@ -873,17 +1004,18 @@ async fn handle_client_message(
 
         // ── Mobile position updates ──────────────────────────────────────
         ClientMessage::MobilePositionUpdate { position_secs } => {
-            // Update stats tracker
-            if let Ok(mut tracker) = app_state.stats_tracker.lock() {
-                let now_ms = crate::stats::current_time_ms();
-                let song_id = app_state.player.lock().ok().and_then(|g| {
-                    g.as_ref().and_then(|p| p.get_status().track.map(|t| t.path))
-                });
-                if let Some(event) = tracker.update_mobile_position(song_id, position_secs, now_ms) {
-                    let _ = crate::stats::record_stats_event(&app_state, event);
-                    state.broadcast(ServerEvent::StatsUpdated { timestamp: now_ms });
-                    let _ = state.app_handle.emit("stats-updated", ());
-                }
+            // The phone plays whatever track the desktop player has loaded
+            let song_id = app_state.player.lock().ok().and_then(|g| {
+                g.as_ref().and_then(|p| p.get_status().track.map(|t| t.path))
+            });
+            let now_ms = crate::stats::current_time_ms();
+            let event = app_state
+                .stats_tracker
+                .lock()
+                .ok()
+                .and_then(|mut t| t.update_mobile_position(song_id, position_secs, now_ms));
+            if let Some(event) = event {
+                record_stats(state, &app_state, event, now_ms);
             }
             // Sync with React frontend
             let _ = state.app_handle.emit("mobile-position-update", serde_json::json!({
@@ -1034,12 +1166,13 @@ async fn send_full_state(
     // If the active output is mobile, send a fresh HandoffPrepare so the reconnecting
     // phone can start playing immediately instead of waiting for a song change.
     if state.active_output.read().await.as_str() == "mobile" {
-        if let Some((path, position)) = current_track_info(app_state) {
+        *state.mobile_output_client.lock().unwrap() = Some(reply_tx.clone());
+        if let Some((path, position, sample_rate)) = current_track_info(app_state) {
             let url = build_stream_url(state, &path);
             // StreamStopped first to tear down any stale session on the phone side.
             let _ = reply_tx.send(ServerMessage::StreamStopped).await;
             let _ = reply_tx.send(ServerMessage::HandoffPrepare {
-                sample: (position * 44100.0) as u64,
+                sample: handoff_sample(position, sample_rate),
                 url,
             }).await;
             log::info!("[WS] Sent reconnect HandoffPrepare for mobile output @ {:.1}s", position);
@@ -1050,6 +1183,13 @@ async fn send_full_state(
     let _ = state.app_handle.emit("refresh-player-state", ());
 }
 
+/// Current media session, status and queue, for a client that missed broadcasts
+async fn state_snapshot(state: &Arc<ServerState>) -> Vec<ServerMessage> {
+    let app_state: tauri::State<'_, crate::AppState> = state.app_handle.state();
+    let (media, status) = build_state_events(state, &app_state).await;
+    vec![media.into(), status.into(), build_queue_message(&app_state)]
+}
+
 /// Broadcast player state to **all** connected clients.
 async fn broadcast_player_state(
     state: &Arc<ServerState>,
@@ -1078,51 +1218,26 @@ pub async fn send_current_status_with_handle(state: &Arc<ServerState>, app_handl
     state.broadcast(status);
 }
 
-/// Broadcast a fresh mobile stream handoff for the provided track path.
-/// Used by server-side autoplay paths that do not go through per-client reply channels.
-pub fn broadcast_mobile_handoff_for_path(
+/// Send a fresh mobile stream handoff for the provided track path to the
+/// client playing mobile output. Used by server-side autoplay paths that do not
+/// go through per-client reply channels. Stream URLs carry the control token,
+/// so they never go out as a broadcast.
+pub fn send_mobile_handoff_for_path(
     state: &Arc<ServerState>,
     path: &str,
     sample: u64,
 ) {
+    let Some(target) = state.mobile_output_client.lock().unwrap().clone() else {
+        log::warn!("[WS] No mobile output client to hand {} to", path);
+        return;
+    };
     let url = build_stream_url(state, path);
-    state.broadcast(ServerEvent::StreamStopped);
-    state.broadcast(ServerEvent::HandoffPrepare { sample, url });
+    let _ = target.try_send(ServerMessage::StreamStopped);
+    let _ = target.try_send(ServerMessage::HandoffPrepare { sample, url });
 }
 
 // ─── Track / queue helpers ───────────────────────────────────────────────────
 
-/// Advance the queue by `delta` positions (+1 = next, -1 = previous).
-/// Returns the path to play, or `None` if the queue is empty.
-fn advance_queue(
-    app_state: &tauri::State<'_, crate::AppState>,
-    delta: i32,
-) -> Option<String> {
-    let queue = app_state.queue.lock().unwrap();
-    if queue.is_empty() { return None; }
-
-    let mut index = app_state.current_queue_index.lock().unwrap();
-    let repeat = app_state.repeat_mode.lock().unwrap();
-    let len = queue.len() as i32;
-
-    let mut next = *index as i32 + delta;
-    if next >= len {
-        match repeat.as_str() {
-            "all" => next = 0,
-            "one" => next = *index as i32,
-            _ => return None, // repeat off: stop at end
-        }
-    } else if next < 0 {
-        match repeat.as_str() {
-            "all" => next = len - 1,
-            "one" => next = *index as i32,
-            _ => next = 0, // repeat off: stay at first track
-        }
-    }
-    *index = next as usize;
-    Some(queue[next as usize].path.clone())
-}
-
 /// Load and optionally start playback of a track, handling both desktop and mobile output.
 async fn play_track_internal(
     state: &Arc<ServerState>,
@@ -1174,6 +1289,12 @@ async fn play_track_internal(
         }
     };
 
+    if should_broadcast_queue {
+        crate::queue::queue_changed(app_state);
+    }
+    crate::sync_next_track(app_state);
+    crate::schedule_play_count(&state.app_handle, path.clone());
+
     if should_broadcast_queue {
         broadcast_queue(state, app_state).await;
     }
@@ -1285,7 +1406,7 @@ fn extract_audio_quality(path: &str) -> (Option<u32>, Option<u32>, Option<String
 }
 
 /// Read the current queue & index, mapping to `TrackSummary`.
-fn read_queue(app_state: &tauri::State<'_, crate::AppState>) -> (Vec<super::TrackSummary>, usize) {
+pub(crate) fn read_queue(app_state: &crate::AppState) -> (Vec<super::TrackSummary>, usize) {
     let queue = app_state.queue.lock().unwrap();
     let index = *app_state.current_queue_index.lock().unwrap();
     let tracks = queue.iter().map(|t| super::TrackSummary {
@@ -1312,34 +1433,6 @@ fn build_queue_message(app_state: &tauri::State<'_, crate::AppState>) -> ServerM
     ServerMessage::QueueUpdate { queue: tracks, current_index: index as i32 }
 }
 
-/// Replace the entire queue and reset the index.
-fn set_queue(app_state: &tauri::State<'_, crate::AppState>, tracks: Vec<crate::audio::TrackInfo>) {
-    let mut q = app_state.queue.lock().unwrap();
-    let mut i = app_state.current_queue_index.lock().unwrap();
-    *q = VecDeque::from(tracks);
-    *i = 0;
-}
-
-/// Fetch sorted tracks from DB matching a predicate.
-fn get_sorted_tracks(
-    app_state: &tauri::State<'_, crate::AppState>,
-    predicate: impl Fn(&crate::audio::TrackInfo) -> bool,
-) -> Vec<crate::audio::TrackInfo> {
-    let db_guard = app_state.db.lock().unwrap();
-    if let Some(ref db) = *db_guard {
-        if let Ok(all) = db.get_all_tracks() {
-            let mut filtered: Vec<_> = all.into_iter().filter(|t| predicate(t)).collect();
-            filtered.sort_by(|a, b| {
-                a.album.cmp(&b.album)
-                    .then(a.disc_number.cmp(&b.disc_number))
-                    .then(a.track_number.cmp(&b.track_number))
-            });
-            return filtered;
-        }
-    }
-    Vec::new()
-}
-
 /// Build full `TrackDetail` list from DB.
 fn build_track_details(app_state: &tauri::State<'_, crate::AppState>) -> Vec<super::routes::TrackDetail> {
     let db_guard = app_state.db.lock().unwrap();
@@ -1351,69 +1444,6 @@ fn build_track_details(app_state: &tauri::State<'_, crate::AppState>) -> Vec<sup
     Vec::new()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{ClientMessage, ServerMessage};
-
-    #[test]
-    fn hello_supports_legacy_payload() {
-        let json = r#"{"type":"hello","clientName":"Android"}"#;
-        let parsed: ClientMessage = serde_json::from_str(json).expect("legacy hello should deserialize");
-        match parsed {
-            ClientMessage::Hello {
-                client_name,
-                protocol_version,
-                capabilities,
-            } => {
-                assert_eq!(client_name, "Android");
-                assert_eq!(protocol_version, None);
-                assert!(capabilities.is_empty());
-            }
-            _ => panic!("expected hello variant"),
-        }
-    }
-
-    #[test]
-    fn hello_supports_protocol_and_capabilities() {
-        let json = r#"{
-            "type":"hello",
-            "clientName":"Android",
-            "protocolVersion":"1.1",
-            "capabilities":["queue.sync","lyrics.romaji"]
-        }"#;
-        let parsed: ClientMessage = serde_json::from_str(json).expect("modern hello should deserialize");
-        match parsed {
-            ClientMessage::Hello {
-                client_name,
-                protocol_version,
-                capabilities,
-            } => {
-                assert_eq!(client_name, "Android");
-                assert_eq!(protocol_version.as_deref(), Some("1.1"));
-                assert_eq!(capabilities, vec!["queue.sync".to_string(), "lyrics.romaji".to_string()]);
-            }
-            _ => panic!("expected hello variant"),
-        }
-    }
-
-    #[test]
-    fn connected_serializes_protocol_metadata() {
-        let msg = ServerMessage::Connected {
-            client_id: "abc-123".to_string(),
-            protocol_version: "1.1".to_string(),
-            server_capabilities: vec!["queue.sync".to_string()],
-            negotiated_capabilities: vec!["queue.sync".to_string()],
-        };
-
-        let json = serde_json::to_value(msg).expect("connected message should serialize");
-        assert_eq!(json["type"], "connected");
-        assert_eq!(json["clientId"], "abc-123");
-        assert_eq!(json["protocolVersion"], "1.1");
-        assert_eq!(json["serverCapabilities"], serde_json::json!(["queue.sync"]));
-        assert_eq!(json["negotiatedCapabilities"], serde_json::json!(["queue.sync"]));
-    }
-}
-
 /// Build the playlists list.
 fn build_playlists_list(app_state: &tauri::State<'_, crate::AppState>) -> Vec<PlaylistResponse> {
     let db_guard = app_state.db.lock().unwrap();
@@ -1476,12 +1506,18 @@ fn track_to_detail(t: &crate::audio::TrackInfo) -> super::routes::TrackDetail {
 
 // ─── Network helpers ─────────────────────────────────────────────────────────
 
-/// Build `http://<ip>:<port>/stream/<encoded_path>`.
+/// Build `http(s)://<ip>:<port>/stream/<encoded_path>?token=<token>`.
 fn build_stream_url(state: &Arc<ServerState>, path: &str) -> String {
     let ip = determine_accessible_ip().unwrap_or_else(|| "127.0.0.1".to_string());
     let port = state.config.port;
+    let scheme = if state.config.use_tls { "https" } else { "http" };
     let encoded = urlencoding::encode(path);
-    format!("http://{}:{}/stream/{}", ip, port, encoded)
+    let mut url = format!("{}://{}:{}/stream/{}", scheme, ip, port, encoded);
+    if let Some(token) = state.config.control_token.as_deref() {
+        url.push_str("?token=");
+        url.push_str(&urlencoding::encode(token));
+    }
+    url
 }
 
 /// Determine the best LAN IP address reachable by mobile clients.
@@ -1516,16 +1552,22 @@ fn determine_accessible_ip() -> Option<String> {
     None
 }
 
-/// Get path and position of the currently-loaded track.
-fn current_track_info(app_state: &tauri::State<'_, crate::AppState>) -> Option<(String, f64)> {
+/// Path, position and native sample rate of the desktop's current track
+fn current_track_info(app_state: &tauri::State<'_, crate::AppState>) -> Option<(String, f64, Option<u32>)> {
     app_state.player.lock().ok().and_then(|g| {
         g.as_ref().and_then(|p| {
             let s = p.get_status();
-            s.track.map(|t| (t.path, s.position_secs))
+            s.track.map(|t| (t.path, s.position_secs, s.sample_rate))
         })
     })
 }
 
+/// Sample offset for a handoff at `position` seconds. Uses the file's own rate so
+/// 48 kHz and hi-res tracks line up; 44.1 kHz is only a fallback for unknown files.
+fn handoff_sample(position: f64, sample_rate: Option<u32>) -> u64 {
+    (position * sample_rate.unwrap_or(44_100) as f64) as u64
+}
+
 // ─── Stats helpers ───────────────────────────────────────────────────────────
 
 /// Finalize any active desktop stats session before switching to mobile.
@@ -1533,16 +1575,34 @@ async fn finalize_desktop_stats(
     app_state: &tauri::State<'_, crate::AppState>,
     state: &Arc<ServerState>,
 ) {
-    if let Ok(mut tracker) = app_state.stats_tracker.lock() {
-        let now_ms = crate::stats::current_time_ms();
-        if let Some(event) = tracker.update_desktop(None, 0.0, false, now_ms) {
-            let _ = crate::stats::record_stats_event(app_state, event);
-            state.broadcast(ServerEvent::StatsUpdated { timestamp: now_ms });
-            let _ = state.app_handle.emit("stats-updated", ());
-        }
+    let now_ms = crate::stats::current_time_ms();
+    let event = app_state
+        .stats_tracker
+        .lock()
+        .ok()
+        .and_then(|mut t| t.update_desktop(None, 0.0, false, now_ms));
+    if let Some(event) = event {
+        record_stats(state, app_state, event, now_ms);
     }
 }
 
+/// Save a finished listening session and tell clients to refresh their stats.
+/// Before the database is open the event can't be stored; it is logged and
+/// dropped instead of announcing an update that didn't happen.
+fn record_stats(
+    state: &Arc<ServerState>,
+    app_state: &tauri::State<'_, crate::AppState>,
+    event: crate::stats::PlaybackEvent,
+    now_ms: i64,
+) {
+    if let Err(e) = crate::stats::record_stats_event(app_state, event) {
+        log::warn!("[WS] Listening session not recorded: {}", e);
+        return;
+    }
+    state.broadcast(ServerEvent::StatsUpdated { timestamp: now_ms });
+    let _ = state.app_handle.emit("stats-updated", ());
+}
+
 // ─── Discord Rich Presence ───────────────────────────────────────────────────
 
 /// Sync Discord Rich Presence with the current player state.
@@ -1583,3 +1643,76 @@ pub async fn sync_discord(
         let _ = app_state.discord.clear_activity();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{handoff_sample, ClientMessage, ServerMessage};
+
+    #[test]
+    fn handoff_offset_uses_track_sample_rate() {
+        assert_eq!(handoff_sample(2.0, Some(48_000)), 96_000);
+        assert_eq!(handoff_sample(1.5, Some(96_000)), 144_000);
+        assert_eq!(handoff_sample(2.0, None), 88_200);
+    }
+
+    #[test]
+    fn hello_supports_legacy_payload() {
+        let json = r#"{"type":"hello","clientName":"Android"}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).expect("legacy hello should deserialize");
+        match parsed {
+            ClientMessage::Hello {
+                client_name,
+                protocol_version,
+                capabilities,
+                token,
+            } => {
+                assert_eq!(client_name, "Android");
+                assert_eq!(protocol_version, None);
+                assert!(capabilities.is_empty());
+                assert_eq!(token, None);
+            }
+            _ => panic!("expected hello variant"),
+        }
+    }
+
+    #[test]
+    fn hello_supports_protocol_and_capabilities() {
+        let json = r#"{
+            "type":"hello",
+            "clientName":"Android",
+            "protocolVersion":"1.1",
+            "capabilities":["queue.sync","lyrics.romaji"]
+        }"#;
+        let parsed: ClientMessage = serde_json::from_str(json).expect("modern hello should deserialize");
+        match parsed {
+            ClientMessage::Hello {
+                client_name,
+                protocol_version,
+                capabilities,
+                ..
+            } => {
+                assert_eq!(client_name, "Android");
+                assert_eq!(protocol_version.as_deref(), Some("1.1"));
+                assert_eq!(capabilities, vec!["queue.sync".to_string(), "lyrics.romaji".to_string()]);
+            }
+            _ => panic!("expected hello variant"),
+        }
+    }
+
+    #[test]
+    fn connected_serializes_protocol_metadata() {
+        let msg = ServerMessage::Connected {
+            client_id: "abc-123".to_string(),
+            protocol_version: "1.1".to_string(),
+            server_capabilities: vec!["queue.sync".to_string()],
+            negotiated_capabilities: vec!["queue.sync".to_string()],
+        };
+
+        let json = serde_json::to_value(msg).expect("connected message should serialize");
+        assert_eq!(json["type"], "connected");
+        assert_eq!(json["clientId"], "abc-123");
+        assert_eq!(json["protocolVersion"], "1.1");
+        assert_eq!(json["serverCapabilities"], serde_json::json!(["queue.sync"]));
+        assert_eq!(json["negotiatedCapabilities"], serde_json::json!(["queue.sync"]));
+    }
+}
@@ -0,0 +1,249 @@
+//! User preferences, persisted as `settings.json` in the app data dir
+//!
+//! Loaded once at startup into `AppState::settings`. `update_settings` and the
+//! individual feature commands change them through `save_settings` in lib.rs,
+//! which writes the whole file. Keys missing from an older file take their
+//! defaults; values that used to live in the database's `settings` table are
+//! imported the first time the file is created, or, for the ones moved out of
+//! it later (`MOVED_DB_KEYS`), the first time this version starts.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::audio::formats;
+use crate::audio::player::PREAMP_RANGE_DB;
+use crate::audio::replaygain::NormalizationMode;
+use crate::audio::state::VolumeCurve;
+use crate::database::DatabaseManager;
+
+/// Database keys of values that moved here after `settings.json` existed.
+/// Their rows are deleted once imported.
+pub const MOVED_DB_KEYS: [&str; 3] = [PAIRED_PEERS_KEY, WATCHED_FOLDERS_KEY, SCROBBLED_UNTIL_KEY];
+const PAIRED_PEERS_KEY: &str = "p2p_paired_peers";
+const WATCHED_FOLDERS_KEY: &str = "library_watched_folders";
+const SCROBBLED_UNTIL_KEY: &str = "lastfm_scrobbled_until";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub discord_enabled: bool,
+    /// Up to two (label, url) buttons on the Discord activity
+    pub discord_buttons: Vec<(String, String)>,
+    /// Master gain ahead of the EQ (dB)
+    pub preamp_db: f32,
+    pub normalization_mode: NormalizationMode,
+    /// Gain for tracks without ReplayGain tags while normalizing (dB)
+    pub default_gain_db: f32,
+    pub volume_curve: VolumeCurve,
+    /// Port the companion server tries first; `None` uses the built-in default
+    pub server_port: Option<u16>,
+    /// Extensions library scans treat as audio; `None` uses the built-in list
+    pub scan_extensions: Option<Vec<String>>,
+    /// Where playback was when last saved, offered as "resume" on next launch
+    pub last_session: Option<LastSession>,
+    /// Library folders kept in sync with the filesystem
+    pub watched_folders: Vec<String>,
+    /// Peers allowed to stream tracks from this device (base58 peer IDs)
+    pub paired_peers: Vec<String>,
+    /// Timestamp (ms) of the last play sent to Last.fm; `None` until an account is linked
+    pub lastfm_scrobbled_until: Option<i64>,
+}
+
+/// Track and position recorded periodically while playing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastSession {
+    pub track_path: String,
+    pub position_secs: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            discord_enabled: true,
+            discord_buttons: Vec::new(),
+            preamp_db: 0.0,
+            normalization_mode: NormalizationMode::default(),
+            default_gain_db: 0.0,
+            volume_curve: VolumeCurve::default(),
+            server_port: None,
+            scan_extensions: None,
+            last_session: None,
+            watched_folders: Vec::new(),
+            paired_peers: Vec::new(),
+            lastfm_scrobbled_until: None,
+        }
+    }
+}
+
+/// Fields to change in `update_settings`; absent ones are left alone
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsPatch {
+    pub discord_enabled: Option<bool>,
+    pub discord_buttons: Option<Vec<(String, String)>>,
+    pub preamp_db: Option<f32>,
+    pub normalization_mode: Option<NormalizationMode>,
+    pub default_gain_db: Option<f32>,
+    pub volume_curve: Option<VolumeCurve>,
+    pub server_port: Option<u16>,
+    /// An empty list goes back to the built-in extensions
+    pub scan_extensions: Option<Vec<String>>,
+}
+
+impl Settings {
+    /// Read `path`; a missing or unreadable file gives the defaults
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("[Settings] Ignoring unreadable {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Write to `path` via a temporary file, so a crash can't leave half a file
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    }
+
+    /// Apply a partial update, rejecting invalid values before changing anything
+    pub fn apply(&mut self, patch: SettingsPatch) -> Result<(), String> {
+        if patch.server_port == Some(0) {
+            return Err("Port must be between 1 and 65535".to_string());
+        }
+        if [patch.preamp_db, patch.default_gain_db]
+            .iter()
+            .flatten()
+            .any(|db| !db.is_finite())
+        {
+            return Err("Gain must be a finite number of dB".to_string());
+        }
+        let (min, max) = PREAMP_RANGE_DB;
+
+        if let Some(enabled) = patch.discord_enabled {
+            self.discord_enabled = enabled;
+        }
+        if let Some(buttons) = patch.discord_buttons {
+            self.discord_buttons = buttons;
+        }
+        if let Some(db) = patch.preamp_db {
+            self.preamp_db = db.clamp(min, max);
+        }
+        if let Some(mode) = patch.normalization_mode {
+            self.normalization_mode = mode;
+        }
+        if let Some(db) = patch.default_gain_db {
+            self.default_gain_db = db;
+        }
+        if let Some(curve) = patch.volume_curve {
+            self.volume_curve = curve;
+        }
+        if let Some(port) = patch.server_port {
+            self.server_port = Some(port);
+        }
+        if let Some(extensions) = patch.scan_extensions {
+            let extensions = formats::normalize(extensions);
+            self.scan_extensions = (!extensions.is_empty()).then_some(extensions);
+        }
+        Ok(())
+    }
+
+    /// Settings as older versions stored them, one key each in the database
+    pub fn import_legacy(db: &DatabaseManager) -> Self {
+        let get = |key: &str| db.get_setting(key).ok().flatten();
+        let mut settings = Self::default();
+        if let Some(value) = get("discord_enabled") {
+            settings.discord_enabled = value == "true";
+        }
+        if let Some(buttons) = get("discord_buttons").and_then(|v| serde_json::from_str(&v).ok()) {
+            settings.discord_buttons = buttons;
+        }
+        if let Some(db) = get("audio_preamp_db").and_then(|v| v.parse().ok()) {
+            settings.preamp_db = db;
+        }
+        settings.server_port = get("server_port").and_then(|v| v.parse().ok());
+        settings
+    }
+
+    /// Take over the `MOVED_DB_KEYS` values a previous version kept in the
+    /// database. Returns whether any were there; delete them once this is saved.
+    pub fn import_moved(&mut self, db: &DatabaseManager) -> bool {
+        let get = |key: &str| db.get_setting(key).ok().flatten();
+        let mut found = false;
+        if let Some(value) = get(PAIRED_PEERS_KEY) {
+            self.paired_peers = serde_json::from_str(&value).unwrap_or_default();
+            found = true;
+        }
+        if let Some(value) = get(WATCHED_FOLDERS_KEY) {
+            self.watched_folders = serde_json::from_str(&value).unwrap_or_default();
+            found = true;
+        }
+        if let Some(value) = get(SCROBBLED_UNTIL_KEY) {
+            self.lastfm_scrobbled_until = value.parse().ok();
+            found = true;
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_take_defaults_and_patches_validate() {
+        let mut settings: Settings = serde_json::from_str(r#"{"preampDb": -3.0}"#).unwrap();
+        assert_eq!(settings.preamp_db, -3.0);
+        assert!(settings.discord_enabled);
+        assert_eq!(settings.volume_curve, VolumeCurve::Log);
+
+        let bad = SettingsPatch {
+            preamp_db: Some(6.0),
+            server_port: Some(0),
+            ..Default::default()
+        };
+        assert!(settings.apply(bad).is_err());
+        assert_eq!(settings.preamp_db, -3.0);
+
+        let patch: SettingsPatch =
+            serde_json::from_str(r#"{"preampDb": 100, "normalizationMode": "album"}"#).unwrap();
+        settings.apply(patch).unwrap();
+        assert_eq!(settings.preamp_db, PREAMP_RANGE_DB.1);
+        assert_eq!(settings.normalization_mode, NormalizationMode::Album);
+    }
+
+    #[test]
+    fn non_finite_gains_are_rejected() {
+        let mut settings = Settings::default();
+        for patch in [
+            SettingsPatch { preamp_db: Some(f32::NAN), ..Default::default() },
+            SettingsPatch { default_gain_db: Some(f32::INFINITY), ..Default::default() },
+        ] {
+            assert!(settings.apply(patch).is_err());
+        }
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn moved_database_keys_are_imported() {
+        let db = DatabaseManager::open_in_memory().unwrap();
+        let mut settings = Settings::default();
+        assert!(!settings.import_moved(&db));
+
+        db.set_setting("p2p_paired_peers", r#"["12D3KooWpeer"]"#).unwrap();
+        db.set_setting("library_watched_folders", r#"["/music"]"#).unwrap();
+        db.set_setting("lastfm_scrobbled_until", "1700000000000").unwrap();
+        assert!(settings.import_moved(&db));
+        assert_eq!(settings.paired_peers, vec!["12D3KooWpeer".to_string()]);
+        assert_eq!(settings.watched_folders, vec!["/music".to_string()]);
+        assert_eq!(settings.lastfm_scrobbled_until, Some(1_700_000_000_000));
+    }
+}
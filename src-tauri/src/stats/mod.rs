@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 const MIN_SESSION_LISTEN_MS: i64 = 5_000;
 
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaybackEvent {
@@ -25,6 +29,49 @@ pub struct TrackAnalytics {
     pub avg_listen_pct: f64,
 }
 
+/// A song ranked by listening time within a time range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SongRanking {
+    pub song_id: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub play_count: i64,
+    pub total_listen_ms: i64,
+}
+
+/// An artist ranked by listening time within a time range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistRanking {
+    pub artist: String,
+    pub play_count: i64,
+    pub track_count: i64,
+    pub total_listen_ms: i64,
+}
+
+/// An album ranked by listening time within a time range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumRanking {
+    pub album: String,
+    pub artist: String,
+    pub play_count: i64,
+    pub total_listen_ms: i64,
+}
+
+/// A playback event with its song resolved against the library, for export.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedEvent {
+    #[serde(flatten)]
+    pub event: PlaybackEvent,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
 #[derive(Default)]
 pub struct StatsTracker {
     desktop_session: Option<PlaybackSession>,
@@ -79,6 +126,14 @@ impl StatsTracker {
         Self::finalize_session(&mut self.mobile_session, now_ms)
     }
 
+    /// End both sessions, e.g. when the app quits mid-song
+    pub fn finalize_all(&mut self, now_ms: i64) -> Vec<PlaybackEvent> {
+        [&mut self.desktop_session, &mut self.mobile_session]
+            .into_iter()
+            .filter_map(|slot| Self::finalize_session(slot, now_ms))
+            .collect()
+    }
+
     fn update_session(
         slot: &mut Option<PlaybackSession>,
         song_id: Option<String>,
@@ -152,14 +207,18 @@ impl StatsTracker {
 // Database-backed recording & querying
 // ---------------------------------------------------------------------------
 
-/// Record a playback event into SQLite via the app's DatabaseManager.
+/// Record a playback event into SQLite via the app's DatabaseManager, and pass
+/// it on to live stats subscribers.
 pub fn record_stats_event(
     app_state: &crate::AppState,
     event: PlaybackEvent,
 ) -> Result<(), String> {
     let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
     let db = guard.as_ref().ok_or("database not initialized")?;
-    db.insert_playback_event(&event)
+    db.insert_playback_event(&event)?;
+    // No subscribers is not an error
+    let _ = app_state.stats_updates.send(event);
+    Ok(())
 }
 
 /// Load playback events from SQLite, optionally filtered by time range.
@@ -193,6 +252,172 @@ pub fn get_recently_played(
     db.get_recently_played(limit)
 }
 
+/// Songs in `[start_ms, end_ms]` ranked by total listening time.
+pub fn top_songs(
+    app_state: &crate::AppState,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: usize,
+) -> Result<Vec<SongRanking>, String> {
+    let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
+    let db = guard.as_ref().ok_or("database not initialized")?;
+    db.top_songs(start_ms, end_ms, limit)
+}
+
+/// Artists in `[start_ms, end_ms]` ranked by total listening time.
+pub fn top_artists(
+    app_state: &crate::AppState,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: usize,
+) -> Result<Vec<ArtistRanking>, String> {
+    let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
+    let db = guard.as_ref().ok_or("database not initialized")?;
+    db.top_artists(start_ms, end_ms, limit)
+}
+
+/// Albums in `[start_ms, end_ms]` ranked by total listening time.
+pub fn top_albums(
+    app_state: &crate::AppState,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    limit: usize,
+) -> Result<Vec<AlbumRanking>, String> {
+    let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
+    let db = guard.as_ref().ok_or("database not initialized")?;
+    db.top_albums(start_ms, end_ms, limit)
+}
+
+/// Total time listened in `[start_ms, end_ms]`.
+pub fn total_listening_ms(
+    app_state: &crate::AppState,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+) -> Result<i64, String> {
+    let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
+    let db = guard.as_ref().ok_or("database not initialized")?;
+    db.total_listening_ms(start_ms, end_ms)
+}
+
+/// Listened milliseconds per UTC day (keyed by midnight ms) for events in `[start_ms, end_ms]`.
+pub fn listening_by_day(
+    app_state: &crate::AppState,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+) -> Result<BTreeMap<i64, i64>, String> {
+    let events = load_stats_events(app_state, start_ms, end_ms)?;
+    Ok(bucket_by_day(&events))
+}
+
+/// Spread each event's listening time over the days its `[start, end]` span covers.
+/// When the span is longer than `duration_ms` (the track was paused) the time is
+/// split proportionally to the overlap with each day.
+fn bucket_by_day(events: &[PlaybackEvent]) -> BTreeMap<i64, i64> {
+    let mut days = BTreeMap::new();
+    for event in events {
+        if event.duration_ms <= 0 {
+            continue;
+        }
+        let end = event.end_timestamp.unwrap_or(event.timestamp);
+        let start = event
+            .start_timestamp
+            .unwrap_or(end - event.duration_ms)
+            .min(end);
+        let span = end - start;
+        if span == 0 {
+            *days.entry(end.div_euclid(DAY_MS) * DAY_MS).or_insert(0) += event.duration_ms;
+            continue;
+        }
+
+        let mut assigned = 0;
+        let mut day = start.div_euclid(DAY_MS) * DAY_MS;
+        while day < end {
+            let overlap = (day + DAY_MS).min(end) - day.max(start);
+            let share = if day + DAY_MS >= end {
+                // Last day takes the remainder so rounding never loses time
+                event.duration_ms - assigned
+            } else {
+                (event.duration_ms as i128 * overlap as i128 / span as i128) as i64
+            };
+            *days.entry(day).or_insert(0) += share;
+            assigned += share;
+            day += DAY_MS;
+        }
+    }
+    days
+}
+
+/// Write the whole playback history to `path` as "csv" or "json".
+/// The file is written next to the target and renamed into place, so a failed
+/// export never leaves a partial file behind. Returns the number of events.
+pub fn export_stats(
+    app_state: &crate::AppState,
+    path: &Path,
+    format: &str,
+) -> Result<usize, String> {
+    let format = format.trim().to_ascii_lowercase();
+    if format != "csv" && format != "json" {
+        return Err(format!("Unsupported export format \"{format}\" (expected csv or json)"));
+    }
+
+    let events = {
+        let guard = app_state.db.lock().map_err(|_| "db lock poisoned".to_string())?;
+        let db = guard.as_ref().ok_or("database not initialized")?;
+        db.load_playback_events_for_export()?
+    };
+
+    let contents = if format == "csv" {
+        events_to_csv(&events)
+    } else {
+        serde_json::to_string_pretty(&events).map_err(|e| format!("serialize: {e}"))?
+    };
+
+    let tmp_path = path.with_file_name(format!(
+        ".{}.part",
+        path.file_name().and_then(|n| n.to_str()).unwrap_or("export")
+    ));
+    let write = std::fs::write(&tmp_path, contents).and_then(|_| std::fs::rename(&tmp_path, path));
+    if let Err(e) = write {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(format!("Cannot write export to {}: {e}", path.display()));
+    }
+    Ok(events.len())
+}
+
+const CSV_HEADER: &str =
+    "song_id,title,artist,album,timestamp,duration_ms,start_timestamp,end_timestamp,output";
+
+fn events_to_csv(events: &[ExportedEvent]) -> String {
+    let optional = |v: Option<i64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for e in events {
+        let row = [
+            csv_field(&e.event.song_id),
+            csv_field(e.title.as_deref().unwrap_or("")),
+            csv_field(e.artist.as_deref().unwrap_or("")),
+            csv_field(e.album.as_deref().unwrap_or("")),
+            e.event.timestamp.to_string(),
+            e.event.duration_ms.to_string(),
+            optional(e.event.start_timestamp),
+            optional(e.event.end_timestamp),
+            csv_field(&e.event.output),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 /// Migrate legacy JSON playback_events.json into SQLite (idempotent).
 pub fn migrate_json_to_sqlite(
     app_state: &crate::AppState,
@@ -231,3 +456,70 @@ pub fn current_time_ms() -> i64 {
         .unwrap_or_default()
         .as_millis() as i64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(start: Option<i64>, end: i64, duration_ms: i64) -> PlaybackEvent {
+        PlaybackEvent {
+            song_id: "/music/a.flac".to_string(),
+            timestamp: end,
+            duration_ms,
+            start_timestamp: start,
+            end_timestamp: Some(end),
+            output: "desktop".to_string(),
+        }
+    }
+
+    #[test]
+    fn quitting_mid_song_keeps_the_time_listened() {
+        let mut tracker = StatsTracker::default();
+        let song = || Some("/music/a.flac".to_string());
+        assert!(tracker.update_desktop(song(), 10.0, true, 1_000).is_none());
+        assert!(tracker.update_desktop(song(), 70.0, true, 61_000).is_none());
+
+        let events = tracker.finalize_all(62_000);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].duration_ms, 60_000);
+        assert!(tracker.finalize_all(63_000).is_empty());
+    }
+
+    #[test]
+    fn listening_is_split_across_midnight() {
+        let midnight = 10 * DAY_MS;
+        let days = bucket_by_day(&[
+            event(Some(midnight - 60_000), midnight + 180_000, 240_000),
+            event(None, midnight + 600_000, 120_000),
+        ]);
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[&(midnight - DAY_MS)], 60_000);
+        assert_eq!(days[&midnight], 180_000 + 120_000);
+    }
+
+    #[test]
+    fn csv_export_quotes_fields_and_keeps_one_row_per_event() {
+        let event = ExportedEvent {
+            event: PlaybackEvent {
+                song_id: "/music/a, b.flac".to_string(),
+                timestamp: 1_000,
+                duration_ms: 180_000,
+                start_timestamp: None,
+                end_timestamp: Some(1_000),
+                output: "desktop".to_string(),
+            },
+            title: Some("Say \"Hi\"".to_string()),
+            artist: Some("Artist".to_string()),
+            album: None,
+        };
+
+        let csv = events_to_csv(&[event]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "\"/music/a, b.flac\",\"Say \"\"Hi\"\"\",Artist,,1000,180000,,1000,desktop"
+        );
+    }
+}
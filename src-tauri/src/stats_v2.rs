@@ -24,21 +24,27 @@ pub enum StatsTimeRangeV2 {
 
 impl StatsTimeRangeV2 {
     pub fn parse(value: Option<String>) -> Self {
-        match value
-            .unwrap_or_else(|| "WEEK".to_string())
-            .trim()
-            .to_ascii_uppercase()
-            .as_str()
-        {
-            "DAY" => Self::Day,
-            "WEEK" => Self::Week,
-            "MONTH" => Self::Month,
-            "YEAR" => Self::Year,
-            "ALL" => Self::All,
-            _ => Self::Week,
+        value.and_then(|v| Self::from_name(&v)).unwrap_or(Self::Week)
+    }
+
+    /// Range named `value` (case-insensitive), or `None` if there is no such range
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "DAY" => Some(Self::Day),
+            "WEEK" => Some(Self::Week),
+            "MONTH" => Some(Self::Month),
+            "YEAR" => Some(Self::Year),
+            "ALL" => Some(Self::All),
+            _ => None,
         }
     }
 
+    /// Start and end of the range as the stats screen counts it, up to now.
+    /// `All` has no start.
+    pub fn bounds_ms(&self) -> (Option<i64>, i64) {
+        resolve_bounds(self, &[], current_time_ms())
+    }
+
     fn display_name(&self) -> &'static str {
         match self {
             Self::Day => "Today",
@@ -347,7 +353,7 @@ fn load_summary(
             } else {
                 song.song_id
                     .split(['/', '\\'])
-                    .last()
+                    .next_back()
                     .unwrap_or("Unknown Track")
                     .to_string()
             };
@@ -438,7 +444,7 @@ fn load_summary(
 
     let day_slices = overall_spans
         .iter()
-        .flat_map(|span| slice_span_by_day(span))
+        .flat_map(slice_span_by_day)
         .collect::<Vec<_>>();
 
     let mut events_by_day: HashMap<NaiveDate, i64> = HashMap::new();
@@ -8,7 +8,8 @@ use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
 // Needed for .hwnd() on WebviewWindow
 use raw_window_handle::HasWindowHandle;
 use windows::Win32::UI::Shell::{
-    ITaskbarList3, TaskbarList, THBF_ENABLED, THBN_CLICKED, THUMBBUTTON, THUMBBUTTONMASK,
+    ITaskbarList3, TaskbarList, TBPFLAG, TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED, THBF_ENABLED,
+    THBN_CLICKED, THUMBBUTTON, THUMBBUTTONMASK,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
     CallWindowProcW, DefWindowProcW, LoadImageW, SetWindowLongPtrW, GWLP_WNDPROC, HICON,
@@ -37,6 +38,12 @@ static mut ICON_NEXT: HICON = HICON(0 as _);
 // Cache current playing state to avoid redundant updates
 static mut IS_PLAYING: bool = false;
 
+/// Resolution of the taskbar progress bar
+const PROGRESS_STEPS: u64 = 1000;
+
+// Last progress shown on the taskbar icon: (step, state); step is None with no progress
+static mut LAST_PROGRESS: Option<(Option<u64>, i32)> = None;
+
 pub fn init(window: WebviewWindow) {
     let hwnd_isize = if let Ok(handle) = window.window_handle() {
         if let raw_window_handle::RawWindowHandle::Win32(h) = handle.as_raw() {
@@ -101,6 +108,41 @@ pub fn update_play_status(playing: bool) {
     }
 }
 
+/// Show the playback position on the taskbar icon. Called from the player status
+/// poll, so it only touches COM when the visible bar would actually change.
+pub fn update_progress(status: &crate::audio::state::PlayerStatus) {
+    use crate::audio::PlayerState;
+
+    let duration = status.track.as_ref().map(|t| t.duration_secs).unwrap_or(0.0);
+    let (step, flag): (Option<u64>, TBPFLAG) = match status.state {
+        PlayerState::Stopped => (None, TBPF_NOPROGRESS),
+        _ if duration <= 0.0 => (None, TBPF_NOPROGRESS),
+        state => {
+            let fraction = (status.position_secs / duration).clamp(0.0, 1.0);
+            let step = (fraction * PROGRESS_STEPS as f64) as u64;
+            let flag = if state == PlayerState::Playing { TBPF_NORMAL } else { TBPF_PAUSED };
+            (Some(step), flag)
+        }
+    };
+
+    unsafe {
+        let hwnd = GLOBAL_WINDOW_HANDLE;
+        if hwnd.0 == 0 || LAST_PROGRESS == Some((step, flag.0)) {
+            return;
+        }
+
+        if let Ok(taskbar) =
+            CoCreateInstance::<_, ITaskbarList3>(&TaskbarList, None, CLSCTX_INPROC_SERVER)
+        {
+            let _ = taskbar.SetProgressState(hwnd, flag);
+            if let Some(step) = step {
+                let _ = taskbar.SetProgressValue(hwnd, step, PROGRESS_STEPS);
+            }
+            LAST_PROGRESS = Some((step, flag.0));
+        }
+    }
+}
+
 unsafe extern "system" fn taskbar_wnd_proc(
     hwnd: HWND,
     msg: u32,
@@ -1,11 +1,14 @@
 use librqbit::api::TorrentIdOrHash;
 use librqbit::{AddTorrent, AddTorrentOptions, AddTorrentResponse, Session, SessionOptions};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroU32;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::fs as tokio_fs;
+use tokio::io::AsyncReadExt;
+use tokio::task::JoinHandle;
 
 pub mod search;
 
@@ -15,10 +18,14 @@ pub mod search;
 
 const STATE_FILE: &str = "vibe_torrents.json";
 
-/// Audio file extensions we care about
-const AUDIO_EXTENSIONS: &[&str] = &[
-    "mp3", "flac", "wav", "aac", "ogg", "m4a", "wma", "aiff", "alac", "opus",
-];
+/// How often transfer totals are folded in and seed ratios checked
+const RATIO_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Transfer totals are written to the state file every this many ratio checks
+const TOTALS_SAVE_EVERY: u32 = 12;
+
+/// In sequential mode a file can be played once this much of its start is on disk
+const PLAYABLE_LEAD_BYTES: u64 = 8 * 1024 * 1024;
 
 /// Public trackers for better peer discovery - reduced for faster initialization
 const PUBLIC_TRACKERS: &[&str] = &[
@@ -46,6 +53,16 @@ pub struct TorrentFile {
     pub is_audio: bool,
 }
 
+/// Download progress of one file inside a torrent
+#[derive(Serialize, Clone, Debug)]
+pub struct TorrentFileProgress {
+    pub index: usize,
+    pub name: String,
+    pub size: u64,
+    pub bytes_downloaded: u64,
+    pub selected: bool,
+}
+
 /// Status of an active torrent download
 #[derive(Serialize, Clone, Debug)]
 pub struct TorrentStatus {
@@ -59,6 +76,68 @@ pub struct TorrentStatus {
     pub downloaded_size: u64,
     pub peers_connected: u32,
     pub error: Option<String>,
+    /// Files are fetched one at a time in selection order, and the pieces of
+    /// the current file front to back
+    pub sequential: bool,
+    /// Indices of files that are fully downloaded, or in sequential mode have
+    /// their first `PLAYABLE_LEAD_BYTES` on disk
+    pub playable_files: Vec<usize>,
+    /// Uploaded / downloaded across all sessions
+    pub ratio: f64,
+    /// Ratio at which seeding stops, after per-torrent override
+    pub seed_ratio_limit: Option<f64>,
+}
+
+/// Session-wide transfer limits in KiB/s; `None` means unlimited
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpeedLimits {
+    pub download_kbps: Option<u32>,
+    pub upload_kbps: Option<u32>,
+}
+
+impl SpeedLimits {
+    /// Treat a limit of 0 as "unlimited"
+    fn normalized(self) -> Self {
+        Self {
+            download_kbps: self.download_kbps.filter(|&k| k > 0),
+            upload_kbps: self.upload_kbps.filter(|&k| k > 0),
+        }
+    }
+}
+
+/// KiB/s to the bytes/s librqbit expects
+fn kbps_to_bps(kbps: Option<u32>) -> Option<NonZeroU32> {
+    kbps.and_then(|k| NonZeroU32::new(k.saturating_mul(1024)))
+}
+
+/// Contents of the state file
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct StateFile {
+    torrents: Vec<PersistedTorrent>,
+    #[serde(default)]
+    speed_limits: SpeedLimits,
+    #[serde(default)]
+    seed_ratio_limit: Option<f64>,
+}
+
+/// State files written before limits existed are a bare list of torrents
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredState {
+    Current(StateFile),
+    Legacy(Vec<PersistedTorrent>),
+}
+
+impl From<StoredState> for StateFile {
+    fn from(stored: StoredState) -> Self {
+        match stored {
+            StoredState::Current(state) => state,
+            StoredState::Legacy(torrents) => StateFile {
+                torrents,
+                ..Default::default()
+            },
+        }
+    }
 }
 
 /// Persisted torrent for saving/loading state
@@ -70,6 +149,14 @@ struct PersistedTorrent {
     selected_files: Option<Vec<usize>>,
     info_hash: String,
     name: String,
+    #[serde(default)]
+    sequential: bool,
+    #[serde(default)]
+    uploaded_bytes: u64,
+    #[serde(default)]
+    downloaded_bytes: u64,
+    #[serde(default)]
+    seed_ratio_limit: Option<f64>,
 }
 
 /// Metadata about a torrent we're tracking
@@ -83,6 +170,78 @@ struct TorrentMetadata {
     file_bytes: Option<Vec<u8>>,
     output_folder: String,
     selected_files: Option<Vec<usize>>,
+    sequential: bool,
+    /// Cumulative bytes across sessions
+    uploaded_bytes: u64,
+    downloaded_bytes: u64,
+    /// Session counters seen at the last check, to turn them into deltas
+    last_uploaded: u64,
+    last_downloaded: u64,
+    /// Overrides the global limit; `Some(0.0)` disables it for this torrent
+    seed_ratio_limit: Option<f64>,
+    /// Bytes at the start of each file known to be on disk, from the sequential reader
+    leading_bytes: HashMap<usize, u64>,
+}
+
+impl TorrentMetadata {
+    /// Fold librqbit's per-session counters into the cumulative totals
+    fn record_transfer(&mut self, uploaded: u64, downloaded: u64) {
+        self.uploaded_bytes += counter_delta(self.last_uploaded, uploaded);
+        self.downloaded_bytes += counter_delta(self.last_downloaded, downloaded);
+        self.last_uploaded = uploaded;
+        self.last_downloaded = downloaded;
+    }
+}
+
+/// Growth of a counter that restarts from zero when a torrent is paused
+fn counter_delta(last: u64, current: u64) -> u64 {
+    if current >= last {
+        current - last
+    } else {
+        current
+    }
+}
+
+/// Share ratio. Data that was already on disk (e.g. restored from a previous
+/// run) counts as downloaded so seeding it doesn't report an infinite ratio.
+fn share_ratio(uploaded: u64, downloaded: u64, on_disk: u64) -> f64 {
+    let downloaded = downloaded.max(on_disk);
+    if downloaded == 0 {
+        0.0
+    } else {
+        uploaded as f64 / downloaded as f64
+    }
+}
+
+/// The limit that applies to a torrent; non-positive values mean "no limit"
+fn effective_ratio_limit(torrent: Option<f64>, global: Option<f64>) -> Option<f64> {
+    torrent
+        .or(global)
+        .filter(|ratio| ratio.is_finite() && *ratio > 0.0)
+}
+
+/// Files to enable for a sequential download: everything up to and including the
+/// first incomplete file in `order`. `None` once every file is complete.
+fn sequential_window(order: &[usize], lengths: &[u64], progress: &[u64]) -> Option<HashSet<usize>> {
+    let complete = |i: usize| {
+        progress.get(i).copied().unwrap_or(0) >= lengths.get(i).copied().unwrap_or(u64::MAX)
+    };
+    let pos = order.iter().position(|&i| !complete(i))?;
+    Some(order[..=pos].iter().copied().collect())
+}
+
+/// Indices of files whose bytes are all downloaded, or whose first
+/// `PLAYABLE_LEAD_BYTES` (per `leading`) are
+fn playable_files(lengths: &[u64], progress: &[u64], leading: &HashMap<usize, u64>) -> Vec<usize> {
+    lengths
+        .iter()
+        .zip(progress)
+        .enumerate()
+        .filter(|(i, (&len, &done))| {
+            done >= len || leading.get(i).is_some_and(|&lead| lead >= len.min(PLAYABLE_LEAD_BYTES))
+        })
+        .map(|(i, _)| i)
+        .collect()
 }
 
 // ============================================================================
@@ -96,6 +255,10 @@ pub struct TorrentManager {
     pub download_dir: PathBuf,
     /// Map of torrent ID -> metadata
     torrents: Arc<RwLock<HashMap<usize, TorrentMetadata>>>,
+    /// Active session rate limits
+    speed_limits: Arc<RwLock<SpeedLimits>>,
+    /// Default seed ratio at which finished torrents are paused
+    seed_ratio_limit: Arc<RwLock<Option<f64>>>,
 }
 
 impl TorrentManager {
@@ -140,6 +303,8 @@ impl TorrentManager {
             session,
             download_dir: download_dir.clone(),
             torrents: Arc::new(RwLock::new(HashMap::new())),
+            speed_limits: Arc::new(RwLock::new(SpeedLimits::default())),
+            seed_ratio_limit: Arc::new(RwLock::new(None)),
         };
 
         // Load persisted state
@@ -147,6 +312,8 @@ impl TorrentManager {
             eprintln!("[Torrent] Warning: Failed to load state: {}", e);
         }
 
+        manager.spawn_ratio_monitor();
+
         Ok(manager)
     }
 
@@ -198,7 +365,7 @@ impl TorrentManager {
                         .extension()
                         .map(|e| e.to_string_lossy().to_lowercase())
                         .unwrap_or_default();
-                    let is_audio = AUDIO_EXTENSIONS.contains(&extension.as_str());
+                    let is_audio = crate::audio::formats::is_audio_extension(&extension);
                     
                     files.push(TorrentFile {
                         index: idx,
@@ -271,7 +438,7 @@ impl TorrentManager {
                         .extension()
                         .map(|e| e.to_string_lossy().to_lowercase())
                         .unwrap_or_default();
-                    let is_audio = AUDIO_EXTENSIONS.contains(&extension.as_str());
+                    let is_audio = crate::audio::formats::is_audio_extension(&extension);
                     
                     files.push(TorrentFile {
                         index: idx,
@@ -306,12 +473,15 @@ impl TorrentManager {
 
     /// Add a torrent and start downloading
     /// selected_files: MUST be provided as file indices to download. Pass None only to download ALL files.
+    /// sequential: fetch the selected files one after another, so the first ones finish (and become
+    /// playable) early. This orders files, not pieces: nothing plays while still downloading.
     pub async fn add_torrent(
         &self,
         magnet: Option<String>,
         file_bytes: Option<Vec<u8>>,
         output_folder: String,
         selected_files: Option<Vec<usize>>,
+        sequential: bool,
     ) -> Result<usize, String> {
         println!("[Torrent] Adding torrent to: {}", output_folder);
         println!("[Torrent] Selected files: {:?}", selected_files);
@@ -380,9 +550,20 @@ impl TorrentManager {
                 file_bytes,
                 output_folder,
                 selected_files,
+                sequential,
+                uploaded_bytes: 0,
+                downloaded_bytes: 0,
+                last_uploaded: 0,
+                last_downloaded: 0,
+                seed_ratio_limit: None,
+                leading_bytes: HashMap::new(),
             });
         }
 
+        if sequential {
+            self.spawn_sequential_driver(id);
+        }
+
         // Save state
         if let Err(e) = self.save_state().await {
             eprintln!("[Torrent] Warning: Failed to save state: {}", e);
@@ -409,6 +590,31 @@ impl TorrentManager {
                             .unwrap_or_else(|| format!("Torrent {}", id))
                     });
 
+                    let global_limit = *self.seed_ratio_limit.read().unwrap();
+                    let (sequential, ratio, seed_ratio_limit, leading_bytes) = self
+                        .torrents
+                        .read()
+                        .ok()
+                        .and_then(|t| {
+                            t.get(&id).map(|m| {
+                                (
+                                    m.sequential,
+                                    share_ratio(
+                                        m.uploaded_bytes,
+                                        m.downloaded_bytes,
+                                        stats.progress_bytes,
+                                    ),
+                                    effective_ratio_limit(m.seed_ratio_limit, global_limit),
+                                    m.leading_bytes.clone(),
+                                )
+                            })
+                        })
+                        .unwrap_or((false, 0.0, global_limit, HashMap::new()));
+                    let playable_files = torrent
+                        .with_metadata(|m| m.file_infos.iter().map(|f| f.len).collect::<Vec<u64>>())
+                        .map(|lengths| playable_files(&lengths, &stats.file_progress, &leading_bytes))
+                        .unwrap_or_default();
+
                     let progress = if stats.total_bytes > 0 {
                         stats.progress_bytes as f64 / stats.total_bytes as f64
                     } else {
@@ -451,12 +657,125 @@ impl TorrentManager {
                         downloaded_size: stats.progress_bytes,
                         peers_connected: peers,
                         error: stats.error.clone(),
+                        sequential,
+                        playable_files,
+                        ratio,
+                        seed_ratio_limit,
                     }
                 })
                 .collect()
         })
     }
 
+    /// Limit session download/upload speed (KiB/s). `None` or 0 means unlimited.
+    pub async fn set_speed_limits(
+        &self,
+        download_kbps: Option<u32>,
+        upload_kbps: Option<u32>,
+    ) -> Result<(), String> {
+        let limits = SpeedLimits { download_kbps, upload_kbps }.normalized();
+        self.apply_speed_limits(limits);
+        self.save_state().await?;
+        println!("[Torrent] Speed limits set: {:?}", limits);
+        Ok(())
+    }
+
+    pub fn get_speed_limits(&self) -> SpeedLimits {
+        *self.speed_limits.read().unwrap()
+    }
+
+    /// Pause finished torrents once they reach `ratio`. `None` or 0 seeds forever.
+    pub async fn set_seed_ratio_limit(&self, ratio: Option<f64>) -> Result<(), String> {
+        let ratio = effective_ratio_limit(ratio, None);
+        *self.seed_ratio_limit.write().unwrap() = ratio;
+        self.save_state().await?;
+        println!("[Torrent] Seed ratio limit set: {:?}", ratio);
+        Ok(())
+    }
+
+    pub fn get_seed_ratio_limit(&self) -> Option<f64> {
+        *self.seed_ratio_limit.read().unwrap()
+    }
+
+    /// Override the seed ratio limit for one torrent. `None` falls back to the
+    /// global limit, 0 keeps this torrent seeding regardless of it.
+    pub async fn set_torrent_seed_ratio_limit(
+        &self,
+        id: usize,
+        ratio: Option<f64>,
+    ) -> Result<(), String> {
+        {
+            let mut torrents = self.torrents.write().unwrap();
+            let meta = torrents.get_mut(&id).ok_or("Torrent not found")?;
+            meta.seed_ratio_limit = ratio.map(|r| if r.is_finite() { r.max(0.0) } else { 0.0 });
+        }
+        self.save_state().await
+    }
+
+    /// Per-file progress of a torrent and whether each file is selected
+    pub fn get_files(&self, id: usize) -> Result<Vec<TorrentFileProgress>, String> {
+        let handle = self.get_handle(id).ok_or("Torrent not found")?;
+        let selected = self
+            .torrents
+            .read()
+            .unwrap()
+            .get(&id)
+            .and_then(|m| m.selected_files.clone());
+        let progress = handle.stats().file_progress;
+
+        handle
+            .with_metadata(|metadata| {
+                metadata
+                    .file_infos
+                    .iter()
+                    .enumerate()
+                    .map(|(index, file_info)| TorrentFileProgress {
+                        index,
+                        name: file_info.relative_filename.to_string_lossy().into_owned(),
+                        size: file_info.len,
+                        bytes_downloaded: progress.get(index).copied().unwrap_or(0),
+                        selected: selected.as_ref().is_none_or(|s| s.contains(&index)),
+                    })
+                    .collect()
+            })
+            .map_err(|e| format!("Torrent metadata not available yet: {}", e))
+    }
+
+    /// Change which files of a torrent are downloaded
+    pub async fn update_selected_files(&self, id: usize, selected: Vec<usize>) -> Result<(), String> {
+        let handle = self.get_handle(id).ok_or("Torrent not found")?;
+        let file_count = handle
+            .with_metadata(|metadata| metadata.file_infos.len())
+            .map_err(|e| format!("Torrent metadata not available yet: {}", e))?;
+
+        if selected.is_empty() {
+            return Err("At least one file must be selected".to_string());
+        }
+        if let Some(bad) = selected.iter().find(|&&i| i >= file_count) {
+            return Err(format!("File index {} out of range", bad));
+        }
+
+        let sequential = {
+            let mut torrents = self.torrents.write().unwrap();
+            let meta = torrents.get_mut(&id).ok_or("Torrent not found")?;
+            meta.selected_files = Some(selected.clone());
+            meta.sequential
+        };
+
+        // The sequential driver picks the new selection up on its next pass
+        if !sequential {
+            let only_files: HashSet<usize> = selected.iter().copied().collect();
+            self.session
+                .update_only_files(&handle, &only_files)
+                .await
+                .map_err(|e| format!("Failed to update selected files: {}", e))?;
+        }
+
+        self.save_state().await?;
+        println!("[Torrent] Selected files for {}: {:?}", id, selected);
+        Ok(())
+    }
+
     /// Pause a torrent
     pub async fn pause(&self, id: usize) -> Result<(), String> {
         let handle = self.get_handle(id).ok_or("Torrent not found")?;
@@ -503,6 +822,158 @@ impl TorrentManager {
     // Private Helpers
     // ========================================================================
 
+    fn apply_speed_limits(&self, limits: SpeedLimits) {
+        self.session.ratelimits.set_download_bps(kbps_to_bps(limits.download_kbps));
+        self.session.ratelimits.set_upload_bps(kbps_to_bps(limits.upload_kbps));
+        *self.speed_limits.write().unwrap() = limits;
+    }
+
+    /// librqbit has no piece-order setting, so sequential mode is driven from here.
+    /// Only the files up to the first incomplete one are enabled, and the window
+    /// moves forward as files finish; within the current file, `spawn_leading_reader`
+    /// pulls the pieces in order.
+    fn spawn_sequential_driver(&self, id: usize) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut applied: Option<HashSet<usize>> = None;
+            let mut reader: Option<(usize, JoinHandle<()>)> = None;
+            loop {
+                let Some(handle) = manager.get_handle(id) else { break };
+                let selection = match manager.torrents.read().unwrap().get(&id) {
+                    Some(m) => m.selected_files.clone(),
+                    None => break,
+                };
+
+                if let Ok(lengths) = handle
+                    .with_metadata(|m| m.file_infos.iter().map(|f| f.len).collect::<Vec<u64>>())
+                {
+                    let order = selection.unwrap_or_else(|| (0..lengths.len()).collect());
+                    let progress = handle.stats().file_progress;
+                    let current = order.iter().copied().find(|&i| {
+                        progress.get(i).copied().unwrap_or(0) < lengths.get(i).copied().unwrap_or(u64::MAX)
+                    });
+                    // Restart the reader if it stopped early, e.g. while the torrent was initializing
+                    let running = reader.as_ref().filter(|(_, task)| !task.is_finished());
+                    if running.map(|(file, _)| *file) != current {
+                        if let Some((_, task)) = reader.take() {
+                            task.abort();
+                        }
+                        reader = current.map(|file| (file, manager.spawn_leading_reader(id, &handle, file)));
+                    }
+
+                    // Once everything is complete keep the whole selection enabled, and
+                    // keep watching in case the selection changes later
+                    let window = sequential_window(&order, &lengths, &progress)
+                        .unwrap_or_else(|| order.iter().copied().collect());
+                    if applied.as_ref() != Some(&window) {
+                        if let Err(e) = manager.session.update_only_files(&handle, &window).await {
+                            eprintln!(
+                                "[Torrent] Failed to advance sequential download {}: {}",
+                                id, e
+                            );
+                        }
+                        applied = Some(window);
+                    }
+                }
+
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+            if let Some((_, task)) = reader {
+                task.abort();
+            }
+        });
+    }
+
+    /// Read `file` front to back through librqbit's file stream. A read waits for
+    /// the piece under it, and librqbit fetches the pieces just ahead of an open
+    /// stream before any others, so the file arrives in order. How far the reader
+    /// got is how much of the file's start is on disk.
+    fn spawn_leading_reader(
+        &self,
+        id: usize,
+        handle: &Arc<librqbit::ManagedTorrent>,
+        file: usize,
+    ) -> JoinHandle<()> {
+        let manager = self.clone();
+        let handle = Arc::clone(handle);
+        tokio::spawn(async move {
+            let mut stream = match handle.stream(file) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("[Torrent] Can't stream file {} of torrent {}: {}", file, id, e);
+                    return;
+                }
+            };
+            let mut buf = vec![0u8; 256 * 1024];
+            loop {
+                match stream.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        let mut torrents = manager.torrents.write().unwrap();
+                        let Some(m) = torrents.get_mut(&id) else { break };
+                        m.leading_bytes.insert(file, stream.position());
+                    }
+                    Err(e) => {
+                        eprintln!("[Torrent] Sequential read of torrent {} stopped: {}", id, e);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Periodically fold transfer counters into the totals, pause torrents that
+    /// reached their seed ratio and persist the totals.
+    fn spawn_ratio_monitor(&self) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut checks: u32 = 0;
+            loop {
+                tokio::time::sleep(RATIO_CHECK_INTERVAL).await;
+
+                for id in manager.update_transfer_totals() {
+                    println!("[Torrent] Seed ratio reached for torrent {}, pausing", id);
+                    if let Err(e) = manager.pause(id).await {
+                        eprintln!("[Torrent] Failed to pause torrent {}: {}", id, e);
+                    }
+                }
+
+                checks = checks.wrapping_add(1);
+                if checks.is_multiple_of(TOTALS_SAVE_EVERY) {
+                    if let Err(e) = manager.save_state().await {
+                        eprintln!("[Torrent] Warning: Failed to save state: {}", e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Update cumulative totals and return the finished, still-active torrents
+    /// whose ratio has reached their limit
+    fn update_transfer_totals(&self) -> Vec<usize> {
+        let global_limit = *self.seed_ratio_limit.read().unwrap();
+        self.session.with_torrents(|handles| {
+            let mut torrents = self.torrents.write().unwrap();
+            handles
+                .filter_map(|(id, handle)| {
+                    let meta = torrents.get_mut(&id)?;
+                    let stats = handle.stats();
+                    let downloaded = stats
+                        .live
+                        .as_ref()
+                        .map(|live| live.snapshot.downloaded_and_checked_bytes)
+                        .unwrap_or(0);
+                    meta.record_transfer(stats.uploaded_bytes, downloaded);
+
+                    let limit = effective_ratio_limit(meta.seed_ratio_limit, global_limit)?;
+                    let ratio =
+                        share_ratio(meta.uploaded_bytes, meta.downloaded_bytes, stats.progress_bytes);
+                    (stats.finished && stats.live.is_some() && ratio >= limit).then_some(id)
+                })
+                .collect()
+        })
+    }
+
     fn get_handle(&self, id: usize) -> Option<Arc<librqbit::ManagedTorrent>> {
         self.session.with_torrents(|torrents| {
             for (tid, handle) in torrents {
@@ -540,7 +1011,7 @@ impl TorrentManager {
                                         .map(|e| e.to_string_lossy().to_lowercase())
                                         .unwrap_or_default();
                                     
-                                    let is_audio = AUDIO_EXTENSIONS.contains(&extension.as_str());
+                                    let is_audio = crate::audio::formats::is_audio_extension(&extension);
 
                                     TorrentFile {
                                         index: idx,
@@ -575,7 +1046,7 @@ impl TorrentManager {
     async fn save_state(&self) -> Result<(), String> {
         let state_path = self.download_dir.join(STATE_FILE);
 
-        let persisted: Vec<PersistedTorrent> = {
+        let torrents: Vec<PersistedTorrent> = {
             let torrents = self.torrents.read().unwrap();
             torrents
                 .values()
@@ -586,11 +1057,21 @@ impl TorrentManager {
                     selected_files: m.selected_files.clone(),
                     info_hash: m.info_hash.clone(),
                     name: m.name.clone(),
+                    sequential: m.sequential,
+                    uploaded_bytes: m.uploaded_bytes,
+                    downloaded_bytes: m.downloaded_bytes,
+                    seed_ratio_limit: m.seed_ratio_limit,
                 })
                 .collect()
         };
 
-        let json = serde_json::to_string_pretty(&persisted)
+        let state = StateFile {
+            torrents,
+            speed_limits: self.get_speed_limits(),
+            seed_ratio_limit: self.get_seed_ratio_limit(),
+        };
+
+        let json = serde_json::to_string_pretty(&state)
             .map_err(|e| format!("Failed to serialize state: {}", e))?;
 
         tokio_fs::write(&state_path, json)
@@ -611,8 +1092,13 @@ impl TorrentManager {
             .await
             .map_err(|e| format!("Failed to read state file: {}", e))?;
 
-        let persisted: Vec<PersistedTorrent> = serde_json::from_str(&json)
-            .map_err(|e| format!("Failed to parse state file: {}", e))?;
+        let state: StateFile = serde_json::from_str::<StoredState>(&json)
+            .map_err(|e| format!("Failed to parse state file: {}", e))?
+            .into();
+
+        self.apply_speed_limits(state.speed_limits.normalized());
+        *self.seed_ratio_limit.write().unwrap() = effective_ratio_limit(state.seed_ratio_limit, None);
+        let persisted = state.torrents;
 
         println!("[Torrent] Loading {} persisted torrents", persisted.len());
         
@@ -645,8 +1131,7 @@ impl TorrentManager {
                         _ => continue,
                     };
 
-                    let mut torrents = self.torrents.write().unwrap();
-                    torrents.insert(id, TorrentMetadata {
+                    self.torrents.write().unwrap().insert(id, TorrentMetadata {
                         id,
                         name: p.name.clone(),
                         info_hash: p.info_hash,
@@ -654,7 +1139,17 @@ impl TorrentManager {
                         file_bytes: p.file_bytes,
                         output_folder: p.output_folder,
                         selected_files: p.selected_files,
+                        sequential: p.sequential,
+                        uploaded_bytes: p.uploaded_bytes,
+                        downloaded_bytes: p.downloaded_bytes,
+                        last_uploaded: 0,
+                        last_downloaded: 0,
+                        seed_ratio_limit: p.seed_ratio_limit,
+                        leading_bytes: HashMap::new(),
                     });
+                    if p.sequential {
+                        self.spawn_sequential_driver(id);
+                    }
 
                     println!("[Torrent] Restored: {}", p.name);
                 }
@@ -667,3 +1162,52 @@ impl TorrentManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_state_file_loads_without_limits() {
+        let legacy = r#"[{"magnet":"magnet:?xt=urn:btih:abc","file_bytes":null,"output_folder":"/dl","selected_files":null,"info_hash":"abc","name":"Album"}]"#;
+        let state: StateFile = serde_json::from_str::<StoredState>(legacy).unwrap().into();
+        assert_eq!(state.torrents.len(), 1);
+        assert_eq!(state.speed_limits, SpeedLimits::default());
+    }
+
+    #[test]
+    fn sequential_window_advances_as_files_complete() {
+        let lengths = [100, 200, 300];
+        let order = [2, 0, 1];
+        assert_eq!(sequential_window(&order, &lengths, &[0, 0, 0]), Some(HashSet::from([2])));
+        assert_eq!(sequential_window(&order, &lengths, &[0, 0, 300]), Some(HashSet::from([2, 0])));
+        assert_eq!(sequential_window(&order, &lengths, &[100, 200, 300]), None);
+        assert_eq!(playable_files(&lengths, &[100, 50, 300], &HashMap::new()), vec![0, 2]);
+    }
+
+    #[test]
+    fn files_are_playable_once_their_start_is_on_disk() {
+        let lengths = [PLAYABLE_LEAD_BYTES * 4, 1000, PLAYABLE_LEAD_BYTES * 4];
+        let progress = [PLAYABLE_LEAD_BYTES * 2, 1000, PLAYABLE_LEAD_BYTES * 2];
+        // Only the leading reader knows the bytes are at the start of the file
+        let leading = HashMap::from([(0, PLAYABLE_LEAD_BYTES), (2, PLAYABLE_LEAD_BYTES - 1)]);
+        assert_eq!(playable_files(&lengths, &progress, &leading), vec![0, 1]);
+    }
+
+    #[test]
+    fn transfer_totals_survive_counter_resets() {
+        assert_eq!(counter_delta(100, 150), 50);
+        assert_eq!(counter_delta(150, 20), 20);
+        assert_eq!(share_ratio(300, 100, 200), 1.5);
+        assert_eq!(share_ratio(0, 0, 0), 0.0);
+        assert_eq!(effective_ratio_limit(Some(0.0), Some(2.0)), None);
+        assert_eq!(effective_ratio_limit(None, Some(2.0)), Some(2.0));
+    }
+
+    #[test]
+    fn zero_limit_means_unlimited() {
+        let limits = SpeedLimits { download_kbps: Some(0), upload_kbps: Some(256) }.normalized();
+        assert_eq!(limits.download_kbps, None);
+        assert_eq!(kbps_to_bps(limits.upload_kbps), NonZeroU32::new(256 * 1024));
+    }
+}
@@ -44,6 +44,13 @@ import { usePlaylistStore } from './store/playlistStore';
 type LibraryScanProgressPayload = {
   processed: number;
   total: number;
+  /** 'metadata' while tags are read, then 'database' while tracks are saved */
+  phase: 'metadata' | 'database';
+};
+
+type LibraryScanCompletePayload = {
+  total: number;
+  inserted: number;
 };
 
 type TauriListenEvent<TPayload> = {
@@ -53,9 +60,9 @@ type TauriListenEvent<TPayload> = {
 type TauriWindowBridge = Window & {
   __TAURI__?: {
     event?: {
-      listen: (
+      listen: <TPayload>(
         eventName: string,
-        handler: (event: TauriListenEvent<LibraryScanProgressPayload>) => void
+        handler: (event: TauriListenEvent<TPayload>) => void
       ) => Promise<() => void>;
     };
   };
@@ -151,33 +158,44 @@ function App() {
       let scanStarted = false;
       let lastProgressPct = -1;
 
-      const unlistenProgress = tauriWindow.__TAURI__.event.listen('library-scan-progress', (event) => {
-        const { processed, total } = event.payload;
+      const unlistenProgress = tauriWindow.__TAURI__.event.listen<LibraryScanProgressPayload>('library-scan-progress', (event) => {
+        const { processed, total, phase } = event.payload;
 
         // Show a "scan started" toast on the very first event
         if (!scanStarted) {
           scanStarted = true;
           showToast(`Scanning library… (${total} files found)`);
+          return;
         }
 
-        // Show progress every ~10% of total (minimum every 50 songs)
-        const step = Math.max(50, Math.floor(total * 0.1));
-        const pct = Math.floor((processed / total) * 10); // 0-10 buckets
-        if (pct !== lastProgressPct && processed % step === 0) {
-          lastProgressPct = pct;
-          showToast(`Library scan: ${processed} / ${total}`);
+        // Events are throttled by the backend; only toast each new 10% step
+        const pct = total > 0 ? Math.floor((processed / total) * 10) : 10; // 0-10 buckets
+        const bucket = (phase === 'database' ? 11 : 0) + pct;
+        if (bucket !== lastProgressPct) {
+          lastProgressPct = bucket;
+          const label = phase === 'database' ? 'Saving tracks' : 'Library scan';
+          showToast(`${label}: ${processed} / ${total}`);
         }
+      });
 
-        // Completion toast
-        if (processed === total && total > 0) {
-          showToast(`✓ Library scan complete — ${total} tracks processed`);
-        }
+      const unlistenComplete = tauriWindow.__TAURI__.event.listen<LibraryScanCompletePayload>('library-scan-complete', (event) => {
+        scanStarted = false;
+        lastProgressPct = -1;
+        showToast(`✓ Library scan complete — ${event.payload.total} tracks processed`);
+      });
+
+      const unlistenCancelled = tauriWindow.__TAURI__.event.listen<LibraryScanCompletePayload>('library-scan-cancelled', (event) => {
+        scanStarted = false;
+        lastProgressPct = -1;
+        showToast(`Library scan cancelled — ${event.payload.inserted} tracks saved`);
       });
 
       window.addEventListener('resize', handleResize);
       return () => {
         window.removeEventListener('resize', handleResize);
         unlistenProgress.then((unlisten) => unlisten());
+        unlistenComplete.then((unlisten) => unlisten());
+        unlistenCancelled.then((unlisten) => unlisten());
       };
     } else {
       window.addEventListener('resize', handleResize);
@@ -5,7 +5,7 @@ import { useThemeStore } from '@/store/themeStore';
 import { useNavigationStore } from '@/store/navigationStore';
 import { useCoverArt } from '@/hooks/useCoverArt';
 import type { TrackDisplay } from '@/types';
-import { getDisplayText } from '@/utils/textUtils';
+import { getAlbumArtist, getDisplayText } from '@/utils/textUtils';
 import { IconMusicNote, IconPlay, IconAlbum } from '@/components/common/Icons';
 import { M3SquircleImage } from '@/components/common/ShapeComponents';
 import { motion } from 'motion/react';
@@ -81,13 +81,15 @@ export function AlbumGrid() {
 
         library.forEach(track => {
             const normalizedName = normalizeAlbumName(track.album);
-            // Group by Artist + Album Name to differentiate same album name by different artists
-            const key = `${track.artist}-${normalizedName}`;
+            const artist = getAlbumArtist(track);
+            // Group by Album Artist + Album Name to differentiate same album name by different
+            // artists, while keeping albums with several track artists together
+            const key = `${artist}-${normalizedName}`;
 
             if (!albumMap.has(key)) {
                 albumMap.set(key, {
                     name: normalizedName,
-                    artist: track.artist,
+                    artist,
                     cover: track.cover_image || null,
                     tracks: []
                 });
@@ -195,7 +197,10 @@ const AlbumItem = ({
 }) => {
     const firstTrack = album.tracks[0];
     const displayAlbumName = getDisplayText(firstTrack, 'album', displayLanguage);
-    const displayArtistName = getDisplayText(firstTrack, 'artist', displayLanguage);
+    // The track artist is only the album's when no album artist says otherwise
+    const displayArtistName = album.artist === firstTrack?.artist
+        ? getDisplayText(firstTrack, 'artist', displayLanguage)
+        : album.artist;
     const coverUrl = useCoverArt(album.cover, firstTrack?.path);
 
     return (
@@ -256,8 +261,9 @@ function AlbumDetailView({ album, onBack }: { album: Album, onBack: () => void }
             const discB = b.disc_number || 1;
             if (discA !== discB) return discA - discB;
 
-            const trackA = a.track_number || 0;
-            const trackB = b.track_number || 0;
+            // Untagged tracks go after numbered ones
+            const trackA = a.track_number ?? Number.MAX_SAFE_INTEGER;
+            const trackB = b.track_number ?? Number.MAX_SAFE_INTEGER;
             return trackA - trackB;
         });
 
@@ -300,7 +306,7 @@ function AlbumDetailView({ album, onBack }: { album: Album, onBack: () => void }
             const discA = a.disc_number || 1;
             const discB = b.disc_number || 1;
             if (discA !== discB) return discA - discB;
-            return (a.track_number || 0) - (b.track_number || 0);
+            return (a.track_number ?? Number.MAX_SAFE_INTEGER) - (b.track_number ?? Number.MAX_SAFE_INTEGER);
         });
     }, [album.tracks]);
 
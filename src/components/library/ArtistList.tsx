@@ -278,7 +278,7 @@ function ArtistDetailView({ artist, onBack }: { artist: Artist, onBack: () => vo
             const discA = a.disc_number || 1;
             const discB = b.disc_number || 1;
             if (discA !== discB) return discA - discB;
-            return (a.track_number || 0) - (b.track_number || 0);
+            return (a.track_number ?? Number.MAX_SAFE_INTEGER) - (b.track_number ?? Number.MAX_SAFE_INTEGER);
         });
     }, [artist.tracks]);
 
@@ -5,6 +5,7 @@ import { M3SquircleImage, M3CircleImage } from '@/components/common/ShapeCompone
 import { IconAlbum, IconMicrophone } from '@/components/common/Icons';
 import { TrackDisplay } from '@/types';
 import { useCoverArt } from '@/hooks/useCoverArt';
+import { getAlbumArtist } from '@/utils/textUtils';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 
@@ -58,7 +59,7 @@ export function HomeView() {
         // Try history first (unique albums)
         const uniqueAlbums = new Map<string, TrackDisplay>();
         for (const track of history) {
-            const key = `${track.album}-${track.artist}`;
+            const key = `${track.album}-${getAlbumArtist(track)}`;
             if (!uniqueAlbums.has(key)) {
                 uniqueAlbums.set(key, track);
             }
@@ -69,7 +70,7 @@ export function HomeView() {
         if (uniqueAlbums.size === 0) {
             for (let i = 0; i < Math.min(6, library.length); i++) {
                 const track = library[i];
-                const key = `${track.album}-${track.artist}`;
+                const key = `${track.album}-${getAlbumArtist(track)}`;
                 if (!uniqueAlbums.has(key)) {
                     uniqueAlbums.set(key, track);
                 }
@@ -83,7 +84,7 @@ export function HomeView() {
     const jumpBackInItems = useMemo(() => {
         const uniqueAlbums = new Map<string, TrackDisplay>();
         for (const track of history) {
-            const key = `${track.album}-${track.artist}`;
+            const key = `${track.album}-${getAlbumArtist(track)}`;
             if (!uniqueAlbums.has(key)) {
                 uniqueAlbums.set(key, track);
             }
@@ -98,7 +99,7 @@ export function HomeView() {
         // Iterate backwards through library
         for (let i = library.length - 1; i >= 0; i--) {
             const track = library[i];
-            const key = `${track.album}-${track.artist}`;
+            const key = `${track.album}-${getAlbumArtist(track)}`;
             if (!uniqueAlbums.has(key)) {
                 uniqueAlbums.set(key, track);
             }
@@ -126,7 +127,7 @@ export function HomeView() {
 
     const handlePlayAlbum = (track: TrackDisplay, e: React.MouseEvent) => {
         e.stopPropagation();
-        const albumTracks = library.filter(t => t.album === track.album && t.artist === track.artist);
+        const albumTracks = library.filter(t => t.album === track.album && getAlbumArtist(t) === getAlbumArtist(track));
         if (albumTracks.length > 0) {
             playQueue(albumTracks, 0);
         }
@@ -149,7 +150,7 @@ export function HomeView() {
                             key={track.path}
                             track={track}
                             onPlay={handlePlayAlbum}
-                            onClick={() => navigateToAlbum(track.album, track.artist)}
+                            onClick={() => navigateToAlbum(track.album, getAlbumArtist(track))}
                         />
                     ))}
                 </div>
@@ -160,7 +161,7 @@ export function HomeView() {
                 <CarouselSection
                     title="Jump back in"
                     items={jumpBackInItems}
-                    onItemClick={(track) => navigateToAlbum(track.album, track.artist)}
+                    onItemClick={(track) => navigateToAlbum(track.album, getAlbumArtist(track))}
                     onPlayClick={handlePlayAlbum}
                     type="album"
                 />
@@ -171,7 +172,7 @@ export function HomeView() {
                 <CarouselSection
                     title="Most played"
                     items={mostPlayedTracks}
-                    onItemClick={(track) => navigateToAlbum(track.album, track.artist)}
+                    onItemClick={(track) => navigateToAlbum(track.album, getAlbumArtist(track))}
                     onPlayClick={handlePlayAlbum}
                     type="album"
                 />
@@ -182,7 +183,7 @@ export function HomeView() {
                 <CarouselSection
                     title="Recently added"
                     items={recentlyAddedItems}
-                    onItemClick={(track) => navigateToAlbum(track.album, track.artist)}
+                    onItemClick={(track) => navigateToAlbum(track.album, getAlbumArtist(track))}
                     onPlayClick={handlePlayAlbum}
                     type="album"
                 />
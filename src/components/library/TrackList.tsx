@@ -176,6 +176,7 @@ export function TrackList() {
     const library = usePlayerStore(state => state.library);
     const playQueue = usePlayerStore(state => state.playQueue);
     const isLoading = usePlayerStore(state => state.isLoading);
+    const cancelLibraryScan = usePlayerStore(state => state.cancelLibraryScan);
     const currentPath = usePlayerStore(state => state.status.track?.path);
     const isPlaying = usePlayerStore(state => state.status.state === 'Playing');
     const searchQuery = usePlayerStore(state => state.searchQuery);
@@ -259,6 +260,12 @@ export function TrackList() {
         return (
             <div className="flex flex-col items-center justify-center h-full text-on-surface-variant">
                 <div className="animate-pulse">Scanning music folder...</div>
+                <button
+                    onClick={() => cancelLibraryScan()}
+                    className="mt-4 px-4 py-2 rounded-full bg-surface-container-highest hover:bg-surface-container-high text-on-surface transition-colors text-sm font-medium"
+                >
+                    Cancel
+                </button>
             </div>
         );
     }
@@ -233,7 +233,7 @@ export const Equalizer: React.FC = () => {
                         {/* DSP & Effects Sidebar */}
                         <div className="flex flex-col gap-6">
                             {[
-                                { label: 'Preamp', value: preampDb, setter: setPreamp, min: -12, max: 12, step: 0.5, format: (v: number) => `${v > 0 ? '+' : ''}${v.toFixed(1)} dB`, resetVal: 0 },
+                                { label: 'Preamp', value: preampDb, setter: setPreamp, min: -24, max: 12, step: 0.5, format: (v: number) => `${v > 0 ? '+' : ''}${v.toFixed(1)} dB`, resetVal: 0 },
                                 { label: 'Balance', value: balance, setter: setBalance, min: -1, max: 1, step: 0.01, format: (v: number) => v === 0 ? 'C' : v < 0 ? 'L' : 'R', resetVal: 0 },
                                 { label: 'Tempo', value: speed, setter: setSpeed, min: 0.5, max: 2.0, step: 0.05, format: (v: number) => `${v.toFixed(2)}x`, resetVal: 1.0 }
                             ].map((control) => (
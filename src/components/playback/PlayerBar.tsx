@@ -165,6 +165,8 @@ export function PlayerBar() {
     const track = usePlayerStore(s => s.status.track);
     const library = usePlayerStore(s => s.library);
     const position_secs = usePlayerStore(s => s.status.position_secs);
+    // Radio and other live streams have no length to seek in or finish at
+    const isLive = usePlayerStore(s => s.status.is_live ?? false);
 
     // Hydrate track with library data (Romaji/En fields) if available
     const displayTrack = useMemo(() => {
@@ -216,7 +218,7 @@ export function PlayerBar() {
 
     // Auto-play next track when current track ends
     useEffect(() => {
-        if (lastStateRef.current === 'Playing' && state === 'Stopped' && track) {
+        if (lastStateRef.current === 'Playing' && state === 'Stopped' && track && !isLive) {
             const isFinished = position_secs >= track.duration_secs - 2.0;
 
             if (isFinished) {
@@ -228,7 +230,7 @@ export function PlayerBar() {
             }
         }
         lastStateRef.current = state;
-    }, [state, track, position_secs, nextTrack, repeatMode, playFile]);
+    }, [state, track, isLive, position_secs, nextTrack, repeatMode, playFile]);
 
     const handlePlayPause = (e?: React.MouseEvent) => {
         if (e) {
@@ -521,16 +523,20 @@ export function PlayerBar() {
                                 <div className="mt-1 w-[min(42vw,30rem)] flex items-center gap-2 text-label-small text-on-surface-variant/85">
                                     <span className="w-10 text-right">{formatTime(position_secs)}</span>
                                     <div className="flex-1">
-                                        <SquigglySlider
-                                            value={position_secs}
-                                            max={track?.duration_secs || 100}
-                                            onChange={handleSeek}
-                                            isPlaying={state === 'Playing'}
-                                            accentColor="var(--md-sys-color-primary)"
-                                            className="w-full"
-                                        />
+                                        {isLive ? (
+                                            <div className="h-1 w-full rounded-full bg-primary/40" />
+                                        ) : (
+                                            <SquigglySlider
+                                                value={position_secs}
+                                                max={track?.duration_secs || 100}
+                                                onChange={handleSeek}
+                                                isPlaying={state === 'Playing'}
+                                                accentColor="var(--md-sys-color-primary)"
+                                                className="w-full"
+                                            />
+                                        )}
                                     </div>
-                                    <span className="w-10">{track ? formatTime(track.duration_secs) : '0:00'}</span>
+                                    <span className="w-10">{isLive ? 'LIVE' : track ? formatTime(track.duration_secs) : '0:00'}</span>
                                     {trackQualityText && (
                                         <span className="max-w-40 truncate rounded-full border border-white/20 px-2 py-0.5 text-[10px] uppercase tracking-[0.08em] text-on-surface/80">
                                             {trackQualityText}
@@ -3,6 +3,8 @@ import { usePlayerStore } from '@/store/playerStore';
 import { useLyricsStore } from '@/store/lyricsStore';
 import { useSettingsStore } from '@/store/settingsStore';
 import { useMobileStore } from '@/store/mobileStore';
+import { useToastStore } from '@/store/toastStore';
+import type { TrackDisplay } from '@/types';
 import { invoke } from '@tauri-apps/api/core';
 import { emit } from '@tauri-apps/api/event';
 
@@ -17,6 +19,9 @@ export function GlobalEffects() {
     })));
 
     useEffect(() => {
+        // Whether the last backend queue we saw had tracks in it
+        let backendHadQueue = false;
+
         import('@tauri-apps/api/event').then(({ listen }) => {
             const unlisten = Promise.all([
                 listen('media:play', () => resume()),
@@ -29,6 +34,11 @@ export function GlobalEffects() {
                     else store.resume();
                 }),
                 listen('media:stop', () => usePlayerStore.getState().stop()),
+                listen<number>('media:seek', (event) => usePlayerStore.getState().seek(event.payload)),
+                listen<number>('media:seek_by', (event) => {
+                    const store = usePlayerStore.getState();
+                    store.seek(Math.max(0, store.status.position_secs + event.payload));
+                }),
 
                 // Mobile Events
                 listen('mobile_client_connected', (event: any) => {
@@ -84,10 +94,22 @@ export function GlobalEffects() {
                                 return { ...t, ...(libMatch || {}), id: t.path };
                             });
 
+                            if (tracks.length === 0) {
+                                // The backend queue is empty whenever the desktop queue is only
+                                // kept here, so only follow it when it was emptied (clear_queue,
+                                // removing the last track)
+                                if (backendHadQueue && store.queue.length > 0) {
+                                    usePlayerStore.setState({ queue: [], originalQueue: [] });
+                                }
+                                backendHadQueue = false;
+                                return;
+                            }
+                            backendHadQueue = true;
+
                             // Only update if queue actually changed (avoid clobbering user edits)
                             const backendPaths = tracks.map((t: any) => t.path).join(',');
                             const frontendPaths = store.queue.map(t => t.path).join(',');
-                            if (backendPaths !== frontendPaths && tracks.length > 0) {
+                            if (backendPaths !== frontendPaths) {
                                 usePlayerStore.setState({
                                     queue: tracks,
                                     originalQueue: tracks,
@@ -118,6 +140,27 @@ export function GlobalEffects() {
                     }
                 }),
 
+                listen<{ message: string }>('playback-device-error', (event) => {
+                    console.warn('[Audio] Output device error:', event.payload.message);
+                    useToastStore.getState().showToast(`${event.payload.message}. Playback paused.`);
+                    usePlayerStore.getState().refreshStatus();
+                }),
+
+                // Covers saved by the background pass after a scan; show them on the album's tracks
+                listen<{ album: string; artist: string; cover: string }>('cover-cached', (event) => {
+                    const { album, artist, cover } = event.payload;
+                    const withCover = (tracks: TrackDisplay[]) => tracks.map(t =>
+                        !t.cover_image && t.album === album && (t.album_artist ?? t.artist) === artist
+                            ? { ...t, cover_image: cover }
+                            : t
+                    );
+                    usePlayerStore.setState(state => ({
+                        library: withCover(state.library),
+                        queue: withCover(state.queue),
+                        originalQueue: withCover(state.originalQueue),
+                    }));
+                }),
+
                 listen('mobile-position-update', (event: any) => {
                     const { position_secs } = event.payload;
                     const store = usePlayerStore.getState();
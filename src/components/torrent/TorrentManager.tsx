@@ -16,6 +16,10 @@ interface TorrentStatus {
     downloaded_size: number;
     peers_connected: number;
     error: string | null;
+    sequential: boolean;
+    playable_files: number[];
+    ratio: number;
+    seed_ratio_limit: number | null;
 }
 
 export function TorrentManager() {
@@ -1,11 +1,52 @@
 import { useState, useEffect, useMemo } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { usePlayerStore } from '@/store/playerStore';
 
+// The backend server requires its shared token on every route; fetch it once.
+let serverTokenPromise: Promise<string | null> | null = null;
+function getServerToken(): Promise<string | null> {
+    if (!serverTokenPromise) {
+        serverTokenPromise = invoke<string>('get_server_token').catch(() => {
+            serverTokenPromise = null;
+            return null;
+        });
+    }
+    return serverTokenPromise;
+}
+
+// Covers are served on whichever port the backend server actually bound. Until
+// it is listening there is no URL, and the next lookup asks again.
+let serverBasePromise: Promise<string | null> | null = null;
+function getServerBase(): Promise<string | null> {
+    if (!serverBasePromise) {
+        serverBasePromise = invoke<{ running: boolean; port: number | null }>('get_server_status')
+            .then(({ port }) => {
+                if (port == null) serverBasePromise = null;
+                return port == null ? null : `http://localhost:${port}`;
+            })
+            .catch(() => {
+                serverBasePromise = null;
+                return null;
+            });
+    }
+    return serverBasePromise;
+}
+// A restarted server may be on another port
+listen('server-started', () => { serverBasePromise = null; });
+
+async function coverUrl(path: string): Promise<string | null> {
+    const [base, token] = await Promise.all([getServerBase(), getServerToken()]);
+    if (!base) return null;
+    const url = `${base}/cover/${encodeURIComponent(path)}`;
+    return token ? `${url}?token=${encodeURIComponent(token)}` : url;
+}
+
 /**
  * Loads cover art with a robust priority chain:
  * 1. Direct HTTP URL → use as-is
- * 2. Cached cover filename → route through backend HTTP server (port 5000)
- * 3. Full local path → route through backend HTTP server (port 5000)
+ * 2. Cached cover filename → route through backend HTTP server
+ * 3. Full local path → route through backend HTTP server
  * 4. Extraction from audio file → only if allowExtraction is true (e.g. PlayerBar)
  *    (Uses the same backend HTTP endpoint for extraction)
  * 5. null / empty → null
@@ -22,7 +63,7 @@ export function useCoverArt(coverPathRaw: string | null | undefined, trackPath?:
     useEffect(() => {
         let cancelled = false;
 
-        function resolve() {
+        async function resolve() {
             // 1. Already a resolved HTTP URL — use directly
             if (coverPath?.startsWith('http') && !coverPath.includes('asset.localhost')) {
                 if (!cancelled) setImageUrl(coverPath);
@@ -33,9 +74,9 @@ export function useCoverArt(coverPathRaw: string | null | undefined, trackPath?:
             //    If we also have trackPath, prefer requesting by track path so backend
             //    can recover from stale/missing cached filenames and still resolve art.
             if (coverPath && !coverPath.includes('/')) {
+                const url = await coverUrl(trackPath || coverPath);
                 if (!cancelled) {
-                    const resolvedPath = trackPath || coverPath;
-                    setImageUrl(`http://localhost:5000/cover/${encodeURIComponent(resolvedPath)}`);
+                    setImageUrl(url);
                     return;
                 }
             }
@@ -50,8 +91,9 @@ export function useCoverArt(coverPathRaw: string | null | undefined, trackPath?:
                         ? `${coversDir}/${coverPath}`
                         : coverPath;
 
+                const url = await coverUrl(pathForServer);
                 if (!cancelled) {
-                    setImageUrl(`http://localhost:5000/cover/${encodeURIComponent(pathForServer)}`);
+                    setImageUrl(url);
                 }
                 return;
             }
@@ -59,10 +101,10 @@ export function useCoverArt(coverPathRaw: string | null | undefined, trackPath?:
             // 4. No coverPath at all — try backend extraction from audio file.
             //    ONLY if allowExtraction is true (to avoid overwhelming WebKit with 100s of requests in lists)
             if (trackPath && allowExtraction) {
-                const encodedPath = encodeURIComponent(trackPath);
                 // Assign directly without HEAD probe to reduce network orchestration overhead.
                 // WebKit handles individual failing images better than an overwhelmed network process.
-                if (!cancelled) setImageUrl(`http://localhost:5000/cover/${encodedPath}`);
+                const url = await coverUrl(trackPath);
+                if (!cancelled) setImageUrl(url);
                 return;
             }
 
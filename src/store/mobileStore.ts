@@ -118,8 +118,9 @@ export const useMobileStore = create<MobileStore>()(
 
             checkServerStatus: async () => {
                 try {
-                    const running = await invoke<boolean>('get_server_status');
+                    const { running, port } = await invoke<{ running: boolean; port: number | null }>('get_server_status');
                     set({ serverRunning: running });
+                    if (port) set({ serverPort: port });
                     if (running && get().status === 'disconnected') {
                         set({ status: 'searching' });
                         await get().fetchLocalIP();
@@ -2,7 +2,7 @@ import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
 import { invoke } from '@tauri-apps/api/core';
 import { emit } from '@tauri-apps/api/event';
-import type { PlayerStatus, TrackDisplay } from '@/types';
+import type { PlayerStatus, Settings, Station, TrackDisplay } from '@/types';
 import { useToastStore } from './toastStore';
 
 type RepeatMode = 'off' | 'all' | 'one';
@@ -39,6 +39,9 @@ const DEFAULT_PRESETS: EqPreset[] = [
     { id: 'increase-vocals', name: 'Increase Vocals', gains: [-2, -2, -1, 1, 3, 5, 4, 2, 0, -1] },
 ];
 
+// Set by cancelLibraryScan so a multi-folder refresh doesn't start the next folder
+let scanCancelRequested = false;
+
 // Helper function to broadcast queue updates to mobile clients via WebSocket
 const broadcastQueueUpdate = async (queue: TrackDisplay[]) => {
     try {
@@ -98,6 +101,7 @@ interface PlayerStore {
     coversDir: string | null;
     currentFolder: string | null; // Kept for now, maybe deprecated later
     folders: string[]; // NEW: List of scanned folders
+    stations: Station[]; // Saved internet radio stations
     isLoading: boolean;
     error: string | null;
     sort: { key: keyof TrackDisplay; direction: 'asc' | 'desc' } | null;
@@ -139,6 +143,10 @@ interface PlayerStore {
 
     // Actions
     playFile: (path: string) => Promise<void>;
+    playUrl: (url: string) => Promise<void>;
+    loadStations: () => Promise<void>;
+    addStation: (name: string, url: string) => Promise<void>;
+    removeStation: (id: number) => Promise<void>;
     pause: () => Promise<void>;
     resume: () => Promise<void>;
     stop: () => Promise<void>;
@@ -160,6 +168,7 @@ interface PlayerStore {
     syncAudioSettings: () => void;
 
     scanFolder: (path: string) => Promise<void>;
+    cancelLibraryScan: () => Promise<void>;
     removeFolder: (path: string) => Promise<void>;
     loadLibrary: () => Promise<void>;
     setError: (error: string | null) => void;
@@ -217,6 +226,7 @@ export const usePlayerStore = create<PlayerStore>()(
             },
             library: [],
             history: [],
+            stations: [],
             playCounts: {},
             coversDir: null,
             currentFolder: null,
@@ -658,6 +668,44 @@ export const usePlayerStore = create<PlayerStore>()(
                 }
             },
 
+            playUrl: async (url: string) => {
+                try {
+                    set({ error: null });
+                    await invoke('play_url', { url });
+                    await get().refreshStatus();
+                } catch (e) {
+                    console.error("[PlayerStore] Stream failed:", e);
+                    set({ error: String(e) });
+                }
+            },
+
+            loadStations: async () => {
+                try {
+                    const stations = await invoke<Station[]>('get_stations');
+                    set({ stations });
+                } catch (e) {
+                    set({ error: String(e) });
+                }
+            },
+
+            addStation: async (name: string, url: string) => {
+                try {
+                    await invoke('add_station', { name, url });
+                    await get().loadStations();
+                } catch (e) {
+                    set({ error: String(e) });
+                }
+            },
+
+            removeStation: async (id: number) => {
+                try {
+                    await invoke('remove_station', { id });
+                    await get().loadStations();
+                } catch (e) {
+                    set({ error: String(e) });
+                }
+            },
+
             pause: async () => {
                 try {
                     await invoke('pause');
@@ -698,6 +746,7 @@ export const usePlayerStore = create<PlayerStore>()(
             },
 
             seek: async (value: number) => {
+                if (get().status.seekable === false) return;
                 try {
                     await invoke('seek', { value });
                 } catch (e) {
@@ -743,8 +792,8 @@ export const usePlayerStore = create<PlayerStore>()(
 
             setPreamp: (val: number) => {
                 set({ preampDb: val });
-                console.log(`[PlayerStore] set_preamp(band 10) gain=${val}`);
-                invoke('set_eq', { band: 10, gain: val }).catch(console.error);
+                console.log(`[PlayerStore] set_preamp gain=${val}`);
+                invoke('set_preamp', { db: val }).catch(console.error);
             },
             setBalance: (val: number) => {
                 set({ balance: val });
@@ -775,8 +824,10 @@ export const usePlayerStore = create<PlayerStore>()(
                     invoke('set_eq', { band: index, gain }).catch(console.error);
                 });
 
-                // DSP
-                invoke('set_eq', { band: 10, gain: state.preampDb }).catch(console.error);
+                // DSP. The pre-amp is kept in the backend settings and already applied there
+                invoke<Settings>('get_settings')
+                    .then(settings => set({ preampDb: settings.preampDb }))
+                    .catch(console.error);
                 invoke('set_eq', { band: 11, gain: state.balance }).catch(console.error);
                 invoke('set_eq', { band: 12, gain: state.stereoWidth }).catch(console.error);
             },
@@ -803,8 +854,10 @@ export const usePlayerStore = create<PlayerStore>()(
                     set({ isLoading: true });
                     const { folders } = get();
 
-                    // Re-scan all folders
+                    // Re-scan all folders, stopping after the current one if the scan is cancelled
+                    scanCancelRequested = false;
                     for (const folder of folders) {
+                        if (scanCancelRequested) break;
                         console.log("Rescanning:", folder);
                         await invoke('init_library', { path: folder });
                     }
@@ -846,6 +899,15 @@ export const usePlayerStore = create<PlayerStore>()(
                 }
             },
 
+            cancelLibraryScan: async () => {
+                scanCancelRequested = true;
+                try {
+                    await invoke('cancel_library_scan');
+                } catch (e) {
+                    console.error('Failed to cancel library scan:', e);
+                }
+            },
+
             removeFolder: async (path: string) => {
                 try {
                     set({ isLoading: true });
@@ -1002,6 +1064,16 @@ export const usePlayerStore = create<PlayerStore>()(
         }),
         {
             name: 'vibe-player-storage',
+            // 1: the volume slider follows the backend's log curve instead of raw
+            // amplitude. Saved volumes are converted so they play at the same level.
+            version: 1,
+            migrate: (persistedState: any, version) => {
+                if (version < 1 && typeof persistedState?.savedVolume === 'number' && persistedState.savedVolume > 0) {
+                    const slider = 1 + Math.log10(persistedState.savedVolume) / 3;
+                    persistedState.savedVolume = Math.min(1, Math.max(0, slider));
+                }
+                return persistedState;
+            },
             partialize: (state) => ({
                 history: state.history.slice(0, 20), // Cap persisted history
                 playCounts: state.playCounts,
@@ -1017,7 +1089,6 @@ export const usePlayerStore = create<PlayerStore>()(
                 eqGains: state.eqGains,
                 presets: state.presets,
                 activePresetId: state.activePresetId,
-                preampDb: state.preampDb,
                 balance: state.balance,
                 stereoWidth: state.stereoWidth,
                 speed: state.speed,
@@ -1040,7 +1111,6 @@ export const usePlayerStore = create<PlayerStore>()(
                 miniPlayer: persistedState?.miniPlayer || false, // Restore Mini-Player state
                 eqGains: persistedState?.eqGains || Array(10).fill(0),
                 activePresetId: persistedState?.activePresetId || 'flat',
-                preampDb: persistedState?.preampDb ?? 0,
                 balance: persistedState?.balance ?? 0,
                 stereoWidth: persistedState?.stereoWidth ?? 1.0,
                 speed: persistedState?.speed ?? 1.0,
@@ -16,6 +16,21 @@ export interface TrackInfo {
   sample_rate_hz?: number | null;
   bitrate_kbps?: number | null;
   codec?: string | null;
+  genre?: string | null;
+  year?: number | null;
+  album_artist?: string | null;
+  is_compilation?: boolean;
+}
+
+// A-B repeat region matching Rust struct
+export interface LoopRegion {
+  start_secs: number;
+  end_secs: number;
+}
+
+export interface SilenceTrim {
+  start_secs: number;
+  end_secs: number;
 }
 
 // Complete player status matching Rust struct
@@ -24,6 +39,44 @@ export interface PlayerStatus {
   track: TrackInfo | null;
   position_secs: number;
   volume: number;
+  ab_loop?: LoopRegion | null;
+  exclusive_output?: boolean;
+  sample_rate?: number | null;
+  bit_depth?: number | null;
+  channels?: number | null;
+  preamp_db?: number;
+  balance?: number;
+  mono?: boolean;
+  skip_silence?: boolean;
+  silence_trim?: SilenceTrim | null;
+  // False for remote streams whose server doesn't accept range requests
+  seekable?: boolean;
+  // Live stream without a fixed length, such as internet radio
+  is_live?: boolean;
+  volume_curve?: 'linear' | 'log' | 'cubic';
+}
+
+// Saved internet radio station matching Rust struct
+export interface Station {
+  id: number;
+  name: string;
+  url: string;
+}
+
+// Persisted preferences (settings.json); update_settings takes any subset
+export interface Settings {
+  discordEnabled: boolean;
+  discordButtons: [string, string][];
+  preampDb: number;
+  normalizationMode: 'off' | 'track' | 'album';
+  defaultGainDb: number;
+  volumeCurve: 'linear' | 'log' | 'cubic';
+  serverPort: number | null;
+  scanExtensions: string[] | null;
+  lastSession: { trackPath: string; positionSecs: number } | null;
+  watchedFolders: string[];
+  pairedPeers: string[];
+  lastfmScrobbledUntil: number | null;
 }
 
 // Track display info for library
@@ -46,11 +99,26 @@ export interface LyricsLine {
   romaji?: string; // Romaji translation
 }
 
+// Word-level timing from enhanced LRC (`<mm:ss.xx>` tags)
+export interface TimedWord {
+  startMs: number;
+  endMs: number | null;
+  text: string;
+}
+
+export interface WordTimedLine {
+  startMs: number;
+  text: string;
+  words: TimedWord[];
+}
+
 // Lyrics response from backend
 export interface LyricsData {
   syncedLyrics: string | null;  // LRC format string
   plainLyrics: string | null;   // Plain text fallback
   instrumental: boolean;
+  wordTimedLyrics?: WordTimedLine[] | null; // Only set for enhanced LRC
+  source?: string | null;       // Provider that answered: lrclib, netease, local
 }
 
 
@@ -34,3 +34,13 @@ export const getDisplayText = (
 
     return original;
 };
+
+/** Album artist of compilations without an album artist tag, as the library stores it */
+export const VARIOUS_ARTISTS = 'Various Artists';
+
+/**
+ * Artist an album is filed under: its album artist tag, else "Various Artists"
+ * for compilations and the track artist otherwise
+ */
+export const getAlbumArtist = (track: TrackInfo | TrackDisplay): string =>
+    track.album_artist || (track.is_compilation ? VARIOUS_ARTISTS : track.artist);